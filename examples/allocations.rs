@@ -0,0 +1,94 @@
+//! Counts how many heap allocations sending and handling a message takes once the actor is
+//! running, to keep track of the cost of envelopes and response channels. With the `perf` feature,
+//! their memory is recycled, so that none are needed once the actor has handled a few messages.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use xtra::prelude::*;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct Counter {
+    count: usize,
+}
+
+impl Actor for Counter {}
+
+struct Increment;
+
+impl Message for Increment {
+    type Result = usize;
+}
+
+impl SyncHandler<Increment> for Counter {
+    fn handle(&mut self, _: Increment, _ctx: &mut Context<Self>) -> usize {
+        self.count += 1;
+        self.count
+    }
+}
+
+const COUNT: usize = 100_000;
+
+/// How many messages are sent with `do_send` before waiting for the actor to handle them, which is
+/// kept below how many blocks of memory a thread keeps to be recycled
+const BATCH: usize = 10;
+
+/// What is left once sending and handling a message does not allocate: smol's executor allocates a
+/// block of its run queue for every 31 times that a task is woken
+const EXECUTOR_ALLOCATIONS: f64 = 1.0 / 31.0;
+
+fn assert_recycled(method: &str, allocations: f64) {
+    assert!(
+        allocations <= EXECUTOR_ALLOCATIONS + 0.01,
+        "{} allocated {:.4} times per message",
+        method,
+        allocations,
+    );
+}
+
+fn allocations_per_message(before: usize) -> f64 {
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / COUNT as f64
+}
+
+fn main() {
+    smol::run(async {
+        let addr = Counter { count: 0 }.spawn();
+        // Let the mailbox and the executor allocate what they need up front
+        addr.send(Increment).await.unwrap();
+
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        for _ in 0..COUNT / BATCH {
+            for _ in 0..BATCH {
+                let _ = addr.do_send(Increment);
+            }
+            addr.send(Increment).await.unwrap();
+        }
+        let do_send = allocations_per_message(before);
+        println!("do_send: {:.4} allocations per message", do_send);
+        assert_recycled("do_send", do_send);
+
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        for _ in 0..COUNT {
+            addr.send(Increment).await.unwrap();
+        }
+        let send = allocations_per_message(before);
+        println!("send: {:.4} allocations per message", send);
+        assert_recycled("send", send);
+    });
+}
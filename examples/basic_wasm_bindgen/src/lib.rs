@@ -1,3 +1,5 @@
+#![cfg(target_arch = "wasm32")]
+
 use wasm_bindgen::{prelude::*, JsValue};
 use xtra::prelude::*;
 
@@ -0,0 +1,78 @@
+use std::time::Instant;
+use xtra::prelude::*;
+use xtra::MailboxConfig;
+
+struct Counter {
+    count: usize,
+}
+
+impl Actor for Counter {}
+
+struct Increment;
+
+impl Message for Increment {
+    type Result = ();
+}
+
+struct GetCount;
+
+impl Message for GetCount {
+    type Result = usize;
+}
+
+impl SyncHandler<Increment> for Counter {
+    fn handle(&mut self, _: Increment, _ctx: &mut Context<Self>) {
+        self.count += 1;
+    }
+}
+
+impl SyncHandler<GetCount> for Counter {
+    fn handle(&mut self, _: GetCount, _ctx: &mut Context<Self>) -> usize {
+        let count = self.count;
+        self.count = 0;
+        count
+    }
+}
+
+const SENDERS: usize = 8;
+const COUNT: usize = 2_000_000; // Per sender
+
+/// Send `COUNT` messages from each of `SENDERS` tasks at once, returning the average time it took
+/// to send and process each message.
+async fn bench(config: MailboxConfig) -> u128 {
+    let addr = Counter { count: 0 }.spawn_with(config);
+
+    let start = Instant::now();
+    let senders: Vec<_> = (0..SENDERS)
+        .map(|_| {
+            let addr = addr.clone();
+            tokio::spawn(async move {
+                for _ in 0..COUNT {
+                    let _ = addr.do_send(Increment);
+                }
+            })
+        })
+        .collect();
+
+    for sender in senders {
+        sender.await.unwrap();
+    }
+
+    let total_count = addr.send(GetCount).await.unwrap();
+    let duration = Instant::now() - start;
+    assert_eq!(
+        total_count,
+        SENDERS * COUNT,
+        "total_count should equal SENDERS * COUNT!"
+    );
+    duration.as_nanos() / total_count as u128
+}
+
+#[tokio::main]
+async fn main() {
+    let locked = bench(MailboxConfig::unbounded()).await;
+    println!("locked mailbox avg time of processing: {}ns", locked);
+
+    let lock_free = bench(MailboxConfig::unbounded().lock_free()).await;
+    println!("lock-free mailbox avg time of processing: {}ns", lock_free);
+}
@@ -0,0 +1,62 @@
+//! Two actors which wait on each other's responses. Without the `deadlock-detection` feature, this
+//! would hang forever. With it, the cycle is detected and `Pong`'s handler panics, which stops the
+//! `Pong` actor and lets `Ping` continue with an error.
+
+use xtra::prelude::*;
+use xtra::SendError;
+
+struct Ping {
+    pong: Address<Pong>,
+}
+
+impl Actor for Ping {}
+
+struct Pong;
+
+impl Actor for Pong {}
+
+struct Start;
+
+impl Message for Start {
+    type Result = Result<(), SendError>;
+}
+
+struct Call(Address<Ping>);
+
+impl Message for Call {
+    type Result = ();
+}
+
+struct CallBack;
+
+impl Message for CallBack {
+    type Result = ();
+}
+
+#[async_trait::async_trait]
+impl Handler<Start> for Ping {
+    async fn handle(&mut self, _: Start, ctx: &mut Context<Self>) -> Result<(), SendError> {
+        let me = ctx.address().unwrap();
+        self.pong.send(Call(me)).await
+    }
+}
+
+impl SyncHandler<CallBack> for Ping {
+    fn handle(&mut self, _: CallBack, _: &mut Context<Self>) {}
+}
+
+#[async_trait::async_trait]
+impl Handler<Call> for Pong {
+    async fn handle(&mut self, Call(ping): Call, _: &mut Context<Self>) {
+        // Ping is still waiting on this handler, so it can never handle this
+        let _ = ping.send(CallBack).await;
+    }
+}
+
+#[smol_potat::main]
+async fn main() {
+    let ping = Ping { pong: Pong.spawn() }.spawn();
+    let res = ping.send(Start).await.unwrap();
+    assert!(matches!(res, Err(SendError::Disconnected(_))));
+    println!("The deadlock was detected");
+}
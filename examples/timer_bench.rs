@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+use xtra::prelude::*;
+
+struct Counter {
+    count: usize,
+}
+
+impl Actor for Counter {}
+
+struct Schedule;
+
+impl Message for Schedule {
+    type Result = ();
+}
+
+struct Fired;
+
+impl Message for Fired {
+    type Result = ();
+}
+
+struct GetCount;
+
+impl Message for GetCount {
+    type Result = usize;
+}
+
+const COUNT: usize = 100_000;
+
+impl SyncHandler<Schedule> for Counter {
+    fn handle(&mut self, _: Schedule, ctx: &mut Context<Self>) {
+        for i in 0..COUNT {
+            // Spread the notifications over a second, and cancel every other one
+            let handle = ctx.notify_after(Duration::from_micros(10 * i as u64), Fired);
+            if i % 2 == 0 {
+                handle.cancel();
+            }
+        }
+    }
+}
+
+impl SyncHandler<Fired> for Counter {
+    fn handle(&mut self, _: Fired, _ctx: &mut Context<Self>) {
+        self.count += 1;
+    }
+}
+
+impl SyncHandler<GetCount> for Counter {
+    fn handle(&mut self, _: GetCount, _ctx: &mut Context<Self>) -> usize {
+        self.count
+    }
+}
+
+/// Schedule `COUNT` delayed notifications at once and wait until the half which was not cancelled
+/// has been handled.
+#[tokio::main]
+async fn main() {
+    let addr = Counter { count: 0 }.spawn();
+
+    let start = Instant::now();
+    addr.send(Schedule).await.unwrap();
+    let scheduled = start.elapsed();
+
+    while addr.send(GetCount).await.unwrap() < COUNT / 2 {
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+    }
+    let fired = start.elapsed();
+
+    assert_eq!(addr.send(GetCount).await.unwrap(), COUNT / 2);
+    println!(
+        "scheduled {} notifications in {:?}, all handled after {:?}",
+        COUNT, scheduled, fired
+    );
+}
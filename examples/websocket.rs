@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use xtra::prelude::*;
+use xtra::remote::{Connection, Node, RemoteMessage, WebSocketTransport};
+
+struct Greeter;
+impl Actor for Greeter {}
+
+#[derive(Serialize, Deserialize)]
+struct Greet(String);
+
+impl Message for Greet {
+    type Result = String;
+}
+
+impl RemoteMessage for Greet {
+    const NAME: &'static str = "Greet";
+}
+
+impl SyncHandler<Greet> for Greeter {
+    fn handle(&mut self, Greet(name): Greet, _ctx: &mut Context<Self>) -> String {
+        format!("Hello, {}!", name)
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    // The server side, which a browser could also connect to
+    let listener = WebSocketTransport::bind("127.0.0.1:0".parse().unwrap()).await?;
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let node = Node::new().expose::<Greeter, Greet>("greeter", Greeter.spawn());
+    tokio::spawn(async move { node.listen(&listener).await });
+
+    // The client side
+    let (connection, driver) = Connection::connect(&WebSocketTransport::default(), url).await?;
+    tokio::spawn(driver);
+
+    let greeter = connection.address::<Greeter>("greeter");
+    let greeting = greeter
+        .send(Greet("Bob".to_string()))
+        .await
+        .expect("Greeter should be exposed");
+    println!("{}", greeting);
+
+    Ok(())
+}
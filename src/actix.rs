@@ -0,0 +1,197 @@
+//! A compatibility layer for migrating from [actix](https://docs.rs/actix) one actor at a time.
+//! This module has traits shaped like those of actix, which are implemented in terms of those of
+//! xtra, so an actor ported to them runs as a regular xtra actor, and can be sent messages by and
+//! send messages to actors which were already rewritten for xtra. Porting an actor is mostly a
+//! matter of importing these items instead of those of actix:
+//!
+//! - [`Actor`](trait.Actor.html) has the same `started`, `stopping` and `stopped` methods, with
+//!   `type Context = Context<Self>`, and implements xtra's [`Actor`](../trait.Actor.html).
+//!   `MyActor.start()` spawns it as with [`Actor::spawn`](../trait.Actor.html#method.spawn).
+//! - [`Handler`](trait.Handler.html) handles a message synchronously, like an actix handler, and
+//!   implements xtra's [`SyncHandler`](../trait.SyncHandler.html). Actix handlers which respond
+//!   asynchronously, such as with `ResponseFuture`, are ported by implementing xtra's
+//!   [`Handler`](../trait.Handler.html) instead, which is async.
+//! - [`Message`](../trait.Message.html) is that of xtra, which has the same `Result` type, and
+//!   [`Context`](../struct.Context.html) is that of xtra.
+//! - [`Addr`](type.Addr.html), [`WeakAddr`](type.WeakAddr.html), [`Recipient`](type.Recipient.html)
+//!   and [`WeakRecipient`](type.WeakRecipient.html) are the addresses and message channels of xtra,
+//!   and [`AddrExt`](trait.AddrExt.html) adds `connected` and `recipient` to them. Note that
+//!   `do_send` returns an error rather than dropping the message silently if the actor stopped.
+//!
+//! Actix's `System` and `Arbiter` have no counterpart, since xtra actors are spawned onto the
+//! executor of the runtime in use.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use xtra::actix::{Actor, AddrExt, Context, Handler, Message};
+//!
+//! // The actor as it was written for actix, except for the imports
+//! struct Counter {
+//!     count: usize,
+//! }
+//!
+//! impl Actor for Counter {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! struct Ping(usize);
+//!
+//! impl Message for Ping {
+//!     type Result = usize;
+//! }
+//!
+//! impl Handler<Ping> for Counter {
+//!     type Result = usize;
+//!
+//!     fn handle(&mut self, msg: Ping, _: &mut Context<Self>) -> Self::Result {
+//!         self.count += msg.0;
+//!         self.count
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let addr = Counter { count: 10 }.start();
+//!     assert_eq!(addr.send(Ping(10)).await, Ok(20));
+//!
+//!     // It can be sent messages through a channel by actors which were rewritten for xtra
+//!     let recipient = addr.clone().recipient::<Ping>();
+//!     assert_eq!(recipient.send(Ping(5)).await, Ok(25));
+//!     assert!(addr.connected());
+//! }
+//! ```
+
+use crate::KeepRunning;
+pub use crate::{Context, Message};
+
+/// The address of an actor, as with actix's `Addr`. This is xtra's
+/// [`Address`](../struct.Address.html).
+pub type Addr<A> = crate::Address<A>;
+
+/// A weak address of an actor, as with actix's `WeakAddr`. This is xtra's
+/// [`WeakAddress`](../struct.WeakAddress.html).
+pub type WeakAddr<A> = crate::WeakAddress<A>;
+
+/// A channel through which one type of message is sent to any actor handling it, as with actix's
+/// `Recipient`. This is xtra's [`MessageChannel`](../struct.MessageChannel.html).
+pub type Recipient<M> = crate::MessageChannel<M>;
+
+/// A weak channel through which one type of message is sent, as with actix's `WeakRecipient`. This
+/// is xtra's [`WeakMessageChannel`](../struct.WeakMessageChannel.html).
+pub type WeakRecipient<M> = crate::WeakMessageChannel<M>;
+
+/// Whether an actor should stop, as returned from
+/// [`Actor::stopping`](trait.Actor.html#method.stopping). This is xtra's
+/// [`KeepRunning`](../enum.KeepRunning.html) under the names of actix.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Running {
+    /// Stop the actor
+    Stop,
+    /// Keep the actor running
+    Continue,
+}
+
+impl From<Running> for KeepRunning {
+    fn from(running: Running) -> Self {
+        match running {
+            Running::Stop => KeepRunning::No,
+            Running::Continue => KeepRunning::Yes,
+        }
+    }
+}
+
+/// An actor as written for actix. Every type implementing it with `type Context = Context<Self>`
+/// also implements xtra's [`Actor`](../trait.Actor.html), whose methods call those of this trait.
+pub trait Actor: Send + Sized + 'static {
+    /// The context of the actor, which must be `Context<Self>`.
+    type Context;
+
+    /// Called when the actor is started, as with
+    /// [`Actor::started`](../trait.Actor.html#method.started).
+    #[allow(unused_variables)]
+    fn started(&mut self, ctx: &mut Self::Context) {}
+
+    /// Called when the actor is asked to stop, as with
+    /// [`Actor::stopping`](../trait.Actor.html#method.stopping). By default, it stops.
+    #[allow(unused_variables)]
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        Running::Stop
+    }
+
+    /// Called once the actor has stopped, as with
+    /// [`Actor::stopped`](../trait.Actor.html#method.stopped).
+    #[allow(unused_variables)]
+    fn stopped(&mut self, ctx: &mut Self::Context) {}
+
+    /// Spawn the actor and return its address, as with
+    /// [`Actor::spawn`](../trait.Actor.html#method.spawn).
+    fn start(self) -> Addr<Self>
+    where
+        Self: Actor<Context = Context<Self>>,
+    {
+        crate::Actor::spawn(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl<A: Actor<Context = Context<A>>> crate::Actor for A {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        Actor::started(self, ctx)
+    }
+
+    fn stopping(&mut self, ctx: &mut Context<Self>) -> KeepRunning {
+        Actor::stopping(self, ctx).into()
+    }
+
+    fn stopped(&mut self, ctx: &mut Context<Self>) {
+        Actor::stopped(self, ctx)
+    }
+}
+
+/// A handler of a message as written for actix, which responds synchronously. Every type
+/// implementing it also implements xtra's [`SyncHandler`](../trait.SyncHandler.html).
+pub trait Handler<M: Message>: Actor {
+    /// What the handler responds with, which is converted into the result of the message.
+    type Result: Into<M::Result>;
+
+    /// Handle the message, as with [`SyncHandler::handle`](../trait.SyncHandler.html#tymethod.handle).
+    fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result;
+}
+
+impl<A, M> crate::SyncHandler<M> for A
+where
+    A: Handler<M> + Actor<Context = Context<A>>,
+    M: Message,
+{
+    fn handle(&mut self, msg: M, ctx: &mut Context<Self>) -> M::Result {
+        Handler::handle(self, msg, ctx).into()
+    }
+}
+
+/// The methods of actix's `Addr` which are named differently in xtra.
+pub trait AddrExt<A: crate::Actor> {
+    /// Returns whether the actor is still running, as with
+    /// [`AddressExt::is_connected`](../trait.AddressExt.html#tymethod.is_connected).
+    fn connected(&self) -> bool;
+
+    /// Get a channel through which the message is sent to the actor, as with
+    /// [`Address::into_channel`](../struct.Address.html#method.into_channel).
+    fn recipient<M: Message>(self) -> Recipient<M>
+    where
+        A: crate::Handler<M>;
+}
+
+impl<A: crate::Actor> AddrExt<A> for Addr<A> {
+    fn connected(&self) -> bool {
+        crate::AddressExt::is_connected(self)
+    }
+
+    fn recipient<M: Message>(self) -> Recipient<M>
+    where
+        A: crate::Handler<M>,
+    {
+        self.into_channel()
+    }
+}
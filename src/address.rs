@@ -1,10 +1,23 @@
-use crate::envelope::{NonReturningEnvelope, ReturningEnvelope};
+use crate::envelope::{
+    BatchEnvelope, ConflatingEnvelope, NonReturningEnvelope, ReadOnlyEnvelope, ReturningEnvelope,
+};
+use crate::mailbox::{Disconnect, PushError, Sender};
 use crate::manager::ManagerMessage;
+use crate::response::{Canceled, Receiver};
 use crate::*;
-use futures::channel::mpsc::UnboundedSender;
-use futures::channel::oneshot::Receiver;
+use futures::future::{self, BoxFuture, Either};
 use futures::task::{Context, Poll};
-use futures::{Future, Sink};
+use futures::{Future, FutureExt, Sink, SinkExt, Stream, StreamExt};
+use std::any::TypeId;
+use std::cmp;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
 #[cfg(any(
     doc,
     feature = "with-tokio-0_2",
@@ -12,58 +25,715 @@ use futures::{Future, Sink};
     feature = "with-wasm_bindgen-0_2",
     feature = "with-smol-0_1"
 ))]
-use futures::{Stream, StreamExt};
-use std::pin::Pin;
-use std::sync::{Arc, Weak};
-use std::error::Error;
-use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
 
 /// The future returned by a method such as [`AddressExt::send`](trait.AddressExt.html#method.send).
-/// It resolves to `Result<M::Result, Disconnected>`.
-pub struct MessageResponseFuture<M: Message>(MessageResponseFutureInner<M>);
+/// It resolves to `Result<M::Result, SendError>`.
+pub struct MessageResponseFuture<M: Message> {
+    inner: MessageResponseFutureInner<M>,
+    /// Resolves once the response has taken too long, if there is a timeout
+    timeout: Option<BoxFuture<'static, ()>>,
+    /// Why the actor's mailbox was closed, to tell why the message was dropped if it was
+    disconnect: Option<Disconnect>,
+    /// The actor which the response will come from, until the future is first polled
+    #[cfg(feature = "deadlock-detection")]
+    responder: Option<ActorId>,
+    /// Marks the actor polling this future as waiting on the responder
+    #[cfg(feature = "deadlock-detection")]
+    waiting: Option<crate::deadlock::Waiting>,
+}
 
 impl<M: Message> MessageResponseFuture<M> {
-    fn result(res: Receiver<M::Result>) -> Self {
-        MessageResponseFuture(MessageResponseFutureInner::Result(res))
+    fn new(inner: MessageResponseFutureInner<M>) -> Self {
+        MessageResponseFuture {
+            inner,
+            timeout: None,
+            disconnect: None,
+            #[cfg(feature = "deadlock-detection")]
+            responder: None,
+            #[cfg(feature = "deadlock-detection")]
+            waiting: None,
+        }
+    }
+
+    /// Record that the response will come from the actor owning the mailbox of `sender`, so that
+    /// it can tell why the message was dropped, and so that an actor waiting on it can be checked
+    /// for deadlocks.
+    pub(crate) fn responded_by<T>(mut self, sender: &Sender<T>) -> Self {
+        self.disconnect = Some(sender.disconnect());
+        #[cfg(feature = "deadlock-detection")]
+        {
+            self.responder = Some(sender.id());
+        }
+        self
+    }
+
+    /// Make this future resolve to `SendError::Timeout` if the response has not arrived within the
+    /// given duration.
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    pub(crate) fn with_timeout(mut self, duration: Duration) -> Self {
+        self.timeout = Some(crate::runtime::delay(duration).boxed());
+        self
+    }
+
+    pub(crate) fn result(res: Receiver<M::Result>) -> Self {
+        MessageResponseFuture::new(MessageResponseFutureInner::Result(res))
+    }
+
+    pub(crate) fn disconnected(reason: DisconnectReason) -> Self {
+        MessageResponseFuture::rejected(SendError::Disconnected(reason))
+    }
+
+    pub(crate) fn rejected(err: SendError) -> Self {
+        MessageResponseFuture::new(MessageResponseFutureInner::Error(err))
     }
 
-    fn disconnected() -> Self {
-        MessageResponseFuture(MessageResponseFutureInner::Disconnected)
+    pub(crate) fn unsent(err: TrySendError<M>) -> Self {
+        MessageResponseFuture::new(MessageResponseFutureInner::Unsent(err))
+    }
+
+    /// Get the message back if it was not put into the actor's mailbox, because the actor was
+    /// already disconnected, or because its mailbox was full and its overflow policy is
+    /// [`OverflowPolicy::Error`](enum.OverflowPolicy.html#variant.Error). Otherwise, this future is
+    /// handed back to wait for the response. Once the message is in the mailbox, or waiting for
+    /// space in it, it can no longer be handed back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::TrySendError;
+    /// struct Printer;
+    /// impl Actor for Printer {}
+    ///
+    /// struct Print(String);
+    /// impl Message for Print {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Print> for Printer {
+    ///     fn handle(&mut self, Print(s): Print, _: &mut Context<Self>) {
+    ///         println!("{}", s);
+    ///     }
+    /// }
+    ///
+    /// let (addr, mgr) = Printer.create();
+    /// drop(mgr); // The actor will never run
+    ///
+    /// match addr.send(Print("hello".to_string())).sent() {
+    ///     Err(TrySendError::Disconnected(Print(s), _)) => assert_eq!(s, "hello"),
+    ///     _ => panic!("the actor should be disconnected"),
+    /// }
+    /// ```
+    pub fn sent(self) -> Result<Self, TrySendError<M>> {
+        match self.inner {
+            MessageResponseFutureInner::Unsent(err) => Err(err),
+            inner => Ok(MessageResponseFuture { inner, ..self }),
+        }
+    }
+
+    /// Create a response future from the result of trying to enqueue the message
+    fn enqueued(enqueued: Enqueued, res: Receiver<M::Result>) -> Self {
+        match enqueued {
+            Enqueued::Waiting(fut) => {
+                MessageResponseFuture::new(MessageResponseFutureInner::Sending(fut, res))
+            }
+            Enqueued::Rejected(err) => {
+                MessageResponseFuture::new(MessageResponseFutureInner::Error(err))
+            }
+            // If the actor was disconnected or the message was dropped, the envelope has been dropped
+            // with the sender half of the response channel, so `res` will resolve to `Disconnected`
+            // with the reason taken from the mailbox
+            Enqueued::Sent => MessageResponseFuture::result(res),
+        }
     }
 }
 
 enum MessageResponseFutureInner<M: Message> {
-    Disconnected,
+    Error(SendError),
+    /// The mailbox refused the message straight away, so it can still be handed back
+    Unsent(TrySendError<M>),
+    /// Waiting for space in the actor's mailbox to send the message
+    Sending(
+        BoxFuture<'static, Result<(), SendError>>,
+        Receiver<M::Result>,
+    ),
     Result(Receiver<M::Result>),
 }
 
+// The message is only ever moved out of the future, never pinned within it
+impl<M: Message> Unpin for MessageResponseFuture<M> {}
+
 impl<M: Message> Future for MessageResponseFuture<M> {
-    type Output = Result<M::Result, Disconnected>;
+    type Output = Result<M::Result, SendError>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut futures::task::Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Poll::Ready(res) = this.poll_response(ctx) {
+            #[cfg(feature = "deadlock-detection")]
+            {
+                this.waiting = None;
+            }
+            return Poll::Ready(res);
+        }
+
+        #[cfg(feature = "deadlock-detection")]
+        if let Some(responder) = this.responder.take() {
+            this.waiting = crate::deadlock::wait(responder);
+        }
+
+        let timed_out = match &mut this.timeout {
+            Some(timeout) => timeout.as_mut().poll(ctx).is_ready(),
+            None => false,
+        };
+
+        if timed_out {
+            #[cfg(feature = "deadlock-detection")]
+            {
+                this.waiting = None;
+            }
+            this.timeout = None;
+            this.inner = MessageResponseFutureInner::Error(SendError::Timeout);
+            Poll::Ready(Err(SendError::Timeout))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<M: Message> MessageResponseFuture<M> {
+    fn poll_response(
+        &mut self,
+        ctx: &mut futures::task::Context,
+    ) -> Poll<Result<M::Result, SendError>> {
+        loop {
+            match &mut self.inner {
+                MessageResponseFutureInner::Error(err) => return Poll::Ready(Err(err.clone())),
+                MessageResponseFutureInner::Unsent(err) => {
+                    return Poll::Ready(Err(match err {
+                        TrySendError::Full(_) => SendError::MailboxFull,
+                        TrySendError::Disconnected(_, reason) => SendError::Disconnected(*reason),
+                    }))
+                }
+                MessageResponseFutureInner::Sending(fut, _) => match fut.as_mut().poll(ctx) {
+                    Poll::Ready(Ok(())) => {
+                        // Only a placeholder, which is replaced straight away
+                        let err = MessageResponseFutureInner::Error(SendError::Timeout);
+                        if let MessageResponseFutureInner::Sending(_, rx) =
+                            mem::replace(&mut self.inner, err)
+                        {
+                            self.inner = MessageResponseFutureInner::Result(rx);
+                        }
+                    }
+                    Poll::Ready(Err(err)) => self.inner = MessageResponseFutureInner::Error(err),
+                    Poll::Pending => return Poll::Pending,
+                },
+                MessageResponseFutureInner::Result(rx) => {
+                    let rx = Pin::new(rx);
+                    let disconnect = &self.disconnect;
+                    return rx.poll(ctx).map(|res| {
+                        res.map_err(|canceled| match canceled {
+                            Canceled::Duplicate => SendError::Duplicate,
+                            Canceled::TimedOut => SendError::Timeout,
+                            Canceled::Dropped => {
+                                // If the mailbox is still open, the actor dropped only this message
+                                let reason = disconnect.as_ref().and_then(Disconnect::get);
+                                SendError::Disconnected(
+                                    reason.unwrap_or(DisconnectReason::MessageDropped),
+                                )
+                            }
+                        })
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The future returned by [`AddressExt::do_send_async`](trait.AddressExt.html#method.do_send_async).
+/// It resolves to `Result<(), SendError>` once the message has been put into the actor's mailbox.
+pub struct DoSendFuture(Enqueued);
+
+impl DoSendFuture {
+    pub(crate) fn disconnected(reason: DisconnectReason) -> Self {
+        DoSendFuture(Enqueued::Rejected(SendError::Disconnected(reason)))
+    }
+
+    #[cfg(feature = "testing")]
+    pub(crate) fn sent() -> Self {
+        DoSendFuture(Enqueued::Sent)
+    }
+}
+
+impl Future for DoSendFuture {
+    type Output = Result<(), SendError>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut futures::task::Context) -> Poll<Self::Output> {
         match &mut self.get_mut().0 {
-            MessageResponseFutureInner::Disconnected => Poll::Ready(Err(Disconnected)),
-            MessageResponseFutureInner::Result(rx) => {
-                let rx = Pin::new(rx);
-                rx.poll(ctx).map(|res| res.map_err(|_| Disconnected))
+            Enqueued::Sent => Poll::Ready(Ok(())),
+            Enqueued::Rejected(err) => Poll::Ready(Err(err.clone())),
+            Enqueued::Waiting(fut) => fut.as_mut().poll(ctx),
+        }
+    }
+}
+
+/// The result of trying to put a message into an actor's mailbox
+enum Enqueued {
+    /// The message was sent, or dropped according to the mailbox's overflow policy
+    Sent,
+    Rejected(SendError),
+    /// The mailbox is bounded and full, so the message is waiting to be sent
+    Waiting(BoxFuture<'static, Result<(), SendError>>),
+}
+
+/// Why a mailbox which refused a message because it was closed was closed. The reason is recorded
+/// before the mailbox is marked as closed, so it is always there by then.
+fn closed_reason<T>(sender: &Sender<T>) -> DisconnectReason {
+    sender
+        .disconnect_reason()
+        .unwrap_or(DisconnectReason::Stopped)
+}
+
+/// Send every message of the stream into the sink of an address, waiting for the sink to be ready
+/// before each one, until either the stream ends or the actor's mailbox is closed.
+fn forward<A, K, M, S>(
+    mut sink: K,
+    sender: &Sender<ManagerMessage<A>>,
+    stream: S,
+) -> impl Future<Output = Result<(), SendError>> + Send + 'static
+where
+    A: Actor,
+    K: Sink<M, Error = SendError> + Send + Unpin + 'static,
+    M: Message,
+    S: Stream<Item = M> + Send + 'static,
+{
+    let mut closed = Box::pin(sender.closed());
+    let sender = sender.clone();
+
+    async move {
+        let mut stream = Box::pin(stream);
+        loop {
+            // Stop waiting for the next message as soon as the mailbox closes
+            match future::select(stream.next(), closed.as_mut()).await {
+                Either::Left((Some(message), _)) => sink.feed(message).await?,
+                Either::Left((None, _)) => return Ok(()),
+                Either::Right(_) => return Err(SendError::Disconnected(closed_reason(&sender))),
             }
         }
     }
 }
 
-/// The actor is no longer running and disconnected from the sending address. For why this could
-/// occur, see the [`Actor::stopping`](trait.Actor.html#method.stopping) and
-/// [`Actor::stopped`](trait.Actor.html#method.stopped) methods.
+/// Send all of the messages to the actor, putting as many as there is space for into its mailbox at
+/// once, and then the rest one after another as with `AddressExt::send`, and wait for all of their
+/// responses.
+fn send_batch<A, M, I>(
+    sender: &Sender<ManagerMessage<A>>,
+    messages: I,
+) -> impl Future<Output = Vec<Result<M::Result, SendError>>> + Send + 'static
+where
+    A: Handler<M>,
+    M: Message,
+    I: IntoIterator<Item = M>,
+{
+    let mut envelopes = VecDeque::new();
+    let mut responses: Vec<MessageResponseFuture<M>> = Vec::new();
+    for message in messages {
+        let (envelope, rx) = ReturningEnvelope::<A, M>::new(message);
+        envelopes.push_back(ManagerMessage::Message(pool::boxed(envelope)));
+        responses.push(MessageResponseFuture::result(rx).responded_by(sender));
+    }
+
+    // If the mailbox is closed, the envelopes are dropped here, so the responses resolve to why
+    let _ = sender.try_send_many(&mut envelopes, M::PRIORITY);
+    let sent = responses.len() - envelopes.len();
+    let sender = sender.clone();
+
+    async move {
+        // The messages which did not fit are sent in order, each once the one before it was sent
+        for (envelope, response) in envelopes.into_iter().zip(&mut responses[sent..]) {
+            let res = match enqueue(&sender, envelope, M::PRIORITY) {
+                Enqueued::Sent => Ok(()),
+                Enqueued::Rejected(err) => Err(err),
+                Enqueued::Waiting(fut) => fut.await,
+            };
+            if let Err(err) = res {
+                *response = MessageResponseFuture::new(MessageResponseFutureInner::Error(err));
+            }
+        }
+
+        future::join_all(responses).await
+    }
+}
+
+/// How many of the strong and weak references to an actor are held by its context, which are not
+/// counted by `strong_count` and `weak_count`. The context is dropped along with the receiver.
+fn context_refs<T>(sender: &Sender<T>) -> usize {
+    if sender.is_receiver_dropped() {
+        0
+    } else {
+        1
+    }
+}
+
+/// Put a message into the mailbox if there is space. Otherwise, act according to the mailbox's
+/// overflow policy, which could mean returning a future which waits for space to become available
+/// before sending it.
+fn enqueue<A: Actor>(
+    sender: &Sender<ManagerMessage<A>>,
+    msg: ManagerMessage<A>,
+    priority: Priority,
+) -> Enqueued {
+    match sender.try_send(msg, priority) {
+        Ok(()) => Enqueued::Sent,
+        Err(PushError::Closed(_)) => {
+            Enqueued::Rejected(SendError::Disconnected(closed_reason(sender)))
+        }
+        // The mailbox drops messages itself if the policy says so, so the policy must be to either
+        // block or return an error here
+        Err(PushError::Full(msg)) => match sender.overflow_policy() {
+            OverflowPolicy::Error => Enqueued::Rejected(SendError::MailboxFull),
+            _ => {
+                let disconnect = sender.disconnect();
+                let fut = sender.send_async(msg, priority).map(move |res| {
+                    res.map_err(|_| {
+                        let reason = disconnect.get().unwrap_or(DisconnectReason::Stopped);
+                        SendError::Disconnected(reason)
+                    })
+                });
+                Enqueued::Waiting(fut.boxed())
+            }
+        },
+    }
+}
+
+/// Put a message which is waited on into the mailbox in the same way as `enqueue`, with `envelope`
+/// creating its envelope and response channel. If the mailbox refuses the message straight away,
+/// it is kept in the response future, to be handed back by `MessageResponseFuture::sent`.
+fn enqueue_request<A, M, E>(
+    sender: &Sender<ManagerMessage<A>>,
+    message: M,
+    envelope: E,
+) -> MessageResponseFuture<M>
+where
+    A: Actor,
+    M: Message,
+    E: Fn(M) -> (ManagerMessage<A>, Receiver<M::Result>),
+{
+    let mut res = None;
+    let wrap = |message| {
+        let (envelope, rx) = envelope(message);
+        res = Some(rx);
+        envelope
+    };
+    let response = match sender.try_send_with(message, wrap, M::PRIORITY) {
+        Ok(()) => match res {
+            Some(rx) => MessageResponseFuture::result(rx),
+            // The mailbox was full and dropped the message according to its overflow policy
+            None => MessageResponseFuture::disconnected(DisconnectReason::MessageDropped),
+        },
+        Err(PushError::Closed(message)) => MessageResponseFuture::unsent(
+            TrySendError::Disconnected(message, closed_reason(sender)),
+        ),
+        Err(PushError::Full(message)) => match sender.overflow_policy() {
+            OverflowPolicy::Error => MessageResponseFuture::unsent(TrySendError::Full(message)),
+            _ => {
+                let (envelope, rx) = envelope(message);
+                MessageResponseFuture::enqueued(enqueue(sender, envelope, M::PRIORITY), rx)
+            }
+        },
+    };
+    response.responded_by(sender)
+}
+
+/// Put a message into the mailbox without waiting, even if the overflow policy is to block. If
+/// this fails, the message is handed back.
+fn enqueue_now<A, M>(sender: &Sender<ManagerMessage<A>>, message: M) -> Result<(), TrySendError<M>>
+where
+    A: Handler<M>,
+    M: Message,
+{
+    enqueue_now_with(sender, message, |message| {
+        ManagerMessage::Message(pool::boxed(NonReturningEnvelope::<A, M>::new(message)))
+    })
+}
+
+/// Like `enqueue_now`, but the message is put into an envelope with `wrap`.
+fn enqueue_now_with<A, M, F>(
+    sender: &Sender<ManagerMessage<A>>,
+    message: M,
+    wrap: F,
+) -> Result<(), TrySendError<M>>
+where
+    A: Actor,
+    M: Message,
+    F: Fn(M) -> ManagerMessage<A> + Copy,
+{
+    match sender.try_send_with(message, wrap, M::PRIORITY) {
+        Ok(()) => Ok(()),
+        Err(PushError::Closed(message)) => {
+            Err(TrySendError::Disconnected(message, closed_reason(sender)))
+        }
+        Err(PushError::Full(message)) => match sender.overflow_policy() {
+            OverflowPolicy::Error => Err(TrySendError::Full(message)),
+            _ => sender
+                .force_send_with(message, wrap, M::PRIORITY)
+                .map_err(|message| TrySendError::Disconnected(message, closed_reason(sender))),
+        },
+    }
+}
+
+/// Put a message to be handled by a `BatchHandler` into the mailbox in the same way as `enqueue_now`.
+fn enqueue_batched<A, M>(
+    sender: &Sender<ManagerMessage<A>>,
+    message: M,
+) -> Result<(), TrySendError<M>>
+where
+    A: BatchHandler<M>,
+    M: Message<Result = ()>,
+{
+    enqueue_now_with(sender, message, |message| {
+        ManagerMessage::Message(Box::new(BatchEnvelope::<A, M>::new(message)))
+    })
+}
+
+/// Put a message into the mailbox in the place of a message of the same type sent in the same way
+/// which is still queued, or otherwise in the same way as `enqueue_now`.
+fn enqueue_conflated<A, M>(
+    sender: &Sender<ManagerMessage<A>>,
+    message: M,
+) -> Result<(), TrySendError<M>>
+where
+    A: Handler<M>,
+    M: Message,
+{
+    let wrap =
+        |message| ManagerMessage::Message(Box::new(ConflatingEnvelope::<A, M>::new(message)));
+    let matches = |queued: &ManagerMessage<A>| match queued {
+        ManagerMessage::Message(envelope) => envelope.conflation_type() == Some(TypeId::of::<M>()),
+        _ => false,
+    };
+    match sender.replace_or_send_with(message, wrap, M::PRIORITY, matches) {
+        Ok(()) => Ok(()),
+        Err(PushError::Closed(message)) => {
+            Err(TrySendError::Disconnected(message, closed_reason(sender)))
+        }
+        Err(PushError::Full(message)) => match sender.overflow_policy() {
+            OverflowPolicy::Error => Err(TrySendError::Full(message)),
+            _ => sender
+                .force_send_with(message, wrap, M::PRIORITY)
+                .map_err(|message| TrySendError::Disconnected(message, closed_reason(sender))),
+        },
+    }
+}
+
+/// Put a message into the mailbox only if it can be done without waiting, handing the message back
+/// otherwise.
+fn enqueue_or_return<A, M>(
+    sender: &Sender<ManagerMessage<A>>,
+    message: M,
+) -> Result<(), TrySendError<M>>
+where
+    A: Handler<M>,
+    M: Message,
+{
+    let wrap =
+        |message| ManagerMessage::Message(pool::boxed(NonReturningEnvelope::<A, M>::new(message)));
+    match sender.try_send_with(message, wrap, M::PRIORITY) {
+        Ok(()) => Ok(()),
+        Err(PushError::Closed(message)) => {
+            Err(TrySendError::Disconnected(message, closed_reason(sender)))
+        }
+        Err(PushError::Full(message)) => Err(TrySendError::Full(message)),
+    }
+}
+
+/// Why a message could not be delivered to an actor which is disconnected from its addresses.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::{DisconnectReason, SendError};
+/// # struct MyActor;
+/// # impl Actor for MyActor {}
+/// # struct Msg;
+/// # impl Message for Msg {
+/// #    type Result = ();
+/// # }
+/// # impl SyncHandler<Msg> for MyActor {
+/// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+/// # }
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, _) = MyActor.create(); // Oops, the manager is dropped straight away
+///     let res = addr.send(Msg).await;
+///     assert_eq!(res, Err(SendError::Disconnected(DisconnectReason::NeverStarted)));
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DisconnectReason {
+    /// The actor stopped. It either stopped itself (see
+    /// [`Context::stop`](struct.Context.html#method.stop) and
+    /// [`Actor::stopping`](trait.Actor.html#method.stopping)), was stopped gracefully, decided to
+    /// stop after one of its handlers panicked, or its manage loop was dropped.
+    Stopped,
+    /// All strong addresses to the actor were dropped, so it stopped. Only weak addresses and
+    /// message channels can still try to send to it.
+    AllAddressesDropped,
+    /// The actor's [`ActorManager`](struct.ActorManager.html) was dropped without its manage loop
+    /// ever being started, which usually means that the actor was created but never spawned.
+    NeverStarted,
+    /// The actor is still running, but the message was dropped before the actor responded to it,
+    /// such as because its handler panicked or it was pushed out of a full mailbox.
+    MessageDropped,
+}
+
+impl Display for DisconnectReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DisconnectReason::Stopped => f.write_str("the actor stopped"),
+            DisconnectReason::AllAddressesDropped => {
+                f.write_str("all strong addresses to the actor were dropped")
+            }
+            DisconnectReason::NeverStarted => {
+                f.write_str("the actor's manager was dropped without ever being started")
+            }
+            DisconnectReason::MessageDropped => f.write_str("the message was dropped"),
+        }
+    }
+}
+
+impl From<ExitReason> for DisconnectReason {
+    fn from(reason: ExitReason) -> Self {
+        match reason {
+            ExitReason::AllAddressesDropped => DisconnectReason::AllAddressesDropped,
+            ExitReason::Stopped | ExitReason::Panicked | ExitReason::MailboxClosed => {
+                DisconnectReason::Stopped
+            }
+        }
+    }
+}
+
+/// An error which can occur when sending a message to an actor. Sending may fail in more ways in
+/// later versions, so matches on it need a wildcard arm.
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub struct Disconnected;
+#[non_exhaustive]
+pub enum SendError {
+    /// The actor is no longer running and disconnected from the sending address, for the given
+    /// reason, or the message was dropped before the actor responded to it. For why the actor could
+    /// stop, see the [`Actor::stopping`](trait.Actor.html#method.stopping) and
+    /// [`Actor::stopped`](trait.Actor.html#method.stopped) methods.
+    Disconnected(DisconnectReason),
+    /// The actor's mailbox is full, and its overflow policy is
+    /// [`OverflowPolicy::Error`](enum.OverflowPolicy.html#variant.Error).
+    MailboxFull,
+    /// The actor did not respond in time to a message sent with
+    /// [`AddressExt::send_timeout`](trait.AddressExt.html#method.send_timeout) or
+    /// [`MessageChannelExt::send_timeout`](trait.MessageChannelExt.html#method.send_timeout), or
+    /// the handler of the message was dropped since it ran for longer than the actor's handler
+    /// timeout (see [`ActorManager::handler_timeout`](struct.ActorManager.html#method.handler_timeout)).
+    Timeout,
+    /// The message was not handled, since the actor had already handled a message of the same
+    /// type with the same [`Message::message_id`](trait.Message.html#method.message_id) within its
+    /// dedup window.
+    Duplicate,
+}
 
-impl Display for Disconnected {
+impl Display for SendError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str("Actor address disconnected")
+        match self {
+            SendError::Disconnected(reason) => {
+                write!(f, "Actor address disconnected: {}", reason)
+            }
+            SendError::MailboxFull => f.write_str("Actor mailbox full"),
+            SendError::Timeout => f.write_str("Timed out waiting for the actor's response"),
+            SendError::Duplicate => f.write_str("Message was already handled by the actor"),
+        }
     }
 }
 
-impl Error for Disconnected {}
+impl Error for SendError {}
+
+/// An error returned by [`AddressExt::try_send`](trait.AddressExt.html#method.try_send) when the
+/// message could not be sent right away. It contains the message, so that it can be sent again
+/// later or elsewhere. Sending may fail in more ways in later versions, so matches on it need a
+/// wildcard arm.
+#[derive(Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TrySendError<M> {
+    /// The actor's mailbox is bounded and full.
+    Full(M),
+    /// The actor is no longer running and disconnected from the sending address, for the given
+    /// reason.
+    Disconnected(M, DisconnectReason),
+}
+
+impl<M> TrySendError<M> {
+    /// Get back the message which could not be sent.
+    pub fn into_inner(self) -> M {
+        match self {
+            TrySendError::Full(message) | TrySendError::Disconnected(message, _) => message,
+        }
+    }
+
+    /// Map the message which could not be sent, keeping the reason that it could not be sent.
+    pub fn map<N, F: FnOnce(M) -> N>(self, f: F) -> TrySendError<N> {
+        match self {
+            TrySendError::Full(message) => TrySendError::Full(f(message)),
+            TrySendError::Disconnected(message, reason) => {
+                TrySendError::Disconnected(f(message), reason)
+            }
+        }
+    }
+
+    /// Returns whether the message could not be sent because the actor's mailbox is full.
+    pub fn is_full(&self) -> bool {
+        matches!(self, TrySendError::Full(_))
+    }
+
+    /// Returns whether the message could not be sent because the actor is disconnected.
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self, TrySendError::Disconnected(..))
+    }
+}
+
+// Implemented by hand so that the message does not have to be `Debug`
+impl<M> fmt::Debug for TrySendError<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(_, reason) => write!(f, "Disconnected(.., {:?})", reason),
+        }
+    }
+}
+
+impl<M> Display for TrySendError<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("Actor mailbox full"),
+            TrySendError::Disconnected(_, reason) => {
+                write!(f, "Actor address disconnected: {}", reason)
+            }
+        }
+    }
+}
+
+impl<M> Error for TrySendError<M> {}
+
+impl<M> From<TrySendError<M>> for SendError {
+    fn from(err: TrySendError<M>) -> Self {
+        match err {
+            TrySendError::Full(_) => SendError::MailboxFull,
+            TrySendError::Disconnected(_, reason) => SendError::Disconnected(reason),
+        }
+    }
+}
 
 /// General trait for any kind of address to an actor, be it strong or weak. This trait contains all
 /// functions of the address.
@@ -99,23 +769,317 @@ pub trait AddressExt<A: Actor> {
     /// ```
     fn is_connected(&self) -> bool;
 
+    /// The number of messages waiting in the actor's mailbox. This is only a snapshot, as the actor
+    /// may be handling messages at the same time, and it also counts any internal messages (such as
+    /// notifications that an address was dropped), so it should be treated as approximate.
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {}
+    /// # struct Msg;
+    /// # impl Message for Msg {
+    /// #    type Result = ();
+    /// # }
+    /// # impl SyncHandler<Msg> for MyActor {
+    /// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+    /// # }
+    /// let (addr, _mgr) = MyActor.create_bounded(4);
+    /// addr.do_send(Msg).unwrap();
+    /// addr.do_send(Msg).unwrap();
+    ///
+    /// assert_eq!(addr.len(), 2);
+    /// assert_eq!(addr.capacity(), Some(4));
+    /// ```
+    fn len(&self) -> usize;
+
+    /// Returns whether the actor's mailbox is empty. See
+    /// [`AddressExt::len`](trait.AddressExt.html#method.len).
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The capacity of the actor's mailbox, or `None` if it is unbounded.
+    fn capacity(&self) -> Option<usize>;
+
+    /// Stop the actor once it has handled all messages which are already in its mailbox. Like
+    /// [`Context::stop_gracefully`](struct.Context.html#method.stop_gracefully), the actor stops
+    /// accepting new messages right away, so sending to it will return
+    /// [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected) from now on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// # use smol::Timer;
+    /// struct Printer;
+    /// impl Actor for Printer {}
+    ///
+    /// struct Print(&'static str);
+    /// impl Message for Print {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Print> for Printer {
+    ///     fn handle(&mut self, Print(s): Print, _: &mut Context<Self>) {
+    ///         println!("{}", s);
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Printer.spawn();
+    ///     let queued = addr.send(Print("still printed"));
+    ///     addr.stop_gracefully();
+    ///     assert!(addr.do_send(Print("rejected")).is_err());
+    ///     assert_eq!(queued.await, Ok(()));
+    ///     # Timer::after(Duration::from_millis(10)).await;
+    ///     # assert!(!addr.is_connected());
+    /// }
+    /// ```
+    fn stop_gracefully(&self);
+
     /// Sends a [`Message`](trait.Message.html) to the actor, and does not wait for a response.
-    /// If this returns `Err(Disconnected)`, then the actor is stopped and not accepting messages.
+    /// If this returns `Err(TrySendError::Disconnected(message, _))`, then the actor is stopped and not
+    /// accepting messages, and the message is handed back so that it can be sent elsewhere.
     /// If this returns `Ok(())`, the will be delivered, but may not be handled in the event that the
     /// actor stops itself (by calling [`Context::stop`](struct.Context.html#method.stop))
     /// before it was handled.
-    fn do_send<M>(&self, message: M) -> Result<(), Disconnected>
+    ///
+    /// **Note:** this method never waits, so if the actor's mailbox is bounded and full and its
+    /// overflow policy is [`OverflowPolicy::Block`](enum.OverflowPolicy.html#variant.Block), the
+    /// message will be put into the mailbox regardless, past its capacity (see
+    /// [`MailboxConfig`](struct.MailboxConfig.html)). To wait for space instead, use
+    /// [`AddressExt::do_send_async`](trait.AddressExt.html#method.do_send_async), or to get the
+    /// message back if there is no space, use [`AddressExt::try_send`](trait.AddressExt.html#method.try_send).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {}
+    /// # struct Msg;
+    /// # impl Message for Msg {
+    /// #     type Result = ();
+    /// # }
+    /// # impl SyncHandler<Msg> for MyActor {
+    /// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+    /// # }
+    /// let (addr, _mgr) = MyActor.create_bounded(1);
+    /// addr.do_send(Msg).unwrap();
+    /// assert!(addr.try_send(Msg).unwrap_err().is_full());
+    ///
+    /// // Bounded mailboxes block by default, but `do_send` cannot wait, so it goes past the capacity
+    /// addr.do_send(Msg).unwrap();
+    /// assert_eq!(addr.len(), 2);
+    /// ```
+    fn do_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>;
+
+    /// Sends a [`Message`](trait.Message.html) to the actor to be handled in a batch with the
+    /// messages of the same type queued right before or after it (see
+    /// [`BatchHandler`](trait.BatchHandler.html)), and does not wait for a response. Like
+    /// [`AddressExt::do_send`](trait.AddressExt.html#method.do_send), this does not wait for space
+    /// in the actor's mailbox either.
+    fn do_send_batched<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message<Result = ()>,
+        A: BatchHandler<M>;
+
+    /// Sends a [`Message`](trait.Message.html) to the actor in the same way as
+    /// [`AddressExt::do_send`](trait.AddressExt.html#method.do_send), but if a message of the same
+    /// type which was sent with this method is still waiting in the actor's mailbox, it is replaced
+    /// by this one (which takes its place in the queue). This suits messages which represent the
+    /// latest state of something, such as a resize event or a price, where the actor only needs to
+    /// see the most recent one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// #[derive(Default)]
+    /// struct Display(Vec<u32>);
+    /// impl Actor for Display {}
+    ///
+    /// struct Resize(u32);
+    /// impl Message for Resize {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Resize> for Display {
+    ///     fn handle(&mut self, Resize(width): Resize, _: &mut Context<Self>) {
+    ///         self.0.push(width);
+    ///     }
+    /// }
+    /// # struct Widths;
+    /// # impl Message for Widths {
+    /// #     type Result = Vec<u32>;
+    /// # }
+    /// # impl SyncHandler<Widths> for Display {
+    /// #     fn handle(&mut self, _: Widths, _: &mut Context<Self>) -> Vec<u32> {
+    /// #         self.0.clone()
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = Display::default().create();
+    ///     for width in 1..=3 {
+    ///         addr.do_send_conflated(Resize(width)).unwrap();
+    ///     }
+    ///
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///     assert_eq!(addr.send(Widths).await, Ok(vec![3])); // Only the latest size was handled
+    /// }
+    /// ```
+    fn do_send_conflated<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>;
+
+    /// Sends a [`Message`](trait.Message.html) to the actor if that can be done without waiting,
+    /// and does not wait for a response. Unlike [`AddressExt::do_send`](trait.AddressExt.html#method.do_send),
+    /// if the actor's mailbox is bounded and full, this returns `Err(TrySendError::Full(message))`
+    /// regardless of its overflow policy, unless the policy is to drop a message. If the actor is
+    /// stopped, this returns `Err(TrySendError::Disconnected(message, _))`. Either way, the message
+    /// is handed back so that it can be retried later or sent elsewhere.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::TrySendError;
+    /// struct Printer;
+    /// impl Actor for Printer {}
+    ///
+    /// struct Print(&'static str);
+    /// impl Message for Print {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Print> for Printer {
+    ///     fn handle(&mut self, Print(s): Print, _: &mut Context<Self>) {
+    ///         println!("{}", s);
+    ///     }
+    /// }
+    ///
+    /// let (addr, _mgr) = Printer.create_bounded(1);
+    /// assert!(addr.try_send(Print("hello")).is_ok());
+    ///
+    /// match addr.try_send(Print("world")) {
+    ///     Err(TrySendError::Full(Print(s))) => assert_eq!(s, "world"),
+    ///     _ => panic!("the mailbox should be full"),
+    /// }
+    /// ```
+    fn try_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>;
+
+    /// Sends a [`Message`](trait.Message.html) to the actor, and does not wait for a response, but
+    /// does wait for there to be space in the actor's mailbox if it is bounded and full (and its
+    /// overflow policy is to block). If this resolves to `Err(SendError::Disconnected(_))`, then the
+    /// actor is stopped and not accepting messages.
+    fn do_send_async<M>(&self, message: M) -> DoSendFuture
     where
         M: Message,
         A: Handler<M>;
 
     /// Sends a [`Message`](trait.Message.html) to the actor, and waits for a response. If this
-    /// returns `Err(Disconnected)`, then the actor is stopped and not accepting messages.
+    /// returns `Err(SendError::Disconnected(_))`, then the actor is stopped and not accepting messages. If the
+    /// actor's mailbox is bounded and full, this will act according to its
+    /// [`OverflowPolicy`](enum.OverflowPolicy.html), which by default is to wait for space to
+    /// become available. If the mailbox refuses the message straight away, such as because the
+    /// actor is already disconnected, the message is kept in the returned future, and
+    /// [`MessageResponseFuture::sent`](struct.MessageResponseFuture.html#method.sent) hands it back
+    /// as `Err(TrySendError::Disconnected(message, _))`, like `do_send` does. If the returned
+    /// future is dropped before the actor gets to the message, the message is not handled (see
+    /// [`Context::is_cancelled`](struct.Context.html#method.is_cancelled)).
     fn send<M>(&self, message: M) -> MessageResponseFuture<M>
     where
         M: Message,
         A: Handler<M>;
 
+    /// Sends a [`Message`](trait.Message.html) to be handled by a
+    /// [`ReadHandler`](trait.ReadHandler.html), and waits for a response. This behaves like
+    /// [`AddressExt::send`](trait.AddressExt.html#method.send), except that the message is handled
+    /// concurrently with the read-only messages queued right before or after it.
+    fn send_read<M>(&self, message: M) -> MessageResponseFuture<M>
+    where
+        M: Message,
+        A: ReadHandler<M>;
+
+    /// Like [`AddressExt::send`](trait.AddressExt.html#method.send), but resolves to
+    /// `Err(SendError::Timeout)` if the response has not arrived within the given duration
+    /// (including any time spent waiting for space in the actor's mailbox). The message may still
+    /// be handled after the timeout has elapsed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::SendError;
+    /// # use std::time::Duration;
+    /// struct Slow;
+    /// impl Actor for Slow {}
+    ///
+    /// struct Work;
+    /// impl Message for Work {
+    ///     type Result = ();
+    /// }
+    ///
+    /// #[async_trait::async_trait]
+    /// impl Handler<Work> for Slow {
+    ///     async fn handle(&mut self, _: Work, _: &mut Context<Self>) {
+    ///         smol::Timer::after(Duration::from_secs(1)).await;
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Slow.spawn();
+    ///     let res = addr.send_timeout(Work, Duration::from_millis(10)).await;
+    ///     assert_eq!(res, Err(SendError::Timeout));
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    fn send_timeout<M>(&self, message: M, duration: Duration) -> MessageResponseFuture<M>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        self.send(message).with_timeout(duration)
+    }
+
+    /// Sends a [`Message`](trait.Message.html) whose handler responds with a
+    /// [`ResponseStream`](struct.ResponseStream.html) to the actor, and returns the stream of the
+    /// items it sends right away (see [`Context::yielder`](struct.Context.html#method.yielder) for
+    /// an example). The message is sent in the same way as with
+    /// [`AddressExt::send`](trait.AddressExt.html#method.send). If the message could not be
+    /// handled, such as because the actor stopped first, the stream ends without any items.
+    fn send_stream<M, T>(&self, message: M) -> ResponseStream<T>
+    where
+        M: Message<Result = ResponseStream<T>>,
+        A: Handler<M>,
+        T: Send + 'static,
+    {
+        ResponseStream::pending(self.send(message).map(Result::ok))
+    }
+
     /// Attaches a stream to this actor such that all messages produced by it are forwarded to the
     /// actor. This could, for instance, be used to forward messages from a socket to the actor
     /// (after the messages have been appropriately `map`ped). This is a convenience method over
@@ -133,60 +1097,403 @@ pub trait AddressExt<A: Actor> {
         feature = "with-wasm_bindgen-0_2",
         feature = "with-smol-0_1"
     ))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-tokio-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-async_std-1")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-smol-0_1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
     fn attach_stream<S, M, K>(self, mut stream: S)
-        where
-            K: Into<KeepRunning> + Send,
-            M: Message<Result = K>,
-            A: Handler<M>,
-            S: Stream<Item = M> + Send + Unpin + 'static,
-            Self: Sized + Send + Sink<M, Error = Disconnected> + 'static,
+    where
+        K: Into<KeepRunning> + Send,
+        M: Message<Result = K>,
+        A: Handler<M>,
+        S: Stream<Item = M> + Send + Unpin + 'static,
+        Self: Sized + Send + Sink<M, Error = SendError> + 'static,
+    {
+        let fut = async move {
+            while let Some(m) = stream.next().await {
+                let res = self.send(m); // Bound to make it Sync
+                if !matches!(res.await.map(Into::into), Ok(KeepRunning::Yes)) {
+                    break;
+                }
+            }
+        };
+
+        crate::runtime::spawn(fut);
+    }
+}
+
+/// An `Address` is a reference to an actor through which [`Message`s](trait.Message.html) can be
+/// sent. It can be cloned, and when all `Address`es are dropped, the actor will be stopped. Therefore,
+/// any existing `Address`es will inhibit the dropping of an actor. If this is undesirable, then
+/// the [`WeakAddress`](struct.WeakAddress.html) struct should be used instead. This struct is created
+/// by calling the [`Actor::create`](trait.Actor.html#method.create) or  [`Actor::spawn`](trait.Actor.html#method.spawn)
+/// methods.
+///
+/// An `Address` is also a [`Sink`](https://docs.rs/futures/0.3/futures/sink/trait.Sink.html) of
+/// any message that its actor can handle, as are [`WeakAddress`](struct.WeakAddress.html),
+/// [`MessageChannel`](struct.MessageChannel.html), and [`WeakMessageChannel`](struct.WeakMessageChannel.html).
+/// If the actor's mailbox is bounded and its overflow policy is to block, the sink is only ready
+/// when there is space in the mailbox, so forwarding a stream into it applies backpressure to the
+/// stream.
+///
+/// Addresses (and weak addresses) are equal if they refer to the same actor, and can be hashed, so
+/// they can be kept in a `HashSet` of subscribers without duplicates:
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use std::collections::HashSet;
+/// struct Subscriber;
+/// impl Actor for Subscriber {}
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (a, b) = (Subscriber.spawn(), Subscriber.spawn());
+///     let subscribers: HashSet<_> = vec![a.clone(), b, a.clone()].into_iter().collect();
+///     assert_eq!(subscribers.len(), 2);
+///     assert!(subscribers.contains(&a));
+///     assert!(a.downgrade() == a.downgrade());
+/// }
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use futures::stream::{self, StreamExt};
+/// struct Sum(u32);
+/// impl Actor for Sum {}
+///
+/// struct Add(u32);
+/// impl Message for Add {
+///     type Result = ();
+/// }
+///
+/// impl SyncHandler<Add> for Sum {
+///     fn handle(&mut self, Add(n): Add, _: &mut Context<Self>) {
+///         self.0 += n;
+///     }
+/// }
+/// # struct Get;
+/// # impl Message for Get {
+/// #     type Result = u32;
+/// # }
+/// # impl SyncHandler<Get> for Sum {
+/// #     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> u32 {
+/// #         self.0
+/// #     }
+/// # }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = Sum(0).create_bounded(2);
+///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+///
+///     // Only ever has two messages waiting in the actor's mailbox at once
+///     stream::iter(1..=10).map(|n| Ok(Add(n))).forward(addr.clone()).await.unwrap();
+///     assert_eq!(addr.send(Get).await, Ok(55));
+/// }
+/// ```
+pub struct Address<A: Actor> {
+    pub(crate) sender: Sender<ManagerMessage<A>>,
+    pub(crate) ref_counter: Arc<()>,
+}
+
+impl<A: Actor> Address<A> {
+    /// Get the id of the actor which this address refers to.
+    pub fn id(&self) -> ActorId {
+        self.sender.id()
+    }
+
+    /// Why the actor is disconnected from its addresses, or `None` if it is still connected. The
+    /// actor is disconnected as soon as it stops accepting messages, which for an actor stopping
+    /// gracefully is before it has handled the messages left in its mailbox.
+    pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.sender.disconnect_reason()
+    }
+
+    /// Create a weak address to the actor. Unlike with the strong variety of address (this kind),
+    /// an actor will not be prevented from being dropped if only weak addresses exist.
+    pub fn downgrade(&self) -> WeakAddress<A> {
+        WeakAddress {
+            sender: self.sender.clone(),
+            ref_counter: Arc::downgrade(&self.ref_counter),
+        }
+    }
+
+    /// Returns a future which resolves once the actor has disconnected, that is, once
+    /// [`AddressExt::is_connected`](trait.AddressExt.html#method.is_connected) returns `false` for
+    /// this address. It resolves immediately if it already has. This can be used to wait for an
+    /// actor to stop, or to clean up state which refers to it once it has.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// struct Stop;
+    /// impl Message for Stop {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Stop> for MyActor {
+    ///     fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+    ///         ctx.stop();
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.spawn();
+    ///     assert!(addr.is_connected());
+    ///
+    ///     addr.do_send(Stop).unwrap();
+    ///     addr.disconnected().await;
+    ///     assert!(!addr.is_connected());
+    /// }
+    /// ```
+    pub fn disconnected(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.sender.closed()
+    }
+
+    /// Returns a future which resolves once the actor's manage loop has exited, after its
+    /// [`Actor::stopped`](trait.Actor.html#method.stopped) method has been called. Unlike
+    /// [`Address::disconnected`](struct.Address.html#method.disconnected), this waits for an actor
+    /// which is stopping gracefully to handle the rest of its mailbox, so it can be used to shut
+    /// down several actors in order.
+    ///
+    /// Note that the actor will not stop while this address is held unless it is stopped
+    /// explicitly, so awaiting this without stopping the actor may never resolve.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct MyActor(Arc<AtomicBool>);
+    ///
+    /// impl Actor for MyActor {
+    ///     fn stopped(&mut self, _: &mut Context<Self>) {
+    ///         self.0.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let stopped = Arc::new(AtomicBool::new(false));
+    ///     let addr = MyActor(stopped.clone()).spawn();
+    ///
+    ///     addr.stop_gracefully();
+    ///     addr.join().await;
+    ///     assert!(stopped.load(Ordering::SeqCst));
+    /// }
+    /// ```
+    pub fn join(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.sender.dropped()
+    }
+
+    /// Returns a future which resolves once the actor's manage loop has exited, like
+    /// [`Address::join`](struct.Address.html#method.join), to why it exited. This is the same
+    /// [`ExitReason`](enum.ExitReason.html) as the manage loop returns, or `None` if the manage
+    /// loop was dropped before it exited, or never started. If the actor is supervised and
+    /// restarted, this only resolves once it is not restarted anymore. The future does not hold an
+    /// address to the actor, so it does not keep the actor running, and it can also be created
+    /// from a [`WeakAddress`](struct.WeakAddress.html#method.monitor).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// use xtra::ExitReason;
+    ///
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// struct Stop;
+    /// impl Message for Stop {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Stop> for MyActor {
+    ///     fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+    ///         ctx.stop();
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.spawn();
+    ///     let monitor = addr.monitor();
+    ///     addr.do_send(Stop).unwrap();
+    ///     assert_eq!(monitor.await, Some(ExitReason::Stopped));
+    ///
+    ///     let weak = MyActor.spawn().downgrade();
+    ///     assert_eq!(weak.monitor().await, Some(ExitReason::AllAddressesDropped));
+    /// }
+    /// ```
+    pub fn monitor(&self) -> impl Future<Output = Option<ExitReason>> + Send + 'static {
+        self.sender.exited()
+    }
+
+    /// Sends many [`Message`s](trait.Message.html) of one type to the actor, and returns a future
+    /// which resolves to their responses, in the order the messages were given in, once all of them
+    /// have arrived. As many messages as there is space for in the actor's mailbox are put into it
+    /// at once, locking it and waking the actor only once, which takes less time than sending each
+    /// of them with [`AddressExt::send`](trait.AddressExt.html#method.send). If the mailbox is
+    /// bounded and does not have space for all of them, the rest are sent as with `send`, one after
+    /// another, when the future is polled. Each response is the same as that of `send`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// struct Doubler;
+    /// impl Actor for Doubler {}
+    ///
+    /// struct Double(u32);
+    /// impl Message for Double {
+    ///     type Result = u32;
+    /// }
+    ///
+    /// impl SyncHandler<Double> for Doubler {
+    ///     fn handle(&mut self, Double(n): Double, _: &mut Context<Self>) -> u32 {
+    ///         n * 2
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Doubler.spawn();
+    ///     let responses = addr.send_batch((1..=3).map(Double)).await;
+    ///     assert_eq!(responses, vec![Ok(2), Ok(4), Ok(6)]);
+    ///
+    ///     // With a bounded mailbox, the messages which do not fit are sent once there is space
+    ///     let addr = Doubler.spawn_with(xtra::MailboxConfig::bounded(2));
+    ///     let responses = addr.send_batch((1..=5).map(Double)).await;
+    ///     assert_eq!(responses.len(), 5);
+    ///     assert!(responses.iter().all(Result::is_ok));
+    /// }
+    /// ```
+    pub fn send_batch<M, I>(
+        &self,
+        messages: I,
+    ) -> impl Future<Output = Vec<Result<M::Result, SendError>>> + Send + 'static
+    where
+        M: Message,
+        A: Handler<M>,
+        I: IntoIterator<Item = M>,
     {
-        let fut = async move {
-            while let Some(m) = stream.next().await {
-                let res = self.send(m); // Bound to make it Sync
-                if !matches!(res.await.map(Into::into), Ok(KeepRunning::Yes)) {
-                    break;
-                }
-            }
-        };
-
-        #[cfg(feature = "with-async_std-1")]
-        async_std::task::spawn(fut);
-
-        #[cfg(feature = "with-tokio-0_2")]
-        tokio::spawn(fut);
-
-        #[cfg(feature = "with-wasm_bindgen-0_2")]
-        wasm_bindgen_futures::spawn_local(fut);
+        send_batch(&self.sender, messages)
+    }
 
-        #[cfg(feature = "with-smol-0_1")]
-        smol::Task::spawn(fut).detach();
+    /// The number of strong addresses to the actor, including this one. These are the addresses
+    /// which keep the actor running, including those inside message channels. This can be used to
+    /// decide when an actor which is kept in a pool or registry is no longer used elsewhere, or to
+    /// check in tests that addresses are not leaked. The actor's own context is not counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.spawn();
+    ///     assert_eq!(addr.strong_count(), 1);
+    ///     assert_eq!(addr.weak_count(), 0);
+    ///
+    ///     let other = addr.clone();
+    ///     let weak = addr.downgrade();
+    ///     assert_eq!(addr.strong_count(), 2);
+    ///     assert_eq!(addr.weak_count(), 1);
+    ///
+    ///     drop((other, weak));
+    ///     assert_eq!(addr.strong_count(), 1);
+    ///     assert_eq!(addr.weak_count(), 0);
+    /// }
+    /// ```
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.ref_counter) - context_refs(&self.sender)
     }
-}
 
-/// An `Address` is a reference to an actor through which [`Message`s](trait.Message.html) can be
-/// sent. It can be cloned, and when all `Address`es are dropped, the actor will be stopped. Therefore,
-/// any existing `Address`es will inhibit the dropping of an actor. If this is undesirable, then
-/// the [`WeakAddress`](struct.WeakAddress.html) struct should be used instead. This struct is created
-/// by calling the [`Actor::create`](trait.Actor.html#method.create) or  [`Actor::spawn`](trait.Actor.html#method.spawn)
-/// methods.
-pub struct Address<A: Actor> {
-    pub(crate) sender: UnboundedSender<ManagerMessage<A>>,
-    pub(crate) ref_counter: Arc<()>,
-}
+    /// The number of weak addresses to the actor, including those inside weak message channels.
+    /// The weak address held by the actor's own context is not counted, but copies of it which are
+    /// made on the actor's behalf are, such as those held by the futures of
+    /// [`Context::spawn_then_notify`](struct.Context.html#method.spawn_then_notify) and
+    /// [`Context::add_stream`](struct.Context.html#method.add_stream) until they finish, and by a
+    /// [`Broker`](struct.Broker.html) which the actor subscribed to with
+    /// [`Context::subscribe`](struct.Context.html#method.subscribe). See
+    /// [`Address::strong_count`](struct.Address.html#method.strong_count) for an example.
+    pub fn weak_count(&self) -> usize {
+        Arc::weak_count(&self.ref_counter).saturating_sub(context_refs(&self.sender))
+    }
 
-impl<A: Actor> Address<A> {
-    /// Create a weak address to the actor. Unlike with the strong variety of address (this kind),
-    /// an actor will not be prevented from being dropped if only weak addresses exist.
-    pub fn downgrade(&self) -> WeakAddress<A> {
-        WeakAddress {
-            sender: self.sender.clone(),
-            ref_counter: Arc::downgrade(&self.ref_counter),
-        }
+    /// Returns a future which sends every message of the stream to the actor in order, and does not
+    /// wait for their responses. Like forwarding the stream into the address as a
+    /// [`Sink`](https://docs.rs/futures/0.3/futures/sink/trait.Sink.html), this waits for space in
+    /// the actor's mailbox before taking the next message from the stream if the mailbox is bounded
+    /// and its overflow policy is to block, which applies backpressure to the stream. The future
+    /// resolves to `Ok(())` once the stream has ended, or to an error as soon as the actor stops
+    /// accepting messages (in which case the rest of the stream is dropped), or a message cannot be
+    /// sent for another reason, such as the mailbox being full.
+    ///
+    /// **Note:** the future holds a strong address to the actor, which keeps it running until the
+    /// stream has ended. To avoid that, use
+    /// [`WeakAddress::forward`](struct.WeakAddress.html#method.forward) instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// #[derive(Default)]
+    /// struct Sum(u32);
+    /// impl Actor for Sum {}
+    ///
+    /// struct Add(u32);
+    /// impl Message for Add {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Add> for Sum {
+    ///     fn handle(&mut self, Add(n): Add, _: &mut Context<Self>) {
+    ///         self.0 += n;
+    ///     }
+    /// }
+    /// # struct Get;
+    /// # impl Message for Get {
+    /// #     type Result = u32;
+    /// # }
+    /// # impl SyncHandler<Get> for Sum {
+    /// #     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> u32 {
+    /// #         self.0
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     // At most two messages wait in the mailbox while the rest of the stream is held back
+    ///     let addr = Sum::default().builder().capacity(2).spawn();
+    ///     let numbers = futures::stream::iter((1..=100).map(Add));
+    ///
+    ///     assert_eq!(addr.forward(numbers).await, Ok(()));
+    ///     assert_eq!(addr.send(Get).await, Ok(5050));
+    /// }
+    /// ```
+    pub fn forward<M, S>(
+        &self,
+        stream: S,
+    ) -> impl Future<Output = Result<(), SendError>> + Send + 'static
+    where
+        M: Message,
+        A: Handler<M>,
+        S: Stream<Item = M> + Send + 'static,
+    {
+        forward(self.clone(), &self.sender, stream)
     }
 
     /// Converts this address into a weak address to the actor. Unlike with the strong variety of
@@ -223,12 +1530,13 @@ impl<A: Actor> Address<A> {
     }
 }
 
-impl<A, M> Into<MessageChannel<M>> for Address<A>
-    where A: Handler<M>,
-          M: Message,
+impl<A, M> From<Address<A>> for MessageChannel<M>
+where
+    A: Handler<M>,
+    M: Message,
 {
-    fn into(self) -> MessageChannel<M> {
-        self.into_channel()
+    fn from(address: Address<A>) -> MessageChannel<M> {
+        address.into_channel()
     }
 }
 
@@ -237,28 +1545,83 @@ impl<A: Actor> AddressExt<A> for Address<A> {
         !self.sender.is_closed()
     }
 
-    fn do_send<M>(&self, message: M) -> Result<(), Disconnected>
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
+    }
+
+    fn stop_gracefully(&self) {
+        self.sender.close();
+    }
+
+    fn do_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        enqueue_now(&self.sender, message)
+    }
+
+    fn do_send_batched<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message<Result = ()>,
+        A: BatchHandler<M>,
+    {
+        enqueue_batched(&self.sender, message)
+    }
+
+    fn do_send_conflated<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        enqueue_conflated(&self.sender, message)
+    }
+
+    fn try_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        enqueue_or_return(&self.sender, message)
+    }
+
+    fn do_send_async<M>(&self, message: M) -> DoSendFuture
     where
         M: Message,
         A: Handler<M>,
     {
-        // To read more about what an envelope is and why we use them, look under `envelope.rs`
         let envelope = NonReturningEnvelope::<A, M>::new(message);
-        self.sender
-            .unbounded_send(ManagerMessage::Message(Box::new(envelope)))
-            .map_err(|_| Disconnected)
+        DoSendFuture(enqueue(
+            &self.sender,
+            ManagerMessage::Message(pool::boxed(envelope)),
+            M::PRIORITY,
+        ))
     }
 
     fn send<M>(&self, message: M) -> MessageResponseFuture<M>
     where
         M: Message,
-        A: Handler<M>
+        A: Handler<M>,
     {
-        let (envelope, rx) = ReturningEnvelope::<A, M>::new(message);
-        let _ = self
-            .sender
-            .unbounded_send(ManagerMessage::Message(Box::new(envelope)));
-        MessageResponseFuture::result(rx)
+        enqueue_request(&self.sender, message, |message| {
+            let (envelope, rx) = ReturningEnvelope::<A, M>::new(message);
+            (ManagerMessage::Message(pool::boxed(envelope)), rx)
+        })
+    }
+
+    fn send_read<M>(&self, message: M) -> MessageResponseFuture<M>
+    where
+        M: Message,
+        A: ReadHandler<M>,
+    {
+        enqueue_request(&self.sender, message, |message| {
+            let (envelope, rx) = ReadOnlyEnvelope::<A, M>::new(message);
+            (ManagerMessage::Message(Box::new(envelope)), rx)
+        })
     }
 }
 
@@ -267,38 +1630,33 @@ where
     M: Message,
     A: Handler<M>,
 {
-    type Error = Disconnected;
+    type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         if self.is_connected() {
-            self.sender.poll_ready(ctx).map_err(|_| Disconnected)
+            self.sender
+                .poll_ready(ctx)
+                .map_err(|_| SendError::Disconnected(closed_reason(&self.sender)))
         } else {
-            Poll::Ready(Err(Disconnected))
+            Poll::Ready(Err(SendError::Disconnected(closed_reason(&self.sender))))
         }
     }
 
     fn start_send(self: Pin<&mut Self>, message: M) -> Result<(), Self::Error> {
         if self.is_connected() {
-            let envelope = NonReturningEnvelope::<A, M>::new(message);
-            let msg = ManagerMessage::Message(Box::new(envelope));
-            Pin::new(&mut self.get_mut().sender)
-                .start_send(msg)
-                .map_err(|_| Disconnected)
+            // Space was already waited for in `poll_ready` if the overflow policy is to block
+            enqueue_now(&self.sender, message).map_err(Into::into)
         } else {
-            Err(Disconnected)
+            Err(SendError::Disconnected(closed_reason(&self.sender)))
         }
     }
 
-    fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.get_mut().sender)
-            .poll_flush(ctx)
-            .map_err(|_| Disconnected)
+    fn poll_flush(self: Pin<&mut Self>, _ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
     }
 
-    fn poll_close(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.get_mut().sender)
-            .poll_close(ctx)
-            .map_err(|_| Disconnected)
+    fn poll_close(self: Pin<&mut Self>, _ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
     }
 }
 
@@ -312,27 +1670,213 @@ impl<A: Actor> Clone for Address<A> {
     }
 }
 
+// Addresses are equal when they refer to the same actor, so they can be deduplicated or used as
+// keys. They are ordered by the id of the actor, which corresponds one-to-one with its mailbox.
+impl<A: Actor> PartialEq for Address<A> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ref_counter, &other.ref_counter)
+    }
+}
+
+impl<A: Actor> Eq for Address<A> {}
+
+impl<A: Actor> Hash for Address<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.ref_counter).hash(state)
+    }
+}
+
+impl<A: Actor> PartialOrd for Address<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Actor> Ord for Address<A> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.id().cmp(&other.id())
+    }
+}
+
 impl<A: Actor> Drop for Address<A> {
     fn drop(&mut self) {
         // ActorManager holds one strong address, so if there are 2 strong addresses, this would be
         // the only external one in existence. Therefore, we should notify the ActorManager that
         // there are potentially no more strong Addresses and the actor should be stopped.
         if Arc::strong_count(&self.ref_counter) == 2 {
-            let _ = self.sender.unbounded_send(ManagerMessage::LastAddress);
+            let _ = self
+                .sender
+                .force_send(ManagerMessage::LastAddress, Priority::Normal);
         }
     }
 }
 
+/// A unique identifier for an actor. Every mailbox created by xtra gets a new id, so the actor
+/// behind a [`Supervisor`](struct.Supervisor.html) keeps the same id across restarts. It is got
+/// with [`Address::id`](struct.Address.html#method.id) or
+/// [`Context::id`](struct.Context.html#method.id), and can be used to tell actors apart in logs, as
+/// the key of a registry, or to match [`Terminated`](struct.Terminated.html) notifications to the
+/// actors they are about. It is displayed as its number.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "recording", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActorId(u64);
+
+impl ActorId {
+    pub(crate) fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        ActorId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for ActorId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// A `WeakAddress` is a reference to an actor through which [`Message`s](trait.Message.html) can be
 /// sent. It can be cloned. Unlike [`Address`](struct.Address.html), a `WeakAddress` will not inhibit
 /// the dropping of an actor. It is created by the [`Address::downgrade`](struct.Address.html#method.downgrade)
 /// or [`Address::into_downgraded`](struct.Address.html#method.into_downgraded) methods.
 pub struct WeakAddress<A: Actor> {
-    pub(crate) sender: UnboundedSender<ManagerMessage<A>>,
+    pub(crate) sender: Sender<ManagerMessage<A>>,
     pub(crate) ref_counter: Weak<()>,
 }
 
 impl<A: Actor> WeakAddress<A> {
+    /// Get the id of the actor which this address refers to.
+    pub fn id(&self) -> ActorId {
+        self.sender.id()
+    }
+
+    /// Why the actor is disconnected from its addresses, or `None` if it is still connected. See
+    /// [`Address::disconnect_reason`](struct.Address.html#method.disconnect_reason).
+    pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
+        if self.is_connected() {
+            None
+        } else {
+            Some(self.closed_reason())
+        }
+    }
+
+    /// Why the actor is disconnected, once `is_connected` returned false. If its mailbox does not
+    /// know yet, the actor is about to stop because there are no strong addresses left.
+    fn closed_reason(&self) -> DisconnectReason {
+        self.sender
+            .disconnect_reason()
+            .unwrap_or(DisconnectReason::AllAddressesDropped)
+    }
+
+    /// Returns a future which resolves once the actor has stopped. Unlike a strong address, a weak
+    /// address does not keep the actor running, so this also resolves once the actor stops due to
+    /// all of its strong addresses having been dropped. See
+    /// [`Address::disconnected`](struct.Address.html#method.disconnected).
+    pub fn disconnected(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.sender.closed()
+    }
+
+    /// Returns a future which resolves once the actor's manage loop has exited. See
+    /// [`Address::join`](struct.Address.html#method.join).
+    pub fn join(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.sender.dropped()
+    }
+
+    /// Returns a future which resolves once the actor's manage loop has exited, to why it exited.
+    /// See [`Address::monitor`](struct.Address.html#method.monitor).
+    pub fn monitor(&self) -> impl Future<Output = Option<ExitReason>> + Send + 'static {
+        self.sender.exited()
+    }
+
+    /// Sends many [`Message`s](trait.Message.html) of one type to the actor at once, and returns a
+    /// future which resolves to their responses. See
+    /// [`Address::send_batch`](struct.Address.html#method.send_batch).
+    pub fn send_batch<M, I>(
+        &self,
+        messages: I,
+    ) -> impl Future<Output = Vec<Result<M::Result, SendError>>> + Send + 'static
+    where
+        M: Message,
+        A: Handler<M>,
+        I: IntoIterator<Item = M>,
+    {
+        if self.is_connected() {
+            Either::Left(send_batch(&self.sender, messages))
+        } else {
+            let err = SendError::Disconnected(self.closed_reason());
+            let responses = messages.into_iter().map(|_| Err(err.clone())).collect();
+            Either::Right(future::ready(responses))
+        }
+    }
+
+    /// The number of strong addresses to the actor. See
+    /// [`Address::strong_count`](struct.Address.html#method.strong_count).
+    pub fn strong_count(&self) -> usize {
+        self.ref_counter
+            .strong_count()
+            .saturating_sub(context_refs(&self.sender))
+    }
+
+    /// The number of weak addresses to the actor, including this one. See
+    /// [`Address::weak_count`](struct.Address.html#method.weak_count). Once the actor has stopped
+    /// and there are no strong addresses to it left, this is always zero.
+    pub fn weak_count(&self) -> usize {
+        self.ref_counter
+            .weak_count()
+            .saturating_sub(context_refs(&self.sender))
+    }
+
+    /// Returns a future which sends every message of the stream to the actor in order, applying
+    /// backpressure to the stream, until either the stream ends or the actor stops. See
+    /// [`Address::forward`](struct.Address.html#method.forward). Unlike with a strong address, the
+    /// future does not keep the actor running.
+    pub fn forward<M, S>(
+        &self,
+        stream: S,
+    ) -> impl Future<Output = Result<(), SendError>> + Send + 'static
+    where
+        M: Message,
+        A: Handler<M>,
+        S: Stream<Item = M> + Send + 'static,
+    {
+        forward(self.clone(), &self.sender, stream)
+    }
+
+    /// Get a strong address to the actor, if it is still connected (see
+    /// [`AddressExt::is_connected`](trait.AddressExt.html#method.is_connected)). This keeps the
+    /// actor from being stopped for a lack of strong addresses for as long as the returned address
+    /// is held.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.spawn();
+    ///     let weak = addr.downgrade();
+    ///     assert!(weak.upgrade().is_some());
+    ///
+    ///     drop(addr);
+    ///     # smol::Timer::after(Duration::from_millis(10)).await;
+    ///     assert!(weak.upgrade().is_none());
+    /// }
+    /// ```
+    pub fn upgrade(&self) -> Option<Address<A>> {
+        if !self.is_connected() {
+            return None;
+        }
+
+        let ref_counter = self.ref_counter.upgrade()?;
+        Some(Address {
+            sender: self.sender.clone(),
+            ref_counter,
+        })
+    }
+
     /// Gets a message channel to the actor. Like an address, a message channel allows messages
     /// to be sent to an actor. Unlike an address, rather than allowing you to send any kind of
     /// message to one kind of actor, a message channel allows you to send one kind of message to
@@ -355,57 +1899,135 @@ impl<A: Actor> WeakAddress<A> {
         A: Handler<M>,
     {
         WeakMessageChannel {
-            address: Box::new(self)
+            address: Box::new(self),
         }
     }
 }
 
-impl<A, M> Into<WeakMessageChannel<M>> for WeakAddress<A>
-    where A: Handler<M>,
-          M: Message,
+impl<A, M> From<WeakAddress<A>> for WeakMessageChannel<M>
+where
+    A: Handler<M>,
+    M: Message,
 {
-    fn into(self) -> WeakMessageChannel<M> {
-        self.into_channel()
+    fn from(address: WeakAddress<A>) -> WeakMessageChannel<M> {
+        address.into_channel()
     }
 }
 
 impl<A: Actor> AddressExt<A> for WeakAddress<A> {
     fn is_connected(&self) -> bool {
-        // Check that there are external strong addresses. If there are none, the actor is
-        // disconnected and our message would interrupt its dropping. strong_count() == 2 because
-        // Context and manager both hold a strong arc to the refcount
-        self.ref_counter.strong_count() > 1 && !self.sender.is_closed()
+        // Check that there are external strong addresses, unless the actor is kept alive without
+        // them. If there are none, the actor is disconnected and our message would interrupt its
+        // dropping. strong_count() == 2 because Context and manager both hold a strong arc to the
+        // refcount
+        (self.ref_counter.strong_count() > 1 || self.sender.is_kept_alive())
+            && !self.sender.is_closed()
+    }
+
+    fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.sender.capacity()
     }
 
-    fn do_send<M>(&self, message: M) -> Result<(), Disconnected>
+    fn stop_gracefully(&self) {
+        self.sender.close();
+    }
+
+    fn do_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        if self.is_connected() {
+            enqueue_now(&self.sender, message)
+        } else {
+            Err(TrySendError::Disconnected(message, self.closed_reason()))
+        }
+    }
+
+    fn do_send_batched<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message<Result = ()>,
+        A: BatchHandler<M>,
+    {
+        if self.is_connected() {
+            enqueue_batched(&self.sender, message)
+        } else {
+            Err(TrySendError::Disconnected(message, self.closed_reason()))
+        }
+    }
+
+    fn do_send_conflated<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        if self.is_connected() {
+            enqueue_conflated(&self.sender, message)
+        } else {
+            Err(TrySendError::Disconnected(message, self.closed_reason()))
+        }
+    }
+
+    fn try_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        if self.is_connected() {
+            enqueue_or_return(&self.sender, message)
+        } else {
+            Err(TrySendError::Disconnected(message, self.closed_reason()))
+        }
+    }
+
+    fn do_send_async<M>(&self, message: M) -> DoSendFuture
     where
         M: Message,
         A: Handler<M>,
     {
         if self.is_connected() {
-            // To read more about what an envelope is and why we use them, look under `envelope.rs`
             let envelope = NonReturningEnvelope::<A, M>::new(message);
-            self.sender
-                .unbounded_send(ManagerMessage::Message(Box::new(envelope)))
-                .map_err(|_| Disconnected)
+            DoSendFuture(enqueue(
+                &self.sender,
+                ManagerMessage::Message(pool::boxed(envelope)),
+                M::PRIORITY,
+            ))
         } else {
-            Err(Disconnected)
+            DoSendFuture::disconnected(self.closed_reason())
         }
     }
 
     fn send<M>(&self, message: M) -> MessageResponseFuture<M>
     where
         M: Message,
-        A: Handler<M>
+        A: Handler<M>,
     {
         if self.is_connected() {
-            let (envelope, rx) = ReturningEnvelope::<A, M>::new(message);
-            let _ = self
-                .sender
-                .unbounded_send(ManagerMessage::Message(Box::new(envelope)));
-            MessageResponseFuture::result(rx)
+            enqueue_request(&self.sender, message, |message| {
+                let (envelope, rx) = ReturningEnvelope::<A, M>::new(message);
+                (ManagerMessage::Message(pool::boxed(envelope)), rx)
+            })
+        } else {
+            MessageResponseFuture::unsent(TrySendError::Disconnected(message, self.closed_reason()))
+        }
+    }
+
+    fn send_read<M>(&self, message: M) -> MessageResponseFuture<M>
+    where
+        M: Message,
+        A: ReadHandler<M>,
+    {
+        if self.is_connected() {
+            enqueue_request(&self.sender, message, |message| {
+                let (envelope, rx) = ReadOnlyEnvelope::<A, M>::new(message);
+                (ManagerMessage::Message(Box::new(envelope)), rx)
+            })
         } else {
-            MessageResponseFuture::disconnected()
+            MessageResponseFuture::unsent(TrySendError::Disconnected(message, self.closed_reason()))
         }
     }
 }
@@ -415,45 +2037,40 @@ where
     M: Message,
     A: Handler<M>,
 {
-    type Error = Disconnected;
+    type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         if self.is_connected() {
-            self.sender.poll_ready(ctx).map_err(|_| Disconnected)
+            self.sender
+                .poll_ready(ctx)
+                .map_err(|_| SendError::Disconnected(self.closed_reason()))
         } else {
-            Poll::Ready(Err(Disconnected))
+            Poll::Ready(Err(SendError::Disconnected(self.closed_reason())))
         }
     }
 
     fn start_send(self: Pin<&mut Self>, message: M) -> Result<(), Self::Error> {
         if self.is_connected() {
-            let envelope = NonReturningEnvelope::<A, M>::new(message);
-            let msg = ManagerMessage::Message(Box::new(envelope));
-            Pin::new(&mut self.get_mut().sender)
-                .start_send(msg)
-                .map_err(|_| Disconnected)
+            // Space was already waited for in `poll_ready` if the overflow policy is to block
+            enqueue_now(&self.sender, message).map_err(Into::into)
         } else {
-            Err(Disconnected)
+            Err(SendError::Disconnected(self.closed_reason()))
         }
     }
 
-    fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    fn poll_flush(self: Pin<&mut Self>, _ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         if self.is_connected() {
-            Pin::new(&mut self.get_mut().sender)
-                .poll_flush(ctx)
-                .map_err(|_| Disconnected)
+            Poll::Ready(Ok(()))
         } else {
-            Poll::Ready(Err(Disconnected))
+            Poll::Ready(Err(SendError::Disconnected(self.closed_reason())))
         }
     }
 
-    fn poll_close(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    fn poll_close(self: Pin<&mut Self>, _ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         if self.is_connected() {
-            Pin::new(&mut self.get_mut().sender)
-                .poll_close(ctx)
-                .map_err(|_| Disconnected)
+            Poll::Ready(Ok(()))
         } else {
-            Poll::Ready(Err(Disconnected))
+            Poll::Ready(Err(SendError::Disconnected(self.closed_reason())))
         }
     }
 }
@@ -467,3 +2084,30 @@ impl<A: Actor> Clone for WeakAddress<A> {
         }
     }
 }
+
+// Compared, hashed, and ordered in the same way as `Address`
+impl<A: Actor> PartialEq for WeakAddress<A> {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.ref_counter, &other.ref_counter)
+    }
+}
+
+impl<A: Actor> Eq for WeakAddress<A> {}
+
+impl<A: Actor> Hash for WeakAddress<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Weak::as_ptr(&self.ref_counter).hash(state)
+    }
+}
+
+impl<A: Actor> PartialOrd for WeakAddress<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Actor> Ord for WeakAddress<A> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.id().cmp(&other.id())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{pool, Actor, Context, Handler, Message};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The type of the handlers stored in a `Behavior`, before its type is erased
+type HandlerFn<A, M> =
+    dyn Fn(&mut A, M, &mut Context<A>) -> <M as Message>::Result + Send + Sync + 'static;
+
+/// A set of handlers which an actor can switch to with
+/// [`Context::become_behavior`](struct.Context.html#method.become_behavior), replacing its
+/// [`Handler`](trait.Handler.html) implementations for the messages which the behavior handles.
+/// Messages which it does not handle are still handled by the actor's `Handler` implementations.
+/// This allows protocol state machines to treat the same message differently depending on their
+/// state, without matching on the state in every handler.
+///
+/// The handlers of a behavior are synchronous, and are given the actor, the message, and the
+/// actor's context, just like [`SyncHandler::handle`](trait.SyncHandler.html#tymethod.handle).
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use xtra::Behavior;
+///
+/// struct Door;
+/// impl Actor for Door {}
+///
+/// struct Knock;
+/// impl Message for Knock {
+///     type Result = &'static str;
+/// }
+///
+/// struct Lock;
+/// impl Message for Lock {
+///     type Result = ();
+/// }
+///
+/// struct Unlock;
+/// impl Message for Unlock {
+///     type Result = ();
+/// }
+///
+/// impl SyncHandler<Knock> for Door {
+///     fn handle(&mut self, _: Knock, _: &mut Context<Self>) -> &'static str {
+///         "come in"
+///     }
+/// }
+///
+/// impl SyncHandler<Lock> for Door {
+///     fn handle(&mut self, _: Lock, ctx: &mut Context<Self>) {
+///         let locked = Behavior::new()
+///             .on(|_: &mut Door, _: Knock, _: &mut Context<Door>| "go away")
+///             .on(|_: &mut Door, _: Unlock, ctx: &mut Context<Door>| ctx.unbecome());
+///         ctx.become_behavior(locked);
+///     }
+/// }
+///
+/// impl SyncHandler<Unlock> for Door {
+///     fn handle(&mut self, _: Unlock, _: &mut Context<Self>) {}
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let door = Door.spawn();
+///     assert_eq!(door.send(Knock).await, Ok("come in"));
+///
+///     door.send(Lock).await.unwrap();
+///     assert_eq!(door.send(Knock).await, Ok("go away"));
+///
+///     door.send(Unlock).await.unwrap();
+///     assert_eq!(door.send(Knock).await, Ok("come in"));
+/// }
+/// ```
+pub struct Behavior<A: Actor> {
+    /// Maps the type id of each message to an `Arc<Box<HandlerFn<A, M>>>`
+    handlers: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    phantom: PhantomData<fn(&mut A)>,
+}
+
+impl<A: Actor> Behavior<A> {
+    /// Create a behavior which does not handle any messages.
+    pub fn new() -> Self {
+        Behavior {
+            handlers: HashMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Handle the message `M` with `handler` while this behavior is active, replacing any handler
+    /// for it which was given before.
+    pub fn on<M, F>(mut self, handler: F) -> Self
+    where
+        M: Message,
+        A: Handler<M>,
+        F: Fn(&mut A, M, &mut Context<A>) -> M::Result + Send + Sync + 'static,
+    {
+        let handler: Box<HandlerFn<A, M>> = Box::new(handler);
+        self.handlers.insert(TypeId::of::<M>(), Arc::new(handler));
+        self
+    }
+
+    /// Get the handler for `M`, if this behavior handles it.
+    pub(crate) fn handler<M: Message>(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.handlers.get(&TypeId::of::<M>()).cloned()
+    }
+}
+
+impl<A: Actor> Default for Behavior<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Call the handler for `M` of the actor's current behavior, or its `Handler` implementation if
+/// it has no current behavior or the behavior does not handle `M`.
+#[cfg(not(feature = "nightly"))]
+pub(crate) fn dispatch<'a, A, M>(
+    act: &'a mut A,
+    message: M,
+    ctx: &'a mut Context<A>,
+) -> futures::future::BoxFuture<'a, M::Result>
+where
+    A: Handler<M>,
+    M: Message,
+{
+    let handler = ctx.behavior().and_then(Behavior::handler::<M>);
+    match handler {
+        Some(handler) => {
+            let handler = handler
+                .downcast_ref::<Box<HandlerFn<A, M>>>()
+                .expect("behavior handlers are stored by the type id of their message");
+            pool::pinned(futures::future::ready(handler(act, message, ctx)))
+        }
+        None => act.handle(message, ctx),
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    Actor, Address, Handler, Message, MessageChannelExt, SendError, TrySendError,
+    WeakMessageChannel,
+};
+use futures::future;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// An address through which a copy of every message is sent to all of its subscribers, which can
+/// be actors of different types. This can be used to fan out events without a
+/// [`Broker`](struct.Broker.html) actor in between. It only holds weak channels to its
+/// subscribers, so it does not keep them alive, and subscribers which have stopped are removed
+/// when the next message is sent. The address can be cloned, with all clones sharing the same
+/// subscribers.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::BroadcastAddress;
+/// #[derive(Clone)]
+/// struct Price(u32);
+/// impl Message for Price {
+///     type Result = u32;
+/// }
+///
+/// struct Display;
+/// impl Actor for Display {}
+///
+/// impl SyncHandler<Price> for Display {
+///     fn handle(&mut self, Price(price): Price, _: &mut Context<Self>) -> u32 {
+///         price
+///     }
+/// }
+///
+/// struct Logger;
+/// impl Actor for Logger {}
+///
+/// impl SyncHandler<Price> for Logger {
+///     fn handle(&mut self, Price(price): Price, _: &mut Context<Self>) -> u32 {
+///         price * 2
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let display = Display.spawn();
+///     let logger = Logger.spawn();
+///
+///     let prices = BroadcastAddress::new();
+///     prices.subscribe(&display);
+///     prices.subscribe(&logger);
+///
+///     let results = prices.send(Price(10)).await;
+///     assert_eq!(results, vec![Ok(10), Ok(20)]);
+///
+///     drop(logger); // Stops the logger, after which it is removed from the subscribers
+///     # smol::Timer::after(std::time::Duration::from_millis(10)).await;
+///     assert_eq!(prices.do_send(Price(20)), 1);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BroadcastAddress<M: Message + Clone> {
+    subscribers: Arc<Mutex<Vec<WeakMessageChannel<M>>>>,
+}
+
+impl<M: Message + Clone> BroadcastAddress<M> {
+    /// Create a broadcast address without any subscribers.
+    pub fn new() -> Self {
+        BroadcastAddress {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn subscribers(&self) -> MutexGuard<'_, Vec<WeakMessageChannel<M>>> {
+        // Nothing which can panic runs while the lock is held, so poisoning can safely be ignored
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Subscribe the actor to all messages sent through this address.
+    pub fn subscribe<A>(&self, address: &Address<A>)
+    where
+        A: Actor + Handler<M>,
+    {
+        self.subscribe_channel(address.downgrade().into_channel());
+    }
+
+    /// Subscribe the channel to all messages sent through this address.
+    pub fn subscribe_channel(&self, channel: WeakMessageChannel<M>) {
+        self.subscribers().push(channel);
+    }
+
+    /// The number of subscribers to this address. This may include subscribers which have
+    /// stopped, but have not been removed yet.
+    pub fn len(&self) -> usize {
+        self.subscribers().len()
+    }
+
+    /// Returns whether this address has no subscribers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends a copy of the [`Message`](trait.Message.html) to all subscribers, and does not wait
+    /// for a response, returning how many subscribers it was sent to. See
+    /// [`MessageChannelExt::do_send`](trait.MessageChannelExt.html#method.do_send).
+    pub fn do_send(&self, message: M) -> usize {
+        let mut sent = 0;
+        self.subscribers()
+            .retain(|subscriber| match subscriber.do_send(message.clone()) {
+                Ok(()) => {
+                    sent += 1;
+                    true
+                }
+                Err(TrySendError::Disconnected(..)) => false,
+                Err(_) => true,
+            });
+        sent
+    }
+
+    /// Sends a copy of the [`Message`](trait.Message.html) to all subscribers, and waits for
+    /// their responses, which are returned in the order in which the subscribers subscribed.
+    /// See [`MessageChannelExt::send`](trait.MessageChannelExt.html#method.send).
+    pub async fn send(&self, message: M) -> Vec<Result<M::Result, SendError>> {
+        let responses: Vec<_> = {
+            let mut subscribers = self.subscribers();
+            subscribers.retain(MessageChannelExt::is_connected);
+            subscribers
+                .iter()
+                .map(|subscriber| subscriber.send(message.clone()))
+                .collect()
+        };
+
+        future::join_all(responses).await
+    }
+}
+
+impl<M: Message + Clone> Default for BroadcastAddress<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
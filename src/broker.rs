@@ -0,0 +1,130 @@
+use crate::{
+    Actor, Address, AddressExt, Context, Handler, Message, MessageChannelExt, SyncHandler,
+    TrySendError, WeakMessageChannel,
+};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// An actor through which messages can be published to all actors which subscribed to their type.
+/// Every published message is cloned and sent to each subscriber. The broker only holds weak
+/// channels to its subscribers, so it does not keep them alive, and subscribers which have stopped
+/// are removed the next time a message they subscribed to is published.
+///
+/// Actors subscribe with [`Context::subscribe`](struct.Context.html#method.subscribe) or by
+/// sending the broker a [`Subscribe`](struct.Subscribe.html) message, and messages are published
+/// with [`Address::publish`](struct.Address.html#method.publish) or by sending the broker a
+/// [`Publish`](struct.Publish.html) message.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::Broker;
+/// # use std::time::Duration;
+/// #[derive(Clone)]
+/// struct Announcement(&'static str);
+/// impl Message for Announcement {
+///     type Result = ();
+/// }
+///
+/// struct Listener(Address<Broker>);
+///
+/// impl Actor for Listener {
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         ctx.subscribe::<Announcement>(&self.0);
+///     }
+/// }
+///
+/// impl SyncHandler<Announcement> for Listener {
+///     fn handle(&mut self, Announcement(text): Announcement, _: &mut Context<Self>) {
+///         println!("Heard: {}", text);
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let broker = Broker::default().spawn();
+///     let _a = Listener(broker.clone()).spawn();
+///     let _b = Listener(broker.clone()).spawn();
+///     # smol::Timer::after(Duration::from_millis(10)).await;
+///
+///     broker.publish(Announcement("hello")).unwrap(); // Printed by both listeners
+///     # smol::Timer::after(Duration::from_millis(10)).await;
+/// }
+/// ```
+#[derive(Default)]
+pub struct Broker {
+    /// Maps from the type id of a message to a `Vec<WeakMessageChannel<M>>` of its subscribers
+    subscribers: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl Broker {
+    /// Create a new broker without any subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn subscribers<M: Message>(&mut self) -> &mut Vec<WeakMessageChannel<M>> {
+        self.subscribers
+            .entry(TypeId::of::<M>())
+            .or_insert_with(|| Box::new(Vec::<WeakMessageChannel<M>>::new()))
+            .downcast_mut()
+            .expect("subscribers are always stored under the type id of their message")
+    }
+}
+
+impl Actor for Broker {}
+
+/// Subscribe the channel to all messages of type `M` published through a
+/// [`Broker`](struct.Broker.html).
+pub struct Subscribe<M: Message>(pub WeakMessageChannel<M>);
+
+impl<M: Message> Message for Subscribe<M> {
+    type Result = ();
+}
+
+impl<M: Message> SyncHandler<Subscribe<M>> for Broker {
+    fn handle(&mut self, Subscribe(channel): Subscribe<M>, _: &mut Context<Self>) {
+        self.subscribers::<M>().push(channel);
+    }
+}
+
+/// Publish the message to all the subscribers of a [`Broker`](struct.Broker.html) which
+/// subscribed to its type.
+pub struct Publish<M: Message + Clone>(pub M);
+
+impl<M: Message + Clone> Message for Publish<M> {
+    type Result = ();
+}
+
+impl<M: Message + Clone> SyncHandler<Publish<M>> for Broker {
+    fn handle(&mut self, Publish(message): Publish<M>, _: &mut Context<Self>) {
+        // A full mailbox is not a reason to unsubscribe, so only prune disconnected subscribers
+        self.subscribers::<M>().retain(|subscriber| {
+            !matches!(
+                subscriber.do_send(message.clone()),
+                Err(TrySendError::Disconnected(..))
+            )
+        });
+    }
+}
+
+impl Address<Broker> {
+    /// Publish the message to all the subscribers of the broker which subscribed to its type. This
+    /// is the same as sending the broker a [`Publish`](struct.Publish.html) message.
+    pub fn publish<M: Message + Clone>(&self, message: M) -> Result<(), TrySendError<M>> {
+        self.do_send(Publish(message))
+            .map_err(|err| err.map(|Publish(message)| message))
+    }
+}
+
+impl<A: Actor> Context<A> {
+    /// Subscribe this actor to all messages of type `M` published through the given
+    /// [`Broker`](struct.Broker.html). The broker does not keep this actor alive.
+    pub fn subscribe<M: Message>(&mut self, broker: &Address<Broker>)
+    where
+        A: Handler<M>,
+    {
+        let _ = broker.do_send(Subscribe(self.address.clone().into_channel()));
+    }
+}
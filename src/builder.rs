@@ -0,0 +1,296 @@
+use crate::dedup::DedupWindow;
+use crate::mailbox::{Custom, Queue};
+use crate::manager::{ActorManager, ManagerMessage, DEFAULT_BUDGET};
+use crate::spawner::{self, Spawner};
+use crate::{Actor, Address, Mailbox, MailboxConfig, Middleware, OverflowPolicy};
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+use {
+    crate::watchdog::{SlowHandler, Watchdog},
+    std::time::Duration,
+};
+
+/// Configures how an actor is run before it is created or spawned, and is created with
+/// [`Actor::builder`](trait.Actor.html#method.builder). This gathers the options of the actor's
+/// mailbox and its instrumentation in one place. By default, the mailbox is unbounded, has
+/// priority lanes, and the actor has no middleware, watchdog or handler timeout.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::{OverflowPolicy, TrySendError};
+/// # use std::time::Duration;
+/// # struct MyActor;
+/// # impl Actor for MyActor {}
+/// # struct Msg;
+/// # impl Message for Msg {
+/// #    type Result = ();
+/// # }
+/// # impl SyncHandler<Msg> for MyActor {
+/// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+/// # }
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = MyActor
+///         .builder()
+///         .capacity(1)
+///         .overflow_policy(OverflowPolicy::Error)
+///         .watchdog(Duration::from_secs(1), |slow| eprintln!("{} is slow", slow.actor))
+///         .create();
+///
+///     assert!(addr.do_send(Msg).is_ok());
+///     assert!(matches!(addr.do_send(Msg), Err(TrySendError::Full(Msg))));
+///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+/// }
+/// ```
+pub struct ActorBuilder<A: Actor> {
+    actor: A,
+    config: MailboxConfig,
+    queue: Option<Box<dyn Queue<ManagerMessage<A>>>>,
+    middleware: Vec<Box<dyn Middleware>>,
+    budget: usize,
+    dedup_window: Option<usize>,
+    keep_alive: bool,
+    #[cfg(feature = "recording")]
+    recorder: Option<crate::recording::Recorder<A>>,
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    watchdog: Option<Watchdog>,
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    handler_timeout: Option<Duration>,
+}
+
+impl<A: Actor> ActorBuilder<A> {
+    pub(crate) fn new(actor: A) -> Self {
+        ActorBuilder {
+            actor,
+            config: MailboxConfig::default(),
+            queue: None,
+            middleware: Vec::new(),
+            budget: DEFAULT_BUDGET,
+            dedup_window: None,
+            keep_alive: false,
+            #[cfg(feature = "recording")]
+            recorder: None,
+            #[cfg(any(
+                doc,
+                feature = "with-tokio-0_2",
+                feature = "with-async_std-1",
+                feature = "with-wasm_bindgen-0_2",
+                feature = "with-smol-0_1"
+            ))]
+            watchdog: None,
+            #[cfg(any(
+                doc,
+                feature = "with-tokio-0_2",
+                feature = "with-async_std-1",
+                feature = "with-wasm_bindgen-0_2",
+                feature = "with-smol-0_1"
+            ))]
+            handler_timeout: None,
+        }
+    }
+
+    /// Create the actor's mailbox according to `config`, replacing any capacity, overflow policy,
+    /// or priority lanes set before.
+    pub fn mailbox(mut self, config: MailboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Bound the actor's mailbox to hold up to `capacity` messages at once, as with
+    /// [`MailboxConfig::bounded`](struct.MailboxConfig.html#method.bounded).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, since no message could ever be sent to the actor.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.config.capacity = MailboxConfig::bounded(capacity).capacity;
+        self
+    }
+
+    /// Set what should happen when a message is sent to the actor while its mailbox is full, as
+    /// with [`MailboxConfig::overflow_policy`](struct.MailboxConfig.html#method.overflow_policy).
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.config = self.config.overflow_policy(policy);
+        self
+    }
+
+    /// Set whether high priority messages are handled before normal priority ones, as with
+    /// [`MailboxConfig::priority_lanes`](struct.MailboxConfig.html#method.priority_lanes).
+    pub fn priority_lanes(mut self, enabled: bool) -> Self {
+        self.config = self.config.priority_lanes(enabled);
+        self
+    }
+
+    /// Hold the messages sent to the actor in a lock-free queue, as with
+    /// [`MailboxConfig::lock_free`](struct.MailboxConfig.html#method.lock_free).
+    #[cfg(feature = "lock-free")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "lock-free")))]
+    pub fn lock_free(mut self) -> Self {
+        self.config = self.config.lock_free();
+        self
+    }
+
+    /// Hold the messages sent to the actor in the given [`Mailbox`](trait.Mailbox.html) rather
+    /// than the default queue. Priority lanes have no effect on a custom mailbox, which decides
+    /// itself in which order messages are handled.
+    pub fn custom_mailbox<Q: Mailbox<A>>(mut self, mailbox: Q) -> Self {
+        self.queue = Some(Box::new(Custom::new(mailbox)));
+        self
+    }
+
+    /// Add [`Middleware`](trait.Middleware.html) which runs around every handler of the actor, as
+    /// with [`ActorManager::with_middleware`](struct.ActorManager.html#method.with_middleware).
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Set how many messages the actor handles in a row before it yields to the executor, as with
+    /// [`ActorManager::budget`](struct.ActorManager.html#method.budget).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero, since the actor could then never handle a message.
+    pub fn budget(mut self, budget: usize) -> Self {
+        assert!(budget > 0, "the budget of an actor must be at least one");
+        self.budget = budget;
+        self
+    }
+
+    /// Remember the ids of the last `capacity` messages the actor handled, so that messages with
+    /// the same id are not handled twice, as with
+    /// [`ActorManager::dedup_window`](struct.ActorManager.html#method.dedup_window).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn dedup_window(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the dedup window of an actor must hold at least one id"
+        );
+        self.dedup_window = Some(capacity);
+        self
+    }
+
+    /// Keep the actor running once all of its strong addresses have been dropped, as with
+    /// [`ActorManager::keep_alive`](struct.ActorManager.html#method.keep_alive).
+    pub fn keep_alive(mut self) -> Self {
+        self.keep_alive = true;
+        self
+    }
+
+    /// Record every message the actor handles with the given recorder, as with
+    /// [`ActorManager::record`](struct.ActorManager.html#method.record).
+    #[cfg(feature = "recording")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "recording")))]
+    pub fn record(mut self, recorder: crate::recording::Recorder<A>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Enable a watchdog which calls `hook` whenever a single handler of the actor has been
+    /// running for longer than `threshold`, as with
+    /// [`ActorManager::watchdog`](struct.ActorManager.html#method.watchdog).
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn watchdog<F>(mut self, threshold: Duration, hook: F) -> Self
+    where
+        F: Fn(SlowHandler) + Send + Sync + 'static,
+    {
+        self.watchdog = Some(Watchdog::new(threshold, hook));
+        self
+    }
+
+    /// Drop any handler of the actor which has been running for longer than `timeout`, as with
+    /// [`ActorManager::handler_timeout`](struct.ActorManager.html#method.handler_timeout).
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn handler_timeout(mut self, timeout: Duration) -> Self {
+        self.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the actor's address and manager in a ready-to-start state, as with
+    /// [`Actor::create`](trait.Actor.html#method.create).
+    pub fn create(self) -> (Address<A>, ActorManager<A>) {
+        let (addr, mut mgr) = ActorManager::start_with_queue(self.actor, self.config, self.queue);
+        mgr.ctx.middleware = self.middleware;
+        mgr.ctx.budget = self.budget;
+        mgr.ctx.dedup = self.dedup_window.map(DedupWindow::new);
+        mgr.ctx.receiver.set_keep_alive(self.keep_alive);
+        #[cfg(feature = "recording")]
+        {
+            mgr.ctx.recorder = self.recorder;
+        }
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        {
+            mgr.ctx.watchdog = self.watchdog;
+            mgr.ctx.handler_timeout = self.handler_timeout;
+        }
+        (addr, mgr)
+    }
+
+    /// Spawns the actor onto the global spawner, as with
+    /// [`Actor::spawn`](trait.Actor.html#method.spawn).
+    pub fn spawn(self) -> Address<A> {
+        let (addr, mgr) = self.create();
+        spawner::spawn(Box::pin(async move {
+            mgr.manage().await;
+        }));
+        addr
+    }
+
+    /// Spawns the actor with the given [`Spawner`](trait.Spawner.html), as with
+    /// [`Actor::spawn_on`](trait.Actor.html#method.spawn_on).
+    pub fn spawn_on<S: Spawner + ?Sized>(self, spawner: &S) -> Address<A> {
+        let (addr, mgr) = self.create();
+        spawner.spawn(Box::pin(async move {
+            mgr.manage().await;
+        }));
+        addr
+    }
+}
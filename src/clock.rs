@@ -0,0 +1,44 @@
+//! A monotonic clock to measure how long messages take to be handled and when actors were
+//! restarted. `std::time::Instant` is not available on `wasm32-unknown-unknown` (`Instant::now`
+//! panics there), so on wasm no time is measured and durations are `None`.
+
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Timestamp {
+    #[cfg(not(target_arch = "wasm32"))]
+    instant: Instant,
+}
+
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Timestamp {
+            #[cfg(not(target_arch = "wasm32"))]
+            instant: Instant::now(),
+        }
+    }
+
+    /// The time elapsed since this timestamp was taken.
+    pub(crate) fn elapsed(self) -> Option<Duration> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(self.instant.elapsed());
+
+        #[cfg(target_arch = "wasm32")]
+        return None;
+    }
+
+    /// The time elapsed since the `earlier` timestamp was taken, until this one was.
+    #[allow(dead_code)] // Only used with the `metrics` feature
+    pub(crate) fn duration_since(self, earlier: Timestamp) -> Option<Duration> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(self.instant.saturating_duration_since(earlier.instant));
+
+        #[cfg(target_arch = "wasm32")]
+        return {
+            let _ = earlier;
+            None
+        };
+    }
+}
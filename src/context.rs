@@ -1,10 +1,19 @@
-use crate::envelope::{MessageEnvelope, NonReturningEnvelope};
-use crate::manager::{ContinueManageLoop, ManagerMessage};
-use crate::{Actor, Address, Handler, KeepRunning, Message, WeakAddress};
-use futures::channel::mpsc::UnboundedReceiver;
-use futures::future::{self, Either, Future};
-use futures::StreamExt;
-use std::sync::Arc;
+use crate::dedup::DedupWindow;
+use crate::envelope::{CancellableEnvelope, MessageEnvelope, NonReturningEnvelope, Request};
+use crate::lifecycle::{self, LifecycleEventKind};
+use crate::mailbox::{self, Queue, Receiver};
+use crate::manager::{ContinueManageLoop, ExitReason, ManagerMessage, DEFAULT_BUDGET};
+use crate::{
+    Actor, ActorId, Address, AddressExt, Behavior, Handler, HandlerInfo, KeepRunning,
+    MailboxConfig, Message, Middleware, PanicAction, Priority, ResponseStream, WeakAddress,
+    Yielder,
+};
+use futures::future::{self, AbortHandle, Either, Future};
+use futures::{FutureExt, StreamExt};
+use std::mem;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
 #[cfg(any(
     doc,
     feature = "with-tokio-0_2",
@@ -12,7 +21,164 @@ use std::sync::Arc;
     feature = "with-wasm_bindgen-0_2",
     feature = "with-smol-0_1"
 ))]
-use {crate::AddressExt, std::time::Duration};
+use {
+    crate::envelope::ClosureEnvelope,
+    crate::runtime::timer,
+    crate::watchdog::Watchdog,
+    futures::channel::mpsc,
+    futures::{Sink, Stream},
+    std::time::Duration,
+};
+// Neither clock is available on `wasm32-unknown-unknown`, where reading either panics
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    )
+))]
+use std::time::{Instant, SystemTime};
+
+/// The message sent to an actor watching another actor with
+/// [`Context::watch`](struct.Context.html#method.watch) once the watched actor has stopped. It
+/// contains the id of the actor which stopped.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Terminated(pub ActorId);
+
+impl Message for Terminated {
+    type Result = ();
+}
+
+/// A handle through which an actor sends items into a sink attached with
+/// [`Context::attach_sink`](struct.Context.html#method.attach_sink). It can be cloned, and can be
+/// kept by the actor or moved into a task.
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+pub struct SinkHandle<T> {
+    sender: mpsc::UnboundedSender<T>,
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl<T> SinkHandle<T> {
+    /// Queue an item to be sent into the sink, returning whether the sink is still open. If the
+    /// sink was closed, because the actor stopped or sending into it failed, the item is discarded.
+    pub fn send(&self, item: T) -> bool {
+        self.sender.unbounded_send(item).is_ok()
+    }
+
+    /// Returns whether the sink was closed, so that no more items will be sent into it.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl<T> Clone for SinkHandle<T> {
+    fn clone(&self) -> Self {
+        SinkHandle {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl<T: Send + 'static> SinkHandle<T> {
+    /// Spawn the task which sends the items of the handle into the sink, returning the handle and
+    /// what closes the sink once the actor it is attached to stops.
+    pub(crate) fn attach<S>(sink: S) -> (Self, AttachedSink)
+    where
+        S: Sink<T> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::unbounded();
+        let open = Arc::new(());
+        let closing = sender.clone();
+        let attached = AttachedSink {
+            close: Box::new(move || closing.close_channel()),
+            open: Arc::downgrade(&open),
+        };
+
+        // Forwarding closes the sink once the channel has been closed and drained
+        crate::runtime::spawn(async move {
+            let _ = receiver.map(Ok).forward(sink).await;
+            drop(open);
+        });
+
+        (SinkHandle { sender }, attached)
+    }
+}
+
+/// A sink attached to an actor, which is closed once all items sent into it so far are sent
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub(crate) struct AttachedSink {
+    close: Box<dyn FnOnce() + Send>,
+    /// Dead once the sink has been closed
+    open: Weak<()>,
+}
+
+/// A handle to a notification scheduled with
+/// [`Context::notify_later`](struct.Context.html#method.notify_later),
+/// [`Context::notify_after`](struct.Context.html#method.notify_after), or
+/// [`Context::notify_interval`](struct.Context.html#method.notify_interval), through which it can
+/// be cancelled before it is handled. Dropping the handle does *not* cancel the notification.
+#[derive(Debug, Clone)]
+pub struct NotifyHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl NotifyHandle {
+    fn new() -> Self {
+        NotifyHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Cancel the notification. If it is already being or has already been handled, this does
+    /// nothing.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the notification was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
 
 /// `Context` is used to control how the actor is managed and to get the actor's address from inside
 /// of a message handler.
@@ -20,39 +186,220 @@ pub struct Context<A: Actor> {
     /// Whether the actor is running. It is changed by the `stop` method as a flag to the `ActorManager`
     /// for it to call the `stopping` method on the actor
     pub(crate) running: bool,
+    /// Set when the actor must exit its manage loop without the chance to prevent it in
+    /// `Actor::stopping`, such as after it decided to stop or restart in `Actor::panicked`
+    pub(crate) exit: Option<ExitReason>,
+    /// Set when the actor decided to restart in `Actor::panicked`
+    pub(crate) restart: bool,
+    /// Set once the context has been reset to run a new instance of the actor
+    pub(crate) restarted: bool,
+    /// Set once it was decided that the current instance of the actor stops, and the
+    /// `LifecycleEventKind::Stopping` event was emitted for it
+    stopping_emitted: bool,
     /// The address kept by the context to allow for the `Context::address` method to work.
-    address: WeakAddress<A>,
+    pub(crate) address: WeakAddress<A>,
     /// Notifications that must be stored for immediate processing.
     pub(crate) immediate_notifications: Vec<Box<dyn MessageEnvelope<Actor = A>>>,
-    pub(crate) receiver: UnboundedReceiver<ManagerMessage<A>>,
+    pub(crate) receiver: Receiver<ManagerMessage<A>>,
+    /// The behaviors switched to with `Context::become_behavior`, the last one being the current one
+    behaviors: Vec<Behavior<A>>,
+    /// Messages deferred with `Context::stash`, in the order that they were stashed
+    stash: Vec<Box<dyn MessageEnvelope<Actor = A>>>,
     /// The reference counter of the actor. This tells us how many external strong addresses
     /// (and weak addresses, but we don't care about those) exist to the actor.
     ref_counter: Arc<()>,
+    /// Handles to the tasks spawned on behalf of the actor which must be cancelled once it stops,
+    /// each with a weak reference which is dead once the task has finished.
+    tasks: Vec<(AbortHandle, Weak<()>)>,
+    /// The cancellation flags of the notifications scheduled by the actor, which are cancelled
+    /// once it stops so that none of them are handled by a restarted instance of the actor.
+    scheduled: Vec<Weak<AtomicBool>>,
+    /// The request whose message is currently being handled, if its sender waits for a response
+    pub(crate) request: Option<Weak<dyn Request>>,
+    /// Close the sinks attached with `Context::attach_sink` once the actor stops.
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    sinks: Vec<AttachedSink>,
+    /// Stop the children spawned with `Context::spawn_child` once the actor stops.
+    children: Vec<Box<dyn FnOnce() + Send>>,
+    /// Runs around every handler, added with `ActorManager::with_middleware`. The first one added
+    /// is the outermost.
+    pub(crate) middleware: Vec<Box<dyn Middleware>>,
+    /// How many messages are handled in a row before yielding to the executor, set by
+    /// `ActorManager::budget`
+    pub(crate) budget: usize,
+    /// The ids of the messages handled most recently, set by `ActorManager::dedup_window`
+    pub(crate) dedup: Option<DedupWindow>,
+    /// Records the messages the actor handles, set by `ActorManager::record`
+    #[cfg(feature = "recording")]
+    pub(crate) recorder: Option<crate::recording::Recorder<A>>,
+    /// What the actor has been doing, as reported by `introspection::actors`
+    #[cfg(feature = "introspection")]
+    pub(crate) activity: Arc<crate::introspection::Activity>,
+    /// How many events the actor has persisted or recovered from its journal, which is the
+    /// sequence number of the last event applied to it
+    #[cfg(feature = "persistence")]
+    pub(crate) persisted: u64,
+    /// Calls a hook if a handler takes too long, set by `ActorManager::watchdog`
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    pub(crate) watchdog: Option<Watchdog>,
+    /// How long a handler may run for before it is dropped, set by `ActorManager::handler_timeout`
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    pub(crate) handler_timeout: Option<std::time::Duration>,
 }
 
 impl<A: Actor> Context<A> {
+    /// Create a new context and the actor's mailbox according to `config`, holding its messages
+    /// in `queue` if one is given, returning it and the first strong address to the actor.
     pub(crate) fn new(
-        address: WeakAddress<A>,
-        receiver: UnboundedReceiver<ManagerMessage<A>>,
-        ref_counter: Arc<()>,
-    ) -> Self {
-        Context {
+        config: MailboxConfig,
+        queue: Option<Box<dyn Queue<ManagerMessage<A>>>>,
+    ) -> (Address<A>, Self) {
+        let (sender, receiver) = mailbox::mailbox(config, queue);
+        let ref_counter = Arc::new(());
+        let weak = WeakAddress {
+            sender: sender.clone(),
+            ref_counter: Arc::downgrade(&ref_counter),
+        };
+        let ctx = Context {
             running: true,
-            address,
+            exit: None,
+            restart: false,
+            restarted: false,
+            stopping_emitted: false,
+            address: weak,
             immediate_notifications: Vec::new(),
             receiver,
+            behaviors: Vec::new(),
+            stash: Vec::new(),
+            ref_counter: ref_counter.clone(),
+            tasks: Vec::new(),
+            scheduled: Vec::new(),
+            request: None,
+            #[cfg(any(
+                doc,
+                feature = "with-tokio-0_2",
+                feature = "with-async_std-1",
+                feature = "with-wasm_bindgen-0_2",
+                feature = "with-smol-0_1"
+            ))]
+            sinks: Vec::new(),
+            children: Vec::new(),
+            middleware: Vec::new(),
+            budget: DEFAULT_BUDGET,
+            dedup: None,
+            #[cfg(feature = "recording")]
+            recorder: None,
+            #[cfg(feature = "introspection")]
+            activity: Arc::new(crate::introspection::Activity::new()),
+            #[cfg(feature = "persistence")]
+            persisted: 0,
+            #[cfg(any(
+                doc,
+                feature = "with-tokio-0_2",
+                feature = "with-async_std-1",
+                feature = "with-wasm_bindgen-0_2",
+                feature = "with-smol-0_1"
+            ))]
+            watchdog: None,
+            #[cfg(any(
+                doc,
+                feature = "with-tokio-0_2",
+                feature = "with-async_std-1",
+                feature = "with-wasm_bindgen-0_2",
+                feature = "with-smol-0_1"
+            ))]
+            handler_timeout: None,
+        };
+        let addr = Address {
+            sender,
             ref_counter,
-        }
+        };
+
+        (addr, ctx)
+    }
+
+    /// Call `Actor::stopped` on the actor, cancel all tasks which were spawned on its behalf, close
+    /// its sinks, and stop its children.
+    pub(crate) fn stopped(&mut self, actor: &mut A) {
+        let id = self.address.id();
+        actor.stopped(self);
+        self.tasks.drain(..).for_each(|(task, _)| task.abort());
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        self.sinks.drain(..).for_each(|sink| (sink.close)());
+        self.scheduled
+            .drain(..)
+            .filter_map(|cancelled| cancelled.upgrade())
+            .for_each(|cancelled| cancelled.store(true, Ordering::Release));
+        self.children.drain(..).for_each(|stop| stop());
+        #[cfg(feature = "introspection")]
+        crate::introspection::deregister(id);
+        lifecycle::emit::<A>(id, LifecycleEventKind::Stopped);
+    }
+
+    /// Reset the context so that it can be used to run a new instance of the actor, keeping the
+    /// same mailbox.
+    pub(crate) fn reset(&mut self) {
+        self.running = true;
+        self.exit = None;
+        self.restart = false;
+        self.restarted = true;
+        self.stopping_emitted = false;
+        self.immediate_notifications.clear();
+        self.stash.clear();
+        self.behaviors.clear();
     }
 
     /// Stop the actor as soon as it has finished processing current message. This will mean that the
     /// [`Actor::stopping`](trait.Actor.html#method.stopping) method will be called.
     /// If that returns [`KeepRunning::No`](enum.KeepRunning.html#variant.No), any subsequent attempts
-    /// to send messages to this actor will return the [`Disconnected`](struct.Disconnected.html) error.
+    /// to send messages to this actor will return the [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected) error.
     pub fn stop(&mut self) {
         self.running = false;
     }
 
+    /// Stop the actor once it has handled all messages which are already in its mailbox. New
+    /// messages are no longer accepted, so any subsequent attempts to send messages to this actor
+    /// will return the [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected)
+    /// error. Unlike [`Context::stop`](struct.Context.html#method.stop), this cannot be prevented
+    /// by [`Actor::stopping`](trait.Actor.html#method.stopping), which is not called.
+    pub fn stop_gracefully(&mut self) {
+        self.address.sender.close();
+        self.emit_stopping();
+    }
+
+    /// Emit the `LifecycleEventKind::Stopping` event, once it has been decided that the actor
+    /// stops. This is only done once for each instance of the actor, however many ways it is
+    /// stopped in.
+    pub(crate) fn emit_stopping(&mut self) {
+        if !mem::replace(&mut self.stopping_emitted, true) {
+            lifecycle::emit::<A>(self.address.id(), LifecycleEventKind::Stopping);
+        }
+    }
+
     /// Get an address to the current actor if the actor is still running.
     pub fn address(&self) -> Option<Address<A>> {
         if self.running {
@@ -67,8 +414,111 @@ impl<A: Actor> Context<A> {
         }
     }
 
+    /// Get the id of the current actor, which is the same as that of its addresses (see
+    /// [`Address::id`](struct.Address.html#method.id)). Unlike
+    /// [`Context::address`](struct.Context.html#method.address), this is available even once the
+    /// actor is stopping.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// use xtra::ActorId;
+    ///
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// struct GetId;
+    /// impl Message for GetId {
+    ///     type Result = ActorId;
+    /// }
+    ///
+    /// impl SyncHandler<GetId> for MyActor {
+    ///     fn handle(&mut self, _: GetId, ctx: &mut Context<Self>) -> ActorId {
+    ///         ctx.id()
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.spawn();
+    ///     assert_eq!(addr.send(GetId).await, Ok(addr.id()));
+    ///     assert_ne!(addr.id(), MyActor.spawn().id());
+    /// }
+    /// ```
+    pub fn id(&self) -> ActorId {
+        self.address.id()
+    }
+
+    /// Whether the sender of the message currently being handled has stopped waiting for its
+    /// result, for instance because the future returned by [`Address::send`](struct.Address.html#method.send)
+    /// was dropped. A long-running handler can check this to give up early. Messages whose sender
+    /// stopped waiting before they were handled are not handled at all. This is always `false` for
+    /// messages which are not waited on, such as those sent with
+    /// [`AddressExt::do_send`](trait.AddressExt.html#method.do_send) or notifications.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// use futures::future::{self, Either};
+    ///
+    /// #[derive(Default)]
+    /// struct Worker(bool);
+    /// impl Actor for Worker {}
+    ///
+    /// struct Work;
+    /// impl Message for Work {
+    ///     type Result = ();
+    /// }
+    ///
+    /// #[async_trait::async_trait]
+    /// impl Handler<Work> for Worker {
+    ///     async fn handle(&mut self, _: Work, ctx: &mut Context<Self>) {
+    ///         while !ctx.is_cancelled() {
+    ///             smol::Timer::after(Duration::from_millis(1)).await;
+    ///         }
+    ///         self.0 = true;
+    ///     }
+    /// }
+    ///
+    /// struct GaveUp;
+    /// impl Message for GaveUp {
+    ///     type Result = bool;
+    /// }
+    ///
+    /// impl SyncHandler<GaveUp> for Worker {
+    ///     fn handle(&mut self, _: GaveUp, _: &mut Context<Self>) -> bool {
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Worker::default().spawn();
+    ///
+    ///     let timeout = smol::Timer::after(Duration::from_millis(10));
+    ///     let res = future::select(addr.send(Work), timeout).await;
+    ///     assert!(matches!(res, Either::Right(_))); // Timed out, so the request is dropped
+    ///     drop(res);
+    ///
+    ///     assert_eq!(addr.send(GaveUp).await, Ok(true));
+    /// }
+    /// ```
+    pub fn is_cancelled(&self) -> bool {
+        self.request
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .is_some_and(|request| request.is_cancelled())
+    }
+
     /// Check if the Context is still set to running, returning whether to continue the manage loop
     pub(crate) fn check_running(&mut self, actor: &mut A) -> bool {
+        if self.exit.is_some() {
+            return false;
+        }
+
         // Check if the context was stopped, and if so return, thereby dropping the
         // manager and calling `stopped` on the actor
         if !self.running {
@@ -77,6 +527,7 @@ impl<A: Actor> Context<A> {
             if keep_running == KeepRunning::Yes {
                 self.running = true;
             } else {
+                self.emit_stopping();
                 return false;
             }
         }
@@ -84,10 +535,187 @@ impl<A: Actor> Context<A> {
         true
     }
 
+    /// Handle an envelope, catching any panic in its handler and letting the actor decide how to
+    /// continue with `Actor::panicked`
+    async fn handle_envelope(
+        &mut self,
+        envelope: Box<dyn MessageEnvelope<Actor = A>>,
+        actor: &mut A,
+    ) {
+        let message_id = envelope.message_id();
+        if let (Some(id), Some(dedup)) = (message_id, &self.dedup) {
+            if dedup.contains(envelope.message_type(), id) {
+                envelope.reject_duplicate();
+                return;
+            }
+        }
+
+        #[cfg(feature = "recording")]
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&*envelope);
+        }
+        #[cfg(feature = "introspection")]
+        self.activity.handling();
+
+        #[cfg(feature = "metrics")]
+        let (labels, started) = {
+            let labels = [
+                ("actor", std::any::type_name::<A>()),
+                ("message", envelope.message_type()),
+            ];
+            let started = crate::clock::Timestamp::now();
+            if let Some(queued) = started.duration_since(envelope.sent_at()) {
+                metrics::histogram!("xtra_message_queue_seconds", queued.as_secs_f64(), &labels);
+            }
+            (labels, started)
+        };
+
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        let watchdog = self.watchdog.clone();
+        // The request is taken before handling, since the handler consumes the envelope
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        let timeout = self
+            .handler_timeout
+            .map(|timeout| (timeout, envelope.request()));
+
+        let info = HandlerInfo {
+            actor: std::any::type_name::<A>(),
+            message: envelope.message_type(),
+        };
+        #[cfg(any(
+            feature = "deadlock-detection",
+            feature = "recording",
+            feature = "topology"
+        ))]
+        let id = self.address.id();
+        // Taken out of the context for the duration of the handler, which borrows the context
+        let middleware = mem::take(&mut self.middleware);
+        let handling = AssertUnwindSafe(async {
+            let handler = middleware
+                .iter()
+                .rev()
+                .fold(envelope.handle(actor, self), |handler, m| {
+                    m.wrap(info, handler)
+                });
+            crate::pool::recycling(handler).await
+        })
+        .catch_unwind();
+        #[cfg(any(
+            feature = "deadlock-detection",
+            feature = "recording",
+            feature = "topology"
+        ))]
+        let handling = crate::current::in_actor(id, info.actor, handling);
+
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        let handling = async {
+            let (timeout, request) = match timeout {
+                Some(timeout) => timeout,
+                None => return Some(handling.await),
+            };
+
+            let mut handling = Box::pin(handling);
+            let expired = crate::runtime::delay(timeout);
+            match future::select(handling.as_mut(), Box::pin(expired)).await {
+                Either::Left((res, _)) => Some(res),
+                Either::Right(_) => {
+                    // Let the sender know before the result sender is dropped along with the
+                    // handler, so that it does not see the message as dropped instead
+                    if let Some(request) = request.and_then(|request| request.upgrade()) {
+                        request.time_out();
+                    }
+                    drop(handling);
+                    None
+                }
+            }
+        };
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        let res = match watchdog {
+            Some(watchdog) => watchdog.watch(info.actor, info.message, handling).await,
+            None => handling.await,
+        };
+        #[cfg(not(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        )))]
+        let res = Some(handling.await);
+
+        self.middleware = middleware;
+        // Let go of the result channel of the message, so that its memory can be recycled
+        self.request = None;
+
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(duration) = started.elapsed() {
+                metrics::histogram!("xtra_handler_seconds", duration.as_secs_f64(), &labels);
+            }
+            metrics::counter!("xtra_messages_handled", 1, &labels);
+        }
+
+        let res = match res {
+            Some(res) => res,
+            None => {
+                match actor.handler_timed_out(self, info.message) {
+                    PanicAction::Resume => return,
+                    PanicAction::Stop => {}
+                    PanicAction::Restart => self.restart = true,
+                }
+                self.running = false;
+                self.exit = Some(ExitReason::Stopped);
+                self.emit_stopping();
+                return;
+            }
+        };
+
+        // Only remember messages which were handled without panicking, so that a message whose
+        // handler panicked is handled again when it is redelivered
+        if let (Ok(()), Some(id), Some(dedup)) = (&res, message_id, &mut self.dedup) {
+            dedup.insert(info.message, id);
+        }
+
+        if let Err(payload) = res {
+            match actor.panicked(self, payload) {
+                PanicAction::Resume => return,
+                PanicAction::Stop => {}
+                PanicAction::Restart => self.restart = true,
+            }
+            self.running = false;
+            self.exit = Some(ExitReason::Panicked);
+            self.emit_stopping();
+        }
+    }
+
     /// Handles a single immediate notification, returning whether to continue the manage loop
     async fn handle_immediate_notification(&mut self, actor: &mut A) -> Option<bool> {
         if let Some(notification) = self.immediate_notifications.pop() {
-            notification.handle(actor, self).await;
+            self.handle_envelope(notification, actor).await;
             return Some(self.check_running(actor));
         }
         None
@@ -113,7 +741,7 @@ impl<A: Actor> Context<A> {
         match msg {
             // A new message from an address or a notification has arrived, so handle it
             ManagerMessage::Message(msg) | ManagerMessage::LateNotification(msg) => {
-                msg.handle(actor, self).await;
+                self.handle_envelope(msg, actor).await;
                 if !self.check_running(actor) {
                     return ContinueManageLoop::ExitImmediately;
                 }
@@ -123,11 +751,19 @@ impl<A: Actor> Context<A> {
             }
             // An address in the process of being dropped has realised that it could be the last
             // strong address to the actor, so we need to check if that is still the case, if so
-            // stopping the actor
+            // stopping the actor unless it is kept alive or prevents it in `Actor::stopping`
             ManagerMessage::LastAddress => {
                 // strong_count() == 1 manager holds a strong arc to the refcount
-                if Arc::strong_count(&self.ref_counter) == 1 {
-                    self.stop();
+                if Arc::strong_count(&self.ref_counter) == 1 && !self.receiver.is_kept_alive() {
+                    if actor.stopping(self) == KeepRunning::Yes {
+                        return ContinueManageLoop::Yes;
+                    }
+
+                    // `stopping` has already been asked, so the late notifications are handled
+                    // without asking it again
+                    self.running = false;
+                    self.exit = Some(ExitReason::AllAddressesDropped);
+                    self.emit_stopping();
                     return ContinueManageLoop::ProcessNotifications;
                 }
             }
@@ -157,7 +793,7 @@ impl<A: Actor> Context<A> {
     /// # Example
     ///
     /// ```
-    #[cfg_attr(doc, doc(include = "../examples/interleaved_messages.rs"))]
+    #[doc = include_str!("../examples/interleaved_messages.rs")]
     /// ```
     pub async fn handle_while<F, R>(&mut self, act: &mut A, mut fut: F) -> R
     where
@@ -185,6 +821,63 @@ impl<A: Actor> Context<A> {
         }
     }
 
+    /// Create a [`ResponseStream`](struct.ResponseStream.html) for a handler to respond with,
+    /// along with the [`Yielder`](struct.Yielder.html) which sends its items. The handler can
+    /// return right away while the actor keeps the yielder to send items later on, or moves it into
+    /// a task. The stream ends once all clones of the yielder have been dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// use futures::StreamExt;
+    /// use xtra::{ResponseStream, Yielder};
+    ///
+    /// #[derive(Default)]
+    /// struct Log {
+    ///     followers: Vec<Yielder<String>>,
+    /// }
+    /// impl Actor for Log {}
+    ///
+    /// struct Follow;
+    /// impl Message for Follow {
+    ///     type Result = ResponseStream<String>;
+    /// }
+    ///
+    /// impl SyncHandler<Follow> for Log {
+    ///     fn handle(&mut self, _: Follow, ctx: &mut Context<Self>) -> ResponseStream<String> {
+    ///         let (yielder, stream) = ctx.yielder();
+    ///         self.followers.push(yielder);
+    ///         stream
+    ///     }
+    /// }
+    ///
+    /// struct Append(&'static str);
+    /// impl Message for Append {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Append> for Log {
+    ///     fn handle(&mut self, Append(line): Append, _: &mut Context<Self>) {
+    ///         // Forget the followers which stopped following
+    ///         self.followers.retain(|follower| follower.send(line.to_string()));
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Log::default().spawn();
+    ///     let lines = addr.send_stream(Follow);
+    ///     addr.send(Append("hello")).await.unwrap();
+    ///     addr.send(Append("world")).await.unwrap();
+    ///
+    ///     assert_eq!(lines.take(2).collect::<Vec<_>>().await, vec!["hello", "world"]);
+    /// }
+    /// ```
+    pub fn yielder<T: Send + 'static>(&self) -> (Yielder<T>, ResponseStream<T>) {
+        ResponseStream::channel()
+    }
+
     /// Notify this actor with a message that is handled synchronously before any other messages
     /// from the general queue are processed (therefore, immediately). If multiple
     /// `notify_immediately` messages are queued, they will still be processed in the order that they
@@ -194,29 +887,310 @@ impl<A: Actor> Context<A> {
         M: Message,
         A: Handler<M>,
     {
-        let envelope = Box::new(NonReturningEnvelope::<A, M>::new(msg));
+        let envelope = crate::pool::boxed(NonReturningEnvelope::<A, M>::new(msg));
         self.immediate_notifications.push(envelope);
     }
 
+    /// Switch to a [`Behavior`](struct.Behavior.html), so that the messages which it handles are
+    /// handled by it rather than by the actor's [`Handler`](trait.Handler.html) implementations,
+    /// starting with the next message. The previous behavior is kept, and can be returned to with
+    /// [`Context::unbecome`](struct.Context.html#method.unbecome).
+    pub fn become_behavior(&mut self, behavior: Behavior<A>) {
+        self.behaviors.push(behavior);
+    }
+
+    /// Return to the behavior which was current before the last call to
+    /// [`Context::become_behavior`](struct.Context.html#method.become_behavior), or to the actor's
+    /// [`Handler`](trait.Handler.html) implementations if there was none.
+    pub fn unbecome(&mut self) {
+        self.behaviors.pop();
+    }
+
+    /// The behavior which the actor currently has, if any.
+    pub(crate) fn behavior(&self) -> Option<&Behavior<A>> {
+        self.behaviors.last()
+    }
+
+    /// Defer a message which the actor cannot handle yet, such as while it is still initialising,
+    /// so that it can be handled later once [`Context::unstash_all`](struct.Context.html#method.unstash_all)
+    /// is called. The message is handled like a notification then, so if it was sent with
+    /// [`Address::send`](struct.Address.html#method.send), its sender receives the result returned
+    /// by the handler which stashed it, not the one which eventually handles it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// #[derive(Default)]
+    /// struct Cache {
+    ///     ready: bool,
+    ///     handled: Vec<u32>,
+    /// }
+    /// impl Actor for Cache {}
+    ///
+    /// struct Lookup(u32);
+    /// impl Message for Lookup {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Loaded;
+    /// impl Message for Loaded {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Lookup> for Cache {
+    ///     fn handle(&mut self, Lookup(key): Lookup, ctx: &mut Context<Self>) {
+    ///         if self.ready {
+    ///             self.handled.push(key);
+    ///         } else {
+    ///             ctx.stash(Lookup(key)); // Try again once loaded
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Loaded> for Cache {
+    ///     fn handle(&mut self, _: Loaded, ctx: &mut Context<Self>) {
+    ///         self.ready = true;
+    ///         ctx.unstash_all();
+    ///     }
+    /// }
+    /// # struct Handled;
+    /// # impl Message for Handled {
+    /// #     type Result = Vec<u32>;
+    /// # }
+    /// # impl SyncHandler<Handled> for Cache {
+    /// #     fn handle(&mut self, _: Handled, _: &mut Context<Self>) -> Vec<u32> {
+    /// #         self.handled.clone()
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Cache::default().spawn();
+    ///     addr.do_send(Lookup(1)).unwrap();
+    ///     addr.do_send(Lookup(2)).unwrap();
+    ///     addr.do_send(Loaded).unwrap();
+    ///
+    ///     assert_eq!(addr.send(Handled).await, Ok(vec![1, 2]));
+    /// }
+    /// ```
+    pub fn stash<M>(&mut self, msg: M)
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        self.stash
+            .push(Box::new(NonReturningEnvelope::<A, M>::new(msg)));
+    }
+
+    /// Handle all messages deferred with [`Context::stash`](struct.Context.html#method.stash), in
+    /// the order that they were stashed, right after the current message and before any other
+    /// messages in the mailbox.
+    pub fn unstash_all(&mut self) {
+        // Immediate notifications are taken from the back
+        self.immediate_notifications
+            .extend(self.stash.drain(..).rev());
+    }
+
+    /// Create the handle of a notification, which is cancelled once the actor stops.
+    fn schedule(&mut self) -> NotifyHandle {
+        // Forget the notifications which have already been handled or dropped, but only once the
+        // list is full, so that scheduling many notifications at once takes linear time
+        if self.scheduled.len() == self.scheduled.capacity() {
+            self.scheduled
+                .retain(|cancelled| cancelled.strong_count() > 0);
+        }
+
+        let handle = NotifyHandle::new();
+        self.scheduled.push(Arc::downgrade(&handle.cancelled));
+        handle
+    }
+
     /// Notify this actor with a message that is handled after any other messages from the general
     /// queue are processed. This is almost equivalent to calling send on
     /// [`Context::address()`](struct.Context.html#method.address), but will never fail to send
-    /// the message.
-    pub fn notify_later<M>(&mut self, msg: M)
+    /// the message, even if the actor's mailbox is bounded and full. The returned
+    /// [`NotifyHandle`](struct.NotifyHandle.html) can be used to cancel the notification before it
+    /// is handled. It is cancelled automatically if the actor stops before handling it.
+    pub fn notify_later<M>(&mut self, msg: M) -> NotifyHandle
     where
         M: Message,
         A: Handler<M>,
     {
+        let handle = self.schedule();
         let envelope = NonReturningEnvelope::<A, M>::new(msg);
-        let _ = self
-            .address
-            .sender
-            .unbounded_send(ManagerMessage::LateNotification(Box::new(envelope)));
+        let envelope = CancellableEnvelope::new(Box::new(envelope), handle.cancelled.clone());
+        let _ = self.address.sender.force_send(
+            ManagerMessage::LateNotification(Box::new(envelope)),
+            Priority::Normal,
+        );
+        handle
     }
 
-    /// Notify the actor with a synchronously handled message every interval until it is stopped
-    /// (either directly with [`Context::stop`](struct.Context.html#method.stop), or for a lack of
-    /// strong [`Address`es](struct.Address.html)). This does not take priority over other messages.
+    /// Watch another actor, so that this actor is sent a [`Terminated`](struct.Terminated.html)
+    /// message containing the other actor's id once it has stopped (i.e after its
+    /// [`Actor::stopped`](trait.Actor.html#method.stopped) method was called). If the other actor
+    /// has already stopped, the message is sent right away. Watching does not keep either actor
+    /// alive.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::Terminated;
+    /// struct Child;
+    /// impl Actor for Child {}
+    ///
+    /// struct Watch(Address<Child>);
+    /// impl Message for Watch {
+    ///     type Result = ();
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Parent(Vec<xtra::ActorId>);
+    /// impl Actor for Parent {}
+    ///
+    /// impl SyncHandler<Watch> for Parent {
+    ///     fn handle(&mut self, Watch(child): Watch, ctx: &mut Context<Self>) {
+    ///         ctx.watch(&child);
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Terminated> for Parent {
+    ///     fn handle(&mut self, Terminated(id): Terminated, _: &mut Context<Self>) {
+    ///         self.0.push(id);
+    ///     }
+    /// }
+    ///
+    /// struct Stopped;
+    /// impl Message for Stopped {
+    ///     type Result = Vec<xtra::ActorId>;
+    /// }
+    ///
+    /// impl SyncHandler<Stopped> for Parent {
+    ///     fn handle(&mut self, _: Stopped, _: &mut Context<Self>) -> Vec<xtra::ActorId> {
+    ///         self.0.clone()
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let parent = Parent::default().spawn();
+    ///     let child = Child.spawn();
+    ///     let id = child.id();
+    ///
+    ///     parent.send(Watch(child)).await.unwrap(); // Dropping the last address stops the child
+    ///     # smol::Timer::after(std::time::Duration::from_millis(50)).await;
+    ///     assert_eq!(parent.send(Stopped).await, Ok(vec![id]));
+    /// }
+    /// ```
+    pub fn watch<B: Actor>(&mut self, address: &Address<B>)
+    where
+        A: Handler<Terminated>,
+    {
+        let id = address.id();
+        let watcher = self.address.sender.clone();
+        let notify = move || {
+            let envelope = NonReturningEnvelope::<A, Terminated>::new(Terminated(id));
+            let _ = watcher.force_send(
+                ManagerMessage::Message(Box::new(envelope)),
+                Priority::Normal,
+            );
+        };
+
+        if let Err(notify) = address.sender.on_drop(notify) {
+            notify();
+        }
+    }
+
+    /// Spawn another actor as a child of this one (see [`Actor::spawn`](trait.Actor.html#method.spawn)),
+    /// returning its address. The child is watched (see [`Context::watch`](struct.Context.html#method.watch)),
+    /// so this actor is sent a [`Terminated`](struct.Terminated.html) message once it stops, and it
+    /// is stopped gracefully (see [`Context::stop_gracefully`](struct.Context.html#method.stop_gracefully))
+    /// once this actor stops or restarts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::Terminated;
+    /// struct Child;
+    /// impl Actor for Child {}
+    ///
+    /// struct Parent;
+    /// impl Actor for Parent {}
+    ///
+    /// impl SyncHandler<Terminated> for Parent {
+    ///     fn handle(&mut self, Terminated(id): Terminated, _: &mut Context<Self>) {
+    ///         println!("Child {:?} stopped", id);
+    ///     }
+    /// }
+    ///
+    /// struct SpawnChild;
+    /// impl Message for SpawnChild {
+    ///     type Result = Address<Child>;
+    /// }
+    ///
+    /// impl SyncHandler<SpawnChild> for Parent {
+    ///     fn handle(&mut self, _: SpawnChild, ctx: &mut Context<Self>) -> Address<Child> {
+    ///         ctx.spawn_child(Child)
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let parent = Parent.spawn();
+    ///     let child = parent.send(SpawnChild).await.unwrap();
+    ///
+    ///     parent.stop_gracefully();
+    ///     child.join().await; // The child is stopped along with its parent
+    ///     assert!(!child.is_connected());
+    /// }
+    /// ```
+    pub fn spawn_child<B: Actor>(&mut self, actor: B) -> Address<B>
+    where
+        A: Handler<Terminated>,
+    {
+        let child = actor.spawn();
+        self.watch(&child);
+
+        let weak = child.downgrade();
+        self.children.push(Box::new(move || weak.stop_gracefully()));
+        child
+    }
+
+    /// Spawn a future onto the runtime executor on behalf of the actor. Unlike spawning it
+    /// directly, the future is cancelled once the actor stops (or is restarted by a supervisor),
+    /// so it cannot outlive the actor and find its address disconnected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// struct Worker(Arc<AtomicBool>);
+    ///
+    /// impl Actor for Worker {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         let finished = self.0.clone();
+    ///         ctx.spawn(async move {
+    ///             smol::Timer::after(Duration::from_millis(100)).await;
+    ///             finished.store(true, Ordering::SeqCst);
+    ///         });
+    ///         ctx.stop();
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let finished = Arc::new(AtomicBool::new(false));
+    ///     let _addr = Worker(finished.clone()).spawn();
+    ///     smol::Timer::after(Duration::from_millis(200)).await;
+    ///     assert!(!finished.load(Ordering::SeqCst)); // Cancelled when the worker stopped
+    /// }
+    /// ```
     #[cfg(any(
         doc,
         feature = "with-tokio-0_2",
@@ -224,72 +1198,488 @@ impl<A: Actor> Context<A> {
         feature = "with-wasm_bindgen-0_2",
         feature = "with-smol-0_1"
     ))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-tokio-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-async_std-1")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-smol-0_1")))]
-    pub fn notify_interval<F, M>(&mut self, duration: Duration, constructor: F)
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn spawn<F>(&mut self, fut: F)
     where
-        F: Send + 'static + Fn() -> M,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_cancellable(fut);
+    }
+
+    /// Spawn a future onto the runtime executor on behalf of the actor like
+    /// [`Context::spawn`](struct.Context.html#method.spawn), and send the message it resolves to
+    /// to the actor once it is done. The actor keeps handling other messages in the meantime. If
+    /// the actor stops first, the future is cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// struct Fetcher(Option<String>);
+    /// impl Actor for Fetcher {}
+    ///
+    /// struct Fetch;
+    /// impl Message for Fetch {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Fetched(String);
+    /// impl Message for Fetched {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Fetch> for Fetcher {
+    ///     fn handle(&mut self, _: Fetch, ctx: &mut Context<Self>) {
+    ///         ctx.spawn_then_notify(async {
+    ///             smol::Timer::after(Duration::from_millis(10)).await; // Some slow request
+    ///             Fetched("body".to_string())
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Fetched> for Fetcher {
+    ///     fn handle(&mut self, Fetched(body): Fetched, _: &mut Context<Self>) {
+    ///         self.0 = Some(body);
+    ///     }
+    /// }
+    /// # struct Get;
+    /// # impl Message for Get {
+    /// #     type Result = Option<String>;
+    /// # }
+    /// # impl SyncHandler<Get> for Fetcher {
+    /// #     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Option<String> {
+    /// #         self.0.clone()
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Fetcher(None).spawn();
+    ///     addr.do_send(Fetch).unwrap();
+    ///     smol::Timer::after(Duration::from_millis(50)).await;
+    ///     assert_eq!(addr.send(Get).await, Ok(Some("body".to_string())));
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn spawn_then_notify<F, M>(&mut self, fut: F)
+    where
+        F: Future<Output = M> + Send + 'static,
+        M: Message,
+        A: Handler<M>,
+    {
+        let addr = self.address.clone();
+
+        self.spawn_cancellable(async move {
+            let msg = fut.await;
+            let _ = addr.do_send_async(msg).await;
+        });
+    }
+
+    /// Spawn a future onto the runtime executor which is cancelled once the actor stops.
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn spawn_cancellable<F>(&mut self, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        // Forget the tasks which have already finished, so that spawning many does not leak
+        self.tasks.retain(|(_, running)| running.strong_count() > 0);
+
+        let running = Arc::new(());
+        let (fut, handle) = future::abortable(fut);
+        self.tasks.push((handle, Arc::downgrade(&running)));
+        crate::runtime::spawn(fut.map(move |_| drop(running)));
+    }
+
+    /// Attach a stream to this actor, so that every item it produces is sent to the actor as a
+    /// message. The stream is forwarded until it ends or the actor stops. Sending waits for space
+    /// in the actor's mailbox if it is bounded and full (and its overflow policy is to block), and
+    /// does not prevent the actor from being dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use futures::stream;
+    /// # use std::time::Duration;
+    /// struct Tick;
+    /// impl Message for Tick {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Counter(u32);
+    ///
+    /// impl Actor for Counter {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.add_stream(stream::iter(vec![Tick, Tick, Tick]));
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Tick> for Counter {
+    ///     fn handle(&mut self, _: Tick, _: &mut Context<Self>) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    /// # struct Get;
+    /// # impl Message for Get {
+    /// #     type Result = u32;
+    /// # }
+    /// # impl SyncHandler<Get> for Counter {
+    /// #     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> u32 {
+    /// #         self.0
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Counter(0).spawn();
+    ///     smol::Timer::after(Duration::from_millis(50)).await;
+    ///     assert_eq!(addr.send(Get).await, Ok(3));
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn add_stream<S, M>(&mut self, stream: S)
+    where
+        S: Stream<Item = M> + Send + 'static,
         M: Message,
         A: Handler<M>,
     {
         let addr = self.address.clone();
 
-        #[cfg(feature = "with-tokio-0_2")]
-        tokio::spawn(async move {
-            let mut timer = tokio::time::interval(duration);
-            loop {
-                timer.tick().await;
-                if let Err(_) = addr.do_send(constructor()) {
+        self.spawn_cancellable(async move {
+            futures::pin_mut!(stream);
+            while let Some(msg) = stream.next().await {
+                if addr.do_send_async(msg).await.is_err() {
                     break;
                 }
             }
         });
+    }
 
-        #[cfg(feature = "with-async_std-1")]
-        {
-            use async_std::prelude::FutureExt;
-            async_std::task::spawn(async move {
-                loop {
-                    futures::future::ready(()).delay(duration.clone()).await;
-                    if let Err(_) = addr.do_send(constructor()) {
-                        break;
-                    }
-                }
-            });
-        }
+    /// Attach a sink to this actor, such as the write half of a socket, returning the
+    /// [`SinkHandle`](struct.SinkHandle.html) through which the actor sends items into it. The items
+    /// are sent into the sink in order by a task spawned onto the runtime executor, so sending
+    /// through the handle never waits for the sink. Once the actor stops (or is restarted by a
+    /// supervisor), the items which were already sent through the handle are still sent into the
+    /// sink, after which it is flushed and closed. If sending into the sink fails, it is dropped and
+    /// the handle is closed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// use futures::channel::mpsc;
+    /// use futures::StreamExt;
+    /// use xtra::SinkHandle;
+    ///
+    /// // The sink stands in for the write half of a connection
+    /// struct Connection(Option<mpsc::UnboundedSender<String>>, Option<SinkHandle<String>>);
+    ///
+    /// impl Actor for Connection {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         let sink = self.0.take().unwrap();
+    ///         self.1 = Some(ctx.attach_sink(sink));
+    ///     }
+    /// }
+    ///
+    /// struct Write(&'static str);
+    /// impl Message for Write {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Write> for Connection {
+    ///     fn handle(&mut self, Write(line): Write, ctx: &mut Context<Self>) {
+    ///         self.1.as_ref().unwrap().send(line.to_string());
+    ///         if line == "bye" {
+    ///             ctx.stop();
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (tx, rx) = mpsc::unbounded();
+    ///     let addr = Connection(Some(tx), None).spawn();
+    ///     addr.send(Write("hello")).await.unwrap();
+    ///     addr.send(Write("bye")).await.unwrap();
+    ///
+    ///     // Everything written before the actor stopped was flushed, and the sink was closed
+    ///     assert_eq!(rx.collect::<Vec<_>>().await, vec!["hello", "bye"]);
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn attach_sink<S, T>(&mut self, sink: S) -> SinkHandle<T>
+    where
+        S: Sink<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (handle, attached) = SinkHandle::attach(sink);
+        self.add_sink(attached);
+        handle
+    }
 
-        #[cfg(feature = "with-wasm_bindgen-0_2")]
-        {
-            use futures_timer::Delay;
-            wasm_bindgen_futures::spawn_local(async move {
-                loop {
-                    Delay::new(duration.clone()).await;
-                    if let Err(_) = addr.do_send(constructor()) {
-                        break;
-                    }
-                }
-            })
-        }
+    /// Close the sink once the actor stops.
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    pub(crate) fn add_sink(&mut self, sink: AttachedSink) {
+        // Forget the sinks which have already been closed, so that attaching many does not leak
+        self.sinks.retain(|sink| sink.open.strong_count() > 0);
+        self.sinks.push(sink);
+    }
 
-        #[cfg(feature = "with-smol-0_1")]
-        {
-            use smol::Timer;
-            smol::Task::spawn(async move {
-                loop {
-                    Timer::after(duration.clone()).await;
-                    if let Err(_) = addr.do_send(constructor()) {
-                        break;
-                    }
-                }
-            })
-            .detach();
-        }
+    /// Run `f` on the blocking thread pool of the runtime, so that synchronous CPU-bound or IO work
+    /// does not hold up the actor's manage loop, and send the message it returns to the actor once
+    /// it is done. The actor keeps handling other messages in the meantime. If the actor stops
+    /// first, the message is discarded (although `f` still runs to completion). On wasm, there is
+    /// no blocking thread pool, so `f` is run on the current thread instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// struct Hasher(Option<u64>);
+    ///
+    /// struct Digest(u64);
+    /// impl Message for Digest {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl Actor for Hasher {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.spawn_blocking(|| {
+    ///             // Some expensive synchronous work
+    ///             Digest((0..1_000_000u64).sum())
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Digest> for Hasher {
+    ///     fn handle(&mut self, Digest(digest): Digest, _: &mut Context<Self>) {
+    ///         self.0 = Some(digest);
+    ///     }
+    /// }
+    /// # struct Get;
+    /// # impl Message for Get {
+    /// #     type Result = Option<u64>;
+    /// # }
+    /// # impl SyncHandler<Get> for Hasher {
+    /// #     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Option<u64> {
+    /// #         self.0
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Hasher(None).spawn();
+    ///     smol::Timer::after(Duration::from_millis(100)).await;
+    ///     assert_eq!(addr.send(Get).await, Ok(Some(499_999_500_000)));
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn spawn_blocking<F, M>(&mut self, f: F)
+    where
+        F: FnOnce() -> M + Send + 'static,
+        M: Message,
+        A: Handler<M>,
+    {
+        let addr = self.address.clone();
+
+        self.spawn_cancellable(async move {
+            let msg = crate::runtime::spawn_blocking(f).await;
+            let _ = addr.do_send_async(msg).await;
+        });
+    }
+
+    /// Notify the actor with a synchronously handled message every interval until it is stopped
+    /// (either directly with [`Context::stop`](struct.Context.html#method.stop), or for a lack of
+    /// strong [`Address`es](struct.Address.html)), or until the interval is cancelled through the
+    /// returned [`NotifyHandle`](struct.NotifyHandle.html). This does not take priority over other
+    /// messages. Like [`Context::notify_later`](struct.Context.html#method.notify_later), the
+    /// notifications are delivered even if the actor's mailbox is bounded and full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// struct Tick;
+    /// impl Message for Tick {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Ticker(u32);
+    ///
+    /// impl Actor for Ticker {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.notify_interval(Duration::from_millis(10), || Tick);
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Tick> for Ticker {
+    ///     fn handle(&mut self, _: Tick, ctx: &mut Context<Self>) {
+    ///         self.0 += 1;
+    ///         if self.0 == 3 {
+    ///             ctx.stop(); // Also stops the interval
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Ticker(0).spawn();
+    ///     smol::Timer::after(Duration::from_millis(100)).await;
+    ///     assert!(!addr.is_connected());
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn notify_interval<F, M>(&mut self, duration: Duration, constructor: F) -> NotifyHandle
+    where
+        F: Send + 'static + Fn() -> M,
+        M: Message,
+        A: Handler<M>,
+    {
+        let handle = self.schedule();
+        let cancelled = handle.cancelled.clone();
+        let sender = self.address.sender.clone();
+
+        timer::schedule_interval(duration, handle.cancelled.clone(), move || {
+            let envelope = NonReturningEnvelope::<A, M>::new(constructor());
+            let envelope = CancellableEnvelope::new(Box::new(envelope), cancelled.clone());
+            let msg = ManagerMessage::Message(Box::new(envelope));
+            sender.force_send(msg, Priority::Normal).is_ok()
+        });
+
+        handle
     }
 
     /// Notify the actor with a synchronously handled message after a certain duration has elapsed.
-    /// This does not take priority over other messages.
+    /// This does not take priority over other messages. The returned
+    /// [`NotifyHandle`](struct.NotifyHandle.html) can be used to cancel the notification before it
+    /// is handled, which is useful to implement timeouts. If the actor stops first, the timer is
+    /// cancelled along with it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::NotifyHandle;
+    /// # use std::time::Duration;
+    /// struct Timeout;
+    /// impl Message for Timeout {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Response;
+    /// impl Message for Response {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Request {
+    ///     timeout: Option<NotifyHandle>,
+    ///     timed_out: bool,
+    /// }
+    ///
+    /// impl Actor for Request {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         self.timeout = Some(ctx.notify_after(Duration::from_millis(50), Timeout));
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Timeout> for Request {
+    ///     fn handle(&mut self, _: Timeout, _: &mut Context<Self>) {
+    ///         self.timed_out = true;
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Response> for Request {
+    ///     fn handle(&mut self, _: Response, _: &mut Context<Self>) {
+    ///         // The response arrived in time, so call off the timeout
+    ///         self.timeout.take().unwrap().cancel();
+    ///     }
+    /// }
+    /// # struct TimedOut;
+    /// # impl Message for TimedOut {
+    /// #     type Result = bool;
+    /// # }
+    /// # impl SyncHandler<TimedOut> for Request {
+    /// #     fn handle(&mut self, _: TimedOut, _: &mut Context<Self>) -> bool {
+    /// #         self.timed_out
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Request { timeout: None, timed_out: false }.spawn();
+    ///     addr.send(Response).await.unwrap();
+    ///     smol::Timer::after(Duration::from_millis(100)).await;
+    ///     assert_eq!(addr.send(TimedOut).await, Ok(false));
+    /// }
+    /// ```
     #[cfg(any(
         doc,
         feature = "with-tokio-0_2",
@@ -297,49 +1687,303 @@ impl<A: Actor> Context<A> {
         feature = "with-wasm_bindgen-0_2",
         feature = "with-smol-0_1"
     ))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-tokio-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-async_std-1")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-smol-0_1")))]
-    pub fn notify_after<M>(&mut self, duration: Duration, notification: M)
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn notify_after<M>(&mut self, duration: Duration, notification: M) -> NotifyHandle
     where
         M: Message,
         A: Handler<M>,
     {
-        let addr = self.address.clone();
+        let handle = self.schedule();
+        let cancelled = handle.cancelled.clone();
+        let sender = self.address.sender.clone();
 
-        #[cfg(feature = "with-tokio-0_2")]
-        tokio::spawn(async move {
-            tokio::time::delay_for(duration).await;
-            let _ = addr.do_send(notification);
+        timer::schedule(duration, handle.cancelled.clone(), move || {
+            let envelope = NonReturningEnvelope::<A, M>::new(notification);
+            let envelope = CancellableEnvelope::new(Box::new(envelope), cancelled);
+            let _ = sender.force_send(
+                ManagerMessage::Message(Box::new(envelope)),
+                Priority::Normal,
+            );
         });
 
-        #[cfg(feature = "with-async_std-1")]
-        {
-            use async_std::prelude::FutureExt;
-            async_std::task::spawn(async move {
-                futures::future::ready(()).delay(duration.clone()).await;
-                let _ = addr.do_send(notification);
-            });
-        }
+        handle
+    }
 
-        #[cfg(feature = "with-wasm_bindgen-0_2")]
-        {
-            use futures_timer::Delay;
-            wasm_bindgen_futures::spawn_local(async move {
-                Delay::new(duration.clone()).await;
-                let _ = addr.do_send(notification);
-            })
-        }
+    /// Notify the actor with a synchronously handled message at the given instant, like
+    /// [`Context::notify_after`](struct.Context.html#method.notify_after) but with a deadline
+    /// rather than a delay. If the instant has already passed, the message is sent right away.
+    /// Since there is no monotonic clock on `wasm32-unknown-unknown`, this is not available there.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::{Duration, Instant};
+    /// struct Deadline;
+    /// impl Message for Deadline {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Auction {
+    ///     closes_at: Instant,
+    /// }
+    ///
+    /// impl Actor for Auction {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.notify_at(self.closes_at, Deadline);
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Deadline> for Auction {
+    ///     fn handle(&mut self, _: Deadline, ctx: &mut Context<Self>) {
+    ///         assert!(Instant::now() >= self.closes_at);
+    ///         ctx.stop();
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let closes_at = Instant::now() + Duration::from_millis(20);
+    ///     let addr = Auction { closes_at }.spawn();
+    ///     smol::Timer::after(Duration::from_millis(100)).await;
+    ///     assert!(!addr.is_connected());
+    /// }
+    /// ```
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        )
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn notify_at<M>(&mut self, instant: Instant, notification: M) -> NotifyHandle
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        let duration = instant.saturating_duration_since(Instant::now());
+        self.notify_after(duration, notification)
+    }
 
-        #[cfg(feature = "with-smol-0_1")]
-        {
-            use smol::Timer;
-            smol::Task::spawn(async move {
-                Timer::after(duration.clone()).await;
-                let _ = addr.do_send(notification);
-            })
-            .detach();
-        }
+    /// Notify the actor with a synchronously handled message at the given time of the system
+    /// clock, such as for a job which must run at a certain time of day. Anything which converts
+    /// into a `SystemTime` can be given, including a `chrono::DateTime`. The delay until then is
+    /// worked out when this is called, so changes to the system clock afterwards do not move the
+    /// notification. If the time has already passed, the message is sent right away. This is not
+    /// available on `wasm32-unknown-unknown`, where the standard library has no system clock.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::{Duration, SystemTime};
+    /// struct Backup;
+    /// impl Message for Backup {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Scheduler {
+    ///     backed_up: bool,
+    /// }
+    ///
+    /// impl Actor for Scheduler {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.notify_at_time(SystemTime::now() + Duration::from_millis(20), Backup);
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Backup> for Scheduler {
+    ///     fn handle(&mut self, _: Backup, _: &mut Context<Self>) {
+    ///         self.backed_up = true;
+    ///     }
+    /// }
+    /// # struct BackedUp;
+    /// # impl Message for BackedUp {
+    /// #     type Result = bool;
+    /// # }
+    /// # impl SyncHandler<BackedUp> for Scheduler {
+    /// #     fn handle(&mut self, _: BackedUp, _: &mut Context<Self>) -> bool {
+    /// #         self.backed_up
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Scheduler { backed_up: false }.spawn();
+    ///     smol::Timer::after(Duration::from_millis(100)).await;
+    ///     assert_eq!(addr.send(BackedUp).await, Ok(true));
+    /// }
+    /// ```
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        )
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn notify_at_time<T, M>(&mut self, time: T, notification: M) -> NotifyHandle
+    where
+        T: Into<SystemTime>,
+        M: Message,
+        A: Handler<M>,
+    {
+        let duration = time
+            .into()
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        self.notify_after(duration, notification)
+    }
+
+    /// Run the closure with access to the actor and its context after a certain duration has
+    /// elapsed, like [`Context::notify_after`](struct.Context.html#method.notify_after) but
+    /// without a message type. The closure is run inside the actor's manage loop, so it does not
+    /// take priority over other messages. The returned [`NotifyHandle`](struct.NotifyHandle.html)
+    /// can be used to cancel it before it is run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// struct Session {
+    ///     expired: bool,
+    /// }
+    ///
+    /// impl Actor for Session {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.run_later(Duration::from_millis(10), |session, ctx| {
+    ///             session.expired = true;
+    ///             ctx.stop();
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Session { expired: false }.spawn();
+    ///     smol::Timer::after(Duration::from_millis(50)).await;
+    ///     assert!(!addr.is_connected());
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn run_later<F>(&mut self, duration: Duration, f: F) -> NotifyHandle
+    where
+        F: FnOnce(&mut A, &mut Context<A>) + Send + 'static,
+    {
+        let handle = self.schedule();
+        let cancelled = handle.cancelled.clone();
+        let sender = self.address.sender.clone();
+
+        timer::schedule(duration, handle.cancelled.clone(), move || {
+            let envelope = ClosureEnvelope::new(f);
+            let envelope = CancellableEnvelope::new(Box::new(envelope), cancelled);
+            let _ = sender.force_send(
+                ManagerMessage::Message(Box::new(envelope)),
+                Priority::Normal,
+            );
+        });
+
+        handle
+    }
+
+    /// Run the closure with access to the actor and its context every interval until the actor is
+    /// stopped or the interval is cancelled through the returned
+    /// [`NotifyHandle`](struct.NotifyHandle.html), like
+    /// [`Context::notify_interval`](struct.Context.html#method.notify_interval) but without a
+    /// message type. This is useful for simple periodic maintenance.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::collections::HashMap;
+    /// # use std::time::Duration;
+    /// struct Cache {
+    ///     entries: HashMap<u32, &'static str>,
+    ///     evictions: u32,
+    /// }
+    ///
+    /// impl Actor for Cache {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.run_interval(Duration::from_millis(10), |cache, _| {
+    ///             cache.entries.clear();
+    ///             cache.evictions += 1;
+    ///         });
+    ///     }
+    /// }
+    /// # struct Evictions;
+    /// # impl Message for Evictions {
+    /// #     type Result = u32;
+    /// # }
+    /// # impl SyncHandler<Evictions> for Cache {
+    /// #     fn handle(&mut self, _: Evictions, _: &mut Context<Self>) -> u32 {
+    /// #         self.evictions
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Cache { entries: HashMap::new(), evictions: 0 }.spawn();
+    ///     smol::Timer::after(Duration::from_millis(50)).await;
+    ///     assert!(addr.send(Evictions).await.unwrap() > 0);
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn run_interval<F>(&mut self, duration: Duration, f: F) -> NotifyHandle
+    where
+        F: Fn(&mut A, &mut Context<A>) + Send + Sync + 'static,
+    {
+        let handle = self.schedule();
+        let cancelled = handle.cancelled.clone();
+        let sender = self.address.sender.clone();
+        let f = Arc::new(f);
+
+        timer::schedule_interval(duration, handle.cancelled.clone(), move || {
+            let f = f.clone();
+            let envelope =
+                ClosureEnvelope::new(move |act: &mut A, ctx: &mut Context<A>| f(act, ctx));
+            let envelope = CancellableEnvelope::new(Box::new(envelope), cancelled.clone());
+            let msg = ManagerMessage::Message(Box::new(envelope));
+            sender.force_send(msg, Priority::Normal).is_ok()
+        });
+
+        handle
     }
 }
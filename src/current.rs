@@ -0,0 +1,39 @@
+//! The actor whose handler is being polled on the current thread, which is used by the
+//! `deadlock-detection` feature to know which actor is waiting on a response, by the `recording`
+//! feature to know which actor sent a message, and by the `topology` feature to know which type of
+//! actor sent it.
+
+use crate::ActorId;
+use futures::future::{self, Future};
+use std::cell::Cell;
+use std::pin::pin;
+
+thread_local! {
+    /// The id and type name of the actor whose handler is being polled on this thread
+    static CURRENT: Cell<Option<(ActorId, &'static str)>> = const { Cell::new(None) };
+}
+
+/// Run the future handling a message of the actor with the given id and type name, marking it as
+/// the current actor whenever the future is polled.
+pub(crate) async fn in_actor<F: Future>(id: ActorId, actor: &'static str, fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    future::poll_fn(|ctx| {
+        let outer = CURRENT.with(|current| current.replace(Some((id, actor))));
+        let res = fut.as_mut().poll(ctx);
+        CURRENT.with(|current| current.set(outer));
+        res
+    })
+    .await
+}
+
+/// The id of the actor whose handler is being polled on this thread, if any.
+#[allow(dead_code)] // Not used with only the `topology` feature
+pub(crate) fn actor() -> Option<ActorId> {
+    CURRENT.with(Cell::get).map(|(id, _)| id)
+}
+
+/// The type name of the actor whose handler is being polled on this thread, if any.
+#[allow(dead_code)] // Only used with the `topology` feature
+pub(crate) fn actor_type() -> Option<&'static str> {
+    CURRENT.with(Cell::get).map(|(_, actor)| actor)
+}
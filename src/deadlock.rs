@@ -0,0 +1,95 @@
+//! Detection of actors which wait on each other's responses, enabled by the `deadlock-detection`
+//! feature. While an actor's handler is being polled, it is the current actor of the thread, so
+//! that a response future polled by the handler knows which actor is waiting on it. The actors
+//! waiting on each other form a graph, and a cycle in it means that none of them can ever continue.
+
+use crate::ActorId;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// For each actor, the actors it is waiting on a response from. An actor appears once for every
+/// response it is waiting on, since a handler could wait on several at once.
+fn graph() -> MutexGuard<'static, HashMap<ActorId, Vec<ActorId>>> {
+    static GRAPH: OnceLock<Mutex<HashMap<ActorId, Vec<ActorId>>>> = OnceLock::new();
+    let graph = GRAPH.get_or_init(Default::default);
+    // The graph is never left in an inconsistent state, so poisoning can safely be ignored
+    graph.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Record that the current actor, if there is one, is waiting on a response from `to`.
+///
+/// # Panics
+///
+/// Panics if `to` is already waiting on the current actor, directly or through other actors, or if
+/// `to` is the current actor, since none of them could then ever continue.
+pub(crate) fn wait(to: ActorId) -> Option<Waiting> {
+    let from = crate::current::actor()?;
+    let mut graph = graph();
+
+    if let Some(mut cycle) = path(&graph, to, from) {
+        drop(graph);
+        cycle.insert(0, from);
+        let cycle: Vec<_> = cycle.iter().map(|id| format!("{:?}", id)).collect();
+        panic!(
+            "deadlock detected: {} are waiting on each other's responses",
+            cycle.join(" -> ")
+        );
+    }
+
+    graph.entry(from).or_default().push(to);
+    Some(Waiting { from, to })
+}
+
+/// Find the actors through which `from` is waiting on `to`, if it is, including both of them.
+fn path(
+    graph: &HashMap<ActorId, Vec<ActorId>>,
+    from: ActorId,
+    to: ActorId,
+) -> Option<Vec<ActorId>> {
+    let mut path = vec![from];
+    let mut visited = vec![from];
+    // The actors at each depth of the search which are still to be tried
+    let mut stack = vec![graph.get(&from).cloned().unwrap_or_default()];
+
+    while let Some(next) = stack.last_mut() {
+        if *path.last().unwrap() == to {
+            return Some(path);
+        }
+
+        match next.pop() {
+            Some(id) if !visited.contains(&id) => {
+                visited.push(id);
+                path.push(id);
+                stack.push(graph.get(&id).cloned().unwrap_or_default());
+            }
+            Some(_) => {}
+            None => {
+                path.pop();
+                stack.pop();
+            }
+        }
+    }
+
+    None
+}
+
+/// Held by a response future while an actor is waiting on it, and removes that from the graph once
+/// dropped.
+pub(crate) struct Waiting {
+    from: ActorId,
+    to: ActorId,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        let mut graph = graph();
+        if let Some(waiting_on) = graph.get_mut(&self.from) {
+            if let Some(i) = waiting_on.iter().position(|id| *id == self.to) {
+                waiting_on.swap_remove(i);
+            }
+            if waiting_on.is_empty() {
+                graph.remove(&self.from);
+            }
+        }
+    }
+}
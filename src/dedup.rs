@@ -0,0 +1,40 @@
+use std::collections::{HashSet, VecDeque};
+
+/// The ids of the messages an actor handled most recently, set with
+/// `ActorManager::dedup_window`, so that messages with an id which is still in the window are not
+/// handled again. Ids are kept per message type, since the ids of different types are unrelated.
+pub(crate) struct DedupWindow {
+    capacity: usize,
+    seen: HashSet<(&'static str, u64)>,
+    /// The ids in `seen` in the order they were handled, so that the oldest can be forgotten
+    order: VecDeque<(&'static str, u64)>,
+}
+
+impl DedupWindow {
+    pub(crate) fn new(capacity: usize) -> Self {
+        DedupWindow {
+            capacity,
+            seen: HashSet::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn contains(&self, message_type: &'static str, id: u64) -> bool {
+        self.seen.contains(&(message_type, id))
+    }
+
+    /// Remember that the message with the given id was handled, forgetting the oldest id if the
+    /// window is full.
+    pub(crate) fn insert(&mut self, message_type: &'static str, id: u64) {
+        if !self.seen.insert((message_type, id)) {
+            return;
+        }
+
+        self.order.push_back((message_type, id));
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+    }
+}
@@ -1,15 +1,65 @@
-use crate::address::MessageResponseFuture;
+use crate::address::{DoSendFuture, MessageResponseFuture};
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+use crate::clock::Timestamp;
+use crate::manager::ManagerMessage;
+use crate::response::{self, Receiver, Sender};
 use crate::*;
-use futures::channel::oneshot::{self, Receiver, Sender};
-#[cfg(feature = "nightly")]
 use futures::future;
 use futures::{Future, FutureExt, Sink};
+use std::any::{Any, TypeId};
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// The type of future returned by `Envelope::handle`
 type Fut<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
 
+/// The span in which a message is handled, recording the actor and message types and how long the
+/// message took to handle. It is a child of the span which was current when the message was sent,
+/// so that traces connect across actors.
+#[cfg(feature = "tracing")]
+fn handler_span<A: Actor, M: Message>(sender_span: &tracing::Span) -> tracing::Span {
+    tracing::debug_span!(
+        parent: sender_span,
+        "handle",
+        actor = std::any::type_name::<A>(),
+        message = std::any::type_name::<M>(),
+        duration_us = tracing::field::Empty,
+    )
+}
+
+/// Run the future handling a message inside of its `handler_span`.
+#[cfg(feature = "tracing")]
+fn instrument<'a, A: Actor, M: Message>(fut: Fut<'a>, sender_span: &tracing::Span) -> Fut<'a> {
+    use tracing::Instrument;
+
+    let span = handler_span::<A, M>(sender_span);
+    let record = span.clone();
+    let start = Timestamp::now();
+    pool::pinned(pool::recycling(fut).instrument(span).map(move |()| {
+        if let Some(duration) = start.elapsed() {
+            record.record("duration_us", duration.as_micros() as u64);
+        }
+    }))
+}
+
+/// The sending half of the channel over which the result of a message is returned, through which
+/// `Context::is_cancelled` can tell whether the response is still being waited for.
+pub(crate) trait Request: Send + Sync {
+    fn is_cancelled(&self) -> bool;
+
+    /// Let the sender know that the handler of the message was dropped because it timed out
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn time_out(&self);
+}
+
 /// A message envelope is a struct that encapsulates a message and its return channel sender (if applicable).
 /// Firstly, this allows us to be generic over returning and non-returning messages (as all use the
 /// same `handle` method and return the same pinned & boxed future), but almost more importantly it
@@ -47,21 +97,108 @@ pub(crate) trait MessageEnvelope: Send {
         act: &'a mut Self::Actor,
         ctx: &'a mut Context<Self::Actor>,
     ) -> Fut<'a>;
+
+    /// The name of the type of the message in this envelope, used in `HandlerInfo`, `SlowHandler`,
+    /// and to label metrics
+    fn message_type(&self) -> &'static str;
+
+    /// When the message was sent, to measure how long it waited in the mailbox
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp;
+
+    /// The message in this envelope, so that a custom `Mailbox` can inspect it
+    fn message(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    /// The actor whose handler sent the message, if it was sent from one, to record it
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        None
+    }
+
+    /// The type of the message if it was sent to be handled in a batch by a `BatchHandler`, so
+    /// that the envelopes which can be batched together can be found in the mailbox
+    fn batch_type(&self) -> Option<TypeId> {
+        None
+    }
+
+    /// Take the message out of an envelope for which `batch_type` is `Some`
+    fn into_batched(self: Box<Self>) -> Option<Box<dyn Any + Send>> {
+        None
+    }
+
+    /// Whether the message was sent to be handled by a `ReadHandler`, so that the envelopes which
+    /// can be handled concurrently with it can be found in the mailbox
+    fn is_read(&self) -> bool {
+        false
+    }
+
+    /// Turn an envelope for which `is_read` is `true` into one which only needs shared access to
+    /// the actor
+    fn into_read(self: Box<Self>) -> Option<Box<dyn ReadEnvelope<Actor = Self::Actor>>> {
+        None
+    }
+
+    /// The type of the message if it was sent to replace any message of the same type which is
+    /// still queued, so that the envelope to replace can be found in the mailbox
+    fn conflation_type(&self) -> Option<TypeId> {
+        None
+    }
+
+    /// The `Message::message_id` of the message, so that the actor's dedup window can tell
+    /// whether it has already been handled
+    fn message_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Drop the envelope without handling it because the message has already been handled,
+    /// letting its sender know if it waits for a result
+    fn reject_duplicate(self: Box<Self>) {}
+
+    /// The request of the message if its sender waits for a result, to let it know when the
+    /// handler times out
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn request(&self) -> Option<std::sync::Weak<dyn Request>> {
+        None
+    }
 }
 
 /// An envelope that returns a result from a message. Constructed by the `AddressExt::do_send` method.
 pub(crate) struct ReturningEnvelope<A: Actor, M: Message> {
     message: M,
     result_sender: Sender<M::Result>,
+    /// The span which was current when the message was sent
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    sent_at: Timestamp,
+    /// The actor whose handler sent the message
+    #[cfg(feature = "recording")]
+    sender: Option<ActorId>,
     phantom: PhantomData<A>,
 }
 
 impl<A: Actor, M: Message> ReturningEnvelope<A, M> {
     pub(crate) fn new(message: M) -> (Self, Receiver<M::Result>) {
-        let (tx, rx) = oneshot::channel();
+        #[cfg(feature = "topology")]
+        crate::topology::sent::<A, M>();
+        let (tx, rx) = response::channel();
         let envelope = ReturningEnvelope {
             message,
             result_sender: tx,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+            #[cfg(feature = "metrics")]
+            sent_at: Timestamp::now(),
+            #[cfg(feature = "recording")]
+            sender: crate::current::actor(),
             phantom: PhantomData,
         };
 
@@ -73,6 +210,43 @@ impl<A: Actor, M: Message> ReturningEnvelope<A, M> {
 impl<A: Handler<M>, M: Message> MessageEnvelope for ReturningEnvelope<A, M> {
     type Actor = A;
 
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        Some(&self.message)
+    }
+
+    fn message_id(&self) -> Option<u64> {
+        self.message.message_id()
+    }
+
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        self.sender
+    }
+
+    fn reject_duplicate(self: Box<Self>) {
+        self.result_sender.reject_duplicate();
+    }
+
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn request(&self) -> Option<std::sync::Weak<dyn Request>> {
+        Some(self.result_sender.request())
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+
     fn handle<'a>(
         self: Box<Self>,
         act: &'a mut Self::Actor,
@@ -81,12 +255,25 @@ impl<A: Handler<M>, M: Message> MessageEnvelope for ReturningEnvelope<A, M> {
         let Self {
             message,
             result_sender,
+            #[cfg(feature = "tracing")]
+            span,
             ..
-        } = *self;
-        Box::pin(act.handle(message, ctx).map(move |r| {
+        } = pool::unbox(self);
+        if result_sender.is_canceled() {
+            // Nobody is waiting for the result anymore, so don't bother computing it
+            return pool::pinned(future::ready(()));
+        }
+
+        ctx.request = Some(result_sender.request());
+        let fut = crate::behavior::dispatch(act, message, ctx);
+        let fut = pool::pinned(pool::recycling(fut).map(move |r| {
             // We don't actually care if the receiver is listening
             let _ = result_sender.send(r);
-        }))
+        }));
+
+        #[cfg(feature = "tracing")]
+        let fut = instrument::<A, M>(fut, &span);
+        fut
     }
 }
 
@@ -97,6 +284,43 @@ where
 {
     type Actor = A;
 
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        Some(&self.message)
+    }
+
+    fn message_id(&self) -> Option<u64> {
+        self.message.message_id()
+    }
+
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        self.sender
+    }
+
+    fn reject_duplicate(self: Box<Self>) {
+        self.result_sender.reject_duplicate();
+    }
+
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn request(&self) -> Option<std::sync::Weak<dyn Request>> {
+        Some(self.result_sender.request())
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+
     default fn handle<'a>(
         self: Box<Self>,
         act: &'a mut Self::Actor,
@@ -105,12 +329,24 @@ where
         let Self {
             message,
             result_sender,
+            #[cfg(feature = "tracing")]
+            span,
             ..
         } = *self;
-        Box::pin(act.handle(message, ctx).map(move |r| {
+        if result_sender.is_canceled() {
+            // Nobody is waiting for the result anymore, so don't bother computing it
+            return Box::pin(future::ready(()));
+        }
+
+        ctx.request = Some(result_sender.request());
+        let fut = Box::pin(act.handle(message, ctx).map(move |r| {
             // We don't actually care if the receiver is listening
             let _ = result_sender.send(r);
-        }))
+        }));
+
+        #[cfg(feature = "tracing")]
+        let fut = instrument::<A, M>(fut, &span);
+        fut
     }
 }
 
@@ -119,18 +355,32 @@ impl<A, M> MessageEnvelope for ReturningEnvelope<A, M>
 where
     A: Handler<M> + SyncHandler<M>,
     M: Message,
-
 {
     fn handle<'a>(
         self: Box<Self>,
         act: &'a mut Self::Actor,
         ctx: &'a mut Context<Self::Actor>,
     ) -> Fut<'a> {
+        #[cfg(feature = "tracing")]
+        let (span, start) = (handler_span::<A, M>(&self.span), Timestamp::now());
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        if self.result_sender.is_canceled() {
+            return Box::pin(future::ready(()));
+        }
+
+        let result_sender = self.result_sender;
+        ctx.request = Some(result_sender.request());
         let message_result = SyncHandler::handle(act, self.message, ctx);
 
         // We don't actually care if the receiver is listening
-        let _ = self.result_sender.send(message_result);
+        let _ = result_sender.send(message_result);
 
+        #[cfg(feature = "tracing")]
+        if let Some(duration) = start.elapsed() {
+            span.record("duration_us", duration.as_micros() as u64);
+        }
         Box::pin(future::ready(()))
     }
 }
@@ -139,13 +389,29 @@ where
 /// method.
 pub(crate) struct NonReturningEnvelope<A: Actor, M: Message> {
     message: M,
+    /// The span which was current when the message was sent
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    sent_at: Timestamp,
+    /// The actor whose handler sent the message
+    #[cfg(feature = "recording")]
+    sender: Option<ActorId>,
     phantom: PhantomData<A>,
 }
 
 impl<A: Actor, M: Message> NonReturningEnvelope<A, M> {
     pub(crate) fn new(message: M) -> Self {
+        #[cfg(feature = "topology")]
+        crate::topology::sent::<A, M>();
         NonReturningEnvelope {
             message,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+            #[cfg(feature = "metrics")]
+            sent_at: Timestamp::now(),
+            #[cfg(feature = "recording")]
+            sender: crate::current::actor(),
             phantom: PhantomData,
         }
     }
@@ -155,12 +421,40 @@ impl<A: Actor, M: Message> NonReturningEnvelope<A, M> {
 impl<A: Handler<M>, M: Message> MessageEnvelope for NonReturningEnvelope<A, M> {
     type Actor = A;
 
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        Some(&self.message)
+    }
+
+    fn message_id(&self) -> Option<u64> {
+        self.message.message_id()
+    }
+
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        self.sender
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+
     fn handle<'a>(
         self: Box<Self>,
         act: &'a mut Self::Actor,
         ctx: &'a mut Context<Self::Actor>,
     ) -> Fut<'a> {
-        Box::pin(act.handle(self.message, ctx).map(|_| ()))
+        let this = pool::unbox(self);
+        let fut = crate::behavior::dispatch(act, this.message, ctx);
+        let fut = pool::pinned(pool::recycling(fut).map(|_| ()));
+
+        #[cfg(feature = "tracing")]
+        let fut = instrument::<A, M>(fut, &this.span);
+        fut
     }
 }
 
@@ -171,12 +465,40 @@ where
 {
     type Actor = A;
 
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        Some(&self.message)
+    }
+
+    fn message_id(&self) -> Option<u64> {
+        self.message.message_id()
+    }
+
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        self.sender
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+
     default fn handle<'a>(
         self: Box<Self>,
         act: &'a mut Self::Actor,
         ctx: &'a mut Context<Self::Actor>,
     ) -> Fut<'a> {
-        Box::pin(act.handle(self.message, ctx).map(|_| ()))
+        #[cfg(feature = "tracing")]
+        let span = self.span.clone();
+        let fut = Box::pin(act.handle(self.message, ctx).map(|_| ()));
+
+        #[cfg(feature = "tracing")]
+        let fut = instrument::<A, M>(fut, &span);
+        fut
     }
 }
 
@@ -191,20 +513,430 @@ where
         act: &'a mut Self::Actor,
         ctx: &'a mut Context<Self::Actor>,
     ) -> Fut<'a> {
+        #[cfg(feature = "tracing")]
+        let (span, start) = (handler_span::<A, M>(&self.span), Timestamp::now());
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
         SyncHandler::handle(act, self.message, ctx);
+
+        #[cfg(feature = "tracing")]
+        if let Some(duration) = start.elapsed() {
+            span.record("duration_us", duration.as_micros() as u64);
+        }
+        Box::pin(future::ready(()))
+    }
+}
+
+/// An envelope for a message to be handled in a batch by a `BatchHandler`. Constructed by the
+/// `AddressExt::do_send_batched` method. When it is handled, the envelopes for the same type of
+/// message directly behind it in the mailbox are taken out and handled along with it.
+pub(crate) struct BatchEnvelope<A: Actor, M: Message> {
+    message: M,
+    /// The span which was current when the message was sent
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    sent_at: Timestamp,
+    phantom: PhantomData<A>,
+}
+
+impl<A: Actor, M: Message> BatchEnvelope<A, M> {
+    pub(crate) fn new(message: M) -> Self {
+        #[cfg(feature = "topology")]
+        crate::topology::sent::<A, M>();
+        BatchEnvelope {
+            message,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+            #[cfg(feature = "metrics")]
+            sent_at: Timestamp::now(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<A, M> MessageEnvelope for BatchEnvelope<A, M>
+where
+    A: BatchHandler<M>,
+    M: Message<Result = ()>,
+{
+    type Actor = A;
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        Some(&self.message)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+
+    fn batch_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<M>())
+    }
+
+    fn into_batched(self: Box<Self>) -> Option<Box<dyn Any + Send>> {
+        Some(Box::new(self.message))
+    }
+
+    fn handle<'a>(
+        self: Box<Self>,
+        act: &'a mut Self::Actor,
+        ctx: &'a mut Context<Self::Actor>,
+    ) -> Fut<'a> {
+        #[cfg(feature = "tracing")]
+        let span = self.span.clone();
+
+        let mut messages = vec![self.message];
+        let is_batched = |msg: &ManagerMessage<A>| match msg {
+            ManagerMessage::Message(envelope) => envelope.batch_type() == Some(TypeId::of::<M>()),
+            _ => false,
+        };
+        while messages.len() < A::MAX_BATCH_SIZE {
+            let message = match ctx.receiver.try_recv_if(is_batched) {
+                Some(ManagerMessage::Message(envelope)) => envelope.into_batched(),
+                _ => None,
+            };
+            match message.and_then(|message| message.downcast::<M>().ok()) {
+                Some(message) => messages.push(*message),
+                None => break,
+            }
+        }
+
+        let fut = act.handle_batch(messages, ctx);
+
+        #[cfg(feature = "tracing")]
+        let fut = instrument::<A, M>(fut, &span);
+        fut
+    }
+}
+
+/// An envelope whose message is handled by a `ReadHandler`, which only needs shared access to the
+/// actor, so that it can be handled concurrently with others.
+pub(crate) trait ReadEnvelope: Send {
+    /// The type of actor that this envelope carries a message for
+    type Actor: Actor;
+
+    /// Handle the message by calling `ReadHandler::handle`, returning its result over the return
+    /// channel
+    fn handle_read<'a>(self: Box<Self>, act: &'a Self::Actor) -> Fut<'a>;
+}
+
+/// An envelope for a message handled by a `ReadHandler`, constructed by the
+/// `AddressExt::send_read` method. When it is handled, the envelopes for read-only messages
+/// directly behind it in the mailbox are taken out and handled concurrently with it.
+pub(crate) struct ReadOnlyEnvelope<A: Actor, M: Message> {
+    message: M,
+    result_sender: Sender<M::Result>,
+    /// The span which was current when the message was sent
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "metrics")]
+    sent_at: Timestamp,
+    phantom: PhantomData<A>,
+}
+
+impl<A: Actor, M: Message> ReadOnlyEnvelope<A, M> {
+    pub(crate) fn new(message: M) -> (Self, Receiver<M::Result>) {
+        #[cfg(feature = "topology")]
+        crate::topology::sent::<A, M>();
+        let (tx, rx) = response::channel();
+        let envelope = ReadOnlyEnvelope {
+            message,
+            result_sender: tx,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+            #[cfg(feature = "metrics")]
+            sent_at: Timestamp::now(),
+            phantom: PhantomData,
+        };
+
+        (envelope, rx)
+    }
+}
+
+impl<A: ReadHandler<M>, M: Message> ReadEnvelope for ReadOnlyEnvelope<A, M> {
+    type Actor = A;
+
+    fn handle_read<'a>(self: Box<Self>, act: &'a Self::Actor) -> Fut<'a> {
+        let Self {
+            message,
+            result_sender,
+            #[cfg(feature = "tracing")]
+            span,
+            ..
+        } = *self;
+        if result_sender.is_canceled() {
+            // Nobody is waiting for the result anymore, so don't bother computing it
+            return Box::pin(future::ready(()));
+        }
+
+        let fut = Box::pin(ReadHandler::handle(act, message).map(move |r| {
+            // We don't actually care if the receiver is listening
+            let _ = result_sender.send(r);
+        }));
+
+        #[cfg(feature = "tracing")]
+        let fut = instrument::<A, M>(fut, &span);
+        fut
+    }
+}
+
+impl<A: ReadHandler<M>, M: Message> MessageEnvelope for ReadOnlyEnvelope<A, M> {
+    type Actor = A;
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        Some(&self.message)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+
+    fn is_read(&self) -> bool {
+        true
+    }
+
+    fn into_read(self: Box<Self>) -> Option<Box<dyn ReadEnvelope<Actor = A>>> {
+        Some(self)
+    }
+
+    fn handle<'a>(
+        self: Box<Self>,
+        act: &'a mut Self::Actor,
+        ctx: &'a mut Context<Self::Actor>,
+    ) -> Fut<'a> {
+        let mut reads: Vec<Box<dyn ReadEnvelope<Actor = A>>> = vec![self];
+        let is_read = |msg: &ManagerMessage<A>| match msg {
+            ManagerMessage::Message(envelope) => envelope.is_read(),
+            _ => false,
+        };
+        while reads.len() < ctx.budget {
+            match ctx.receiver.try_recv_if(is_read) {
+                Some(ManagerMessage::Message(envelope)) => reads.extend(envelope.into_read()),
+                _ => break,
+            }
+        }
+
+        // The actor is only borrowed immutably by the handlers, so they can all run at once
+        let act: &'a A = act;
+        let handlers = reads.into_iter().map(|read| read.handle_read(act));
+        Box::pin(future::join_all(handlers).map(|_| ()))
+    }
+}
+
+/// An envelope for a message which replaces any message of the same type which is still queued.
+/// Constructed by the `AddressExt::do_send_conflated` method.
+pub(crate) struct ConflatingEnvelope<A: Actor, M: Message>(NonReturningEnvelope<A, M>);
+
+impl<A: Actor, M: Message> ConflatingEnvelope<A, M> {
+    pub(crate) fn new(message: M) -> Self {
+        ConflatingEnvelope(NonReturningEnvelope::new(message))
+    }
+}
+
+impl<A: Handler<M>, M: Message> MessageEnvelope for ConflatingEnvelope<A, M> {
+    type Actor = A;
+
+    fn message_type(&self) -> &'static str {
+        self.0.message_type()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        self.0.message()
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.0.sent_at()
+    }
+
+    fn conflation_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<M>())
+    }
+
+    fn message_id(&self) -> Option<u64> {
+        self.0.message_id()
+    }
+
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        self.0.sender()
+    }
+
+    fn handle<'a>(
+        self: Box<Self>,
+        act: &'a mut Self::Actor,
+        ctx: &'a mut Context<Self::Actor>,
+    ) -> Fut<'a> {
+        Box::new(self.0).handle(act, ctx)
+    }
+}
+
+/// An envelope for a closure which is run with access to the actor and its context rather than a
+/// message. Constructed by `Context::run_later` and `Context::run_interval`.
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub(crate) struct ClosureEnvelope<A: Actor, F> {
+    f: F,
+    #[cfg(feature = "metrics")]
+    sent_at: Timestamp,
+    phantom: PhantomData<fn(&mut A)>,
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl<A: Actor, F> ClosureEnvelope<A, F> {
+    pub(crate) fn new(f: F) -> Self {
+        ClosureEnvelope {
+            f,
+            #[cfg(feature = "metrics")]
+            sent_at: Timestamp::now(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl<A, F> MessageEnvelope for ClosureEnvelope<A, F>
+where
+    A: Actor,
+    F: FnOnce(&mut A, &mut Context<A>) + Send,
+{
+    type Actor = A;
+
+    fn handle<'a>(
+        self: Box<Self>,
+        act: &'a mut Self::Actor,
+        ctx: &'a mut Context<Self::Actor>,
+    ) -> Fut<'a> {
+        (self.f)(act, ctx);
         Box::pin(future::ready(()))
     }
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<F>()
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.sent_at
+    }
+}
+
+/// An envelope wrapping another envelope, which is only handled if it was not cancelled in the
+/// meantime. Constructed by `Context::notify_later` and `Context::notify_after`.
+pub(crate) struct CancellableEnvelope<A: Actor> {
+    envelope: Box<dyn MessageEnvelope<Actor = A>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<A: Actor> CancellableEnvelope<A> {
+    pub(crate) fn new(
+        envelope: Box<dyn MessageEnvelope<Actor = A>>,
+        cancelled: Arc<AtomicBool>,
+    ) -> Self {
+        CancellableEnvelope {
+            envelope,
+            cancelled,
+        }
+    }
+}
+
+impl<A: Actor> MessageEnvelope for CancellableEnvelope<A> {
+    type Actor = A;
+
+    fn handle<'a>(
+        self: Box<Self>,
+        act: &'a mut Self::Actor,
+        ctx: &'a mut Context<Self::Actor>,
+    ) -> Fut<'a> {
+        if self.cancelled.load(Ordering::Acquire) {
+            Box::pin(future::ready(()))
+        } else {
+            self.envelope.handle(act, ctx)
+        }
+    }
+
+    fn message_type(&self) -> &'static str {
+        self.envelope.message_type()
+    }
+
+    fn message(&self) -> Option<&dyn Any> {
+        self.envelope.message()
+    }
+
+    fn message_id(&self) -> Option<u64> {
+        self.envelope.message_id()
+    }
+
+    #[cfg(feature = "recording")]
+    fn sender(&self) -> Option<ActorId> {
+        self.envelope.sender()
+    }
+
+    fn reject_duplicate(self: Box<Self>) {
+        self.envelope.reject_duplicate()
+    }
+
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn request(&self) -> Option<std::sync::Weak<dyn Request>> {
+        self.envelope.request()
+    }
+
+    #[cfg(feature = "metrics")]
+    fn sent_at(&self) -> Timestamp {
+        self.envelope.sent_at()
+    }
 }
 
 /// Similar to `MessageEnvelope`, but used to erase the type of the actor instead of the channel.
 /// This is used in `message_channel.rs`. All of its methods map to an equivalent method in
 /// `Address` or `AddressExt`
 pub(crate) trait AddressEnvelope<M: Message>:
-    Sink<M, Error = Disconnected> + Unpin + Send
+    Sink<M, Error = SendError> + Unpin + Send + Sync
 {
     fn is_connected(&self) -> bool;
-    fn do_send(&self, message: M) -> Result<(), Disconnected>;
+    fn len(&self) -> usize;
+    fn capacity(&self) -> Option<usize>;
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>>;
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>>;
+    fn do_send_async(&self, message: M) -> DoSendFuture;
     fn send(&self, message: M) -> MessageResponseFuture<M>;
+    fn id(&self) -> ActorId;
+    fn clone_channel(&self) -> Box<dyn AddressEnvelope<M>>;
 
     /// It is an error for this method to be called on an already weak address
     fn downgrade(&self) -> Box<dyn AddressEnvelope<M>>;
@@ -219,14 +951,38 @@ where
         AddressExt::is_connected(self)
     }
 
-    fn do_send(&self, message: M) -> Result<(), Disconnected> {
+    fn len(&self) -> usize {
+        AddressExt::len(self)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        AddressExt::capacity(self)
+    }
+
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>> {
         AddressExt::do_send(self, message)
     }
 
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        AddressExt::try_send(self, message)
+    }
+
+    fn do_send_async(&self, message: M) -> DoSendFuture {
+        AddressExt::do_send_async(self, message)
+    }
+
     fn send(&self, message: M) -> MessageResponseFuture<M> {
         AddressExt::send(self, message)
     }
 
+    fn id(&self) -> ActorId {
+        Address::id(self)
+    }
+
+    fn clone_channel(&self) -> Box<dyn AddressEnvelope<M>> {
+        Box::new(self.clone())
+    }
+
     fn downgrade(&self) -> Box<dyn AddressEnvelope<M>> {
         Box::new(Address::downgrade(self))
     }
@@ -241,14 +997,38 @@ where
         AddressExt::is_connected(self)
     }
 
-    fn do_send(&self, message: M) -> Result<(), Disconnected> {
+    fn len(&self) -> usize {
+        AddressExt::len(self)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        AddressExt::capacity(self)
+    }
+
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>> {
         AddressExt::do_send(self, message)
     }
 
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        AddressExt::try_send(self, message)
+    }
+
+    fn do_send_async(&self, message: M) -> DoSendFuture {
+        AddressExt::do_send_async(self, message)
+    }
+
     fn send(&self, message: M) -> MessageResponseFuture<M> {
         AddressExt::send(self, message)
     }
 
+    fn id(&self) -> ActorId {
+        WeakAddress::id(self)
+    }
+
+    fn clone_channel(&self) -> Box<dyn AddressEnvelope<M>> {
+        Box::new(self.clone())
+    }
+
     fn downgrade(&self) -> Box<dyn AddressEnvelope<M>> {
         unimplemented!()
     }
@@ -0,0 +1,180 @@
+use crate::{Address, Handler, Message, MessageChannelExt, TrySendError, WeakMessageChannel};
+use arc_swap::ArcSwap;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// The subscribers of a topic to one type of message, which is a `Vec<WeakMessageChannel<M>>`.
+type Subscribers = Arc<dyn Any + Send + Sync>;
+
+/// A bus through which messages are published on topics, and delivered to every actor which
+/// subscribed to that topic and type of message. Subscribers can be actors of different types.
+///
+/// Unlike a [`Broker`](struct.Broker.html), publishing does not go through the mailbox of any
+/// actor in between: each message is sent straight to the subscribers from the task publishing
+/// it. Looking up the subscribers takes no lock, so many tasks can publish at once, while
+/// subscribing and unsubscribing replace the subscribers with an updated copy. The bus only holds
+/// weak channels to its subscribers, so it does not keep them alive, and subscribers which have
+/// stopped are unsubscribed the next time a message is published to them. The bus can be cloned,
+/// with all clones sharing the same subscribers.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::EventBus;
+/// #[derive(Clone)]
+/// struct Trade(u32);
+/// impl Message for Trade {
+///     type Result = ();
+/// }
+///
+/// struct Ticker(&'static str);
+/// impl Actor for Ticker {}
+///
+/// impl SyncHandler<Trade> for Ticker {
+///     fn handle(&mut self, Trade(price): Trade, _: &mut Context<Self>) {
+///         println!("{} traded at {}", self.0, price);
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let bus = EventBus::new();
+///     let apple = Ticker("AAPL").spawn();
+///     let google = Ticker("GOOG").spawn();
+///     bus.subscribe::<Trade, _>("AAPL", &apple);
+///     bus.subscribe::<Trade, _>("GOOG", &google);
+///
+///     assert_eq!(bus.publish(&"AAPL", Trade(150)), 1); // Only printed by the AAPL ticker
+///
+///     drop(google); // Stops the GOOG ticker, after which it is unsubscribed
+///     # smol::Timer::after(std::time::Duration::from_millis(10)).await;
+///     assert_eq!(bus.publish(&"GOOG", Trade(100)), 0);
+///     assert_eq!(bus.subscriber_count::<Trade>(&"GOOG"), 0);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct EventBus<K> {
+    /// Maps a topic and the type id of a message to the subscribers of that topic to it
+    topics: Arc<ArcSwap<HashMap<(K, TypeId), Subscribers>>>,
+}
+
+impl<K> EventBus<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + 'static,
+{
+    /// Create an event bus without any subscribers.
+    pub fn new() -> Self {
+        EventBus {
+            topics: Arc::new(ArcSwap::from_pointee(HashMap::new())),
+        }
+    }
+
+    /// Subscribe the actor to all messages of type `M` published on the given topic. Subscribing
+    /// the same actor twice means that it receives every message twice.
+    pub fn subscribe<M, A>(&self, topic: K, address: &Address<A>)
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        let channel = address.downgrade().into_channel();
+        self.update::<M, _>(&topic, |subscribers| subscribers.push(channel.clone()));
+    }
+
+    /// Unsubscribe the actor from the messages of type `M` published on the given topic.
+    pub fn unsubscribe<M, A>(&self, topic: &K, address: &Address<A>)
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        let id = address.id();
+        self.update::<M, _>(topic, |subscribers| subscribers.retain(|s| s.id() != id));
+    }
+
+    /// Publish the message on the given topic, sending a copy of it to every actor which
+    /// subscribed to that topic and type of message without waiting for them to handle it. This
+    /// returns how many subscribers it was sent to.
+    pub fn publish<M>(&self, topic: &K, message: M) -> usize
+    where
+        M: Message + Clone,
+    {
+        let topics = self.topics.load();
+        let subscribers = match subscribers::<K, M>(&topics, topic) {
+            Some(subscribers) => subscribers,
+            None => return 0,
+        };
+
+        let mut sent = 0;
+        let mut disconnected = false;
+        for subscriber in subscribers {
+            match subscriber.do_send(message.clone()) {
+                Ok(()) => sent += 1,
+                Err(TrySendError::Disconnected(..)) => disconnected = true,
+                // A full mailbox is not a reason to unsubscribe
+                Err(_) => {}
+            }
+        }
+
+        if disconnected {
+            self.update::<M, _>(topic, |subscribers| {
+                subscribers.retain(|s| s.is_connected())
+            });
+        }
+        sent
+    }
+
+    /// The number of actors subscribed to messages of type `M` on the given topic. This may
+    /// include subscribers which have stopped since a message was last published to them.
+    pub fn subscriber_count<M: Message>(&self, topic: &K) -> usize {
+        subscribers::<K, M>(&self.topics.load(), topic).map_or(0, Vec::len)
+    }
+
+    /// Replace the subscribers of the topic to `M` with an updated copy of them.
+    fn update<M, F>(&self, topic: &K, f: F)
+    where
+        M: Message,
+        F: Fn(&mut Vec<WeakMessageChannel<M>>),
+    {
+        self.topics.rcu(|topics| {
+            let mut topics = HashMap::clone(topics);
+            let key = (topic.clone(), TypeId::of::<M>());
+            let mut subscribers = subscribers::<K, M>(&topics, topic)
+                .cloned()
+                .unwrap_or_default();
+            f(&mut subscribers);
+
+            if subscribers.is_empty() {
+                topics.remove(&key);
+            } else {
+                topics.insert(key, Arc::new(subscribers));
+            }
+            topics
+        });
+    }
+}
+
+fn subscribers<'a, K, M>(
+    topics: &'a HashMap<(K, TypeId), Subscribers>,
+    topic: &K,
+) -> Option<&'a Vec<WeakMessageChannel<M>>>
+where
+    K: Eq + Hash + Clone,
+    M: Message,
+{
+    let subscribers = topics.get(&(topic.clone(), TypeId::of::<M>()))?;
+    let subscribers = subscribers
+        .downcast_ref()
+        .expect("subscribers are always stored under the type id of their message");
+    Some(subscribers)
+}
+
+impl<K> Default for EventBus<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        EventBus::new()
+    }
+}
@@ -0,0 +1,313 @@
+//! Bridging a connection such as a TCP stream to an actor. The bytes read from the connection are
+//! split into frames by a [`FrameCodec`](trait.FrameCodec.html), and every frame is sent to the
+//! actor as a [`Frame`](struct.Frame.html) message, while the actor writes its own frames to the
+//! connection through a [`SinkHandle`](../struct.SinkHandle.html).
+//!
+//! [`spawn_framed`](fn.spawn_framed.html) does all of this for an actor, and
+//! [`framed`](fn.framed.html) splits a connection into a stream and a sink of frames to bridge it
+//! to actors in other ways. Connections are any byte stream implementing `AsyncRead` and
+//! `AsyncWrite` from the `futures` crate, and [`Lines`](struct.Lines.html) is provided to frame
+//! them by line.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use futures::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+//! use xtra::framed::{self, Frame, Lines};
+//! use xtra::SinkHandle;
+//!
+//! struct Echo(SinkHandle<String>);
+//! impl Actor for Echo {}
+//!
+//! impl SyncHandler<Frame<String>> for Echo {
+//!     fn handle(&mut self, Frame(line): Frame<String>, _: &mut Context<Self>) {
+//!         self.0.send(line.to_uppercase());
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let listener = smol::Async::<std::net::TcpListener>::bind("127.0.0.1:0")?;
+//!     let local_addr = listener.get_ref().local_addr()?;
+//!     smol::Task::spawn(async move {
+//!         let (stream, _) = listener.accept().await.unwrap();
+//!         framed::spawn_framed(stream, Lines, Echo);
+//!     })
+//!     .detach();
+//!
+//!     let mut client = smol::Async::<std::net::TcpStream>::connect(local_addr).await?;
+//!     client.write_all(b"hello\n").await?;
+//!
+//!     let mut reply = String::new();
+//!     BufReader::new(client).read_line(&mut reply).await?;
+//!     assert_eq!(reply, "HELLO\n");
+//!     Ok(())
+//! }
+//! ```
+
+use crate::Message;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadHalf, WriteHalf};
+use futures::task::{Context, Poll};
+use futures::{ready, Sink, Stream};
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+use {
+    crate::{spawner, Actor, Address, Handler, SinkHandle},
+    futures::{future, FutureExt, StreamExt},
+};
+
+/// How many bytes are read from the connection at once.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// How many bytes of encoded frames are buffered before they are written to the connection
+/// without waiting for a flush.
+const WRITE_BUFFER: usize = 8 * 1024;
+
+/// The longest line which [`Lines`](struct.Lines.html) reads, to protect against a peer making
+/// this process allocate arbitrary amounts of memory.
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Splits the bytes read from a connection into frames, and encodes the frames written to it. This
+/// is implemented by [`Lines`](struct.Lines.html), and can be implemented for other protocols,
+/// such as length delimited or binary frames.
+pub trait FrameCodec: Send + Sync + 'static {
+    /// The frames read from the connection.
+    type In: Send + 'static;
+    /// The frames written to the connection.
+    type Out: Send + 'static;
+
+    /// Decode the next frame from the start of `buf`, removing its bytes. If `buf` does not hold a
+    /// whole frame yet, this returns `Ok(None)` and is called again once more bytes were read.
+    fn decode(&self, buf: &mut Vec<u8>) -> io::Result<Option<Self::In>>;
+
+    /// Encode a frame by appending its bytes to `buf`.
+    fn encode(&self, frame: Self::Out, buf: &mut Vec<u8>) -> io::Result<()>;
+}
+
+/// A [`FrameCodec`](trait.FrameCodec.html) of UTF-8 lines, which are ended by `\n` or `\r\n`. The
+/// line endings are not part of the frames read, and `\n` is appended to every frame written.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Lines;
+
+impl FrameCodec for Lines {
+    type In = String;
+    type Out = String;
+
+    fn decode(&self, buf: &mut Vec<u8>) -> io::Result<Option<String>> {
+        let end = match buf.iter().position(|&b| b == b'\n') {
+            Some(end) => end,
+            None if buf.len() > MAX_LINE_LEN => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"))
+            }
+            None => return Ok(None),
+        };
+
+        let mut line: Vec<u8> = buf.drain(..=end).collect();
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        String::from_utf8(line)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn encode(&self, line: String, buf: &mut Vec<u8>) -> io::Result<()> {
+        buf.extend_from_slice(line.as_bytes());
+        buf.push(b'\n');
+        Ok(())
+    }
+}
+
+/// A frame read from a connection bridged to an actor with
+/// [`spawn_framed`](fn.spawn_framed.html), which is sent to the actor as a message.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Frame<T>(pub T);
+
+impl<T: Send + 'static> Message for Frame<T> {
+    type Result = ();
+}
+
+/// The reading half of a connection, which is a stream of the frames read from it. This is
+/// created by [`framed`](fn.framed.html). The stream ends once the other side has closed the
+/// connection, and yields an error if the connection closes in the middle of a frame.
+pub struct FramedRead<R, C> {
+    reader: R,
+    codec: Arc<C>,
+    buf: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin, C: FrameCodec> Stream for FramedRead<R, C> {
+    type Item = io::Result<C::In>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(frame) = this.codec.decode(&mut this.buf).transpose() {
+                return Poll::Ready(Some(frame));
+            }
+
+            if this.eof {
+                return Poll::Ready(if this.buf.is_empty() {
+                    None
+                } else {
+                    this.buf.clear();
+                    let err = io::Error::new(io::ErrorKind::UnexpectedEof, "incomplete frame");
+                    Some(Err(err))
+                });
+            }
+
+            let start = this.buf.len();
+            this.buf.resize(start + READ_CHUNK, 0);
+            let res = Pin::new(&mut this.reader).poll_read(ctx, &mut this.buf[start..]);
+            let read = match &res {
+                Poll::Ready(Ok(read)) => *read,
+                _ => 0,
+            };
+            this.buf.truncate(start + read);
+
+            match res {
+                Poll::Ready(Ok(0)) => this.eof = true,
+                Poll::Ready(Ok(_)) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// The writing half of a connection, which is a sink of the frames written to it. This is created
+/// by [`framed`](fn.framed.html). Encoded frames are buffered until the sink is flushed or enough
+/// of them have been buffered, and closing the sink flushes and closes the connection.
+pub struct FramedWrite<W, C> {
+    writer: W,
+    codec: Arc<C>,
+    buf: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin, C> FramedWrite<W, C> {
+    /// Write all buffered bytes to the connection.
+    fn poll_write_buf(&mut self, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.buf.is_empty() {
+            let written = ready!(Pin::new(&mut self.writer).poll_write(ctx, &self.buf))?;
+            if written == 0 {
+                let err = io::Error::new(io::ErrorKind::WriteZero, "connection closed");
+                return Poll::Ready(Err(err));
+            }
+            self.buf.drain(..written);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin, C: FrameCodec> Sink<C::Out> for FramedWrite<W, C> {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.buf.len() >= WRITE_BUFFER {
+            self.poll_write_buf(ctx)
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, frame: C::Out) -> io::Result<()> {
+        let this = &mut *self;
+        this.codec.encode(frame, &mut this.buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_buf(ctx))?;
+        Pin::new(&mut self.writer).poll_flush(ctx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush(ctx))?;
+        Pin::new(&mut self.writer).poll_close(ctx)
+    }
+}
+
+/// Split a connection into a stream of the frames read from it and a sink of the frames written
+/// to it, both framed with the codec.
+pub fn framed<S, C>(
+    stream: S,
+    codec: C,
+) -> (FramedRead<ReadHalf<S>, C>, FramedWrite<WriteHalf<S>, C>)
+where
+    S: AsyncRead + AsyncWrite,
+    C: FrameCodec,
+{
+    let (reader, writer) = stream.split();
+    let codec = Arc::new(codec);
+    let read = FramedRead {
+        reader,
+        codec: codec.clone(),
+        buf: Vec::new(),
+        eof: false,
+    };
+    let write = FramedWrite {
+        writer,
+        codec,
+        buf: Vec::new(),
+    };
+    (read, write)
+}
+
+/// Bridge a connection to an actor, which is created by `actor` from the handle through which it
+/// writes frames to the connection, and spawned onto the global spawner (see
+/// [`Actor::spawn`](../trait.Actor.html#method.spawn)). Every frame read from the connection is
+/// sent to the actor as a [`Frame`](struct.Frame.html), waiting for space in its mailbox if it is
+/// bounded and full.
+///
+/// The connection is read until the other side closes it, a frame cannot be read or decoded, or the
+/// actor stops. Reading holds a strong address to the actor, so the actor keeps running for as
+/// long as the connection is open, unless it stops itself. Once the actor stops, the frames which
+/// it already wrote are written to the connection, which is then closed (see
+/// [`Context::attach_sink`](../struct.Context.html#method.attach_sink)). If writing to the
+/// connection fails, the handle is closed.
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+pub fn spawn_framed<S, C, A, F>(stream: S, codec: C, actor: F) -> Address<A>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+    C: FrameCodec,
+    A: Actor + Handler<Frame<C::In>>,
+    F: FnOnce(SinkHandle<C::Out>) -> A,
+{
+    let (read, write) = framed(stream, codec);
+    let (writer, attached) = SinkHandle::attach(write);
+    let (addr, mut mgr) = actor(writer).create();
+    mgr.ctx.add_sink(attached);
+    spawner::spawn(Box::pin(async move {
+        mgr.manage().await;
+    }));
+
+    let frames = read
+        .take_while(|res| future::ready(res.is_ok()))
+        .filter_map(|res| future::ready(res.ok().map(Frame)));
+    crate::runtime::spawn(addr.forward(frames).map(|_| ()));
+
+    addr
+}
@@ -0,0 +1,206 @@
+//! Helpers for actors which are finite state machines. The state of such an actor is kept in a
+//! [`StateMachine`](struct.StateMachine.html), which only allows the transitions allowed by its
+//! [`State`](trait.State.html), and decides for each message whether it is handled, stashed until
+//! the state changes, or rejected, according to the state's [`Admit`](trait.Admit.html)
+//! implementation for the message.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use xtra::fsm::{Admission, Admit, State, StateMachine};
+//!
+//! #[derive(Clone, Debug, PartialEq, Eq)]
+//! enum Conn {
+//!     Disconnected,
+//!     Connected,
+//!     Closed,
+//! }
+//!
+//! impl State for Conn {
+//!     fn can_transition_to(&self, _next: &Conn) -> bool {
+//!         *self != Conn::Closed // Closed connections stay closed
+//!     }
+//! }
+//!
+//! struct Connect;
+//! impl Message for Connect {
+//!     type Result = ();
+//! }
+//!
+//! struct Write(&'static str);
+//! impl Message for Write {
+//!     type Result = ();
+//! }
+//!
+//! impl Admit<Write> for Conn {
+//!     fn admit(&self, _: &Write) -> Admission {
+//!         match self {
+//!             Conn::Disconnected => Admission::Stash, // Write once connected
+//!             Conn::Connected => Admission::Handle,
+//!             Conn::Closed => Admission::Reject,
+//!         }
+//!     }
+//! }
+//!
+//! struct Client {
+//!     state: StateMachine<Conn>,
+//!     written: Vec<&'static str>,
+//! }
+//! impl Actor for Client {}
+//!
+//! impl SyncHandler<Connect> for Client {
+//!     fn handle(&mut self, _: Connect, ctx: &mut Context<Self>) {
+//!         self.state.transition(Conn::Connected, ctx).unwrap();
+//!     }
+//! }
+//!
+//! impl SyncHandler<Write> for Client {
+//!     fn handle(&mut self, write: Write, ctx: &mut Context<Self>) {
+//!         if let Some(Write(data)) = self.state.admit(write, ctx) {
+//!             self.written.push(data);
+//!         }
+//!     }
+//! }
+//! # struct Written;
+//! # impl Message for Written {
+//! #     type Result = Vec<&'static str>;
+//! # }
+//! # impl SyncHandler<Written> for Client {
+//! #     fn handle(&mut self, _: Written, _: &mut Context<Self>) -> Vec<&'static str> {
+//! #         self.written.clone()
+//! #     }
+//! # }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let client = Client {
+//!         state: StateMachine::new(Conn::Disconnected),
+//!         written: Vec::new(),
+//!     };
+//!     let addr = client.spawn();
+//!
+//!     addr.do_send(Write("hello")).unwrap();
+//!     addr.do_send(Connect).unwrap();
+//!     addr.do_send(Write("world")).unwrap();
+//!     assert_eq!(addr.send(Written).await, Ok(vec!["hello", "world"]));
+//! }
+//! ```
+
+use crate::{Actor, Context, Handler, Message};
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+
+/// A state of a [`StateMachine`](struct.StateMachine.html).
+pub trait State: Clone + PartialEq + Debug + Send + 'static {
+    /// Whether the state machine may move from this state to `next`. By default, every transition
+    /// is allowed.
+    fn can_transition_to(&self, next: &Self) -> bool {
+        let _ = next;
+        true
+    }
+}
+
+/// What a [`StateMachine`](struct.StateMachine.html) does with a message in its current state.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Admission {
+    /// Handle the message now
+    Handle,
+    /// Defer the message (see [`Context::stash`](../struct.Context.html#method.stash)), so that it
+    /// is handled again once the state changes
+    Stash,
+    /// Drop the message without handling it
+    Reject,
+}
+
+/// Implemented by a [`State`](trait.State.html) to decide what to do with a message of type `M` in
+/// each state.
+pub trait Admit<M>: State {
+    /// What to do with the message in this state.
+    fn admit(&self, message: &M) -> Admission;
+}
+
+/// The error returned by [`StateMachine::transition`](struct.StateMachine.html#method.transition)
+/// when the current state does not allow the transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTransition<S> {
+    /// The state which the state machine is in
+    pub from: S,
+    /// The state which it was asked to move to
+    pub to: S,
+}
+
+impl<S: Debug> Display for InvalidTransition<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid transition from {:?} to {:?}",
+            self.from, self.to
+        )
+    }
+}
+
+impl<S: Debug> Error for InvalidTransition<S> {}
+
+/// The current [`State`](trait.State.html) of an actor which is a finite state machine. It is
+/// kept in the actor, and given the actor's context whenever a message is admitted or the state
+/// changes, so that it can stash messages which cannot be handled in the current state and replay
+/// them once it has changed.
+#[derive(Debug, Clone)]
+pub struct StateMachine<S: State> {
+    state: S,
+}
+
+impl<S: State> StateMachine<S> {
+    /// Create a state machine in the given initial state.
+    pub fn new(initial: S) -> Self {
+        StateMachine { state: initial }
+    }
+
+    /// The current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Move to the `next` state if the current one allows it (see
+    /// [`State::can_transition_to`](trait.State.html#method.can_transition_to)). If the state
+    /// changes, all stashed messages are handled again right after the current one (see
+    /// [`Context::unstash_all`](../struct.Context.html#method.unstash_all)).
+    pub fn transition<A: Actor>(
+        &mut self,
+        next: S,
+        ctx: &mut Context<A>,
+    ) -> Result<(), InvalidTransition<S>> {
+        if !self.state.can_transition_to(&next) {
+            return Err(InvalidTransition {
+                from: self.state.clone(),
+                to: next,
+            });
+        }
+
+        if self.state != next {
+            self.state = next;
+            ctx.unstash_all();
+        }
+        Ok(())
+    }
+
+    /// Decide what to do with a message in the current state (see [`Admit`](trait.Admit.html)).
+    /// If it should be handled, it is returned. Otherwise, it is stashed or dropped, and `None` is
+    /// returned.
+    pub fn admit<A, M>(&self, message: M, ctx: &mut Context<A>) -> Option<M>
+    where
+        S: Admit<M>,
+        A: Handler<M>,
+        M: Message,
+    {
+        match self.state.admit(&message) {
+            Admission::Handle => Some(message),
+            Admission::Stash => {
+                ctx.stash(message);
+                None
+            }
+            Admission::Reject => None,
+        }
+    }
+}
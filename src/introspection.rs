@@ -0,0 +1,165 @@
+//! Introspection of the actors which are running in the process, such as to list them on an admin
+//! endpoint. Every actor is registered once its manage loop has started it, and deregistered once
+//! it has stopped, and [`actors`](fn.actors.html) and [`actor`](fn.actor.html) take a snapshot of
+//! what they are doing. An actor run by a [`Supervisor`](../struct.Supervisor.html) stays
+//! registered under the same id across restarts, except in between its instances.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use xtra::introspection;
+//!
+//! struct Greet;
+//! impl Message for Greet {
+//!     type Result = ();
+//! }
+//!
+//! struct Greeter;
+//! impl Actor for Greeter {}
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Greet> for Greeter {
+//!     async fn handle(&mut self, _: Greet, _: &mut Context<Self>) {}
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let addr = Greeter.spawn();
+//!     addr.send(Greet).await.unwrap();
+//!     addr.send(Greet).await.unwrap();
+//!
+//!     let info = introspection::actor(addr.id()).unwrap();
+//!     assert_eq!(info.actor, std::any::type_name::<Greeter>());
+//!     assert_eq!(info.messages_handled, 2);
+//!     assert_eq!(info.mailbox_len, 0);
+//!
+//!     for info in introspection::actors() {
+//!         println!("{:?} {}: {} handled", info.id, info.actor, info.messages_handled);
+//!     }
+//! }
+//! ```
+
+use crate::clock::Timestamp;
+use crate::{Actor, ActorId, WeakAddress};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+/// The actors which are running, by id
+static ACTORS: Mutex<BTreeMap<ActorId, Registered>> = Mutex::new(BTreeMap::new());
+
+fn registry() -> MutexGuard<'static, BTreeMap<ActorId, Registered>> {
+    // Nothing can panic while the lock is held, so poisoning can safely be ignored
+    ACTORS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// When introspection was first used, from which the times of activity are counted
+fn start() -> Timestamp {
+    static START: OnceLock<Timestamp> = OnceLock::new();
+    *START.get_or_init(Timestamp::now)
+}
+
+/// The time since `start` in nanoseconds, or zero on wasm, where there is no clock
+fn now() -> u64 {
+    start()
+        .elapsed()
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+struct Registered {
+    actor: &'static str,
+    activity: Arc<Activity>,
+    mailbox_len: Box<dyn Fn() -> usize + Send>,
+}
+
+/// What an actor has been doing, kept by its context and updated as it handles messages.
+pub(crate) struct Activity {
+    handled: AtomicU64,
+    /// When the actor last started to handle a message or was started, in nanoseconds since `start`
+    last_active: AtomicU64,
+}
+
+impl Activity {
+    pub(crate) fn new() -> Self {
+        Activity {
+            handled: AtomicU64::new(0),
+            last_active: AtomicU64::new(now()),
+        }
+    }
+
+    /// Note that the actor is starting to handle a message.
+    pub(crate) fn handling(&self) {
+        self.handled.fetch_add(1, Ordering::Relaxed);
+        self.last_active.store(now(), Ordering::Relaxed);
+    }
+}
+
+/// Register an actor once it has been started.
+pub(crate) fn register<A: Actor>(address: &WeakAddress<A>, activity: Arc<Activity>) {
+    activity.last_active.store(now(), Ordering::Relaxed);
+    let id = address.id();
+    // The mailbox is held rather than a weak address, so that it is not counted by `weak_count`
+    let sender = address.sender.clone();
+    let registered = Registered {
+        actor: std::any::type_name::<A>(),
+        activity,
+        mailbox_len: Box::new(move || sender.len()),
+    };
+    registry().insert(id, registered);
+}
+
+/// Deregister an actor once it has stopped.
+pub(crate) fn deregister(id: ActorId) {
+    registry().remove(&id);
+}
+
+/// A snapshot of a running actor, taken with [`actors`](fn.actors.html) or
+/// [`actor`](fn.actor.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorInfo {
+    /// The type name of the actor, as given by `std::any::type_name`
+    pub actor: &'static str,
+    /// The id of the actor, which is that of its addresses
+    pub id: ActorId,
+    /// The number of messages waiting in the mailbox of the actor
+    pub mailbox_len: usize,
+    /// How many messages the actor has handled since it was created, across restarts, including
+    /// the one it is handling
+    pub messages_handled: u64,
+    /// How long ago the actor last started to handle a message, or was started if it has not
+    /// handled any since. This is `None` on wasm, where there is no clock to measure it with.
+    pub idle: Option<Duration>,
+}
+
+impl ActorInfo {
+    fn new(id: ActorId, registered: &Registered) -> Self {
+        let last_active = registered.activity.last_active.load(Ordering::Relaxed);
+        ActorInfo {
+            actor: registered.actor,
+            id,
+            mailbox_len: (registered.mailbox_len)(),
+            messages_handled: registered.activity.handled.load(Ordering::Relaxed),
+            idle: start()
+                .elapsed()
+                .map(|now| now.saturating_sub(Duration::from_nanos(last_active))),
+        }
+    }
+}
+
+/// Take a snapshot of all actors which are running in the process, ordered by id, so in the order
+/// in which they were created.
+pub fn actors() -> Vec<ActorInfo> {
+    registry()
+        .iter()
+        .map(|(&id, registered)| ActorInfo::new(id, registered))
+        .collect()
+}
+
+/// Take a snapshot of the actor with the given id, if it is running.
+pub fn actor(id: ActorId) -> Option<ActorInfo> {
+    registry()
+        .get(&id)
+        .map(|registered| ActorInfo::new(id, registered))
+}
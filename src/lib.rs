@@ -9,7 +9,7 @@
         doc_cfg,
     )
 )]
-#![cfg_attr(doc, feature(doc_cfg, external_doc))]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(missing_docs, unsafe_code)]
 
 mod message_channel;
@@ -17,14 +17,198 @@ pub use message_channel::{MessageChannel, MessageChannelExt, WeakMessageChannel}
 
 mod envelope;
 
+#[cfg(any(
+    doc,
+    feature = "metrics",
+    feature = "tracing",
+    feature = "recording",
+    feature = "introspection",
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+mod clock;
+
+mod mailbox;
+pub use mailbox::{Envelope, Mailbox, MailboxConfig, OverflowPolicy};
+#[cfg(any(
+    feature = "deadlock-detection",
+    feature = "recording",
+    feature = "topology"
+))]
+mod current;
+#[cfg(feature = "deadlock-detection")]
+mod deadlock;
+mod dedup;
+// Reusing memory across types takes raw allocation, which is confined to this opt-in feature
+#[cfg_attr(feature = "perf", allow(unsafe_code))]
+mod pool;
+mod response;
+mod response_stream;
+pub use response_stream::{ResponseStream, Yielder};
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+mod runtime;
+
 mod address;
-pub use address::{Address, AddressExt, Disconnected, MessageResponseFuture, WeakAddress};
+pub use address::{
+    ActorId, Address, AddressExt, DisconnectReason, DoSendFuture, MessageResponseFuture, SendError,
+    TrySendError, WeakAddress,
+};
 
 mod context;
-pub use context::Context;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub use context::SinkHandle;
+pub use context::{Context, NotifyHandle, Terminated};
 
 mod manager;
-pub use manager::ActorManager;
+pub use manager::{ActorManager, ExitReason};
+
+mod builder;
+pub use builder::ActorBuilder;
+
+mod broadcast;
+pub use broadcast::BroadcastAddress;
+
+mod broker;
+pub use broker::{Broker, Publish, Subscribe};
+
+mod event_bus;
+pub use event_bus::EventBus;
+
+mod router;
+pub use router::{ActorPool, HashRouter, KeyedMessage};
+
+mod lifecycle;
+pub use lifecycle::{lifecycle_events, LifecycleEvent, LifecycleEventKind, LifecycleEvents};
+
+mod registry;
+pub use registry::{MissingService, Registry, RegistryKey, Service, ServiceKey};
+
+mod spawner;
+pub use spawner::{set_global_spawner, Spawner, ThreadSpawner};
+
+mod middleware;
+pub use middleware::{HandlerInfo, Middleware};
+
+mod behavior;
+pub use behavior::Behavior;
+
+#[cfg(feature = "actix-compat")]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix-compat")))]
+pub mod actix;
+
+#[cfg(feature = "framed")]
+#[cfg_attr(docsrs, doc(cfg(feature = "framed")))]
+pub mod framed;
+
+pub mod fsm;
+
+#[cfg(feature = "local")]
+#[cfg_attr(docsrs, doc(cfg(feature = "local")))]
+pub mod local;
+
+#[cfg(feature = "introspection")]
+#[cfg_attr(docsrs, doc(cfg(feature = "introspection")))]
+pub mod introspection;
+
+#[cfg(feature = "persistence")]
+#[cfg_attr(docsrs, doc(cfg(feature = "persistence")))]
+pub mod persistence;
+
+#[cfg(feature = "recording")]
+#[cfg_attr(docsrs, doc(cfg(feature = "recording")))]
+pub mod recording;
+
+#[cfg(feature = "remote")]
+#[cfg_attr(docsrs, doc(cfg(feature = "remote")))]
+pub mod remote;
+
+#[cfg(all(
+    feature = "signal",
+    any(
+        feature = "with-tokio-0_2",
+        all(unix, any(feature = "with-async_std-1", feature = "with-smol-0_1"))
+    )
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "signal")))]
+pub mod signal;
+
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
+
+#[cfg(feature = "topology")]
+#[cfg_attr(docsrs, doc(cfg(feature = "topology")))]
+pub mod topology;
+
+mod supervisor;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub use supervisor::Backoff;
+pub use supervisor::{RestartStrategy, Supervisor};
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+mod shutdown;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub use shutdown::{ShutdownCoordinator, ShutdownReport};
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+mod watchdog;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub use watchdog::SlowHandler;
+
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use xtra_macros::{handlers, Actor, Message};
+
+/// Items used by the code generated by the macros. This is not part of the public API.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+}
 
 /// Commonly used types from `xtra`
 pub mod prelude {
@@ -35,6 +219,7 @@ pub mod prelude {
 
 #[cfg(feature = "nightly")]
 use futures::future::{self, Ready};
+use std::any::Any;
 
 /// A message that can be sent to an [`Actor`](trait.Actor.html) for processing. They are processed
 /// one at a time. Only actors implementing the corresponding [`Handler<M>`](trait.Handler.html)
@@ -51,10 +236,95 @@ use futures::future::{self, Ready};
 ///     type Result = MyResult;
 /// }
 /// ```
+///
+/// With the `macros` feature enabled, this trait can also be derived. The result type is given
+/// with the `message` attribute, and is `()` if it is left out:
+///
+/// ```rust
+/// # use xtra::Message;
+/// # struct MyResult;
+/// #[derive(Message)]
+/// #[message(result = "MyResult")]
+/// struct MyMessage;
+///
+/// #[derive(Message)]
+/// #[message(priority = "High")]
+/// struct Shutdown;
+/// ```
 pub trait Message: Send + 'static {
     /// The return type of the message. It will be returned when the [`Address::send`](struct.Address.html#method.send)
     /// method is called.
     type Result: Send;
+
+    /// The priority with which the message is delivered to the actor. Messages with
+    /// [`Priority::High`](enum.Priority.html#variant.High) are handled before any normal priority
+    /// messages waiting in the actor's mailbox, which is useful for control messages that should
+    /// not have to wait behind a backlog of other work. This is normal priority by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::{prelude::*, Priority};
+    /// # struct MyActor(Vec<&'static str>);
+    /// # impl Actor for MyActor {}
+    /// struct Work;
+    ///
+    /// impl Message for Work {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Cancel;
+    ///
+    /// impl Message for Cancel {
+    ///     type Result = ();
+    ///     const PRIORITY: Priority = Priority::High;
+    /// }
+    /// # impl SyncHandler<Work> for MyActor {
+    /// #     fn handle(&mut self, _: Work, _ctx: &mut Context<Self>) { self.0.push("work") }
+    /// # }
+    /// # impl SyncHandler<Cancel> for MyActor {
+    /// #     fn handle(&mut self, _: Cancel, _ctx: &mut Context<Self>) { self.0.push("cancel") }
+    /// # }
+    /// # struct Get;
+    /// # impl Message for Get { type Result = Vec<&'static str>; }
+    /// # impl SyncHandler<Get> for MyActor {
+    /// #     fn handle(&mut self, _: Get, _ctx: &mut Context<Self>) -> Vec<&'static str> {
+    /// #         self.0.clone()
+    /// #     }
+    /// # }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = MyActor(Vec::new()).create();
+    ///     addr.do_send(Work).unwrap();
+    ///     addr.do_send(Cancel).unwrap();
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     // `Cancel` jumped ahead of the `Work` that was sent before it
+    ///     assert_eq!(addr.send(Get).await.unwrap(), vec!["cancel", "work"]);
+    /// }
+    /// ```
+    const PRIORITY: Priority = Priority::Normal;
+
+    /// An id identifying this message among the messages of its type sent to an actor, so that an
+    /// actor with a dedup window (see
+    /// [`ActorManager::dedup_window`](struct.ActorManager.html#method.dedup_window)) does not
+    /// handle it again if it is sent more than once, such as when it is redelivered by
+    /// [`AtLeastOnce`](persistence/struct.AtLeastOnce.html) or resent over a remote connection.
+    /// Messages have no id by default, and are then never deduplicated.
+    fn message_id(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// The priority with which a [`Message`](trait.Message.html) is delivered to an actor. See
+/// [`Message::PRIORITY`](trait.Message.html#associatedconstant.PRIORITY).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Priority {
+    /// The message is handled in the order it was sent, relative to other normal priority messages.
+    Normal,
+    /// The message is handled before any normal priority messages in the actor's mailbox.
+    High,
 }
 
 /// A trait indicating that an [`Actor`](trait.Actor.html) can handle a given [`Message`](trait.Message.html)
@@ -124,6 +394,90 @@ pub trait SyncHandler<M: Message>: Actor {
 ///     assert_eq!(addr.send(Msg).await, Ok(20));
 /// }
 /// ```
+///
+/// With the `macros` feature enabled, the handlers of an actor can instead be written as the
+/// methods of an inherent impl block annotated with `#[xtra::handlers]`. Each `async fn` becomes a
+/// `Handler` and each other `fn` a [`SyncHandler`](trait.SyncHandler.html) of the type of its
+/// second argument. A method marked `#[message]` also implements `Message` for that type, with the
+/// method's return type as its result:
+///
+/// ```
+/// # use xtra::prelude::*;
+/// #[derive(Actor)]
+/// struct Counter(u32);
+///
+/// struct Add(u32);
+/// struct Get;
+///
+/// #[xtra::handlers]
+/// impl Counter {
+///     #[message]
+///     fn add(&mut self, Add(n): Add, _: &mut Context<Self>) {
+///         self.0 += n;
+///     }
+///
+///     #[message]
+///     async fn get(&mut self, _: Get, _: &mut Context<Self>) -> u32 {
+///         self.0
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let addr = Counter(0).spawn();
+///     addr.do_send(Add(5)).unwrap();
+///     assert_eq!(addr.send(Get).await, Ok(5));
+/// }
+/// ```
+///
+/// A method marked `#[message(pub Add)]` instead takes the fields of its message, between `&mut
+/// self` and the context. The message is then generated as a struct with the given name and
+/// visibility, whose fields are named like the method's arguments.
+///
+/// With `#[xtra::handlers(address = "pub CounterAddress")]`, a trait named `CounterAddress` with
+/// the given visibility is also generated and implemented for the addresses of the actor. It has a
+/// method for each handler, named like it, which sends the message and waits for the response, and
+/// one prefixed with `cast_` which sends it without waiting. For a handler taking the fields of its
+/// message, these take the fields too and build the message from them. Otherwise, they take the
+/// message itself, since its fields are not known to the macro:
+///
+/// ```
+/// # use xtra::prelude::*;
+/// #[derive(Actor)]
+/// struct Counter(u32);
+///
+/// struct Reset;
+///
+/// #[xtra::handlers(address = "CounterAddress")]
+/// impl Counter {
+///     #[message(Add)]
+///     fn add(&mut self, n: u32, _: &mut Context<Self>) {
+///         self.0 += n;
+///     }
+///
+///     #[message(Get)]
+///     async fn get(&mut self, _: &mut Context<Self>) -> u32 {
+///         self.0
+///     }
+///
+///     #[message]
+///     fn reset(&mut self, _: Reset, _: &mut Context<Self>) {
+///         self.0 = 0;
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let addr = Counter(0).spawn();
+///     addr.cast_add(5).unwrap();
+///     assert_eq!(addr.get().await, Ok(5));
+///     assert_eq!(addr.send(Add { n: 2 }).await, Ok(()));
+///     assert_eq!(addr.downgrade().get().await, Ok(7));
+///
+///     addr.reset(Reset).await.unwrap();
+///     assert_eq!(addr.get().await, Ok(0));
+/// }
+/// ```
 #[cfg(not(feature = "nightly"))]
 #[async_trait::async_trait]
 pub trait Handler<M: Message>: Actor {
@@ -159,18 +513,146 @@ pub trait Handler<M: Message>: Actor {
     fn handle<'a>(&'a mut self, message: M, ctx: &'a mut Context<Self>) -> Self::Responder<'a>;
 }
 
+/// A trait indicating that an [`Actor`](trait.Actor.html) can handle a given [`Message`](trait.Message.html)
+/// in batches. Messages sent with [`AddressExt::do_send_batched`](trait.AddressExt.html#method.do_send_batched)
+/// which are queued one after the other in the actor's mailbox are handled together in a single
+/// call to [`BatchHandler::handle_batch`](trait.BatchHandler.html#method.handle_batch), in the
+/// order that they were sent. This helps actors which write to a database or socket, where doing so
+/// once for many messages is much faster than doing so for each of them. Since the messages are
+/// not sent with a way to respond to them, their result must be `()`.
+///
+/// This is an [`async_trait`](https://github.com/dtolnay/async-trait/), so implementations should
+/// be annotated `#[async_trait]`.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::BatchHandler;
+/// #[derive(Default)]
+/// struct Writer {
+///     rows: Vec<u32>,
+///     writes: usize,
+/// }
+/// impl Actor for Writer {}
+///
+/// struct Row(u32);
+/// impl Message for Row {
+///     type Result = ();
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl BatchHandler<Row> for Writer {
+///     async fn handle_batch(&mut self, rows: Vec<Row>, _: &mut Context<Self>) {
+///         self.rows.extend(rows.into_iter().map(|Row(n)| n));
+///         self.writes += 1;
+///     }
+/// }
+/// # struct Writes;
+/// # impl Message for Writes {
+/// #     type Result = (Vec<u32>, usize);
+/// # }
+/// # impl SyncHandler<Writes> for Writer {
+/// #     fn handle(&mut self, _: Writes, _: &mut Context<Self>) -> (Vec<u32>, usize) {
+/// #         (self.rows.clone(), self.writes)
+/// #     }
+/// # }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = Writer::default().create();
+///     for n in 0..3 {
+///         addr.do_send_batched(Row(n)).unwrap();
+///     }
+///
+///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+///     assert_eq!(addr.send(Writes).await, Ok((vec![0, 1, 2], 1))); // Written all at once
+/// }
+/// ```
+#[async_trait::async_trait]
+pub trait BatchHandler<M: Message<Result = ()>>: Actor {
+    /// The largest number of messages to handle in a single batch.
+    const MAX_BATCH_SIZE: usize = 64;
+
+    /// Handle a batch of messages, which contains at least one message.
+    async fn handle_batch(&mut self, messages: Vec<M>, ctx: &mut Context<Self>);
+}
+
+/// A handler for messages which only need to read the actor's state, such as queries. Messages
+/// sent with [`AddressExt::send_read`](trait.AddressExt.html#method.send_read) which are queued
+/// one after another are handled concurrently, since each handler only borrows the actor
+/// immutably, and all of them finish before the next message is handled by a mutable handler.
+/// This keeps actors which are mostly queried from handling cheap read-only messages one at a
+/// time while one of them is waiting. At most as many messages as the actor's
+/// [budget](struct.ActorManager.html#method.budget) are handled at once.
+///
+/// Because the handlers run concurrently, they are not given the actor's
+/// [`Context`](struct.Context.html), and the actor must be `Sync`.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::ReadHandler;
+/// # use std::time::{Duration, Instant};
+/// struct Database;
+/// impl Actor for Database {}
+///
+/// struct Query;
+/// impl Message for Query {
+///     type Result = u32;
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl ReadHandler<Query> for Database {
+///     async fn handle(&self, _: Query) -> u32 {
+///         smol::Timer::after(Duration::from_millis(100)).await;
+///         42
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = Database.create();
+///     let queries: Vec<_> = (0..5).map(|_| addr.send_read(Query)).collect();
+///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+///
+///     let start = Instant::now();
+///     for query in queries {
+///         assert_eq!(query.await, Ok(42));
+///     }
+///     assert!(start.elapsed() < Duration::from_millis(500)); // Not handled one after another
+/// }
+/// ```
 #[async_trait::async_trait]
+pub trait ReadHandler<M: Message>: Actor + Sync {
+    /// Handle a given message, returning its result.
+    async fn handle(&self, message: M) -> M::Result;
+}
+
+// Written out rather than with `async_trait`, so that the box of the future can be recycled
+#[cfg(not(feature = "nightly"))]
 impl<M: Message, T: SyncHandler<M>> Handler<M> for T {
-    #[cfg(not(feature = "nightly"))]
-    async fn handle(&mut self, message: M, ctx: &mut Context<Self>) -> M::Result {
-        let res: M::Result = SyncHandler::handle(self, message, ctx);
-        res
+    fn handle<'life0, 'life1, 'async_trait>(
+        &'life0 mut self,
+        message: M,
+        ctx: &'life1 mut Context<Self>,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = M::Result> + Send + 'async_trait>>
+    where
+        'life0: 'async_trait,
+        'life1: 'async_trait,
+        Self: 'async_trait,
+    {
+        pool::pinned(futures::future::lazy(move |_| {
+            SyncHandler::handle(self, message, ctx)
+        }))
     }
+}
 
-    #[cfg(feature = "nightly")]
+#[cfg(feature = "nightly")]
+impl<M: Message, T: SyncHandler<M>> Handler<M> for T {
     type Responder<'a> = Ready<M::Result>;
 
-    #[cfg(feature = "nightly")]
     fn handle(&mut self, message: M, ctx: &mut Context<Self>) -> Self::Responder<'_> {
         let res: M::Result = SyncHandler::handle(self, message, ctx);
         future::ready(res)
@@ -229,18 +711,82 @@ impl<M: Message, T: SyncHandler<M>> Handler<M> for T {
 /// }
 /// ```
 ///
+/// With the `macros` feature enabled, actors which do not need any of these methods can derive
+/// this trait instead. What they do when a handler panicked is given with the `actor` attribute:
+///
+/// ```rust
+/// # use xtra::Actor;
+/// #[derive(Actor)]
+/// struct MyActor;
+///
+/// #[derive(Actor)]
+/// #[actor(panic = "Resume")]
+/// struct Resilient;
+///
+/// assert_eq!(Resilient::PANIC_ACTION, xtra::PanicAction::Resume);
+/// ```
+///
 /// For longer examples, see the `examples` directory.
+#[async_trait::async_trait]
 pub trait Actor: 'static + Send + Sized {
     /// Called as soon as the actor has been started.
     #[allow(unused_variables)]
     fn started(&mut self, ctx: &mut Context<Self>) {}
 
-    /// Called when the actor calls the [`Context::stop`](struct.Context.html#method.stop). This method
-    /// can prevent the actor from stopping by returning [`KeepRunning::Yes`](enum.KeepRunning.html#variant.Yes).
+    /// Called right after [`Actor::started`](trait.Actor.html#method.started), for initialization
+    /// which has to wait on something, such as connecting to a database. The actor does not handle
+    /// any messages until this has completed, so messages sent in the meantime wait in its mailbox.
+    /// This is an [`async_trait`](https://github.com/dtolnay/async-trait/), so implementations
+    /// which override it should be annotated `#[async_trait]`. It is also called when the actor is
+    /// restarted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// struct Accounts {
+    ///     connection: Option<&'static str>,
+    /// }
+    ///
+    /// #[async_trait::async_trait]
+    /// impl Actor for Accounts {
+    ///     async fn async_started(&mut self, _ctx: &mut Context<Self>) {
+    ///         smol::Timer::after(Duration::from_millis(10)).await; // Connect to the database
+    ///         self.connection = Some("postgres://localhost");
+    ///     }
+    /// }
+    ///
+    /// struct Connection;
+    /// impl Message for Connection {
+    ///     type Result = Option<&'static str>;
+    /// }
+    ///
+    /// impl SyncHandler<Connection> for Accounts {
+    ///     fn handle(&mut self, _: Connection, _: &mut Context<Self>) -> Option<&'static str> {
+    ///         self.connection
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Accounts { connection: None }.spawn();
+    ///     assert_eq!(addr.send(Connection).await, Ok(Some("postgres://localhost")));
+    /// }
+    /// ```
+    #[allow(unused_variables)]
+    async fn async_started(&mut self, ctx: &mut Context<Self>) {}
+
+    /// Called when the actor calls the [`Context::stop`](struct.Context.html#method.stop), or when
+    /// the last strong [`Address`](struct.Address.html) to it is dropped. This method can prevent
+    /// the actor from stopping by returning [`KeepRunning::Yes`](enum.KeepRunning.html#variant.Yes),
+    /// for instance because it still has work to finish. An actor which keeps running after its
+    /// last address was dropped can still be reached through weak addresses and notifications it
+    /// sends itself, and has to stop itself with `Context::stop` later on.
     ///
-    /// **Note:** this method will *only* be called when `Context::stop` is called. Other, general
-    /// destructor behaviour should be encapsulated in the [`Actor::stopped`](trait.Actor.html#method.stopped)
-    /// method.
+    /// **Note:** this method will *only* be called in these two cases, and not when the actor is
+    /// stopped gracefully or after it panicked. Other, general destructor behaviour should be
+    /// encapsulated in the [`Actor::stopped`](trait.Actor.html#method.stopped) method.
     ///
     /// # Example
     /// ```no_run
@@ -253,6 +799,50 @@ pub trait Actor: 'static + Send + Sized {
     /// }
     /// # }
     /// ```
+    ///
+    /// An actor can finish its queued work before it stops, even once nothing holds its address:
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::{ExitReason, KeepRunning};
+    /// struct Flusher {
+    ///     queued: u32,
+    ///     flushed: u32,
+    /// }
+    ///
+    /// struct Flush;
+    /// impl Message for Flush {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl Actor for Flusher {
+    ///     fn stopping(&mut self, ctx: &mut Context<Self>) -> KeepRunning {
+    ///         if self.queued == 0 {
+    ///             return KeepRunning::No;
+    ///         }
+    ///         ctx.notify_later(Flush);
+    ///         KeepRunning::Yes
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Flush> for Flusher {
+    ///     fn handle(&mut self, _: Flush, ctx: &mut Context<Self>) {
+    ///         self.flushed += self.queued;
+    ///         self.queued = 0;
+    ///         ctx.stop();
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = Flusher { queued: 3, flushed: 0 }.create();
+    ///     drop(addr);
+    ///
+    ///     let (flusher, reason) = mgr.manage_into().await;
+    ///     assert_eq!(flusher.flushed, 3);
+    ///     assert_eq!(reason, ExitReason::Stopped);
+    /// }
+    /// ```
     #[allow(unused_variables)]
     fn stopping(&mut self, ctx: &mut Context<Self>) -> KeepRunning {
         KeepRunning::No
@@ -263,10 +853,172 @@ pub trait Actor: 'static + Send + Sized {
     /// [`Actor::stopping`](trait.Actor.html#method.stopping) method, or because there are no more
     /// strong addresses ([`Address`](struct.Address.html), as opposed to [`WeakAddress`](struct.WeakAddress.html).
     /// This should be used for any final cleanup before the actor is dropped.
+    ///
+    /// This is called exactly once for every instance of the actor which was started, however it
+    /// stopped: also if it panicked in a handler or any other lifecycle method, or if the future
+    /// managing it (such as that of [`ActorManager::manage`](struct.ActorManager.html#method.manage)
+    /// or [`Supervisor::supervise`](struct.Supervisor.html#method.supervise)) is dropped before it
+    /// stopped, such as because its task was cancelled by the executor. The only exception is a
+    /// panic in `stopped` itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// use futures::FutureExt;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct Connection(Arc<AtomicBool>);
+    ///
+    /// impl Actor for Connection {
+    ///     fn stopped(&mut self, _: &mut Context<Self>) {
+    ///         self.0.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let closed = Arc::new(AtomicBool::new(false));
+    ///     let (_addr, mgr) = Connection(closed.clone()).create();
+    ///
+    ///     // The actor is started and waits for messages, until its future is cancelled
+    ///     let mut manage = Box::pin(mgr.manage());
+    ///     assert!(manage.as_mut().now_or_never().is_none());
+    ///     drop(manage);
+    ///
+    ///     assert!(closed.load(Ordering::SeqCst));
+    /// }
+    /// ```
     #[allow(unused_variables)]
     fn stopped(&mut self, ctx: &mut Context<Self>) {}
 
-    /// Spawns the actor onto the global runtime executor (i.e, `tokio` or `async_std`'s executors).
+    /// Called once the actor has stopped handling messages, right before
+    /// [`Actor::stopped`](trait.Actor.html#method.stopped), for cleanup which has to wait on
+    /// something, such as flushing a connection. The tasks and children of the actor are still
+    /// running while it is awaited. Like [`Actor::async_started`](trait.Actor.html#method.async_started),
+    /// implementations which override it should be annotated `#[async_trait]`. Unlike
+    /// `Actor::stopped`, this is not called if the future managing the actor is dropped before the
+    /// actor stopped, or if the actor panicked in `Actor::started` or `Actor::async_started`.
+    #[allow(unused_variables)]
+    async fn async_stopped(&mut self, ctx: &mut Context<Self>) {}
+
+    /// What to do when one of the actor's handlers panicked, unless
+    /// [`Actor::panicked`](trait.Actor.html#method.panicked) is overridden to decide it from the
+    /// panic. By default, the actor is stopped, since its state could be inconsistent. An actor
+    /// whose state stays consistent even if a handler panics can resume instead, and actors run by
+    /// a [`Supervisor`](struct.Supervisor.html) can be replaced with a new instance by restarting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::{PanicAction, prelude::*};
+    /// struct Cache(Vec<u32>);
+    ///
+    /// impl Actor for Cache {
+    ///     // A panic cannot leave the cache half-updated, so there is no need to stop it
+    ///     const PANIC_ACTION: PanicAction = PanicAction::Resume;
+    /// }
+    ///
+    /// struct Get(usize);
+    /// impl Message for Get {
+    ///     type Result = u32;
+    /// }
+    ///
+    /// impl SyncHandler<Get> for Cache {
+    ///     fn handle(&mut self, Get(index): Get, _: &mut Context<Self>) -> u32 {
+    ///         self.0[index]
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Cache(vec![1, 2]).spawn();
+    ///     assert!(addr.send(Get(2)).await.is_err()); // The handler panicked...
+    ///     assert_eq!(addr.send(Get(1)).await, Ok(2)); // ...but the actor kept running
+    /// }
+    /// ```
+    const PANIC_ACTION: PanicAction = PanicAction::Stop;
+
+    /// Called when one of the actor's handlers panicked, with the panic's payload. The return value
+    /// decides whether the actor resumes handling messages, stops, or restarts (see
+    /// [`PanicAction`](enum.PanicAction.html)). By default, this returns
+    /// [`Actor::PANIC_ACTION`](trait.Actor.html#associatedconstant.PANIC_ACTION), which stops the
+    /// actor unless it is overridden. Either way, the sender of the message which caused the panic will
+    /// receive a [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected) error,
+    /// and other messages are unaffected.
+    ///
+    /// Panics are caught with [`std::panic::catch_unwind`](https://doc.rust-lang.org/std/panic/fn.catch_unwind.html),
+    /// so this is only called if the crate is compiled with `panic = "unwind"` (the default).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::{PanicAction, prelude::*};
+    /// # use std::any::Any;
+    /// struct Resilient;
+    ///
+    /// impl Actor for Resilient {
+    ///     fn panicked(
+    ///         &mut self,
+    ///         _ctx: &mut Context<Self>,
+    ///         payload: Box<dyn Any + Send>,
+    ///     ) -> PanicAction {
+    ///         // Dividing by zero is the sender's mistake, but anything else could be a bug
+    ///         match payload.downcast_ref::<&str>() {
+    ///             Some(message) if message.contains("divide by zero") => PanicAction::Resume,
+    ///             _ => PanicAction::Stop,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// struct Divide(u32, u32);
+    /// impl Message for Divide {
+    ///     type Result = u32;
+    /// }
+    ///
+    /// impl SyncHandler<Divide> for Resilient {
+    ///     fn handle(&mut self, Divide(a, b): Divide, _: &mut Context<Self>) -> u32 {
+    ///         a / b
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Resilient.spawn();
+    ///     assert!(addr.send(Divide(1, 0)).await.is_err()); // The handler panicked...
+    ///     assert_eq!(addr.send(Divide(4, 2)).await, Ok(2)); // ...but the actor kept running
+    /// }
+    /// ```
+    #[allow(unused_variables)]
+    fn panicked(&mut self, ctx: &mut Context<Self>, payload: Box<dyn Any + Send>) -> PanicAction {
+        Self::PANIC_ACTION
+    }
+
+    /// Called when one of the actor's handlers ran for longer than the handler timeout of the
+    /// actor (see [`ActorManager::handler_timeout`](struct.ActorManager.html#method.handler_timeout)),
+    /// with the name of the type of the message it was handling. The handler has already been
+    /// dropped by then, and the sender of the message receives a
+    /// [`SendError::Timeout`](enum.SendError.html#variant.Timeout) error. The return value decides
+    /// whether the actor resumes handling messages, stops, or restarts (see
+    /// [`PanicAction`](enum.PanicAction.html)). By default, the actor resumes, since dropping the
+    /// handler only leaves the actor in a bad state if it was interrupted halfway through updating
+    /// it.
+    #[allow(unused_variables)]
+    fn handler_timed_out(&mut self, ctx: &mut Context<Self>, message: &'static str) -> PanicAction {
+        PanicAction::Resume
+    }
+
+    /// Spawns the actor with the global [`Spawner`](trait.Spawner.html) if one was set with
+    /// [`set_global_spawner`](fn.set_global_spawner.html), or otherwise onto the global executor
+    /// of the enabled runtime (one of Tokio, async-std, smol, or wasm-bindgen-futures, depending on
+    /// the `with-*` feature enabled), returning its address. This creates the actor and spawns its
+    /// [`ActorManager`](struct.ActorManager.html) in one call. To spawn the actor with a bounded
+    /// mailbox, use [`Actor::spawn_with`](trait.Actor.html#method.spawn_with).
+    ///
+    /// # Panics
+    ///
+    /// Panics if no global spawner was set and no runtime is enabled.
     ///
     /// # Example
     ///
@@ -298,35 +1050,52 @@ pub trait Actor: 'static + Send + Sized {
     ///     Timer::after(Duration::from_secs(1)).await; // Give it time to run
     /// }
     /// ```
-    #[cfg(any(
-        doc,
-        feature = "with-tokio-0_2",
-        feature = "with-async_std-1",
-        feature = "with-wasm_bindgen-0_2",
-        feature = "with-smol-0_1"
-    ))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-tokio-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-async_std-1")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-smol-0_1")))]
     fn spawn(self) -> Address<Self>
     where
         Self: Send,
     {
-        let (addr, mgr) = ActorManager::start(self);
-
-        #[cfg(feature = "with-tokio-0_2")]
-        tokio::spawn(mgr.manage());
-
-        #[cfg(feature = "with-async_std-1")]
-        async_std::task::spawn(mgr.manage());
-
-        #[cfg(feature = "with-wasm_bindgen-0_2")]
-        wasm_bindgen_futures::spawn_local(mgr.manage());
+        self.spawn_with(MailboxConfig::default())
+    }
 
-        #[cfg(feature = "with-smol-0_1")]
-        smol::Task::spawn(mgr.manage()).detach();
+    /// Like [`Actor::spawn`](trait.Actor.html#method.spawn), but the actor's mailbox will be
+    /// created according to `config`, as in [`Actor::create_with`](trait.Actor.html#method.create_with).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::MailboxConfig;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {}
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.spawn_with(MailboxConfig::bounded(16));
+    ///     assert_eq!(addr.capacity(), Some(16));
+    /// }
+    /// ```
+    fn spawn_with(self, config: MailboxConfig) -> Address<Self>
+    where
+        Self: Send,
+    {
+        let (addr, mgr) = ActorManager::start(self, config);
+        spawner::spawn(Box::pin(async move {
+            mgr.manage().await;
+        }));
+        addr
+    }
 
+    /// Like [`Actor::spawn`](trait.Actor.html#method.spawn), but the actor is spawned with the
+    /// given [`Spawner`](trait.Spawner.html) rather than the global one. See the `Spawner`
+    /// documentation for an example.
+    fn spawn_on<S>(self, spawner: &S) -> Address<Self>
+    where
+        S: Spawner + ?Sized,
+        Self: Send,
+    {
+        let (addr, mgr) = self.create();
+        spawner.spawn(Box::pin(async move {
+            mgr.manage().await;
+        }));
         addr
     }
 
@@ -344,13 +1113,84 @@ pub trait Actor: 'static + Send + Sized {
     /// #[smol_potat::main]
     /// async fn main() {
     ///     let (addr, mgr) = MyActor.create();
-    ///     smol::Task::spawn(mgr.manage()).detach(); // Actually spawn the actor onto an executor
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach(); // Actually spawn the actor onto an executor
     ///
     ///     Timer::after(Duration::from_secs(1)).await; // Give it time to run
     /// }
     /// ```
     fn create(self) -> (Address<Self>, ActorManager<Self>) {
-        ActorManager::start(self)
+        ActorManager::start(self, MailboxConfig::default())
+    }
+
+    /// Like [`Actor::create`](trait.Actor.html#method.create), but the actor's mailbox will only
+    /// hold up to `capacity` messages at once. When it is full, methods such as
+    /// [`AddressExt::send`](trait.AddressExt.html#method.send) and
+    /// [`AddressExt::do_send_async`](trait.AddressExt.html#method.do_send_async) will wait for
+    /// space to become available, applying backpressure to the senders. This is shorthand for
+    /// calling [`Actor::create_with`](trait.Actor.html#method.create_with) with
+    /// [`MailboxConfig::bounded`](struct.MailboxConfig.html#method.bounded).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, since no message could ever be sent to the actor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {}
+    /// # struct Msg;
+    /// # impl Message for Msg {
+    /// #    type Result = ();
+    /// # }
+    /// # impl SyncHandler<Msg> for MyActor {
+    /// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+    /// # }
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = MyActor.create_bounded(1);
+    ///
+    ///     // The mailbox has space for one message, so this will not wait
+    ///     addr.do_send_async(Msg).await.unwrap();
+    ///
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     // This waits for the actor to process the first message before being sent
+    ///     addr.send(Msg).await.unwrap();
+    /// }
+    /// ```
+    fn create_bounded(self, capacity: usize) -> (Address<Self>, ActorManager<Self>) {
+        self.create_with(MailboxConfig::bounded(capacity))
+    }
+
+    /// Like [`Actor::create`](trait.Actor.html#method.create), but the actor's mailbox will be
+    /// created according to the given [`MailboxConfig`](struct.MailboxConfig.html). This allows
+    /// the mailbox to be bounded and for its [`OverflowPolicy`](enum.OverflowPolicy.html) to be
+    /// chosen. To also configure the actor's instrumentation, use
+    /// [`Actor::builder`](trait.Actor.html#method.builder).
+    fn create_with(self, config: MailboxConfig) -> (Address<Self>, ActorManager<Self>) {
+        ActorManager::start(self, config)
+    }
+
+    /// Returns an [`ActorBuilder`](struct.ActorBuilder.html) through which the actor's mailbox
+    /// capacity, overflow policy, priority lanes, middleware, and watchdog can be configured
+    /// together before it is created or spawned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {}
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = MyActor.builder().capacity(16).priority_lanes(false).spawn();
+    ///     assert_eq!(addr.capacity(), Some(16));
+    /// }
+    /// ```
+    fn builder(self) -> ActorBuilder<Self> {
+        ActorBuilder::new(self)
     }
 }
 
@@ -373,15 +1213,33 @@ impl From<bool> for KeepRunning {
     }
 }
 
-impl Into<bool> for KeepRunning {
-    fn into(self) -> bool {
-        match self {
+impl From<KeepRunning> for bool {
+    fn from(keep_running: KeepRunning) -> bool {
+        match keep_running {
             KeepRunning::Yes => true,
             KeepRunning::No => false,
         }
     }
 }
 
+/// What an actor should do after one of its handlers panicked. This is returned from
+/// [`Actor::panicked`](trait.Actor.html#method.panicked), and is given for all panics of an actor
+/// by [`Actor::PANIC_ACTION`](trait.Actor.html#associatedconstant.PANIC_ACTION).
+/// It is also returned from [`Actor::handler_timed_out`](trait.Actor.html#method.handler_timed_out)
+/// after one of its handlers timed out.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PanicAction {
+    /// Keep handling messages with the actor as it is
+    Resume,
+    /// Stop the actor, without calling [`Actor::stopping`](trait.Actor.html#method.stopping)
+    Stop,
+    /// Call [`Actor::stopped`](trait.Actor.html#method.stopped) and then
+    /// [`Actor::started`](trait.Actor.html#method.started) again on the actor, before handling the
+    /// rest of the messages in its mailbox. If the actor is run by a
+    /// [`Supervisor`](struct.Supervisor.html), it is replaced with a new instance instead.
+    Restart,
+}
+
 impl From<()> for KeepRunning {
     fn from(_: ()) -> KeepRunning {
         KeepRunning::Yes
@@ -0,0 +1,120 @@
+use crate::ActorId;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::task::{Context, Poll};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+
+/// The senders of all streams returned by `lifecycle_events` which have not been dropped yet, as
+/// far as the last event knows
+static SUBSCRIBERS: Mutex<Vec<UnboundedSender<LifecycleEvent>>> = Mutex::new(Vec::new());
+
+fn subscribers() -> MutexGuard<'static, Vec<UnboundedSender<LifecycleEvent>>> {
+    // Nothing can panic while the lock is held, so poisoning can safely be ignored
+    SUBSCRIBERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// What happened to an actor in a [`LifecycleEvent`](struct.LifecycleEvent.html).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum LifecycleEventKind {
+    /// The actor was started, after [`Actor::started`](trait.Actor.html#method.started) and
+    /// [`Actor::async_started`](trait.Actor.html#method.async_started) were called
+    Started,
+    /// The actor is stopping, as soon as that is decided: when it did not prevent it in
+    /// [`Actor::stopping`](trait.Actor.html#method.stopping), was stopped gracefully (in which case
+    /// it still handles the messages left in its mailbox), or stopped after a handler panicked or
+    /// timed out. [`Actor::stopped`](trait.Actor.html#method.stopped) is called after this.
+    Stopping,
+    /// The actor has stopped, after [`Actor::stopped`](trait.Actor.html#method.stopped) was called
+    /// and its children and tasks were stopped. If it is restarted afterwards, a `Restarted`
+    /// event follows.
+    Stopped,
+    /// A new instance of the actor was started after the previous one stopped, either because
+    /// [`Actor::panicked`](trait.Actor.html#method.panicked) decided to restart it or because it
+    /// is run by a [`Supervisor`](struct.Supervisor.html). This takes the place of `Started` for
+    /// every instance after the first.
+    Restarted,
+}
+
+/// An event in the lifecycle of an actor, received from the stream returned by
+/// [`lifecycle_events`](fn.lifecycle_events.html).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct LifecycleEvent {
+    /// The type name of the actor, as given by `std::any::type_name`
+    pub actor: &'static str,
+    /// The id of the actor, which stays the same across restarts
+    pub id: ActorId,
+    /// What happened to the actor
+    pub kind: LifecycleEventKind,
+}
+
+/// Subscribe to the lifecycle events of all actors in the process, to keep track of actors
+/// starting, stopping and restarting at runtime. The returned stream receives every event which
+/// happens after this was called, and events are buffered until they are taken out of it. Events
+/// for a single actor can be picked out by comparing their id to that of its address.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use futures::StreamExt;
+/// use xtra::{LifecycleEvent, LifecycleEventKind};
+///
+/// struct MyActor;
+/// impl Actor for MyActor {}
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let events = xtra::lifecycle_events();
+///
+///     let (addr, mgr) = MyActor.create();
+///     let id = addr.id();
+///     drop(addr);
+///     mgr.manage().await;
+///
+///     let kinds: Vec<_> = events
+///         .filter(|event| futures::future::ready(event.id == id))
+///         .map(|event| event.kind)
+///         .take(3)
+///         .collect()
+///         .await;
+///
+///     assert_eq!(
+///         kinds,
+///         vec![LifecycleEventKind::Started, LifecycleEventKind::Stopping, LifecycleEventKind::Stopped],
+///     );
+/// }
+/// ```
+pub fn lifecycle_events() -> LifecycleEvents {
+    let (tx, rx) = mpsc::unbounded();
+    subscribers().push(tx);
+    LifecycleEvents(rx)
+}
+
+/// Send an event about the actor of the given type to all subscribers, forgetting those whose
+/// streams have been dropped.
+pub(crate) fn emit<A>(id: ActorId, kind: LifecycleEventKind) {
+    let mut subscribers = subscribers();
+    if subscribers.is_empty() {
+        return;
+    }
+
+    let event = LifecycleEvent {
+        actor: std::any::type_name::<A>(),
+        id,
+        kind,
+    };
+    subscribers.retain(|subscriber| subscriber.unbounded_send(event).is_ok());
+}
+
+/// The stream of lifecycle events returned by [`lifecycle_events`](fn.lifecycle_events.html).
+/// Dropping it unsubscribes from the events.
+pub struct LifecycleEvents(UnboundedReceiver<LifecycleEvent>);
+
+impl Stream for LifecycleEvents {
+    type Item = LifecycleEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<LifecycleEvent>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
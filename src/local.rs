@@ -0,0 +1,284 @@
+//! Actors which are not `Send`, such as those holding an `Rc` or a `RefCell`. A
+//! [`LocalActor`](trait.LocalActor.html) is managed by a future which is not `Send` either, and
+//! so must be run on the thread which created it, for instance with a runtime's `spawn_local`.
+//! Its [`LocalAddress`](struct.LocalAddress.html) only carries messages, which are still `Send`,
+//! so it can be sent to other threads and used from there like an [`Address`](../struct.Address.html).
+//!
+//! This is a smaller API than that of regular actors: local actors have no supervision,
+//! middleware, or scheduled notifications, and their mailboxes are unbounded.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use xtra::local::{LocalActor, LocalContext, LocalHandler};
+//!
+//! struct Counter {
+//!     count: Rc<RefCell<usize>>,
+//! }
+//! impl LocalActor for Counter {}
+//!
+//! struct Increment;
+//! impl Message for Increment {
+//!     type Result = usize;
+//! }
+//!
+//! #[async_trait::async_trait(?Send)]
+//! impl LocalHandler<Increment> for Counter {
+//!     async fn handle(&mut self, _: Increment, _: &mut LocalContext<Self>) -> usize {
+//!         *self.count.borrow_mut() += 1;
+//!         *self.count.borrow()
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let count = Rc::new(RefCell::new(0));
+//!     let addr = Counter { count: count.clone() }.spawn_local();
+//!
+//!     assert_eq!(addr.send(Increment).await, Ok(1));
+//!     assert_eq!(addr.send(Increment).await, Ok(2));
+//!     assert_eq!(*count.borrow(), 2);
+//! }
+//! ```
+
+use crate::mailbox::{self, PushError, Receiver, Sender};
+use crate::response;
+use crate::{
+    DisconnectReason, ExitReason, MailboxConfig, Message, MessageResponseFuture, TrySendError,
+};
+use futures::future::LocalBoxFuture;
+use futures::StreamExt;
+
+/// A message for a local actor, which holds it in a closure so that the envelope itself does not
+/// depend on whether the actor is `Send`
+type Envelope<A> =
+    Box<dyn for<'a> FnOnce(&'a mut A, &'a mut LocalContext<A>) -> LocalBoxFuture<'a, ()> + Send>;
+
+/// Put a closure handling a message into an envelope.
+fn envelope<A, F>(f: F) -> Envelope<A>
+where
+    F: for<'a> FnOnce(&'a mut A, &'a mut LocalContext<A>) -> LocalBoxFuture<'a, ()>
+        + Send
+        + 'static,
+{
+    Box::new(f)
+}
+
+/// An actor which does not have to be `Send`. Apart from that, it is like an
+/// [`Actor`](../trait.Actor.html), though with fewer lifecycle methods.
+pub trait LocalActor: 'static + Sized {
+    /// Called as soon as the actor has been started.
+    #[allow(unused_variables)]
+    fn started(&mut self, ctx: &mut LocalContext<Self>) {}
+
+    /// Called once the actor has stopped, either because it was stopped with
+    /// [`LocalContext::stop`](struct.LocalContext.html#method.stop) or because all of its
+    /// addresses were dropped. This is also called if one of its handlers panicked, or if the
+    /// future managing it is dropped before it stopped.
+    fn stopped(&mut self) {}
+
+    /// Returns the actor's address and manager in a ready-to-start state. The future returned by
+    /// [`LocalActorManager::manage`](struct.LocalActorManager.html#method.manage) has to be run on
+    /// the current thread for the actor to start.
+    fn create(self) -> (LocalAddress<Self>, LocalActorManager<Self>) {
+        let (sender, receiver) = mailbox::mailbox(MailboxConfig::default(), None);
+        let ctx = LocalContext {
+            running: true,
+            receiver,
+        };
+        let mgr = LocalActorManager { actor: self, ctx };
+        (LocalAddress { sender }, mgr)
+    }
+
+    /// Spawns the actor onto the current thread with the `spawn_local` of the runtime enabled
+    /// with a `with-*` feature. With tokio, this must be called from within a `LocalSet`.
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    fn spawn_local(self) -> LocalAddress<Self> {
+        let (addr, mgr) = self.create();
+        crate::runtime::spawn_local(async move {
+            mgr.manage().await;
+        });
+        addr
+    }
+}
+
+/// A handler for a message sent to a [`LocalActor`](trait.LocalActor.html). Since the actor is
+/// not `Send`, neither does the future returned by the handler have to be, so this is implemented
+/// with `#[async_trait::async_trait(?Send)]`.
+#[async_trait::async_trait(?Send)]
+pub trait LocalHandler<M: Message>: LocalActor {
+    /// Handle a given message, returning its result.
+    async fn handle(&mut self, message: M, ctx: &mut LocalContext<Self>) -> M::Result;
+}
+
+/// The context of a [`LocalActor`](trait.LocalActor.html), through which it can stop itself.
+pub struct LocalContext<A> {
+    running: bool,
+    receiver: Receiver<Envelope<A>>,
+}
+
+impl<A: LocalActor> LocalContext<A> {
+    /// Stop the actor once the current message has been handled. The messages left in its mailbox
+    /// are dropped, and senders waiting on them receive `SendError::Disconnected(_)`.
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+}
+
+/// The manager of a [`LocalActor`](trait.LocalActor.html), like an
+/// [`ActorManager`](../struct.ActorManager.html) for a regular actor.
+pub struct LocalActorManager<A> {
+    actor: A,
+    ctx: LocalContext<A>,
+}
+
+impl<A: LocalActor> LocalActorManager<A> {
+    /// Starts the manage loop of the actor. The returned future is not `Send`, and resolves to the
+    /// reason why the actor stopped, once it has.
+    pub async fn manage(self) -> ExitReason {
+        let LocalActorManager { actor, ctx } = self;
+        let mut guard = StopOnDrop {
+            actor,
+            ctx: Some(ctx),
+        };
+        let ctx = guard
+            .ctx
+            .as_mut()
+            .expect("the context is only taken once stopped");
+        ctx.receiver.start();
+        guard.actor.started(ctx);
+
+        let reason = loop {
+            if !ctx.running {
+                break ExitReason::Stopped;
+            }
+
+            match ctx.receiver.next().await {
+                Some(envelope) => envelope(&mut guard.actor, ctx).await,
+                None => break ExitReason::AllAddressesDropped,
+            }
+        };
+
+        ctx.receiver.disconnect(reason.into());
+        reason
+    }
+}
+
+/// Calls `LocalActor::stopped` once dropped, so that it is called even if a handler panicked or
+/// the managing future was dropped before the actor stopped.
+struct StopOnDrop<A: LocalActor> {
+    actor: A,
+    ctx: Option<LocalContext<A>>,
+}
+
+impl<A: LocalActor> Drop for StopOnDrop<A> {
+    fn drop(&mut self) {
+        // Drop the mailbox before the actor is told, so that nobody waits on it any longer
+        drop(self.ctx.take());
+        self.actor.stopped();
+    }
+}
+
+/// An address to a [`LocalActor`](trait.LocalActor.html). It is `Send` and `Sync` even though the
+/// actor is not, so messages can be sent to the actor from any thread. The actor stops once all
+/// its addresses are dropped.
+pub struct LocalAddress<A> {
+    sender: Sender<Envelope<A>>,
+}
+
+impl<A: LocalActor> LocalAddress<A> {
+    /// Returns whether the actor is still running.
+    pub fn is_connected(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    /// Why the actor is disconnected from its addresses, or `None` if it is still connected.
+    pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.sender.disconnect_reason()
+    }
+
+    /// Why the actor is disconnected, once its mailbox refused a message.
+    fn closed_reason(&self) -> DisconnectReason {
+        self.disconnect_reason()
+            .unwrap_or(DisconnectReason::Stopped)
+    }
+
+    /// Sends a [`Message`](../trait.Message.html) to the actor, and does not wait for a response.
+    /// If this returns `Err(TrySendError::Disconnected(message, _))`, then the actor has stopped, and
+    /// the message is handed back.
+    pub fn do_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: LocalHandler<M>,
+    {
+        let wrap = |message: M| {
+            envelope(move |act: &mut A, ctx: &mut LocalContext<A>| {
+                Box::pin(async move {
+                    act.handle(message, ctx).await;
+                }) as LocalBoxFuture<'_, ()>
+            })
+        };
+        match self.sender.try_send_with(message, wrap, M::PRIORITY) {
+            Ok(()) => Ok(()),
+            Err(PushError::Closed(message)) => {
+                Err(TrySendError::Disconnected(message, self.closed_reason()))
+            }
+            Err(PushError::Full(message)) => Err(TrySendError::Full(message)),
+        }
+    }
+
+    /// Sends a [`Message`](../trait.Message.html) to the actor, and waits for a response. If this
+    /// resolves to `Err(SendError::Disconnected(_))`, then the actor has stopped. If it had already
+    /// stopped, [`MessageResponseFuture::sent`](../struct.MessageResponseFuture.html#method.sent)
+    /// hands the message back.
+    pub fn send<M>(&self, message: M) -> MessageResponseFuture<M>
+    where
+        M: Message,
+        A: LocalHandler<M>,
+    {
+        let (tx, rx) = response::channel();
+        let wrap = |message: M| {
+            envelope(move |act: &mut A, ctx: &mut LocalContext<A>| {
+                Box::pin(async move {
+                    if tx.is_canceled() {
+                        // Nobody is waiting for the result anymore, so don't bother computing it
+                        return;
+                    }
+                    let _ = tx.send(act.handle(message, ctx).await);
+                }) as LocalBoxFuture<'_, ()>
+            })
+        };
+
+        match self.sender.try_send_with(message, wrap, M::PRIORITY) {
+            Ok(()) => MessageResponseFuture::result(rx).responded_by(&self.sender),
+            // The mailbox is unbounded, so it can only fail to take the message if it is closed
+            Err(PushError::Closed(message) | PushError::Full(message)) => {
+                MessageResponseFuture::unsent(TrySendError::Disconnected(
+                    message,
+                    self.closed_reason(),
+                ))
+            }
+        }
+    }
+}
+
+impl<A> Clone for LocalAddress<A> {
+    fn clone(&self) -> Self {
+        LocalAddress {
+            sender: self.sender.clone(),
+        }
+    }
+}
@@ -0,0 +1,1343 @@
+//! The mailbox is the channel through which messages travel from addresses to the `ActorManager`.
+//! We use our own rather than `futures::channel::mpsc`, because its bounded variant requires
+//! `&mut self` to send and guarantees every clone of a sender its own slot, which would make the
+//! capacity meaningless for addresses (which are cloned freely and send through `&self`).
+
+use crate::manager::ManagerMessage;
+use crate::{Actor, ActorId, DisconnectReason, ExitReason, Message, Priority};
+use futures::channel::oneshot;
+use futures::task::{Context, Poll, Waker};
+use futures::{Future, FutureExt, Stream};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(feature = "lock-free")]
+mod lock_free;
+
+/// The configuration of an actor's mailbox, passed to [`Actor::create_with`](trait.Actor.html#method.create_with).
+/// By default, the mailbox is unbounded.
+///
+/// # Ordering
+///
+/// Messages are handled in the order in which they were put into the mailbox, so messages which
+/// one sender sends one after another are handled in the order it sent them, even if they were
+/// sent through different addresses or message channels to the actor. A message is put into the
+/// mailbox once [`AddressExt::do_send`](trait.AddressExt.html#method.do_send) has returned, or
+/// once the future of a method which may wait for space has stopped waiting. Messages sent by
+/// different senders at the same time are handled in whichever order they were put in. This is
+/// the case whether the mailbox is bounded, unbounded or lock-free, except that:
+///
+/// - Messages sent with [`Priority::High`](enum.Priority.html#variant.High) are handled before
+///   those of normal priority, unless priority lanes are disabled with
+///   [`MailboxConfig::priority_lanes`](struct.MailboxConfig.html#method.priority_lanes).
+/// - An [`OverflowPolicy`](enum.OverflowPolicy.html) other than `Block` may drop messages, and
+///   [`AddressExt::do_send_conflated`](trait.AddressExt.html#method.do_send_conflated) replaces
+///   a queued message in its place.
+/// - A custom [`Mailbox`](trait.Mailbox.html) queue decides the order itself.
+/// - An unordered mailbox (see `MailboxConfig::unordered`, with the `lock-free` feature) handles
+///   normal priority messages in any order, in exchange for less contention between senders.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::{MailboxConfig, OverflowPolicy, TrySendError};
+/// # struct MyActor;
+/// # impl Actor for MyActor {}
+/// # struct Msg;
+/// # impl Message for Msg {
+/// #    type Result = ();
+/// # }
+/// # impl SyncHandler<Msg> for MyActor {
+/// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+/// # }
+/// let config = MailboxConfig::bounded(1).overflow_policy(OverflowPolicy::Error);
+/// let (addr, _mgr) = MyActor.create_with(config);
+///
+/// assert!(addr.do_send(Msg).is_ok());
+/// assert!(matches!(addr.do_send(Msg), Err(TrySendError::Full(Msg))));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MailboxConfig {
+    pub(crate) capacity: Option<usize>,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) priority_lanes: bool,
+    #[cfg(feature = "lock-free")]
+    lock_free: bool,
+    #[cfg(feature = "lock-free")]
+    unordered: bool,
+}
+
+impl MailboxConfig {
+    /// A mailbox which can hold any number of messages.
+    pub fn unbounded() -> Self {
+        MailboxConfig {
+            capacity: None,
+            overflow_policy: OverflowPolicy::Block,
+            priority_lanes: true,
+            #[cfg(feature = "lock-free")]
+            lock_free: false,
+            #[cfg(feature = "lock-free")]
+            unordered: false,
+        }
+    }
+
+    /// A mailbox which holds up to `capacity` messages at once. What happens when it is full is
+    /// decided by the [`OverflowPolicy`](enum.OverflowPolicy.html), which is to block the sender
+    /// by default. Notifications that the actor sends to itself (such as
+    /// [`Context::notify_later`](struct.Context.html#method.notify_later)) are not limited by the
+    /// capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, since no message could ever be sent to the actor.
+    pub fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "mailbox capacity must be greater than zero");
+        MailboxConfig {
+            capacity: Some(capacity),
+            overflow_policy: OverflowPolicy::Block,
+            priority_lanes: true,
+            #[cfg(feature = "lock-free")]
+            lock_free: false,
+            #[cfg(feature = "lock-free")]
+            unordered: false,
+        }
+    }
+
+    /// Set what should happen when a message is sent to the actor while its mailbox is full. This
+    /// has no effect on an unbounded mailbox.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Set whether messages sent with [`Priority::High`](enum.Priority.html#variant.High) are
+    /// handled before normal priority messages, which they are by default. Without priority
+    /// lanes, all messages are handled in the order they were sent, whatever their priority.
+    pub fn priority_lanes(mut self, enabled: bool) -> Self {
+        self.priority_lanes = enabled;
+        self
+    }
+
+    /// Hold the messages in a lock-free queue, so that many senders can send to the actor at once
+    /// without contending for a lock. This only has an effect on an unbounded mailbox which uses
+    /// the default queue. Messages sent with
+    /// [`AddressExt::do_send_conflated`](trait.AddressExt.html#method.do_send_conflated) do not
+    /// replace those already queued in a lock-free mailbox, since it cannot be searched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::MailboxConfig;
+    /// # #[derive(Default)]
+    /// # struct Counter(u32);
+    /// # impl Actor for Counter {}
+    /// # struct Increment;
+    /// # impl Message for Increment {
+    /// #    type Result = u32;
+    /// # }
+    /// # impl SyncHandler<Increment> for Counter {
+    /// #     fn handle(&mut self, _: Increment, _ctx: &mut Context<Self>) -> u32 {
+    /// #         self.0 += 1;
+    /// #         self.0
+    /// #     }
+    /// # }
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Counter::default().spawn_with(MailboxConfig::unbounded().lock_free());
+    ///
+    ///     let senders: Vec<_> = (0..4)
+    ///         .map(|_| {
+    ///             let addr = addr.clone();
+    ///             smol::Task::spawn(async move {
+    ///                 for _ in 0..100 {
+    ///                     addr.do_send(Increment).unwrap();
+    ///                 }
+    ///             })
+    ///         })
+    ///         .collect();
+    ///     futures::future::join_all(senders).await;
+    ///
+    ///     assert_eq!(addr.send(Increment).await, Ok(401));
+    /// }
+    /// ```
+    #[cfg(feature = "lock-free")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "lock-free")))]
+    pub fn lock_free(mut self) -> Self {
+        self.lock_free = true;
+        self
+    }
+
+    /// Hold the messages in a lock-free queue as with
+    /// [`MailboxConfig::lock_free`](struct.MailboxConfig.html#method.lock_free), but give up on
+    /// handling them in the order they were sent in exchange for even less contention between
+    /// senders on different threads. Messages sent with
+    /// [`Priority::High`](enum.Priority.html#variant.High) are still handled before normal
+    /// priority ones, but normal priority messages, even those sent one after another by the
+    /// same sender, may be handled in any order. This only suits actors whose messages do not depend
+    /// on each other, such as counters or sinks of independent events.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::MailboxConfig;
+    /// # #[derive(Default)]
+    /// # struct Counter(u32);
+    /// # impl Actor for Counter {}
+    /// # struct Add(u32);
+    /// # impl Message for Add {
+    /// #    type Result = ();
+    /// # }
+    /// # impl SyncHandler<Add> for Counter {
+    /// #     fn handle(&mut self, Add(n): Add, _ctx: &mut Context<Self>) {
+    /// #         self.0 += n;
+    /// #     }
+    /// # }
+    /// # struct Get;
+    /// # impl Message for Get {
+    /// #    type Result = u32;
+    /// # }
+    /// # impl SyncHandler<Get> for Counter {
+    /// #     fn handle(&mut self, _: Get, _ctx: &mut Context<Self>) -> u32 {
+    /// #         self.0
+    /// #     }
+    /// # }
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let addr = Counter::default().spawn_with(MailboxConfig::unbounded().unordered());
+    ///
+    ///     let senders: Vec<_> = (1..=4)
+    ///         .map(|n| {
+    ///             let addr = addr.clone();
+    ///             smol::Task::spawn(async move {
+    ///                 for _ in 0..100 {
+    ///                     // Waiting for the response is the only way to be sure that a message was
+    ///                     // handled before those sent after it
+    ///                     addr.send(Add(n)).await.unwrap();
+    ///                 }
+    ///             })
+    ///         })
+    ///         .collect();
+    ///     futures::future::join_all(senders).await;
+    ///
+    ///     assert_eq!(addr.send(Get).await, Ok(1000));
+    /// }
+    /// ```
+    #[cfg(feature = "lock-free")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "lock-free")))]
+    pub fn unordered(mut self) -> Self {
+        self.lock_free = true;
+        self.unordered = true;
+        self
+    }
+
+    /// The maximum number of messages the mailbox can hold, or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+}
+
+impl Default for MailboxConfig {
+    fn default() -> Self {
+        MailboxConfig::unbounded()
+    }
+}
+
+/// What should happen when a message is sent to an actor whose bounded mailbox is full.
+///
+/// A message which is dropped will never be handled, so if it was sent with
+/// [`AddressExt::send`](trait.AddressExt.html#method.send), its response future will resolve to
+/// [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Wait for space to become available. Sending methods which cannot wait, such as
+    /// [`AddressExt::do_send`](trait.AddressExt.html#method.do_send), will put the message into
+    /// the mailbox regardless.
+    Block,
+    /// Drop the message being sent, keeping those already in the mailbox.
+    DropNewest,
+    /// Drop the oldest message in the mailbox to make space for the one being sent.
+    DropOldest,
+    /// Refuse the message, returning [`SendError::MailboxFull`](enum.SendError.html#variant.MailboxFull)
+    /// to the sender, or [`TrySendError::Full`](enum.TrySendError.html#variant.Full) with the
+    /// message from `do_send`.
+    Error,
+}
+
+/// A queue in which an actor's mailbox holds the messages sent to the actor until it handles them,
+/// which can be given to [`ActorBuilder::custom_mailbox`](struct.ActorBuilder.html#method.custom_mailbox)
+/// to replace the default queue, for instance to deduplicate messages or to keep them on disk.
+/// Everything else about the mailbox, such as its capacity and how senders wait for space, stays
+/// the same regardless of the queue.
+///
+/// The queue is called while the mailbox is locked, so it must never block, panic, or drop an
+/// envelope, since dropping an envelope could send to this same mailbox. Envelopes which the
+/// queue no longer wants are returned from [`Mailbox::push`](trait.Mailbox.html#tymethod.push)
+/// or [`Mailbox::close`](trait.Mailbox.html#tymethod.close) instead. Envelopes which do not
+/// contain a message, for which [`Envelope::message_type`](struct.Envelope.html#method.message_type)
+/// is `None`, are used by xtra to manage the actor and must be kept like any other.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::{Envelope, Mailbox, Priority};
+/// # use std::collections::VecDeque;
+/// struct Refresh;
+/// impl Message for Refresh {
+///     type Result = ();
+/// }
+///
+/// struct Cache {
+///     refreshes: u32,
+/// }
+/// # impl Actor for Cache {}
+/// # impl SyncHandler<Refresh> for Cache {
+/// #     fn handle(&mut self, _: Refresh, _: &mut Context<Self>) {
+/// #         self.refreshes += 1;
+/// #     }
+/// # }
+///
+/// /// A queue which ignores a refresh if another one is already waiting to be handled.
+/// #[derive(Default)]
+/// struct Deduplicating(VecDeque<Envelope<Cache>>);
+///
+/// impl Mailbox<Cache> for Deduplicating {
+///     fn push(&mut self, envelope: Envelope<Cache>, _: Priority) -> Option<Envelope<Cache>> {
+///         let is_refresh = |e: &Envelope<Cache>| e.downcast_ref::<Refresh>().is_some();
+///         if is_refresh(&envelope) && self.0.iter().any(is_refresh) {
+///             return Some(envelope);
+///         }
+///         self.0.push_back(envelope);
+///         None
+///     }
+///
+///     fn pop(&mut self) -> Option<Envelope<Cache>> {
+///         self.0.pop_front()
+///     }
+///
+///     fn len(&self) -> usize {
+///         self.0.len()
+///     }
+///
+///     fn close(&mut self) -> Vec<Envelope<Cache>> {
+///         self.0.drain(..).collect()
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = Cache { refreshes: 0 }
+///         .builder()
+///         .custom_mailbox(Deduplicating::default())
+///         .create();
+///
+///     for _ in 0..10 {
+///         addr.do_send(Refresh).unwrap();
+///     }
+///     assert_eq!(addr.len(), 1);
+/// #   drop(mgr);
+/// }
+/// ```
+pub trait Mailbox<A: Actor>: Send + 'static {
+    /// Add an envelope to the queue, which was sent with the given priority. This can return an
+    /// envelope to drop rather than keep, such as the one being pushed or one which it replaces.
+    /// A dropped message is never handled, as if the actor's mailbox had overflowed.
+    fn push(&mut self, envelope: Envelope<A>, priority: Priority) -> Option<Envelope<A>>;
+
+    /// Take the envelope which the actor should handle next out of the queue.
+    fn pop(&mut self) -> Option<Envelope<A>>;
+
+    /// The envelope which [`Mailbox::pop`](trait.Mailbox.html#tymethod.pop) would take next,
+    /// without taking it. This is used to find the messages which can be handled in the same
+    /// batch by a [`BatchHandler`](trait.BatchHandler.html), so without it messages are handled
+    /// one at a time.
+    fn peek(&self) -> Option<&Envelope<A>> {
+        None
+    }
+
+    /// The number of envelopes in the queue, which is checked against the mailbox's capacity.
+    fn len(&self) -> usize;
+
+    /// Whether there are no envelopes in the queue.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take every envelope out of the queue, once the actor has stopped and the mailbox is closed.
+    fn close(&mut self) -> Vec<Envelope<A>>;
+}
+
+/// A message sent to an actor, as it is held by a [`Mailbox`](trait.Mailbox.html) until it is
+/// handled. The message can be inspected, but not taken out.
+pub struct Envelope<A: Actor>(pub(crate) ManagerMessage<A>);
+
+impl<A: Actor> Envelope<A> {
+    /// A reference to the message in this envelope if it is of type `M`.
+    pub fn downcast_ref<M: Message>(&self) -> Option<&M> {
+        match &self.0 {
+            ManagerMessage::Message(envelope) | ManagerMessage::LateNotification(envelope) => {
+                envelope.message()?.downcast_ref()
+            }
+            ManagerMessage::LastAddress => None,
+        }
+    }
+
+    /// The name of the type of the message in this envelope, or `None` if it does not contain a
+    /// message but is used by xtra to manage the actor.
+    pub fn message_type(&self) -> Option<&'static str> {
+        match &self.0 {
+            ManagerMessage::Message(envelope) | ManagerMessage::LateNotification(envelope) => {
+                Some(envelope.message_type())
+            }
+            ManagerMessage::LastAddress => None,
+        }
+    }
+
+    /// Whether the actor sent this message to itself, with
+    /// [`Context::notify_later`](struct.Context.html#method.notify_later).
+    pub fn is_notification(&self) -> bool {
+        matches!(self.0, ManagerMessage::LateNotification(_))
+    }
+}
+
+/// The queue in which a mailbox holds its items. This is implemented by `Lanes`, and for each
+/// `Mailbox` through `Custom`.
+pub(crate) trait Queue<T>: Send {
+    /// Add an item, returning one to be dropped once the mailbox is unlocked if any.
+    fn push(&mut self, item: T, priority: Priority) -> Option<T>;
+
+    fn pop(&mut self) -> Option<T>;
+
+    /// The item which `pop` would return, without taking it.
+    fn peek(&self) -> Option<&T>;
+
+    fn len(&self) -> usize;
+
+    /// The first item with the given priority for which `matches` returns true.
+    fn find_mut(&mut self, priority: Priority, matches: &dyn Fn(&T) -> bool) -> Option<&mut T>;
+
+    /// Take out the item to drop to make space under `OverflowPolicy::DropOldest`.
+    fn pop_oldest(&mut self) -> Option<T>;
+
+    /// Take out all items, once the receiver has been dropped.
+    fn drain(&mut self) -> Vec<T>;
+}
+
+/// The default queue, in which items are received in the order they were sent, except that those
+/// sent with `Priority::High` are received first if priority lanes are enabled.
+struct Lanes<T> {
+    queue: VecDeque<T>,
+    /// Items sent with `Priority::High`, which are received before any in `queue`
+    priority_queue: VecDeque<T>,
+    /// Whether high priority items are received before normal priority ones
+    priority_lanes: bool,
+}
+
+impl<T> Lanes<T> {
+    fn lane(&mut self, priority: Priority) -> &mut VecDeque<T> {
+        match priority {
+            Priority::High if self.priority_lanes => &mut self.priority_queue,
+            _ => &mut self.queue,
+        }
+    }
+}
+
+impl<T: Send> Queue<T> for Lanes<T> {
+    fn push(&mut self, item: T, priority: Priority) -> Option<T> {
+        self.lane(priority).push_back(item);
+        None
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.priority_queue
+            .pop_front()
+            .or_else(|| self.queue.pop_front())
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.priority_queue.front().or_else(|| self.queue.front())
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len() + self.priority_queue.len()
+    }
+
+    fn find_mut(&mut self, priority: Priority, matches: &dyn Fn(&T) -> bool) -> Option<&mut T> {
+        self.lane(priority).iter_mut().find(|item| matches(item))
+    }
+
+    fn pop_oldest(&mut self) -> Option<T> {
+        // Prefer to drop normal priority items over high priority ones
+        self.queue
+            .pop_front()
+            .or_else(|| self.priority_queue.pop_front())
+    }
+
+    fn drain(&mut self) -> Vec<T> {
+        let mut items: Vec<T> = self.priority_queue.drain(..).collect();
+        items.extend(self.queue.drain(..));
+        items
+    }
+}
+
+/// Adapts a `Mailbox` to hold the items of an actor's mailbox. Custom queues cannot be searched,
+/// so conflated messages are queued like any other.
+pub(crate) struct Custom<A, Q>(Q, PhantomData<fn() -> A>);
+
+impl<A: Actor, Q: Mailbox<A>> Custom<A, Q> {
+    pub(crate) fn new(mailbox: Q) -> Self {
+        Custom(mailbox, PhantomData)
+    }
+}
+
+impl<A: Actor, Q: Mailbox<A>> Queue<ManagerMessage<A>> for Custom<A, Q> {
+    fn push(&mut self, item: ManagerMessage<A>, priority: Priority) -> Option<ManagerMessage<A>> {
+        self.0
+            .push(Envelope(item), priority)
+            .map(|envelope| envelope.0)
+    }
+
+    fn pop(&mut self) -> Option<ManagerMessage<A>> {
+        self.0.pop().map(|envelope| envelope.0)
+    }
+
+    fn peek(&self) -> Option<&ManagerMessage<A>> {
+        self.0.peek().map(|envelope| &envelope.0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn find_mut(
+        &mut self,
+        _: Priority,
+        _: &dyn Fn(&ManagerMessage<A>) -> bool,
+    ) -> Option<&mut ManagerMessage<A>> {
+        None
+    }
+
+    fn pop_oldest(&mut self) -> Option<ManagerMessage<A>> {
+        self.pop()
+    }
+
+    fn drain(&mut self) -> Vec<ManagerMessage<A>> {
+        self.0
+            .close()
+            .into_iter()
+            .map(|envelope| envelope.0)
+            .collect()
+    }
+}
+
+/// Create a new mailbox, holding its items in `queue` or in the default queue if it is `None`.
+/// If it is bounded, then senders which wait for space (i.e `Sender::send_async` and
+/// `Sender::poll_ready`) will be held back while the mailbox is full. If it is configured to be
+/// lock-free, is unbounded, and uses the default queue, it holds its items in a lock-free queue.
+pub(crate) fn mailbox<T: Send + 'static>(
+    config: MailboxConfig,
+    queue: Option<Box<dyn Queue<T>>>,
+) -> (Sender<T>, Receiver<T>) {
+    #[cfg(feature = "lock-free")]
+    let lock_free = (config.lock_free && queue.is_none() && config.capacity.is_none())
+        .then(|| lock_free::LockFree::new(config.priority_lanes, config.unordered));
+    let queue = queue.unwrap_or_else(|| {
+        Box::new(Lanes {
+            queue: VecDeque::new(),
+            priority_queue: VecDeque::new(),
+            priority_lanes: config.priority_lanes,
+        })
+    });
+    let shared = Arc::new(Shared {
+        inner: Mutex::new(Inner {
+            queue,
+            receiver_waker: None,
+            sender_wakers: VecDeque::new(),
+            close_callbacks: Vec::new(),
+            drop_callbacks: Some(Vec::new()),
+        }),
+        id: ActorId::next(),
+        capacity: config.capacity,
+        overflow_policy: config.overflow_policy,
+        #[cfg(feature = "lock-free")]
+        lock_free,
+        closed: AtomicBool::new(false),
+        keep_alive: AtomicBool::new(false),
+        disconnect: Disconnect(Arc::new(AtomicU8::new(0))),
+        exit: Exit(Arc::new(AtomicU8::new(0))),
+        senders: AtomicUsize::new(1),
+    });
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver {
+            shared,
+            started: false,
+            #[cfg(feature = "lock-free")]
+            peeked: None,
+        },
+    )
+}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    id: ActorId,
+    capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    /// The queue which holds the items instead of `Inner::queue`, without locking, if the mailbox
+    /// is unbounded and uses the default queue
+    #[cfg(feature = "lock-free")]
+    lock_free: Option<lock_free::LockFree<T>>,
+    /// Set when the receiver is dropped. After this, no more items will be accepted.
+    closed: AtomicBool,
+    /// Why the mailbox was closed, which is set before `closed` is
+    disconnect: Disconnect,
+    /// Whether the actor keeps running once there are no strong addresses to it left
+    keep_alive: AtomicBool,
+    /// Why the manage loop of the actor last exited, which is final once the receiver is dropped
+    exit: Exit,
+    senders: AtomicUsize,
+}
+
+/// Why a mailbox was closed. It is shared with the response futures of the messages sent to the
+/// mailbox, so that they can tell why their message was dropped.
+#[derive(Clone)]
+pub(crate) struct Disconnect(Arc<AtomicU8>);
+
+impl Disconnect {
+    /// Record why the mailbox was closed, unless it already was.
+    fn set(&self, reason: DisconnectReason) {
+        let reason = match reason {
+            DisconnectReason::Stopped => 1,
+            DisconnectReason::AllAddressesDropped => 2,
+            DisconnectReason::NeverStarted => 3,
+            DisconnectReason::MessageDropped => 4,
+        };
+        let _ = self
+            .0
+            .compare_exchange(0, reason, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Why the mailbox was closed, if it was.
+    pub(crate) fn get(&self) -> Option<DisconnectReason> {
+        match self.0.load(Ordering::Acquire) {
+            1 => Some(DisconnectReason::Stopped),
+            2 => Some(DisconnectReason::AllAddressesDropped),
+            3 => Some(DisconnectReason::NeverStarted),
+            4 => Some(DisconnectReason::MessageDropped),
+            _ => None,
+        }
+    }
+}
+
+/// Why the manage loop of the actor exited. It is shared with the futures returned by
+/// `Sender::exited`, so that they do not keep the mailbox alive.
+#[derive(Clone)]
+struct Exit(Arc<AtomicU8>);
+
+impl Exit {
+    /// Record why the manage loop exited, replacing the reason it exited for before a restart.
+    fn set(&self, reason: ExitReason) {
+        let reason = match reason {
+            ExitReason::AllAddressesDropped => 1,
+            ExitReason::Stopped => 2,
+            ExitReason::Panicked => 3,
+            ExitReason::MailboxClosed => 4,
+        };
+        self.0.store(reason, Ordering::Release);
+    }
+
+    /// Why the manage loop exited, if it has.
+    fn get(&self) -> Option<ExitReason> {
+        match self.0.load(Ordering::Acquire) {
+            1 => Some(ExitReason::AllAddressesDropped),
+            2 => Some(ExitReason::Stopped),
+            3 => Some(ExitReason::Panicked),
+            4 => Some(ExitReason::MailboxClosed),
+            _ => None,
+        }
+    }
+}
+
+struct Inner<T> {
+    queue: Box<dyn Queue<T>>,
+    receiver_waker: Option<Waker>,
+    /// Senders waiting for space in the mailbox to become available
+    sender_wakers: VecDeque<Waker>,
+    /// Callbacks to run once the mailbox is closed, either by `Sender::close` or by the receiver
+    /// being dropped
+    close_callbacks: Vec<Box<dyn FnOnce() + Send>>,
+    /// Callbacks to run once the receiver has been dropped, or `None` if it already has been
+    drop_callbacks: Option<Vec<Box<dyn FnOnce() + Send>>>,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        // A panic can't happen while the lock is held (we never drop items inside it, and custom
+        // queues must not panic), so poisoning can safely be ignored
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn has_space(&self, inner: &Inner<T>) -> bool {
+        self.capacity.is_none_or(|cap| inner.queue.len() < cap)
+    }
+
+    /// Push an item without checking the capacity. Items are never dropped inside the lock, since
+    /// dropping an envelope could drop an address, which may in turn send to this same mailbox.
+    fn push(&self, mut inner: MutexGuard<'_, Inner<T>>, item: T, priority: Priority) {
+        let rejected = inner.queue.push(item, priority);
+        let waker = inner.receiver_waker.take();
+        drop(inner);
+        drop(rejected);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Wake the receiver if it is waiting on the lock-free queue, so that it sees that the mailbox
+    /// was closed or that there are no senders left.
+    fn wake_lock_free_receiver(&self) {
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.lock_free {
+            queue.wake();
+        }
+    }
+
+    fn wake_next_sender(&self) {
+        let waker = self.lock().sender_wakers.pop_front();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The reason that an item could not be sent without waiting. `Full` is only returned if the
+/// overflow policy is to block or return an error, as otherwise an item is dropped instead.
+pub(crate) enum PushError<T> {
+    Full(T),
+    Closed(T),
+}
+
+/// The sending half of a mailbox. It can be cloned and is held by every address to the actor.
+pub(crate) struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Whether the receiving half has been dropped.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+
+    /// Send an item regardless of the mailbox's capacity. This is used for messages which must
+    /// never be held back, such as notifications from the actor to itself or the `LastAddress`
+    /// signal sent when an address is dropped.
+    pub(crate) fn force_send(&self, item: T, priority: Priority) -> Result<(), T> {
+        self.force_send_with(item, |item| item, priority)
+    }
+
+    /// Like `force_send`, but the item is only created from `value` with `wrap` once it is certain
+    /// that the mailbox is open, so that `value` can be returned otherwise. `wrap` is called while
+    /// the mailbox is locked, so it must not block or panic.
+    pub(crate) fn force_send_with<U, F>(
+        &self,
+        value: U,
+        wrap: F,
+        priority: Priority,
+    ) -> Result<(), U>
+    where
+        F: FnOnce(U) -> T,
+    {
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.shared.lock_free {
+            return self.push_lock_free(queue, value, wrap, priority);
+        }
+
+        let inner = self.shared.lock();
+        if self.is_closed() {
+            return Err(value);
+        }
+        self.shared.push(inner, wrap(value), priority);
+        Ok(())
+    }
+
+    /// Push an item onto the lock-free queue, which is never full.
+    #[cfg(feature = "lock-free")]
+    fn push_lock_free<U, F>(
+        &self,
+        queue: &lock_free::LockFree<T>,
+        value: U,
+        wrap: F,
+        priority: Priority,
+    ) -> Result<(), U>
+    where
+        F: FnOnce(U) -> T,
+    {
+        if self.is_closed() {
+            return Err(value);
+        }
+        queue.push(wrap(value), priority);
+        Ok(())
+    }
+
+    /// Stop accepting new items, while letting the receiver take the ones which are already queued.
+    /// Once those are taken, the receiver's stream ends.
+    pub(crate) fn close(&self) {
+        let mut inner = self.shared.lock();
+        self.shared.disconnect.set(DisconnectReason::Stopped);
+        self.shared.closed.store(true, Ordering::Release);
+        let receiver_waker = inner.receiver_waker.take();
+        let wakers = std::mem::take(&mut inner.sender_wakers);
+        let close_callbacks = std::mem::take(&mut inner.close_callbacks);
+        drop(inner);
+
+        if let Some(waker) = receiver_waker {
+            waker.wake();
+        }
+        self.shared.wake_lock_free_receiver();
+        wakers.into_iter().for_each(Waker::wake);
+        close_callbacks.into_iter().for_each(|callback| callback());
+    }
+
+    /// The id of the actor which this mailbox belongs to.
+    pub(crate) fn id(&self) -> ActorId {
+        self.shared.id
+    }
+
+    /// Why the mailbox was closed, if it was.
+    pub(crate) fn disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.shared.disconnect.get()
+    }
+
+    /// Why the mailbox was closed, which can be checked later without holding on to the sender.
+    pub(crate) fn disconnect(&self) -> Disconnect {
+        self.shared.disconnect.clone()
+    }
+
+    /// Register a callback to be run once the mailbox is closed. If it already is, the callback is
+    /// returned instead.
+    pub(crate) fn on_close<F>(&self, callback: F) -> Result<(), F>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut inner = self.shared.lock();
+        if self.is_closed() {
+            return Err(callback);
+        }
+        inner.close_callbacks.push(Box::new(callback));
+        Ok(())
+    }
+
+    /// A future which resolves once the mailbox is closed, either by the receiver being dropped or
+    /// by `close`.
+    pub(crate) fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        if let Err(notify) = self.on_close(move || {
+            let _ = tx.send(());
+        }) {
+            notify();
+        }
+
+        rx.map(|_| ())
+    }
+
+    /// Register a callback to be run once the receiver has been dropped, which is after the actor
+    /// has stopped. If it already has been, the callback is returned instead.
+    pub(crate) fn on_drop<F>(&self, callback: F) -> Result<(), F>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        match &mut self.shared.lock().drop_callbacks {
+            Some(callbacks) => {
+                callbacks.push(Box::new(callback));
+                Ok(())
+            }
+            None => Err(callback),
+        }
+    }
+
+    /// Whether the actor keeps running once there are no strong addresses to it left.
+    pub(crate) fn is_kept_alive(&self) -> bool {
+        self.shared.keep_alive.load(Ordering::Acquire)
+    }
+
+    /// Whether the receiver has been dropped, which is after the actor has stopped.
+    pub(crate) fn is_receiver_dropped(&self) -> bool {
+        self.shared.lock().drop_callbacks.is_none()
+    }
+
+    /// A future which resolves once the receiver has been dropped.
+    pub(crate) fn dropped(&self) -> impl Future<Output = ()> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        if let Err(notify) = self.on_drop(move || {
+            let _ = tx.send(());
+        }) {
+            notify();
+        }
+
+        rx.map(|_| ())
+    }
+
+    /// A future which resolves once the receiver has been dropped, to why the manage loop of the
+    /// actor exited, or `None` if it was dropped before exiting.
+    pub(crate) fn exited(&self) -> impl Future<Output = Option<ExitReason>> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        let exit = self.shared.exit.clone();
+        if let Err(notify) = self.on_drop(move || {
+            let _ = tx.send(exit.get());
+        }) {
+            notify();
+        }
+
+        rx.map(|reason| reason.ok().flatten())
+    }
+
+    /// The number of items in the mailbox.
+    pub(crate) fn len(&self) -> usize {
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.shared.lock_free {
+            return queue.len();
+        }
+
+        self.shared.lock().queue.len()
+    }
+
+    pub(crate) fn capacity(&self) -> Option<usize> {
+        self.shared.capacity
+    }
+
+    pub(crate) fn overflow_policy(&self) -> OverflowPolicy {
+        self.shared.overflow_policy
+    }
+
+    /// Send an item if there is space in the mailbox. If there is not, the overflow policy decides
+    /// whether an item is dropped or this one is returned.
+    pub(crate) fn try_send(&self, item: T, priority: Priority) -> Result<(), PushError<T>> {
+        self.try_send_with(item, |item| item, priority)
+    }
+
+    /// Like `try_send`, but the item is only created from `value` with `wrap` once it is certain
+    /// that there is space for it, so that `value` can be returned otherwise. `wrap` is called
+    /// while the mailbox is locked, so it must not block or panic.
+    pub(crate) fn try_send_with<U, F>(
+        &self,
+        value: U,
+        wrap: F,
+        priority: Priority,
+    ) -> Result<(), PushError<U>>
+    where
+        F: FnOnce(U) -> T,
+    {
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.shared.lock_free {
+            return self
+                .push_lock_free(queue, value, wrap, priority)
+                .map_err(PushError::Closed);
+        }
+
+        let inner = self.shared.lock();
+        if self.is_closed() {
+            return Err(PushError::Closed(value));
+        }
+
+        self.push_or_overflow(inner, value, wrap, priority)
+    }
+
+    /// Send as many of the items as there is space for, taking them from the front of `items`, while
+    /// locking the mailbox and waking the receiver only once. The items which do not fit are left
+    /// in `items`, whatever the overflow policy. If the mailbox is closed, no items are sent.
+    pub(crate) fn try_send_many(
+        &self,
+        items: &mut VecDeque<T>,
+        priority: Priority,
+    ) -> Result<(), ()> {
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.shared.lock_free {
+            if self.is_closed() {
+                return Err(());
+            }
+            queue.push_many(items.drain(..), priority);
+            return Ok(());
+        }
+
+        let mut inner = self.shared.lock();
+        if self.is_closed() {
+            return Err(());
+        }
+
+        let mut rejected = Vec::new();
+        let mut sent = false;
+        while self.shared.has_space(&inner) {
+            match items.pop_front() {
+                Some(item) => rejected.extend(inner.queue.push(item, priority)),
+                None => break,
+            }
+            sent = true;
+        }
+        let waker = if sent {
+            inner.receiver_waker.take()
+        } else {
+            None
+        };
+        drop(inner);
+        drop(rejected);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Like `try_send_with`, but if there is an item queued with the same priority for which
+    /// `matches` returns true, that item is replaced with the new one instead, keeping its place
+    /// in the queue. `matches` is called while the mailbox is locked, so it must not block or panic.
+    pub(crate) fn replace_or_send_with<U, F, P>(
+        &self,
+        value: U,
+        wrap: F,
+        priority: Priority,
+        matches: P,
+    ) -> Result<(), PushError<U>>
+    where
+        F: FnOnce(U) -> T,
+        P: Fn(&T) -> bool,
+    {
+        // The lock-free queue cannot be searched, so the item is queued like any other
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.shared.lock_free {
+            return self
+                .push_lock_free(queue, value, wrap, priority)
+                .map_err(PushError::Closed);
+        }
+
+        let mut inner = self.shared.lock();
+        if self.is_closed() {
+            return Err(PushError::Closed(value));
+        }
+
+        match inner.queue.find_mut(priority, &matches) {
+            Some(item) => {
+                let replaced = std::mem::replace(item, wrap(value));
+                drop(inner);
+                drop(replaced);
+                Ok(())
+            }
+            None => self.push_or_overflow(inner, value, wrap, priority),
+        }
+    }
+
+    /// Push an item if there is space in the mailbox, or otherwise act according to the overflow
+    /// policy.
+    fn push_or_overflow<U, F>(
+        &self,
+        mut inner: MutexGuard<'_, Inner<T>>,
+        value: U,
+        wrap: F,
+        priority: Priority,
+    ) -> Result<(), PushError<U>>
+    where
+        F: FnOnce(U) -> T,
+    {
+        if self.shared.has_space(&inner) {
+            self.shared.push(inner, wrap(value), priority);
+            return Ok(());
+        }
+
+        match self.shared.overflow_policy {
+            OverflowPolicy::Block | OverflowPolicy::Error => Err(PushError::Full(value)),
+            OverflowPolicy::DropNewest => {
+                drop(inner);
+                drop(value);
+                Ok(())
+            }
+            OverflowPolicy::DropOldest => {
+                let oldest = inner.queue.pop_oldest();
+                self.shared.push(inner, wrap(value), priority);
+                drop(oldest);
+                Ok(())
+            }
+        }
+    }
+
+    /// Send an item, waiting for space in the mailbox if it is full.
+    pub(crate) fn send_async(&self, item: T, priority: Priority) -> SendFuture<T> {
+        SendFuture {
+            sender: self.clone(),
+            item: Some(item),
+            priority,
+            waiting: false,
+        }
+    }
+
+    /// Poll whether there is space in the mailbox, registering to be woken when space becomes
+    /// available if not. This is used for the `Sink` implementations of the addresses. Only the
+    /// blocking overflow policy waits for space.
+    pub(crate) fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        #[cfg(feature = "lock-free")]
+        if self.shared.lock_free.is_some() {
+            return Poll::Ready(if self.is_closed() { Err(()) } else { Ok(()) });
+        }
+
+        let mut inner = self.shared.lock();
+        if self.is_closed() {
+            Poll::Ready(Err(()))
+        } else if self.shared.overflow_policy != OverflowPolicy::Block
+            || self.shared.has_space(&inner)
+        {
+            Poll::Ready(Ok(()))
+        } else {
+            inner.sender_wakers.push_back(ctx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let waker = self.shared.lock().receiver_waker.take();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+            self.shared.wake_lock_free_receiver();
+        }
+    }
+}
+
+/// The future returned by `Sender::send_async`. It resolves once the item has been pushed into
+/// the mailbox, or returns the item if the mailbox was closed.
+pub(crate) struct SendFuture<T> {
+    sender: Sender<T>,
+    item: Option<T>,
+    priority: Priority,
+    /// Whether this future has registered its waker on the queue of waiting senders
+    waiting: bool,
+}
+
+// The item is never pinned, so this is safe to implement regardless of `T`
+impl<T> Unpin for SendFuture<T> {}
+
+impl<T> Future for SendFuture<T> {
+    type Output = Result<(), T>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let shared = &this.sender.shared;
+
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &shared.lock_free {
+            let item = this.item.take().expect("polled after completion");
+            return Poll::Ready(this.sender.push_lock_free(
+                queue,
+                item,
+                |item| item,
+                this.priority,
+            ));
+        }
+
+        let mut inner = shared.lock();
+
+        if this.sender.is_closed() {
+            drop(inner);
+            return Poll::Ready(Err(this.item.take().expect("polled after completion")));
+        }
+
+        if shared.has_space(&inner) {
+            this.waiting = false;
+            let item = this.item.take().expect("polled after completion");
+            shared.push(inner, item, this.priority);
+            Poll::Ready(Ok(()))
+        } else {
+            this.waiting = true;
+            inner.sender_wakers.push_back(ctx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for SendFuture<T> {
+    fn drop(&mut self) {
+        // If we were woken to take a free slot but are being dropped before using it, pass the
+        // wakeup on so that another waiting sender is not starved
+        if self.waiting && self.item.is_some() {
+            self.sender.shared.wake_next_sender();
+        }
+    }
+}
+
+/// The receiving half of a mailbox. It is held by the actor's `Context`.
+pub(crate) struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    /// Whether the actor's manage loop has been started, so that the mailbox being closed can be
+    /// told apart from the actor never having been started
+    started: bool,
+    /// An item taken out of the lock-free queue by `try_recv_if` without being received, since
+    /// that queue cannot be peeked at. It is received before any item still in the queue.
+    #[cfg(feature = "lock-free")]
+    peeked: Option<T>,
+}
+
+impl<T> Receiver<T> {
+    /// Mark the actor's manage loop as started.
+    pub(crate) fn start(&mut self) {
+        self.started = true;
+    }
+
+    /// Record why the mailbox is about to be closed, unless it already was.
+    pub(crate) fn disconnect(&self, reason: DisconnectReason) {
+        self.shared.disconnect.set(reason);
+    }
+
+    /// Set whether the actor keeps running once there are no strong addresses to it left.
+    pub(crate) fn set_keep_alive(&self, keep_alive: bool) {
+        self.shared.keep_alive.store(keep_alive, Ordering::Release);
+    }
+
+    /// Whether the actor keeps running once there are no strong addresses to it left.
+    pub(crate) fn is_kept_alive(&self) -> bool {
+        self.shared.keep_alive.load(Ordering::Acquire)
+    }
+
+    /// Record why the manage loop of the actor exited, before it is restarted or the receiver is
+    /// dropped.
+    pub(crate) fn exited(&self, reason: ExitReason) {
+        self.shared.exit.set(reason);
+    }
+
+    /// Receive an item from the lock-free queue, if the mailbox uses it.
+    #[cfg(feature = "lock-free")]
+    fn try_recv_lock_free(&mut self) -> Option<Option<T>> {
+        let queue = self.shared.lock_free.as_ref()?;
+        Some(self.peeked.take().or_else(|| queue.pop()))
+    }
+
+    /// Receive an item if one is immediately available.
+    pub(crate) fn try_recv(&mut self) -> Option<T> {
+        #[cfg(feature = "lock-free")]
+        if let Some(item) = self.try_recv_lock_free() {
+            return item;
+        }
+
+        let item = self.shared.lock().queue.pop();
+        if item.is_some() {
+            self.shared.wake_next_sender();
+        }
+        item
+    }
+
+    /// Receive the next item if one is immediately available and matches the predicate.
+    pub(crate) fn try_recv_if<F: FnOnce(&T) -> bool>(&mut self, predicate: F) -> Option<T> {
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &self.shared.lock_free {
+            if self.peeked.is_none() {
+                self.peeked = queue.pop();
+            }
+            if !self.peeked.as_ref().is_some_and(predicate) {
+                return None;
+            }
+            return self.peeked.take();
+        }
+
+        let mut inner = self.shared.lock();
+        if !inner.queue.peek().is_some_and(predicate) {
+            return None;
+        }
+
+        let item = inner.queue.pop();
+        drop(inner);
+        self.shared.wake_next_sender();
+        item
+    }
+}
+
+// The peeked item is never pinned, so this is safe to implement regardless of `T`
+impl<T> Unpin for Receiver<T> {}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+
+        #[cfg(feature = "lock-free")]
+        if let Some(queue) = &this.shared.lock_free {
+            // Registered before checking, so that an item pushed after the check wakes us
+            queue.register(ctx.waker());
+            let ended = this.shared.senders.load(Ordering::Acquire) == 0
+                || this.shared.closed.load(Ordering::Acquire);
+            return match this.try_recv_lock_free().flatten() {
+                Some(item) => Poll::Ready(Some(item)),
+                None if ended => Poll::Ready(None),
+                None => Poll::Pending,
+            };
+        }
+
+        let shared = &this.shared;
+        let mut inner = shared.lock();
+
+        match inner.queue.pop() {
+            Some(item) => {
+                let waker = inner.sender_wakers.pop_front();
+                drop(inner);
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+                Poll::Ready(Some(item))
+            }
+            None if shared.senders.load(Ordering::Acquire) == 0
+                || shared.closed.load(Ordering::Acquire) =>
+            {
+                Poll::Ready(None)
+            }
+            None => {
+                inner.receiver_waker = Some(ctx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut inner = self.shared.lock();
+        self.shared.disconnect.set(if self.started {
+            DisconnectReason::Stopped
+        } else {
+            DisconnectReason::NeverStarted
+        });
+        self.shared.closed.store(true, Ordering::Release);
+        let queue = inner.queue.drain();
+        #[cfg(feature = "lock-free")]
+        let lock_free = self.shared.lock_free.as_ref().map(|queue| queue.close());
+        let wakers = std::mem::take(&mut inner.sender_wakers);
+        let close_callbacks = std::mem::take(&mut inner.close_callbacks);
+        let drop_callbacks = inner.drop_callbacks.take().unwrap_or_default();
+        drop(inner);
+
+        // Dropping the queued items resolves any response futures waiting on them as disconnected
+        drop(queue);
+        #[cfg(feature = "lock-free")]
+        drop(lock_free);
+        wakers.into_iter().for_each(Waker::wake);
+        close_callbacks.into_iter().for_each(|callback| callback());
+        drop_callbacks.into_iter().for_each(|callback| callback());
+    }
+}
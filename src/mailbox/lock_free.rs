@@ -0,0 +1,133 @@
+//! The queue used instead of the locked one by unbounded mailboxes when the `lock-free` feature is
+//! enabled. Senders push onto a `SegQueue` and wake the receiver through an `AtomicWaker`, so
+//! neither sending nor receiving takes the mailbox's lock. An unordered mailbox spreads the normal
+//! priority items over several queues, one per group of sending threads, so that senders on
+//! different threads do not contend for the end of the same queue.
+
+use crate::Priority;
+use crossbeam_queue::SegQueue;
+use futures::task::{AtomicWaker, Waker};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// How many queues an unordered mailbox spreads its normal priority items over
+const SHARDS: usize = 8;
+
+thread_local! {
+    /// Which of the queues of an unordered mailbox this thread pushes onto
+    static SHARD: usize = {
+        static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+        NEXT_SHARD.fetch_add(1, Ordering::Relaxed)
+    };
+}
+
+pub(super) struct LockFree<T> {
+    /// The queues of normal priority items, of which there is only one unless the mailbox is
+    /// unordered
+    shards: Box<[SegQueue<T>]>,
+    /// The queue which the receiver pops from first next time, so that it takes turns between them
+    next_shard: AtomicUsize,
+    /// Items sent with `Priority::High`, which are received before any in `queue`
+    priority_queue: SegQueue<T>,
+    /// Whether high priority items are received before normal priority ones
+    priority_lanes: bool,
+    receiver_waker: AtomicWaker,
+    /// Set once the receiver has been dropped and has taken the items out of the queues
+    receiver_dropped: AtomicBool,
+}
+
+impl<T> LockFree<T> {
+    pub(super) fn new(priority_lanes: bool, unordered: bool) -> Self {
+        let shards = if unordered { SHARDS } else { 1 };
+        LockFree {
+            shards: (0..shards).map(|_| SegQueue::new()).collect(),
+            next_shard: AtomicUsize::new(0),
+            priority_queue: SegQueue::new(),
+            priority_lanes,
+            receiver_waker: AtomicWaker::new(),
+            receiver_dropped: AtomicBool::new(false),
+        }
+    }
+
+    /// Push an item and wake the receiver. If the receiver was dropped in the meantime, the items
+    /// left in the queues are dropped here, since the receiver may already have emptied them.
+    pub(super) fn push(&self, item: T, priority: Priority) {
+        match priority {
+            Priority::High if self.priority_lanes => self.priority_queue.push(item),
+            _ => self.push_normal(item),
+        }
+
+        if self.receiver_dropped.load(Ordering::SeqCst) {
+            drop(self.drain());
+        } else {
+            self.receiver_waker.wake();
+        }
+    }
+
+    /// Push a normal priority item onto the queue of this thread.
+    fn push_normal(&self, item: T) {
+        let shard = match self.shards.len() {
+            1 => 0,
+            shards => SHARD.with(|shard| *shard) % shards,
+        };
+        self.shards[shard].push(item);
+    }
+
+    /// Push all of the items and wake the receiver once, as with `push`.
+    pub(super) fn push_many<I: Iterator<Item = T>>(&self, items: I, priority: Priority) {
+        for item in items {
+            match priority {
+                Priority::High if self.priority_lanes => self.priority_queue.push(item),
+                _ => self.push_normal(item),
+            }
+        }
+
+        if self.receiver_dropped.load(Ordering::SeqCst) {
+            drop(self.drain());
+        } else {
+            self.receiver_waker.wake();
+        }
+    }
+
+    pub(super) fn pop(&self) -> Option<T> {
+        if let Some(item) = self.priority_queue.pop() {
+            return Some(item);
+        }
+
+        // Taking turns is only for fairness, so it does not need to be synchronised with anything
+        let start = self.next_shard.load(Ordering::Relaxed);
+        (0..self.shards.len()).find_map(|i| {
+            let shard = (start + i) % self.shards.len();
+            let item = self.shards[shard].pop()?;
+            self.next_shard.store(shard + 1, Ordering::Relaxed);
+            Some(item)
+        })
+    }
+
+    /// The number of items in the queues, which does not include one which the receiver has taken
+    /// out to peek at it.
+    pub(super) fn len(&self) -> usize {
+        self.shards.iter().map(SegQueue::len).sum::<usize>() + self.priority_queue.len()
+    }
+
+    pub(super) fn register(&self, waker: &Waker) {
+        self.receiver_waker.register(waker);
+    }
+
+    pub(super) fn wake(&self) {
+        self.receiver_waker.wake();
+    }
+
+    /// Mark the receiver as dropped and take out all items, so that they can be dropped.
+    pub(super) fn close(&self) -> Vec<T> {
+        self.receiver_dropped.store(true, Ordering::SeqCst);
+        self.drain()
+    }
+
+    fn drain(&self) -> Vec<T> {
+        let mut items = Vec::new();
+        while let Some(item) = self.pop() {
+            items.push(item);
+        }
+        items
+    }
+}
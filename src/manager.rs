@@ -1,8 +1,23 @@
+use crate::dedup::DedupWindow;
 use crate::envelope::MessageEnvelope;
-use crate::{Actor, Address, Context, WeakAddress};
-use futures::channel::mpsc;
+use crate::lifecycle::{self, LifecycleEventKind};
+use crate::mailbox::Queue;
+use crate::{Actor, Address, AddressExt, Context, MailboxConfig, Middleware};
+use futures::future::{self, Either, Future};
+use futures::task::{Context as TaskContext, Poll};
 use futures::StreamExt;
-use std::sync::Arc;
+use std::pin::Pin;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+use {
+    crate::watchdog::{SlowHandler, Watchdog},
+    std::time::Duration,
+};
 
 /// A message that can be sent by an [`Address`](struct.Address.html) to the [`ActorManager`](struct.ActorManager.html)
 pub(crate) enum ManagerMessage<A: Actor> {
@@ -18,6 +33,9 @@ pub(crate) enum ManagerMessage<A: Actor> {
     LateNotification(Box<dyn MessageEnvelope<Actor = A>>),
 }
 
+/// How many messages an actor handles in a row by default before yielding to the executor
+pub(crate) const DEFAULT_BUDGET: usize = 128;
+
 /// If and how to continue the manage loop
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub(crate) enum ContinueManageLoop {
@@ -28,41 +46,339 @@ pub(crate) enum ContinueManageLoop {
 
 /// A manager for the actor which handles incoming messages and stores the context. Its managing
 /// loop can be started with [`ActorManager::manage`](struct.ActorManager.html#method.manage).
+/// If the manager is dropped before its managing loop was started, the actor is dropped without
+/// any of its lifecycle methods being called.
 pub struct ActorManager<A: Actor> {
     actor: A,
-    ctx: Context<A>,
+    pub(crate) ctx: Context<A>,
+}
+
+/// Calls `Actor::stopped` once dropped, so that it is called even if the managing future is
+/// dropped before the actor stopped or panicked outside of a handler.
+pub(crate) struct StopOnDrop<'a, A: Actor> {
+    pub(crate) actor: &'a mut A,
+    pub(crate) ctx: &'a mut Context<A>,
 }
 
-impl<A: Actor> Drop for ActorManager<A> {
+impl<A: Actor> Drop for StopOnDrop<'_, A> {
     fn drop(&mut self) {
-        self.actor.stopped(&mut self.ctx);
+        // Usually already emitted where the actor decided to stop, unless it stops because the
+        // managing future was dropped or panicked
+        self.ctx.emit_stopping();
+        self.ctx.stopped(self.actor);
     }
 }
 
 impl<A: Actor> ActorManager<A> {
     /// Return the actor and its address in ready-to-run the actor by returning its address and
     /// its manager. The `ActorManager::manage` future has to be executed for the actor to actually
-    /// start.
-    pub(crate) fn start(actor: A) -> (Address<A>, ActorManager<A>) {
-        let (sender, receiver) = mpsc::unbounded();
-        let ref_counter = Arc::new(());
-        let addr = WeakAddress {
-            sender: sender.clone(),
-            ref_counter: Arc::downgrade(&ref_counter),
-        };
-        let ctx = Context::new(addr, receiver, ref_counter.clone());
+    /// start. The actor's mailbox will be created according to `config`.
+    pub(crate) fn start(actor: A, config: MailboxConfig) -> (Address<A>, ActorManager<A>) {
+        Self::start_with_queue(actor, config, None)
+    }
 
-        let mgr = ActorManager { actor, ctx };
+    /// Like `ActorManager::start`, but the actor's mailbox holds its messages in `queue` if one is
+    /// given.
+    pub(crate) fn start_with_queue(
+        actor: A,
+        config: MailboxConfig,
+        queue: Option<Box<dyn Queue<ManagerMessage<A>>>>,
+    ) -> (Address<A>, ActorManager<A>) {
+        let (addr, ctx) = Context::new(config, queue);
+        (addr, ActorManager { actor, ctx })
+    }
 
-        let addr = Address {
-            sender,
-            ref_counter,
-        };
+    /// Enable a watchdog which calls `hook` whenever a single handler of the actor has been running
+    /// for longer than `threshold`, to help find actors which block their mailboxes. The hook is
+    /// called at most once per message, while the handler is still running, and could for
+    /// instance log the [`SlowHandler`](struct.SlowHandler.html) it is given.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// # use std::sync::{Arc, Mutex};
+    /// struct Sleepy;
+    /// impl Actor for Sleepy {}
+    ///
+    /// struct Nap;
+    /// impl Message for Nap {
+    ///     type Result = ();
+    /// }
+    ///
+    /// #[async_trait::async_trait]
+    /// impl Handler<Nap> for Sleepy {
+    ///     async fn handle(&mut self, _: Nap, _: &mut Context<Self>) {
+    ///         smol::Timer::after(Duration::from_millis(100)).await;
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let slow = Arc::new(Mutex::new(Vec::new()));
+    ///     let reported = slow.clone();
+    ///
+    ///     let (addr, mgr) = Sleepy.create();
+    ///     let mgr = mgr.watchdog(Duration::from_millis(10), move |handler| {
+    ///         eprintln!("{} took more than {:?} to handle {}", handler.actor, handler.elapsed, handler.message);
+    ///         reported.lock().unwrap().push(handler.message);
+    ///     });
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     addr.send(Nap).await.unwrap();
+    ///     assert_eq!(slow.lock().unwrap().len(), 1);
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn watchdog<F>(mut self, threshold: Duration, hook: F) -> Self
+    where
+        F: Fn(SlowHandler) + Send + Sync + 'static,
+    {
+        self.ctx.watchdog = Some(Watchdog::new(threshold, hook));
+        self
+    }
+
+    /// Drop any handler of the actor which has been running for longer than `timeout`, so that a
+    /// handler which hangs cannot block the mailbox of the actor forever. The sender of the message
+    /// receives a [`SendError::Timeout`](enum.SendError.html#variant.Timeout) error, and
+    /// [`Actor::handler_timed_out`](trait.Actor.html#method.handler_timed_out) decides whether the
+    /// actor keeps running afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::{prelude::*, SendError};
+    /// # use std::time::Duration;
+    /// struct Sleepy;
+    /// impl Actor for Sleepy {}
+    ///
+    /// struct Nap(Duration);
+    /// impl Message for Nap {
+    ///     type Result = ();
+    /// }
+    ///
+    /// #[async_trait::async_trait]
+    /// impl Handler<Nap> for Sleepy {
+    ///     async fn handle(&mut self, Nap(duration): Nap, _: &mut Context<Self>) {
+    ///         smol::Timer::after(duration).await;
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = Sleepy.create();
+    ///     let mgr = mgr.handler_timeout(Duration::from_millis(10));
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     let res = addr.send(Nap(Duration::from_secs(60))).await;
+    ///     assert_eq!(res, Err(SendError::Timeout));
+    ///     // The actor resumed handling messages
+    ///     assert_eq!(addr.send(Nap(Duration::from_millis(1))).await, Ok(()));
+    /// }
+    /// ```
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    pub fn handler_timeout(mut self, timeout: Duration) -> Self {
+        self.ctx.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep the actor running once all of its strong addresses have been dropped, so that it only
+    /// stops when it is stopped explicitly, such as with [`Context::stop`](struct.Context.html#method.stop).
+    /// This suits daemon-style actors which are driven by timers or attached streams rather than
+    /// by the messages sent to them. Weak addresses to such an actor stay connected while it runs,
+    /// so they can still send to it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use std::time::Duration;
+    /// use xtra::ExitReason;
+    ///
+    /// struct Ticker(u32);
+    ///
+    /// impl Actor for Ticker {
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.notify_interval(Duration::from_millis(1), || Tick);
+    ///     }
+    /// }
+    ///
+    /// struct Tick;
+    /// impl Message for Tick {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Tick> for Ticker {
+    ///     fn handle(&mut self, _: Tick, ctx: &mut Context<Self>) {
+    ///         self.0 += 1;
+    ///         if self.0 == 10 {
+    ///             ctx.stop();
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = Ticker(0).create();
+    ///     let mgr = mgr.keep_alive();
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     let weak = addr.downgrade();
+    ///     drop(addr);
+    ///     assert!(weak.is_connected());
+    ///     assert_eq!(weak.monitor().await, Some(ExitReason::Stopped));
+    /// }
+    /// ```
+    pub fn keep_alive(self) -> Self {
+        self.ctx.receiver.set_keep_alive(true);
+        self
+    }
+
+    /// Add [`Middleware`](trait.Middleware.html) which runs around every handler of the actor.
+    /// Middleware added first runs outermost, so it sees the handler wrapped by all middleware
+    /// added after it.
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.ctx.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Set how many messages the actor handles in a row before it yields to the executor, which is
+    /// 128 by default. While messages are already waiting in the mailbox, the actor takes them out
+    /// one after another without waiting to be woken for each of them, and only yields once it has
+    /// handled `budget` messages, so that it does not keep other tasks on the same executor from
+    /// running. A higher budget means less scheduling overhead under load, at the cost of fairness
+    /// towards other tasks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero, since the actor could then never handle a message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor {}
+    /// # struct Msg;
+    /// # impl Message for Msg {
+    /// #    type Result = ();
+    /// # }
+    /// # impl SyncHandler<Msg> for MyActor {
+    /// #     fn handle(&mut self, _: Msg, _ctx: &mut Context<Self>) {}
+    /// # }
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = MyActor.create();
+    ///     let mgr = mgr.budget(16);
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     for _ in 0..100 {
+    ///         addr.do_send(Msg).unwrap();
+    ///     }
+    ///     addr.send(Msg).await.unwrap();
+    /// }
+    /// ```
+    pub fn budget(mut self, budget: usize) -> Self {
+        assert!(budget > 0, "the budget of an actor must be at least one");
+        self.ctx.budget = budget;
+        self
+    }
+
+    /// Remember the ids of the last `capacity` messages the actor handled, so that a message with
+    /// the same [`Message::message_id`](trait.Message.html#method.message_id) as one of them is not
+    /// handled again. This makes an actor safe to send messages to with at-least-once delivery,
+    /// such as through [`AtLeastOnce`](persistence/struct.AtLeastOnce.html), where a message can
+    /// arrive more than once. A duplicate sent with `send` resolves to
+    /// [`SendError::Duplicate`](enum.SendError.html#variant.Duplicate), and one sent with
+    /// `do_send` is dropped. Messages without an id, and those handled by a `BatchHandler` or a
+    /// `ReadHandler`, are never deduplicated. A message which panicked its handler does not count
+    /// as handled. The window is kept when the actor restarts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::SendError;
+    /// struct Ledger(u32);
+    /// impl Actor for Ledger {}
+    ///
+    /// struct Credit {
+    ///     id: u64,
+    ///     amount: u32,
+    /// }
+    ///
+    /// impl Message for Credit {
+    ///     type Result = u32;
+    ///
+    ///     fn message_id(&self) -> Option<u64> {
+    ///         Some(self.id)
+    ///     }
+    /// }
+    ///
+    /// impl SyncHandler<Credit> for Ledger {
+    ///     fn handle(&mut self, credit: Credit, _: &mut Context<Self>) -> u32 {
+    ///         self.0 += credit.amount;
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = Ledger(0).create();
+    ///     let mgr = mgr.dedup_window(1024);
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+    ///
+    ///     assert_eq!(addr.send(Credit { id: 1, amount: 10 }).await, Ok(10));
+    ///     assert_eq!(addr.send(Credit { id: 1, amount: 10 }).await, Err(SendError::Duplicate));
+    ///     assert_eq!(addr.send(Credit { id: 2, amount: 10 }).await, Ok(20));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn dedup_window(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the dedup window of an actor must hold at least one id"
+        );
+        self.ctx.dedup = Some(DedupWindow::new(capacity));
+        self
+    }
 
-        (addr, mgr)
+    /// Record every message the actor handles with the given recorder, so that they can be
+    /// replayed into another instance of the actor later on. See the
+    /// [`recording`](recording/index.html) module for an example.
+    #[cfg(feature = "recording")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "recording")))]
+    pub fn record(mut self, recorder: crate::recording::Recorder<A>) -> Self {
+        self.ctx.recorder = Some(recorder);
+        self
     }
 
-    /// Starts the manager loop. This will start the actor and allow it to respond to messages.
+    /// Starts the manager loop. This will start the actor and allow it to respond to messages. The
+    /// returned future resolves to the reason why the actor stopped, once it has.
     ///
     /// # Example
     ///
@@ -74,38 +390,241 @@ impl<A: Actor> ActorManager<A> {
     /// #[smol_potat::main]
     /// async fn main() {
     ///     let (addr, mgr) = MyActor.create();
-    ///     smol::Task::spawn(mgr.manage()).detach(); // Actually spawn the actor onto an executor
+    ///     smol::Task::spawn(async move { mgr.manage().await; }).detach(); // Actually spawn the actor onto an executor
+    /// }
+    /// ```
+    ///
+    /// The exit reason can be used to react to the actor stopping:
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::ExitReason;
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = MyActor.create();
+    ///     drop(addr);
+    ///     assert_eq!(mgr.manage().await, ExitReason::AllAddressesDropped);
     /// }
     /// ```
-    pub async fn manage(mut self) {
-        self.actor.started(&mut self.ctx);
+    pub async fn manage(self) -> ExitReason {
+        self.manage_into().await.1
+    }
 
-        // Idk why anyone would do this, but we have to check that they didn't do ctx.stop() in the
-        // started method, otherwise it would kinda be a bug
-        if !self.ctx.check_running(&mut self.actor) {
-            return;
+    /// Like [`ActorManager::manage`](struct.ActorManager.html#method.manage), but the actor is
+    /// stopped gracefully (see [`Context::stop_gracefully`](struct.Context.html#method.stop_gracefully))
+    /// once `signal` resolves, if it has not stopped by then. This allows the lifetime of the actor
+    /// to be tied to something like ctrl-c or a connection closing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::ExitReason;
+    /// use futures::channel::oneshot;
+    ///
+    /// struct MyActor;
+    /// impl Actor for MyActor {}
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (shutdown, signal) = oneshot::channel::<()>();
+    ///     let (addr, mgr) = MyActor.create();
+    ///     let task = smol::Task::spawn(mgr.manage_with_shutdown(signal));
+    ///
+    ///     shutdown.send(()).unwrap();
+    ///     assert_eq!(task.await, ExitReason::MailboxClosed);
+    ///     assert!(!addr.is_connected());
+    /// }
+    /// ```
+    pub async fn manage_with_shutdown<F: Future>(self, signal: F) -> ExitReason {
+        let address = self.ctx.address.clone();
+        let manage = Box::pin(self.manage());
+        let shutdown = Box::pin(signal);
+
+        match future::select(manage, shutdown).await {
+            Either::Left((reason, _)) => reason,
+            Either::Right((_, manage)) => {
+                address.stop_gracefully();
+                manage.await
+            }
         }
+    }
 
-        // Listen for any messages for the ActorManager
-        while let Some(msg) = self.ctx.receiver.next().await {
-            match self.ctx.handle_message(msg, &mut self.actor).await {
-                ContinueManageLoop::Yes => {}
-                ContinueManageLoop::ProcessNotifications => break,
-                ContinueManageLoop::ExitImmediately => return,
+    /// Like [`ActorManager::manage`](struct.ActorManager.html#method.manage), but also returns the
+    /// actor once it has stopped (after [`Actor::stopped`](trait.Actor.html#method.stopped) was
+    /// called), so that any state accumulated in it can be retrieved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// struct Counter(u32);
+    /// impl Actor for Counter {}
+    ///
+    /// struct Increment;
+    /// impl Message for Increment {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Increment> for Counter {
+    ///     fn handle(&mut self, _: Increment, _: &mut Context<Self>) {
+    ///         self.0 += 1;
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let (addr, mgr) = Counter(0).create();
+    ///     let task = smol::Task::spawn(mgr.manage_into());
+    ///
+    ///     addr.do_send(Increment).unwrap();
+    ///     addr.do_send(Increment).unwrap();
+    ///     drop(addr);
+    ///
+    ///     let (counter, _) = task.await;
+    ///     assert_eq!(counter.0, 2);
+    /// }
+    /// ```
+    pub async fn manage_into(self) -> (A, ExitReason) {
+        let ActorManager { mut actor, mut ctx } = self;
+
+        let reason = {
+            let guard = StopOnDrop {
+                actor: &mut actor,
+                ctx: &mut ctx,
+            };
+
+            loop {
+                let reason = run(guard.actor, guard.ctx).await;
+                guard.ctx.receiver.exited(reason);
+                guard.actor.async_stopped(guard.ctx).await;
+                if !guard.ctx.restart {
+                    guard.ctx.receiver.disconnect(reason.into());
+                    break reason;
+                }
+
+                guard.ctx.stopped(guard.actor);
+                guard.ctx.reset();
             }
+        };
+
+        (actor, reason)
+    }
+}
+
+/// Why the manage loop of an actor exited. This is returned from
+/// [`ActorManager::manage`](struct.ActorManager.html#method.manage), and resolved to by
+/// [`Address::monitor`](struct.Address.html#method.monitor).
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum ExitReason {
+    /// There were no more strong addresses to the actor, and it did not prevent it from stopping in
+    /// [`Actor::stopping`](trait.Actor.html#method.stopping)
+    AllAddressesDropped,
+    /// The actor was stopped with [`Context::stop`](struct.Context.html#method.stop) and did not
+    /// prevent it in [`Actor::stopping`](trait.Actor.html#method.stopping), or one of its handlers
+    /// timed out and [`Actor::handler_timed_out`](trait.Actor.html#method.handler_timed_out) decided
+    /// to stop the actor
+    Stopped,
+    /// One of the actor's handlers panicked and [`Actor::panicked`](trait.Actor.html#method.panicked)
+    /// decided to stop the actor
+    Panicked,
+    /// The actor was stopped gracefully (see
+    /// [`Context::stop_gracefully`](struct.Context.html#method.stop_gracefully)) and handled all
+    /// messages left in its mailbox
+    MailboxClosed,
+}
+
+/// Run the manage loop of an actor until it exits. This does not call `Actor::stopped`, so that the
+/// caller can decide what to do with the actor afterwards.
+pub(crate) async fn run<A: Actor>(actor: &mut A, ctx: &mut Context<A>) -> ExitReason {
+    ctx.receiver.start();
+    actor.started(ctx);
+    actor.async_started(ctx).await;
+
+    let kind = if ctx.restarted {
+        LifecycleEventKind::Restarted
+    } else {
+        LifecycleEventKind::Started
+    };
+    #[cfg(feature = "introspection")]
+    crate::introspection::register(&ctx.address, ctx.activity.clone());
+    lifecycle::emit::<A>(ctx.address.id(), kind);
+
+    // Idk why anyone would do this, but we have to check that they didn't do ctx.stop() in the
+    // started method, otherwise it would kinda be a bug
+    if !ctx.check_running(actor) {
+        return ctx.exit.take().unwrap_or(ExitReason::Stopped);
+    }
+
+    // Listen for any messages for the ActorManager. Messages which are already in the mailbox are
+    // taken out without waiting on it, so that the actor is not woken for each of them while under
+    // load. Once it has handled its budget of messages in a row, it yields to let other tasks run.
+    let mut handled = 0;
+    loop {
+        if handled >= ctx.budget {
+            YieldNow(false).await;
+            handled = 0;
         }
 
-        // Handle any last late notifications that were sent after the last strong address was dropped
-        // We can't .await, because that would mean that we are awaiting forever! So, instead, we do
-        // `next_message` and check if the result is `Ok`. Because we know that any late notifications
-        // sent from the context must be fully send by now due to it being marked as stopped (so
-        // that no other addresses can be created and sending concurrently), we can make the inference
-        // that if `next_message` returns `Err`, there are no more late notifications to handle.
-        while let Ok(Some(msg)) = self.ctx.receiver.try_next() {
-            let res = self.ctx.handle_message(msg, &mut self.actor).await;
-            if res == ContinueManageLoop::ExitImmediately {
-                break;
+        let msg = match ctx.receiver.try_recv() {
+            Some(msg) => msg,
+            None => {
+                // The mailbox is empty, so the actor will most likely wait and yield here
+                handled = 0;
+                match ctx.receiver.next().await {
+                    Some(msg) => msg,
+                    // The mailbox was closed by `stop_gracefully` and everything in it has been
+                    // handled
+                    None => {
+                        ctx.emit_stopping();
+                        return ExitReason::MailboxClosed;
+                    }
+                }
             }
+        };
+        handled += 1;
+
+        match ctx.handle_message(msg, actor).await {
+            ContinueManageLoop::Yes => {}
+            ContinueManageLoop::ProcessNotifications => break,
+            ContinueManageLoop::ExitImmediately => {
+                return ctx.exit.take().unwrap_or(ExitReason::Stopped)
+            }
+        }
+    }
+
+    // Handle any last late notifications that were sent after the last strong address was dropped
+    // We can't .await, because that would mean that we are awaiting forever! So, instead, we do
+    // `try_recv` and check if the result is `Some`. Because we know that any late notifications
+    // sent from the context must be fully send by now due to it being marked as stopped (so
+    // that no other addresses can be created and sending concurrently), we can make the inference
+    // that if `try_recv` returns `None`, there are no more late notifications to handle.
+    while let Some(msg) = ctx.receiver.try_recv() {
+        let res = ctx.handle_message(msg, actor).await;
+        if res == ContinueManageLoop::ExitImmediately {
+            break;
+        }
+    }
+
+    ExitReason::AllAddressesDropped
+}
+
+/// Returns `Pending` once, waking itself straight away, so that the executor can run other tasks.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
         }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
     }
 }
@@ -1,8 +1,9 @@
-use crate::address::MessageResponseFuture;
+use crate::address::{DoSendFuture, MessageResponseFuture};
 use crate::envelope::AddressEnvelope;
-use crate::{Disconnected, Message};
+use crate::{ActorId, Message, SendError, TrySendError};
 use futures::task::{Context, Poll};
 use futures::Sink;
+use std::pin::Pin;
 #[cfg(any(
     doc,
     feature = "with-tokio-0_2",
@@ -10,8 +11,10 @@ use futures::Sink;
     feature = "with-wasm_bindgen-0_2",
     feature = "with-smol-0_1"
 ))]
-use futures::{FutureExt, Stream, StreamExt};
-use std::pin::Pin;
+use {
+    futures::{FutureExt, Stream, StreamExt},
+    std::time::Duration,
+};
 
 /// General trait for any kind of channel of messages, be it strong or weak. This trait contains all
 /// functions of the channel.
@@ -19,17 +22,69 @@ pub trait MessageChannelExt<M: Message> {
     /// Returns whether the actor referred to by this address is running and accepting messages.
     fn is_connected(&self) -> bool;
 
+    /// The approximate number of messages waiting in the actor's mailbox. See
+    /// [`AddressExt::len`](trait.AddressExt.html#method.len).
+    fn len(&self) -> usize;
+
+    /// Returns whether the actor's mailbox is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The capacity of the actor's mailbox, or `None` if it is unbounded.
+    fn capacity(&self) -> Option<usize>;
+
     /// Sends a [`Message`](trait.Message.html) to the actor, and does not wait for a response.
-    /// If this returns `Err(Disconnected)`, then the actor is stopped and not accepting messages.
+    /// If this returns `Err(TrySendError::Disconnected(message, _))`, then the actor is stopped and not
+    /// accepting messages, and the message is handed back so that it can be sent elsewhere.
     /// If this returns `Ok(())`, the will be delivered, but may not be handled in the event that the
     /// actor stops itself (by calling [`Context::stop`](struct.Context.html#method.stop))
     /// before it was handled.
-    fn do_send(&self, message: M) -> Result<(), Disconnected>;
+    ///
+    /// **Note:** this method never waits, so if the actor's mailbox is bounded and full and its
+    /// overflow policy is [`OverflowPolicy::Block`](enum.OverflowPolicy.html#variant.Block), the
+    /// message will be put into the mailbox regardless. To wait for space instead, use
+    /// [`MessageChannelExt::do_send_async`](trait.MessageChannelExt.html#method.do_send_async).
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>>;
+
+    /// Sends a [`Message`](trait.Message.html) to the actor if that can be done without waiting,
+    /// and does not wait for a response. If the message could not be sent, it is handed back in
+    /// the error. See [`AddressExt::try_send`](trait.AddressExt.html#method.try_send).
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>>;
+
+    /// Sends a [`Message`](trait.Message.html) to the actor, and does not wait for a response, but
+    /// does wait for there to be space in the actor's mailbox if it is bounded and full (and its
+    /// overflow policy is to block). If this resolves to `Err(SendError::Disconnected(_))`, then the
+    /// actor is stopped and not accepting messages.
+    fn do_send_async(&self, message: M) -> DoSendFuture;
 
     /// Sends a [`Message`](trait.Message.html) to the actor, and waits for a response. If this
-    /// returns `Err(Disconnected)`, then the actor is stopped and not accepting messages.
+    /// returns `Err(SendError::Disconnected(_))`, then the actor is stopped and not accepting messages. If the
+    /// actor's mailbox is bounded and full, this will act according to its
+    /// [`OverflowPolicy`](enum.OverflowPolicy.html), which by default is to wait for space to
+    /// become available. If the mailbox refuses the message straight away, such as because the
+    /// actor is already disconnected,
+    /// [`MessageResponseFuture::sent`](struct.MessageResponseFuture.html#method.sent) hands it back.
     fn send(&self, message: M) -> MessageResponseFuture<M>;
 
+    /// Like [`MessageChannelExt::send`](trait.MessageChannelExt.html#method.send), but resolves to
+    /// `Err(SendError::Timeout)` if the response has not arrived within the given duration. See
+    /// [`AddressExt::send_timeout`](trait.AddressExt.html#method.send_timeout).
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    fn send_timeout(&self, message: M, duration: Duration) -> MessageResponseFuture<M> {
+        self.send(message).with_timeout(duration)
+    }
+
     /// Attaches a stream to this channel such that all messages produced by it are forwarded to the
     /// actor. This could, for instance, be used to forward messages from a socket to the actor
     /// (after the messages have been appropriately `map`ped). This is a convenience method over
@@ -47,14 +102,14 @@ pub trait MessageChannelExt<M: Message> {
         feature = "with-wasm_bindgen-0_2",
         feature = "with-smol-0_1"
     ))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-tokio-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-async_std-1")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
-    #[cfg_attr(doc, doc(cfg(feature = "with-smol-0_1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
     fn attach_stream<S>(self, stream: S)
     where
         S: Stream<Item = M> + Send + Unpin + 'static,
-        Self: Sized + Send + Sink<M, Error = Disconnected> + 'static;
+        Self: Sized + Send + Sink<M, Error = SendError> + 'static;
 }
 
 /// A message channel is a channel through which you can send only one kind of message, but to
@@ -98,7 +153,9 @@ pub trait MessageChannelExt<M: Message> {
 ///
 /// #[smol_potat::main]
 /// async fn main() {
-///     let channels = [Alice.spawn().into_channel(), Bob.spawn().into_channel()];
+///     let alice: MessageChannel<WhatsYourName> = Alice.spawn().into_channel();
+///     let bob: MessageChannel<WhatsYourName> = Bob.spawn().into(); // Or with `From`/`Into`
+///     let channels = vec![alice.clone(), bob];
 ///     let name = ["Alice", "Bob"];
 ///
 ///     for (channel, name) in channels.iter().zip(&name) {
@@ -111,6 +168,11 @@ pub struct MessageChannel<M: Message> {
 }
 
 impl<M: Message> MessageChannel<M> {
+    /// Get the id of the actor which this channel refers to.
+    pub fn id(&self) -> ActorId {
+        self.address.id()
+    }
+
     /// Create a weak message channel to the actor. Unlike with the strong variety of channel (this kind),
     /// an actor will not be prevented from being dropped if only weak channels exist.
     pub fn downgrade(&self) -> WeakMessageChannel<M> {
@@ -132,10 +194,26 @@ impl<M: Message> MessageChannelExt<M> for MessageChannel<M> {
         self.address.is_connected()
     }
 
-    fn do_send(&self, message: M) -> Result<(), Disconnected> {
+    fn len(&self) -> usize {
+        self.address.len()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.address.capacity()
+    }
+
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>> {
         self.address.do_send(message)
     }
 
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        self.address.try_send(message)
+    }
+
+    fn do_send_async(&self, message: M) -> DoSendFuture {
+        self.address.do_send_async(message)
+    }
+
     fn send(&self, message: M) -> MessageResponseFuture<M> {
         self.address.send(message)
     }
@@ -150,26 +228,24 @@ impl<M: Message> MessageChannelExt<M> for MessageChannel<M> {
     fn attach_stream<S>(self, stream: S)
     where
         S: Stream<Item = M> + Send + Unpin + 'static,
-        Self: Sized + Send + Sink<M, Error = Disconnected> + 'static,
+        Self: Sized + Send + Sink<M, Error = SendError> + 'static,
     {
         let fut = stream.map(|i| Ok(i)).forward(self).map(|_| ());
 
-        #[cfg(feature = "with-async_std-1")]
-        async_std::task::spawn(fut);
-
-        #[cfg(feature = "with-tokio-0_2")]
-        tokio::spawn(fut);
-
-        #[cfg(feature = "with-wasm_bindgen-0_2")]
-        wasm_bindgen_futures::spawn_local(fut);
+        crate::runtime::spawn(fut);
+    }
+}
 
-        #[cfg(feature = "with-smol-0_1")]
-        smol::Task::spawn(fut).detach();
+impl<M: Message> Clone for MessageChannel<M> {
+    fn clone(&self) -> Self {
+        MessageChannel {
+            address: self.address.clone_channel(),
+        }
     }
 }
 
 impl<M: Message> Sink<M> for MessageChannel<M> {
-    type Error = Disconnected;
+    type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Pin::new(&mut self.get_mut().address).poll_ready(ctx)
@@ -201,15 +277,38 @@ pub struct WeakMessageChannel<M: Message> {
     pub(crate) address: Box<dyn AddressEnvelope<M>>,
 }
 
+impl<M: Message> WeakMessageChannel<M> {
+    /// Get the id of the actor which this channel refers to.
+    pub fn id(&self) -> ActorId {
+        self.address.id()
+    }
+}
+
 impl<M: Message> MessageChannelExt<M> for WeakMessageChannel<M> {
     fn is_connected(&self) -> bool {
         self.address.is_connected()
     }
 
-    fn do_send(&self, message: M) -> Result<(), Disconnected> {
+    fn len(&self) -> usize {
+        self.address.len()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.address.capacity()
+    }
+
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>> {
         self.address.do_send(message)
     }
 
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        self.address.try_send(message)
+    }
+
+    fn do_send_async(&self, message: M) -> DoSendFuture {
+        self.address.do_send_async(message)
+    }
+
     fn send(&self, message: M) -> MessageResponseFuture<M> {
         self.address.send(message)
     }
@@ -224,26 +323,24 @@ impl<M: Message> MessageChannelExt<M> for WeakMessageChannel<M> {
     fn attach_stream<S>(self, stream: S)
     where
         S: Stream<Item = M> + Send + Unpin + 'static,
-        Self: Sized + Send + Sink<M, Error = Disconnected> + 'static,
+        Self: Sized + Send + Sink<M, Error = SendError> + 'static,
     {
         let fut = stream.map(|i| Ok(i)).forward(self).map(|_| ());
 
-        #[cfg(feature = "with-async_std-1")]
-        async_std::task::spawn(fut);
-
-        #[cfg(feature = "with-tokio-0_2")]
-        tokio::spawn(fut);
-
-        #[cfg(feature = "with-wasm_bindgen-0_2")]
-        wasm_bindgen_futures::spawn_local(fut);
+        crate::runtime::spawn(fut);
+    }
+}
 
-        #[cfg(feature = "with-smol-0_1")]
-        smol::Task::spawn(fut).detach();
+impl<M: Message> Clone for WeakMessageChannel<M> {
+    fn clone(&self) -> Self {
+        WeakMessageChannel {
+            address: self.address.clone_channel(),
+        }
     }
 }
 
 impl<M: Message> Sink<M> for WeakMessageChannel<M> {
-    type Error = Disconnected;
+    type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Pin::new(&mut self.get_mut().address).poll_ready(ctx)
@@ -0,0 +1,75 @@
+use futures::future::BoxFuture;
+
+/// The actor and message which a handler called through a [`Middleware`](trait.Middleware.html)
+/// belongs to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct HandlerInfo {
+    /// The name of the type of the actor
+    pub actor: &'static str,
+    /// The name of the type of the message being handled
+    pub message: &'static str,
+}
+
+/// Code which runs around every handler of an actor, such as logging, authorisation, or metrics,
+/// without needing to be written into every [`Handler`](trait.Handler.html). Middleware is added to
+/// an actor with [`ActorManager::with_middleware`](struct.ActorManager.html#method.with_middleware).
+/// It is implemented for any `Fn(HandlerInfo, BoxFuture<'a, ()>) -> BoxFuture<'a, ()>`, so a
+/// closure can be used as middleware.
+///
+/// If the middleware drops the handler instead of awaiting it, the message is not handled, and
+/// the response to it resolves to [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected).
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use futures::future::BoxFuture;
+/// use futures::FutureExt;
+/// use xtra::{HandlerInfo, Middleware};
+///
+/// struct Log;
+///
+/// impl Middleware for Log {
+///     fn wrap<'a>(&self, info: HandlerInfo, handler: BoxFuture<'a, ()>) -> BoxFuture<'a, ()> {
+///         Box::pin(async move {
+///             println!("{} is handling {}", info.actor, info.message);
+///             handler.await;
+///             println!("{} has handled {}", info.actor, info.message);
+///         })
+///     }
+/// }
+///
+/// struct MyActor;
+/// impl Actor for MyActor {}
+///
+/// struct Ping;
+/// impl Message for Ping {
+///     type Result = ();
+/// }
+///
+/// impl SyncHandler<Ping> for MyActor {
+///     fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = MyActor.create();
+///     let mgr = mgr.with_middleware(Log);
+///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+///
+///     addr.send(Ping).await.unwrap();
+/// }
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Wrap the future which runs the handler for a message, returning the future to run instead.
+    fn wrap<'a>(&self, info: HandlerInfo, handler: BoxFuture<'a, ()>) -> BoxFuture<'a, ()>;
+}
+
+impl<F> Middleware for F
+where
+    F: for<'a> Fn(HandlerInfo, BoxFuture<'a, ()>) -> BoxFuture<'a, ()> + Send + Sync,
+{
+    fn wrap<'a>(&self, info: HandlerInfo, handler: BoxFuture<'a, ()>) -> BoxFuture<'a, ()> {
+        self(info, handler)
+    }
+}
@@ -0,0 +1,446 @@
+//! Event sourcing for actors whose state has to survive restarts. A
+//! [`PersistentActor`](trait.PersistentActor.html) does not change its state directly in its
+//! handlers, but [persists](trait.PersistentActor.html#method.persist) events describing the change
+//! to a [`Journal`](trait.Journal.html), and [applies](trait.PersistentActor.html#tymethod.apply)
+//! them to itself once they have been stored. When a new instance of the actor is started, such as
+//! after the process was restarted or by a [`Supervisor`](../struct.Supervisor.html), it is rebuilt
+//! by [recovering](trait.PersistentActor.html#method.recover) from the events in its journal,
+//! usually in [`Actor::async_started`](../trait.Actor.html#method.async_started).
+//!
+//! Every persisted event is given a sequence number, counting up from one for each actor. To keep
+//! recovery from taking longer and longer, a [`SnapshotActor`](trait.SnapshotActor.html) can
+//! [save a snapshot](../struct.Context.html#method.save_snapshot) of its state to a
+//! [`SnapshotStore`](trait.SnapshotStore.html) now and then, from which it
+//! [recovers](trait.SnapshotActor.html#method.recover_from_snapshot) before replaying only the
+//! events persisted after it.
+//!
+//! Messages to an actor can also be sent with at-least-once delivery through
+//! [`AtLeastOnce`](struct.AtLeastOnce.html), which keeps them in a
+//! [`DeliveryStore`](trait.DeliveryStore.html) and sends them again until the actor has handled
+//! them.
+//!
+//! Journals and snapshot stores are pluggable, so events can be stored in a file, a database, or
+//! anywhere else, and are responsible for serializing them. [`MemoryJournal`](struct.MemoryJournal.html)
+//! and [`MemorySnapshotStore`](struct.MemorySnapshotStore.html) keep them in memory, which is
+//! mostly useful for testing.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use std::io;
+//! use xtra::persistence::{Journal, MemoryJournal, PersistentActor};
+//!
+//! #[derive(Clone)]
+//! enum Event {
+//!     Deposited(u32),
+//! }
+//!
+//! struct Account {
+//!     balance: u32,
+//!     journal: MemoryJournal<Event>,
+//! }
+//!
+//! #[async_trait::async_trait]
+//! impl Actor for Account {
+//!     async fn async_started(&mut self, ctx: &mut Context<Self>) {
+//!         if self.recover(ctx).await.is_err() {
+//!             ctx.stop();
+//!         }
+//!     }
+//! }
+//!
+//! impl PersistentActor for Account {
+//!     type Event = Event;
+//!
+//!     fn persistence_id(&self) -> &str {
+//!         "account-1"
+//!     }
+//!
+//!     fn journal(&self) -> &dyn Journal<Event> {
+//!         &self.journal
+//!     }
+//!
+//!     fn apply(&mut self, event: &Event) {
+//!         match event {
+//!             Event::Deposited(amount) => self.balance += amount,
+//!         }
+//!     }
+//! }
+//!
+//! struct Deposit(u32);
+//! impl Message for Deposit {
+//!     type Result = io::Result<u32>;
+//! }
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Deposit> for Account {
+//!     async fn handle(&mut self, Deposit(amount): Deposit, ctx: &mut Context<Self>) -> io::Result<u32> {
+//!         self.persist(ctx, Event::Deposited(amount)).await?;
+//!         Ok(self.balance)
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let journal = MemoryJournal::new();
+//!
+//!     let account = Account { balance: 0, journal: journal.clone() }.spawn();
+//!     account.send(Deposit(10)).await.unwrap().unwrap();
+//!     account.send(Deposit(5)).await.unwrap().unwrap();
+//!     drop(account);
+//!
+//!     // A new instance recovers the balance of the previous one from the journal
+//!     let account = Account { balance: 0, journal }.spawn();
+//!     assert_eq!(account.send(Deposit(1)).await.unwrap().unwrap(), 16);
+//! }
+//! ```
+
+use crate::{Actor, Context};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+mod delivery;
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub use delivery::AtLeastOnce;
+pub use delivery::{Delivered, DeliveryError, DeliveryStore, MemoryDeliveryStore};
+
+/// Where the events of [`PersistentActor`s](trait.PersistentActor.html) are stored, each under the
+/// persistence id of the actor which persisted them. A journal can be shared by many actors.
+#[async_trait]
+pub trait Journal<E>: Send + Sync {
+    /// Store an event at the end of the events of the actor with the given persistence id, where
+    /// it has the given sequence number. Once this has resolved successfully, the event must be
+    /// replayed by every later call to [`Journal::replay`](trait.Journal.html#tymethod.replay) for
+    /// the same id which asks for it.
+    async fn append(&self, persistence_id: &str, sequence_nr: u64, event: &E) -> io::Result<()>;
+
+    /// Read the events of the actor with the given persistence id whose sequence numbers are
+    /// greater than `after`, in the order in which they were appended.
+    async fn replay(&self, persistence_id: &str, after: u64) -> io::Result<Vec<E>>;
+}
+
+/// An actor whose state is built from the events it has persisted to a
+/// [`Journal`](trait.Journal.html), so that it can be recovered by a new instance of the actor.
+#[async_trait]
+pub trait PersistentActor: Actor {
+    /// The event describing a change to the state of the actor.
+    type Event: Send + Sync + 'static;
+
+    /// The id under which the events of this actor are stored. Unlike an
+    /// [`ActorId`](../struct.ActorId.html), this must stay the same across processes, so that a
+    /// new instance of the actor finds the events of the previous one.
+    fn persistence_id(&self) -> &str;
+
+    /// The journal the events of this actor are stored in.
+    fn journal(&self) -> &dyn Journal<Self::Event>;
+
+    /// Change the state of the actor according to an event. This is called for every event once it
+    /// has been persisted, and for every event in the journal when recovering, so it must not fail
+    /// or have any side effects besides changing the actor's state.
+    fn apply(&mut self, event: &Self::Event);
+
+    /// Append an event to the journal, then apply it to the actor. If the journal fails to store
+    /// it, the event is not applied, and the error is returned.
+    async fn persist(&mut self, ctx: &mut Context<Self>, event: Self::Event) -> io::Result<()> {
+        let sequence_nr = ctx.persisted + 1;
+        self.journal()
+            .append(self.persistence_id(), sequence_nr, &event)
+            .await?;
+        self.apply(&event);
+        ctx.persisted = sequence_nr;
+        Ok(())
+    }
+
+    /// Apply the events in the journal which have not been applied to the actor yet, returning how
+    /// many there were. This should be called before the actor handles any messages, such as in
+    /// [`Actor::async_started`](../trait.Actor.html#method.async_started). An actor restarted with
+    /// [`PanicAction::Restart`](../enum.PanicAction.html#variant.Restart) keeps its state, so only
+    /// events it has not seen are applied to it again.
+    async fn recover(&mut self, ctx: &mut Context<Self>) -> io::Result<usize> {
+        let events = self
+            .journal()
+            .replay(self.persistence_id(), ctx.persisted)
+            .await?;
+        events.iter().for_each(|event| self.apply(event));
+        ctx.persisted += events.len() as u64;
+        Ok(events.len())
+    }
+}
+
+/// The state of a [`SnapshotActor`](trait.SnapshotActor.html) once it had applied the event with
+/// the given sequence number.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Snapshot<S> {
+    /// The sequence number of the last event which is included in the state
+    pub sequence_nr: u64,
+    /// The state of the actor
+    pub state: S,
+}
+
+/// Where the snapshots of [`SnapshotActor`s](trait.SnapshotActor.html) are stored, each under the
+/// persistence id of the actor which saved them. Only the latest snapshot of an actor is needed
+/// to recover it.
+#[async_trait]
+pub trait SnapshotStore<S>: Send + Sync {
+    /// Store a snapshot of the actor with the given persistence id.
+    async fn save(&self, persistence_id: &str, snapshot: Snapshot<S>) -> io::Result<()>;
+
+    /// Load the snapshot of the actor with the given persistence id with the highest sequence
+    /// number, if it has saved any.
+    async fn load(&self, persistence_id: &str) -> io::Result<Option<Snapshot<S>>>;
+}
+
+/// A [`PersistentActor`](trait.PersistentActor.html) which saves snapshots of its state, so that
+/// it does not have to replay all of its events when it is recovered.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use std::io;
+/// use xtra::persistence::*;
+///
+/// struct Counter {
+///     count: u64,
+///     journal: MemoryJournal<()>,
+///     snapshots: MemorySnapshotStore<u64>,
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl Actor for Counter {
+///     async fn async_started(&mut self, ctx: &mut Context<Self>) {
+///         if self.recover_from_snapshot(ctx).await.is_err() {
+///             ctx.stop();
+///         }
+///     }
+/// }
+///
+/// impl PersistentActor for Counter {
+///     type Event = ();
+///
+///     fn persistence_id(&self) -> &str {
+///         "counter"
+///     }
+///
+///     fn journal(&self) -> &dyn Journal<()> {
+///         &self.journal
+///     }
+///
+///     fn apply(&mut self, _: &()) {
+///         self.count += 1;
+///     }
+/// }
+///
+/// impl SnapshotActor for Counter {
+///     type Snapshot = u64;
+///
+///     fn snapshot_store(&self) -> &dyn SnapshotStore<u64> {
+///         &self.snapshots
+///     }
+///
+///     fn restore(&mut self, count: u64) {
+///         self.count = count;
+///     }
+/// }
+///
+/// struct Increment;
+/// impl Message for Increment {
+///     type Result = io::Result<u64>;
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl Handler<Increment> for Counter {
+///     async fn handle(&mut self, _: Increment, ctx: &mut Context<Self>) -> io::Result<u64> {
+///         self.persist(ctx, ()).await?;
+///         if self.count % 10 == 0 {
+///             ctx.save_snapshot(self, self.count).await?;
+///         }
+///         Ok(self.count)
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let journal = MemoryJournal::new();
+///     let snapshots = MemorySnapshotStore::new();
+///     let counter = Counter { count: 0, journal: journal.clone(), snapshots: snapshots.clone() }.spawn();
+///     for _ in 0..25 {
+///         counter.send(Increment).await.unwrap().unwrap();
+///     }
+///     drop(counter);
+///
+///     let latest = snapshots.load("counter").await.unwrap().unwrap();
+///     assert_eq!(latest.sequence_nr, 20);
+///
+///     // Recovers from the snapshot of the first 20 events, then replays the last 5
+///     let counter = Counter { count: 0, journal, snapshots }.spawn();
+///     assert_eq!(counter.send(Increment).await.unwrap().unwrap(), 26);
+/// }
+/// ```
+#[async_trait]
+pub trait SnapshotActor: PersistentActor {
+    /// The state of the actor which is saved in a snapshot.
+    type Snapshot: Send + Sync + 'static;
+
+    /// The store the snapshots of this actor are saved in.
+    fn snapshot_store(&self) -> &dyn SnapshotStore<Self::Snapshot>;
+
+    /// Replace the state of the actor with the one from a snapshot.
+    fn restore(&mut self, snapshot: Self::Snapshot);
+
+    /// Restore the actor from its latest snapshot, then apply the events in the journal which were
+    /// persisted after it, returning how many events were applied. Like
+    /// [`PersistentActor::recover`](trait.PersistentActor.html#method.recover), this should be
+    /// called before the actor handles any messages. The snapshot is skipped if the actor has
+    /// already applied the events it includes.
+    async fn recover_from_snapshot(&mut self, ctx: &mut Context<Self>) -> io::Result<usize> {
+        let snapshot = self.snapshot_store().load(self.persistence_id()).await?;
+        if let Some(snapshot) = snapshot {
+            if snapshot.sequence_nr > ctx.persisted {
+                self.restore(snapshot.state);
+                ctx.persisted = snapshot.sequence_nr;
+            }
+        }
+
+        self.recover(ctx).await
+    }
+}
+
+impl<A: SnapshotActor> Context<A> {
+    /// Save a snapshot of the state of the actor, which includes all events it has persisted or
+    /// recovered so far. This is available with the `persistence` feature for a
+    /// [`SnapshotActor`](persistence/trait.SnapshotActor.html).
+    #[cfg_attr(docsrs, doc(cfg(feature = "persistence")))]
+    pub async fn save_snapshot(&mut self, actor: &A, state: A::Snapshot) -> io::Result<()> {
+        let snapshot = Snapshot {
+            sequence_nr: self.persisted,
+            state,
+        };
+        actor
+            .snapshot_store()
+            .save(actor.persistence_id(), snapshot)
+            .await
+    }
+}
+
+/// A [`Journal`](trait.Journal.html) which keeps events in memory, so they are lost once the
+/// process exits. Clones of it share the same events, which makes it useful for testing how
+/// actors recover.
+pub struct MemoryJournal<E> {
+    events: Arc<Mutex<HashMap<String, Vec<E>>>>,
+}
+
+impl<E> MemoryJournal<E> {
+    /// Create an empty journal.
+    pub fn new() -> Self {
+        MemoryJournal {
+            events: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<E>>> {
+        // Nothing can panic while the lock is held, so poisoning can safely be ignored
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<E> Clone for MemoryJournal<E> {
+    fn clone(&self) -> Self {
+        MemoryJournal {
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<E> Default for MemoryJournal<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<E: Clone + Send + Sync> Journal<E> for MemoryJournal<E> {
+    async fn append(&self, persistence_id: &str, sequence_nr: u64, event: &E) -> io::Result<()> {
+        let mut journal = self.lock();
+        let events = journal.entry(persistence_id.to_string()).or_default();
+        if sequence_nr != events.len() as u64 + 1 {
+            // Another instance of the actor has persisted events which this one has not seen
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "an event with this sequence number was already persisted",
+            ));
+        }
+
+        events.push(event.clone());
+        Ok(())
+    }
+
+    async fn replay(&self, persistence_id: &str, after: u64) -> io::Result<Vec<E>> {
+        let journal = self.lock();
+        let events = journal.get(persistence_id).map_or(&[][..], |events| events);
+        Ok(events.iter().skip(after as usize).cloned().collect())
+    }
+}
+
+/// A [`SnapshotStore`](trait.SnapshotStore.html) which keeps the latest snapshot of every actor in
+/// memory, so they are lost once the process exits. Clones of it share the same snapshots.
+pub struct MemorySnapshotStore<S> {
+    snapshots: Arc<Mutex<HashMap<String, Snapshot<S>>>>,
+}
+
+impl<S> MemorySnapshotStore<S> {
+    /// Create an empty snapshot store.
+    pub fn new() -> Self {
+        MemorySnapshotStore {
+            snapshots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Snapshot<S>>> {
+        // Nothing can panic while the lock is held, so poisoning can safely be ignored
+        self.snapshots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<S> Clone for MemorySnapshotStore<S> {
+    fn clone(&self) -> Self {
+        MemorySnapshotStore {
+            snapshots: self.snapshots.clone(),
+        }
+    }
+}
+
+impl<S> Default for MemorySnapshotStore<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<S: Clone + Send + Sync> SnapshotStore<S> for MemorySnapshotStore<S> {
+    async fn save(&self, persistence_id: &str, snapshot: Snapshot<S>) -> io::Result<()> {
+        let mut snapshots = self.lock();
+        match snapshots.get(persistence_id) {
+            // Keep the latest snapshot, even if an older one is saved after it
+            Some(latest) if latest.sequence_nr > snapshot.sequence_nr => {}
+            _ => {
+                snapshots.insert(persistence_id.to_string(), snapshot);
+            }
+        }
+        Ok(())
+    }
+
+    async fn load(&self, persistence_id: &str) -> io::Result<Option<Snapshot<S>>> {
+        Ok(self.lock().get(persistence_id).cloned())
+    }
+}
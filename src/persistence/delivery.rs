@@ -0,0 +1,398 @@
+use crate::{DisconnectReason, Message};
+use async_trait::async_trait;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+use {
+    crate::{Actor, Address, AddressExt, Handler, SendError},
+    std::time::Duration,
+};
+
+/// A message sent by [`AtLeastOnce`](struct.AtLeastOnce.html), along with the ids under which it
+/// was stored. The same message can be handled more than once, so its
+/// [`Message::message_id`](../trait.Message.html#method.message_id) is made from the id of the
+/// store and the delivery id, through which an actor with a dedup window ignores messages it has
+/// already handled. Since each store has its own id, messages from different stores never share a
+/// message id, even if their delivery ids are the same. Its result is that of the message.
+#[derive(Debug, Clone)]
+pub struct Delivered<M> {
+    /// The id of the [`DeliveryStore`](trait.DeliveryStore.html) the message was saved to
+    pub store_id: u64,
+    /// The id of the delivery, which stays the same when the message is redelivered
+    pub delivery_id: u64,
+    /// The message being delivered
+    pub message: M,
+}
+
+impl<M: Message> Message for Delivered<M> {
+    type Result = M::Result;
+
+    fn message_id(&self) -> Option<u64> {
+        // Mixing the delivery id before combining it with the store id keeps nearby delivery ids of
+        // different stores from colliding, and is stable across processes unlike `DefaultHasher`
+        Some(mix(self.store_id ^ mix(self.delivery_id)))
+    }
+}
+
+/// The finalizer of splitmix64, which spreads every bit of `x` over all bits of the result.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Where the messages sent by [`AtLeastOnce`](struct.AtLeastOnce.html) are kept until the receiving
+/// actor has handled them. It must keep them across restarts of the sending process for them to be
+/// redelivered then.
+#[async_trait]
+pub trait DeliveryStore<M>: Send + Sync {
+    /// The id of the store, which tells its deliveries apart from those of other stores sending to
+    /// the same actor. It must stay the same across restarts of the process for redelivered
+    /// messages to be recognised, and should be chosen at random when the store is first created,
+    /// so that it differs from the ids of all other stores.
+    fn store_id(&self) -> u64;
+
+    /// Store a message which is about to be delivered, returning the id of the delivery. Ids must
+    /// never be reused, even once the delivery has been confirmed.
+    async fn save(&self, message: &M) -> io::Result<u64>;
+
+    /// Remove the message with the given delivery id, once it has been handled.
+    async fn confirm(&self, delivery_id: u64) -> io::Result<()>;
+
+    /// All messages which were saved but not confirmed yet, in the order in which they were saved.
+    async fn unconfirmed(&self) -> io::Result<Vec<(u64, M)>>;
+}
+
+/// Why [`AtLeastOnce`](struct.AtLeastOnce.html) could not deliver a message. The message stays in
+/// the [`DeliveryStore`](trait.DeliveryStore.html) either way, so it can be redelivered later on.
+#[derive(Debug)]
+pub enum DeliveryError {
+    /// The delivery store failed to save or confirm the message.
+    Store(io::Error),
+    /// The receiving actor is disconnected for good, such as because it stopped and is not run by
+    /// a [`Supervisor`](../struct.Supervisor.html) which restarts it.
+    Disconnected(DisconnectReason),
+    /// The actor had already handled the message, so it was removed from the store, but its
+    /// result is not known. Since message ids include the id of the store, this is only the case
+    /// for messages which were delivered from this very store before.
+    Duplicate,
+}
+
+impl Display for DeliveryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliveryError::Store(err) => write!(f, "Delivery store failed: {}", err),
+            DeliveryError::Disconnected(reason) => write!(f, "Delivery failed: {}", reason),
+            DeliveryError::Duplicate => f.write_str("Message was already delivered"),
+        }
+    }
+}
+
+impl Error for DeliveryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeliveryError::Store(err) => Some(err),
+            DeliveryError::Disconnected(_) | DeliveryError::Duplicate => None,
+        }
+    }
+}
+
+impl From<io::Error> for DeliveryError {
+    fn from(err: io::Error) -> Self {
+        DeliveryError::Store(err)
+    }
+}
+
+/// Sends messages to an actor with at-least-once delivery. Each message is saved to a
+/// [`DeliveryStore`](trait.DeliveryStore.html) before it is sent as a [`Delivered`](struct.Delivered.html)
+/// message, and is sent again after the retry interval until the actor has handled it, which
+/// acknowledges it and removes it from the store. This covers the actor panicking while handling
+/// it or being restarted by a [`Supervisor`](../struct.Supervisor.html) in the meantime, and
+/// [`AtLeastOnce::redeliver`](struct.AtLeastOnce.html#method.redeliver) sends the messages left in
+/// the store once the sending process has been restarted. Since a message can arrive more than
+/// once, the actor should have a dedup window (see
+/// [`ActorManager::dedup_window`](../struct.ActorManager.html#method.dedup_window)), so that it
+/// handles every delivery id only once, or handle messages idempotently itself.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use std::time::Duration;
+/// use xtra::persistence::{AtLeastOnce, Delivered, MemoryDeliveryStore};
+/// use xtra::{RestartStrategy, Supervisor};
+///
+/// #[derive(Clone)]
+/// struct Charge(u32);
+/// impl Message for Charge {
+///     type Result = u32;
+/// }
+///
+/// struct Payments {
+///     charged: u32,
+///     fail_next: bool,
+/// }
+/// impl Actor for Payments {}
+///
+/// impl SyncHandler<Delivered<Charge>> for Payments {
+///     fn handle(&mut self, delivered: Delivered<Charge>, _: &mut Context<Self>) -> u32 {
+///         if std::mem::take(&mut self.fail_next) {
+///             panic!("payment provider unavailable");
+///         }
+///         self.charged += delivered.message.0;
+///         self.charged
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let mut first = true;
+///     let (addr, supervisor) = Supervisor::new(
+///         move || {
+///             // The first instance panics on the first charge, and its supervisor restarts it
+///             let fail_next = std::mem::take(&mut first);
+///             Payments { charged: 0, fail_next }
+///         },
+///         RestartStrategy::Always,
+///     );
+///     let supervisor = supervisor.dedup_window(1024);
+///     smol::Task::spawn(supervisor.supervise()).detach();
+///
+///     let store = MemoryDeliveryStore::new();
+///     let payments = AtLeastOnce::new(addr, store.clone()).retry_interval(Duration::from_millis(10));
+///
+///     assert_eq!(payments.deliver(Charge(10)).await.unwrap(), 10);
+///     assert!(store.is_empty());
+/// }
+/// ```
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+pub struct AtLeastOnce<A: Actor, M> {
+    address: Address<A>,
+    store: Arc<dyn DeliveryStore<M>>,
+    retry_interval: Duration,
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl<A, M> AtLeastOnce<A, M>
+where
+    A: Handler<Delivered<M>>,
+    M: Message + Clone + Sync,
+{
+    /// Deliver messages to the actor at the given address, keeping them in `store` until they have
+    /// been handled. Messages are retried every second by default.
+    pub fn new<S: DeliveryStore<M> + 'static>(address: Address<A>, store: S) -> Self {
+        AtLeastOnce {
+            address,
+            store: Arc::new(store),
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Set how long to wait before sending a message again after it was not handled.
+    pub fn retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Save the message to the store and send it until the actor has handled it, resolving to its
+    /// result. If the actor is disconnected for good, this gives up, but the message stays in the
+    /// store to be redelivered later on.
+    pub async fn deliver(&self, message: M) -> Result<M::Result, DeliveryError> {
+        let delivery_id = self.store.save(&message).await?;
+        self.send(delivery_id, message)
+            .await?
+            .ok_or(DeliveryError::Duplicate)
+    }
+
+    /// Send all messages left in the store which have not been handled yet, such as after the
+    /// sending process has been restarted, returning how many there were. They are delivered one
+    /// after another, in the order in which they were saved. Messages which the actor had already
+    /// handled before the restart are only removed from the store.
+    pub async fn redeliver(&self) -> Result<usize, DeliveryError> {
+        let unconfirmed = self.store.unconfirmed().await?;
+        let count = unconfirmed.len();
+        for (delivery_id, message) in unconfirmed {
+            self.send(delivery_id, message).await?;
+        }
+        Ok(count)
+    }
+
+    /// Send the message until the actor has handled it, resolving to `None` if it had already
+    /// been handled.
+    async fn send(&self, delivery_id: u64, message: M) -> Result<Option<M::Result>, DeliveryError> {
+        loop {
+            let delivered = Delivered {
+                store_id: self.store.store_id(),
+                delivery_id,
+                message: message.clone(),
+            };
+
+            match self.address.send(delivered).await {
+                Ok(result) => {
+                    self.store.confirm(delivery_id).await?;
+                    return Ok(Some(result));
+                }
+                // The message id includes the id of this store, so the actor has handled this very
+                // delivery before rather than one from another store with the same delivery id
+                Err(SendError::Duplicate) => {
+                    self.store.confirm(delivery_id).await?;
+                    return Ok(None);
+                }
+                // The actor stopped while handling the message, but is still connected, so it was
+                // restarted and can be sent the message again
+                Err(SendError::Disconnected(DisconnectReason::MessageDropped))
+                | Err(SendError::MailboxFull)
+                | Err(SendError::Timeout) => {}
+                Err(SendError::Disconnected(reason)) => {
+                    return Err(DeliveryError::Disconnected(reason))
+                }
+            }
+
+            crate::runtime::delay(self.retry_interval).await;
+        }
+    }
+}
+
+/// A [`DeliveryStore`](trait.DeliveryStore.html) which keeps messages in memory, so they are lost
+/// once the process exits. Clones of it share the same messages.
+/// Every store created with
+/// [`MemoryDeliveryStore::new`](struct.MemoryDeliveryStore.html#method.new) has an id of its own,
+/// so several senders with their own stores can deliver to the same actor, even though the delivery
+/// ids of each store start at zero.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use xtra::persistence::{AtLeastOnce, Delivered, MemoryDeliveryStore};
+///
+/// #[derive(Clone)]
+/// struct Deposit(u32);
+/// impl Message for Deposit {
+///     type Result = u32;
+/// }
+///
+/// struct Account(u32);
+/// impl Actor for Account {}
+///
+/// impl SyncHandler<Delivered<Deposit>> for Account {
+///     fn handle(&mut self, delivered: Delivered<Deposit>, _: &mut Context<Self>) -> u32 {
+///         self.0 += delivered.message.0;
+///         self.0
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, mgr) = Account(0).create();
+///     let mgr = mgr.dedup_window(1024);
+///     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+///
+///     let alice = AtLeastOnce::new(addr.clone(), MemoryDeliveryStore::new());
+///     let bob = AtLeastOnce::new(addr, MemoryDeliveryStore::new());
+///
+///     // Both first deliveries have the delivery id 0, but neither is mistaken for the other
+///     assert_eq!(alice.deliver(Deposit(10)).await.unwrap(), 10);
+///     assert_eq!(bob.deliver(Deposit(5)).await.unwrap(), 15);
+/// }
+/// ```
+pub struct MemoryDeliveryStore<M> {
+    inner: Arc<Mutex<MemoryDeliveries<M>>>,
+}
+
+struct MemoryDeliveries<M> {
+    /// Chosen at random, since the store is lost when the process exits
+    store_id: u64,
+    next_id: u64,
+    unconfirmed: BTreeMap<u64, M>,
+}
+
+impl<M> MemoryDeliveryStore<M> {
+    /// Create an empty delivery store.
+    pub fn new() -> Self {
+        MemoryDeliveryStore {
+            inner: Arc::new(Mutex::new(MemoryDeliveries {
+                store_id: RandomState::new().build_hasher().finish(),
+                next_id: 0,
+                unconfirmed: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Returns whether all messages saved to the store have been confirmed.
+    pub fn is_empty(&self) -> bool {
+        self.lock().unconfirmed.is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryDeliveries<M>> {
+        // Nothing can panic while the lock is held, so poisoning can safely be ignored
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<M> Clone for MemoryDeliveryStore<M> {
+    fn clone(&self) -> Self {
+        MemoryDeliveryStore {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M> Default for MemoryDeliveryStore<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<M: Clone + Send + Sync> DeliveryStore<M> for MemoryDeliveryStore<M> {
+    fn store_id(&self) -> u64 {
+        self.lock().store_id
+    }
+
+    async fn save(&self, message: &M) -> io::Result<u64> {
+        let mut inner = self.lock();
+        let delivery_id = inner.next_id;
+        inner.next_id += 1;
+        inner.unconfirmed.insert(delivery_id, message.clone());
+        Ok(delivery_id)
+    }
+
+    async fn confirm(&self, delivery_id: u64) -> io::Result<()> {
+        self.lock().unconfirmed.remove(&delivery_id);
+        Ok(())
+    }
+
+    async fn unconfirmed(&self) -> io::Result<Vec<(u64, M)>> {
+        let inner = self.lock();
+        let unconfirmed = inner.unconfirmed.iter();
+        Ok(unconfirmed.map(|(id, m)| (*id, m.clone())).collect())
+    }
+}
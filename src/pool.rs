@@ -0,0 +1,288 @@
+//! Recycling of the memory that sending and handling a message takes: the box of its envelope,
+//! the boxed futures which handle it, and the channel over which its result is returned. With the
+//! `perf` feature, memory which is freed through this module is kept in a small cache of the thread
+//! which freed it, by size and alignment, and taken out again for the next value of the same size
+//! and alignment allocated on that thread. Once an actor has handled a few messages, sending and
+//! handling more of them then does not allocate, as long as the sender and the actor run on the
+//! same threads. Without the feature, these are plain allocations.
+//!
+//! A block which was allocated for one type is only handed out for another type of exactly the
+//! same layout, which is what the global allocator will be told when the box is dropped. For
+//! `Arc`s, the standard library explicitly allows this in `Arc::from_raw`.
+
+use futures::task::{Context, Poll};
+use futures::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Box a value, reusing a cached block of its layout if there is one.
+#[cfg(feature = "perf")]
+pub(crate) fn boxed<T>(value: T) -> Box<T> {
+    match cache::take(cache::Kind::Box, std::alloc::Layout::new::<T>()) {
+        // SAFETY: the block is free and was allocated by the global allocator with the layout of
+        // `T`, which is what `Box<T>` frees it with
+        Some(block) => unsafe {
+            let ptr = block.as_ptr() as *mut T;
+            ptr.write(value);
+            Box::from_raw(ptr)
+        },
+        None => Box::new(value),
+    }
+}
+
+/// Box a value, reusing a cached block of its layout if there is one.
+#[cfg(not(feature = "perf"))]
+pub(crate) fn boxed<T>(value: T) -> Box<T> {
+    Box::new(value)
+}
+
+/// Pin a value in a box, reusing a cached block of its layout if there is one.
+pub(crate) fn pinned<T>(value: T) -> Pin<Box<T>> {
+    Box::into_pin(boxed(value))
+}
+
+/// Take the value out of its box, keeping the memory of the box to be reused.
+#[cfg(feature = "perf")]
+pub(crate) fn unbox<T>(value: Box<T>) -> T {
+    let ptr = Box::into_raw(value);
+    // SAFETY: the pointer came from a box, so it is valid to read, and the value is not touched
+    // again once it has been read out
+    unsafe {
+        let value = ptr.read();
+        cache::give_box(std::alloc::Layout::new::<T>(), ptr as *mut u8);
+        value
+    }
+}
+
+/// Take the value out of its box, keeping the memory of the box to be reused.
+#[cfg(not(feature = "perf"))]
+#[allow(clippy::boxed_local)] // Boxed to match the signature with the `perf` feature
+pub(crate) fn unbox<T>(value: Box<T>) -> T {
+    *value
+}
+
+/// Drop the value in a box, keeping the memory of the box to be reused.
+#[cfg(feature = "perf")]
+fn recycle<T: ?Sized>(value: Box<T>) {
+    let layout = std::alloc::Layout::for_value(&*value);
+    let ptr = Box::into_raw(value);
+    // SAFETY: the pointer came from a box, so the value can be dropped in place, after which its
+    // memory is not touched again other than by the cache
+    unsafe {
+        std::ptr::drop_in_place(ptr);
+        cache::give_box(layout, ptr as *mut u8);
+    }
+}
+
+/// Drop the value in a pinned box, keeping the memory of the box to be reused.
+#[cfg(feature = "perf")]
+pub(crate) fn recycle_pinned<T: ?Sized>(value: Pin<Box<T>>) {
+    // SAFETY: the value is dropped in place before its memory is reused, as pinning requires
+    recycle(unsafe { Pin::into_inner_unchecked(value) })
+}
+
+/// Drop the value in a pinned box, keeping the memory of the box to be reused.
+#[cfg(not(feature = "perf"))]
+pub(crate) fn recycle_pinned<T: ?Sized>(value: Pin<Box<T>>) {
+    drop(value)
+}
+
+/// Put a value into an `Arc`, reusing the memory of a recycled `Arc` of its layout if there is one.
+#[cfg(feature = "perf")]
+pub(crate) fn arc<T>(value: T) -> Arc<T> {
+    match cache::take(cache::Kind::Arc, std::alloc::Layout::new::<T>()) {
+        // SAFETY: the block is the free memory of an `Arc` of a type with the same size and
+        // alignment as `T`, which was not shared when it was recycled
+        Some(block) => unsafe {
+            let ptr = block.as_ptr() as *mut T;
+            ptr.write(value);
+            Arc::from_raw(ptr)
+        },
+        None => Arc::new(value),
+    }
+}
+
+/// Put a value into an `Arc`, reusing the memory of a recycled `Arc` of its layout if there is one.
+#[cfg(not(feature = "perf"))]
+pub(crate) fn arc<T>(value: T) -> Arc<T> {
+    Arc::new(value)
+}
+
+/// Drop an `Arc`, keeping its memory to be reused if this was its last strong or weak reference.
+#[cfg(feature = "perf")]
+pub(crate) fn recycle_arc<T>(mut value: Arc<T>) {
+    if Arc::get_mut(&mut value).is_none() {
+        return;
+    }
+
+    let ptr = Arc::into_raw(value) as *mut T;
+    // SAFETY: there are no other references to the `Arc`, so its value can be dropped in place,
+    // after which its memory is not touched again other than by the cache
+    unsafe {
+        std::ptr::drop_in_place(ptr);
+        cache::give_arc(
+            std::alloc::Layout::new::<T>(),
+            ptr as *mut u8,
+            cache::free_arc::<T>,
+        );
+    }
+}
+
+/// Drop an `Arc`, keeping its memory to be reused if this was its last strong or weak reference.
+#[cfg(not(feature = "perf"))]
+pub(crate) fn recycle_arc<T>(value: Arc<T>) {
+    drop(value)
+}
+
+/// Await a boxed future, recycling its box once it has completed.
+pub(crate) fn recycling<F: Future + ?Sized>(fut: Pin<Box<F>>) -> Recycling<F> {
+    Recycling(Some(fut))
+}
+
+/// The future returned by `recycling`
+pub(crate) struct Recycling<F: ?Sized>(Option<Pin<Box<F>>>);
+
+impl<F: Future + ?Sized> Future for Recycling<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let fut = self
+            .0
+            .as_mut()
+            .expect("`Recycling` polled after completion");
+        let output = futures::ready!(fut.as_mut().poll(cx));
+        if let Some(fut) = self.0.take() {
+            recycle_pinned(fut);
+        }
+        Poll::Ready(output)
+    }
+}
+
+#[cfg(feature = "perf")]
+mod cache {
+    use std::alloc::Layout;
+    use std::cell::RefCell;
+    use std::ptr::NonNull;
+
+    /// How many blocks of each kind a thread keeps at most
+    const CAPACITY: usize = 64;
+
+    /// The largest block which is kept, so that big messages do not hold on to much memory
+    const MAX_SIZE: usize = 1024;
+
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    pub(super) enum Kind {
+        /// The memory of a `Box`
+        Box,
+        /// The memory of an `Arc`, which also holds its reference counts
+        Arc,
+    }
+
+    struct Block {
+        kind: Kind,
+        layout: Layout,
+        ptr: NonNull<u8>,
+        /// Frees the memory of the block once it is no longer kept
+        free: unsafe fn(Layout, NonNull<u8>),
+    }
+
+    struct Cache(Vec<Block>);
+
+    impl Drop for Cache {
+        fn drop(&mut self) {
+            for block in self.0.drain(..) {
+                // SAFETY: the block is free, and `free` matches how it was allocated
+                unsafe { (block.free)(block.layout, block.ptr) }
+            }
+        }
+    }
+
+    thread_local! {
+        static CACHE: RefCell<Cache> = const { RefCell::new(Cache(Vec::new())) };
+    }
+
+    /// Take a free block of the given kind and layout out of the cache of this thread.
+    pub(super) fn take(kind: Kind, layout: Layout) -> Option<NonNull<u8>> {
+        if layout.size() == 0 {
+            return None;
+        }
+
+        CACHE
+            .try_with(|cache| {
+                let mut cache = cache.try_borrow_mut().ok()?;
+                let index = cache
+                    .0
+                    .iter()
+                    .rposition(|block| block.kind == kind && block.layout == layout)?;
+                Some(cache.0.swap_remove(index).ptr)
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Keep the free memory of a box in the cache of this thread, or free it if the cache is full.
+    ///
+    /// # Safety
+    ///
+    /// The block must have been allocated by the global allocator with the given layout, and must
+    /// not be used again.
+    pub(super) unsafe fn give_box(layout: Layout, ptr: *mut u8) {
+        give(Kind::Box, layout, ptr, free_box)
+    }
+
+    /// Keep the free memory of an `Arc` of type `T` in the cache of this thread, or free it if the
+    /// cache is full.
+    ///
+    /// # Safety
+    ///
+    /// The block must be the pointer returned by `Arc::into_raw` for an `Arc` without any other
+    /// references, whose value has been dropped, and `free` must be `free_arc::<T>`.
+    pub(super) unsafe fn give_arc(
+        layout: Layout,
+        ptr: *mut u8,
+        free: unsafe fn(Layout, NonNull<u8>),
+    ) {
+        give(Kind::Arc, layout, ptr, free)
+    }
+
+    unsafe fn give(kind: Kind, layout: Layout, ptr: *mut u8, free: unsafe fn(Layout, NonNull<u8>)) {
+        let ptr = match NonNull::new(ptr) {
+            // Zero-sized values were never allocated
+            Some(ptr) if layout.size() != 0 => ptr,
+            _ => return,
+        };
+
+        let block = Block {
+            kind,
+            layout,
+            ptr,
+            free,
+        };
+        let rejected = CACHE.try_with(|cache| match cache.try_borrow_mut() {
+            Ok(mut cache) if layout.size() <= MAX_SIZE && cache.0.len() < CAPACITY => {
+                cache.0.push(block);
+                None
+            }
+            _ => Some(block),
+        });
+
+        match rejected {
+            Ok(None) => {}
+            Ok(Some(block)) => (block.free)(block.layout, block.ptr),
+            // The thread is exiting, so the cache is gone
+            Err(_) => free(layout, ptr),
+        }
+    }
+
+    unsafe fn free_box(layout: Layout, ptr: NonNull<u8>) {
+        std::alloc::dealloc(ptr.as_ptr(), layout)
+    }
+
+    /// Free the memory of an `Arc<T>` whose value has already been dropped.
+    pub(super) unsafe fn free_arc<T>(_: Layout, ptr: NonNull<u8>) {
+        // `ManuallyDrop<T>` has the same layout as `T`, and does not drop the value again
+        drop(std::sync::Arc::from_raw(
+            ptr.as_ptr() as *const std::mem::ManuallyDrop<T>
+        ))
+    }
+}
@@ -0,0 +1,312 @@
+//! Recording the messages an actor handles, to replay them into a fresh instance of it when
+//! debugging what led it into a bad state.
+//!
+//! A [`Recorder`](struct.Recorder.html) is given to an actor with
+//! [`ActorManager::record`](../struct.ActorManager.html#method.record), after which an entry is
+//! added to its trace for every message the actor handles, in the order in which they are handled.
+//! Every entry holds the type of the message, when it was handled, and which actor sent it if it
+//! was sent from the handler of another actor. Messages whose type was registered with
+//! [`Recorder::serialize`](struct.Recorder.html#method.serialize) are also serialized as JSON with
+//! [`serde`](https://serde.rs), which is required to replay them.
+//!
+//! A trace can itself be serialized, so that it can be written out when something goes wrong and
+//! loaded again later on. [`Recorder::replay`](struct.Recorder.html#method.replay) sends the
+//! serialized messages of a trace to an actor in order, such as to a fresh instance of the actor
+//! which is being debugged.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//! use xtra::recording::{RecordedMessage, Recorder};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Add(u32);
+//! impl Message for Add {
+//!     type Result = ();
+//! }
+//!
+//! struct Get;
+//! impl Message for Get {
+//!     type Result = u32;
+//! }
+//!
+//! #[derive(Default)]
+//! struct Counter(u32);
+//! impl Actor for Counter {}
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Add> for Counter {
+//!     async fn handle(&mut self, Add(n): Add, _: &mut Context<Self>) {
+//!         self.0 += n;
+//!     }
+//! }
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Get> for Counter {
+//!     async fn handle(&mut self, _: Get, _: &mut Context<Self>) -> u32 {
+//!         self.0
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let recorder = Recorder::new().serialize::<Add>();
+//!     let (addr, mgr) = Counter::default().create();
+//!     let mgr = mgr.record(recorder.clone());
+//!     smol::Task::spawn(async move { mgr.manage().await; }).detach();
+//!
+//!     addr.send(Add(1)).await.unwrap();
+//!     addr.send(Add(2)).await.unwrap();
+//!     assert_eq!(addr.send(Get).await, Ok(3));
+//!
+//!     // `Get` was recorded, but without its payload since it cannot be serialized
+//!     let trace = recorder.take();
+//!     assert_eq!(trace.len(), 3);
+//!     assert!(trace[2].payload.is_none());
+//!
+//!     // The trace can be saved and loaded again, such as from a file
+//!     let saved = serde_json::to_string(&trace).unwrap();
+//!     let trace: Vec<RecordedMessage> = serde_json::from_str(&saved).unwrap();
+//!
+//!     // Replaying it into a fresh instance brings it into the same state
+//!     let fresh = Counter::default().spawn();
+//!     assert_eq!(recorder.replay(&trace, &fresh).await.unwrap(), 2);
+//!     assert_eq!(fresh.send(Get).await, Ok(3));
+//! }
+//! ```
+
+use crate::clock::Timestamp;
+use crate::envelope::MessageEnvelope;
+use crate::{Actor, ActorId, Address, AddressExt, Handler, Message, SendError};
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// A message handled by an actor, as recorded by a [`Recorder`](struct.Recorder.html).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// The name of the type of the message
+    pub message: String,
+    /// The message serialized as JSON, if its type was registered with
+    /// [`Recorder::serialize`](struct.Recorder.html#method.serialize) and it could be serialized
+    pub payload: Option<serde_json::Value>,
+    /// How long after the recorder was created the message started to be handled. This is `None`
+    /// on wasm, where there is no clock to measure it with.
+    pub elapsed: Option<Duration>,
+    /// The actor whose handler sent the message, if it was sent from the handler of an actor
+    pub sender: Option<ActorId>,
+}
+
+/// Why [`Recorder::replay`](struct.Recorder.html#method.replay) could not replay a trace.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The payload of a recorded message could not be deserialized into its type.
+    Deserialize {
+        /// The name of the type of the message
+        message: String,
+        /// The error which deserializing the message failed with
+        error: serde_json::Error,
+    },
+    /// A recorded message could not be sent to the actor.
+    Send {
+        /// The name of the type of the message
+        message: String,
+        /// The error which sending the message failed with
+        error: SendError,
+    },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Deserialize { message, error } => {
+                write!(f, "Could not deserialize {}: {}", message, error)
+            }
+            ReplayError::Send { message, error } => {
+                write!(f, "Could not replay {}: {}", message, error)
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::Deserialize { error, .. } => Some(error),
+            ReplayError::Send { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Serializes a message of a registered type, and deserializes and sends it again to replay it
+struct Codec<A: Actor> {
+    serialize: fn(&dyn Any) -> Option<serde_json::Value>,
+    replay: fn(serde_json::Value, Address<A>) -> BoxFuture<'static, Result<(), ReplayError>>,
+}
+
+impl<A: Actor> Clone for Codec<A> {
+    fn clone(&self) -> Self {
+        Codec {
+            serialize: self.serialize,
+            replay: self.replay,
+        }
+    }
+}
+
+fn serialize<M: Serialize + 'static>(message: &dyn Any) -> Option<serde_json::Value> {
+    let message = message.downcast_ref::<M>()?;
+    serde_json::to_value(message).ok()
+}
+
+fn replay<A, M>(
+    payload: serde_json::Value,
+    address: Address<A>,
+) -> BoxFuture<'static, Result<(), ReplayError>>
+where
+    A: Handler<M>,
+    M: Message + DeserializeOwned,
+{
+    Box::pin(async move {
+        let message = std::any::type_name::<M>().to_string();
+        let msg = match serde_json::from_value::<M>(payload) {
+            Ok(msg) => msg,
+            Err(error) => return Err(ReplayError::Deserialize { message, error }),
+        };
+
+        match address.send(msg).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(ReplayError::Send { message, error }),
+        }
+    })
+}
+
+/// Records the messages handled by an actor, to replay them later on. Clones of it share the same
+/// trace, so one clone can be given to the actor with
+/// [`ActorManager::record`](../struct.ActorManager.html#method.record) while another one is kept to
+/// read the trace. See the [module level docs](index.html) for an example.
+pub struct Recorder<A: Actor> {
+    inner: Arc<RecorderInner<A>>,
+}
+
+struct RecorderInner<A: Actor> {
+    /// When the recorder was created, from which the time at which messages were handled is counted
+    start: Timestamp,
+    /// The codecs of the message types registered with `Recorder::serialize`, by type name
+    codecs: Mutex<HashMap<&'static str, Codec<A>>>,
+    trace: Mutex<Vec<RecordedMessage>>,
+}
+
+impl<A: Actor> Recorder<A> {
+    /// Create a recorder with an empty trace, which serializes no messages until their types are
+    /// registered with [`Recorder::serialize`](struct.Recorder.html#method.serialize).
+    pub fn new() -> Self {
+        Recorder {
+            inner: Arc::new(RecorderInner {
+                start: Timestamp::now(),
+                codecs: Mutex::new(HashMap::new()),
+                trace: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Serialize the messages of type `M` into the trace, so that they can be replayed.
+    pub fn serialize<M>(self) -> Self
+    where
+        A: Handler<M>,
+        M: Message + Serialize + DeserializeOwned,
+    {
+        let codec = Codec {
+            serialize: serialize::<M>,
+            replay: replay::<A, M>,
+        };
+        lock(&self.inner.codecs).insert(std::any::type_name::<M>(), codec);
+        self
+    }
+
+    /// The messages recorded so far, in the order in which they were handled.
+    pub fn trace(&self) -> Vec<RecordedMessage> {
+        lock(&self.inner.trace).clone()
+    }
+
+    /// Take the messages recorded so far out of the trace, leaving it empty.
+    pub fn take(&self) -> Vec<RecordedMessage> {
+        mem::take(&mut *lock(&self.inner.trace))
+    }
+
+    /// Send the recorded messages of the trace to the actor at the given address in order, waiting
+    /// for each one to be handled before sending the next, and returning how many were sent. Only
+    /// messages which were serialized are replayed, so the others are skipped. Their results are
+    /// discarded.
+    ///
+    /// If a recorded message cannot be deserialized or sent, replaying stops with an error, and
+    /// the messages before it have already been handled by the actor.
+    pub async fn replay(
+        &self,
+        trace: &[RecordedMessage],
+        address: &Address<A>,
+    ) -> Result<usize, ReplayError> {
+        let codecs = lock(&self.inner.codecs).clone();
+        let mut replayed = 0;
+
+        for recorded in trace {
+            let payload = match &recorded.payload {
+                Some(payload) => payload.clone(),
+                None => continue,
+            };
+            // Messages are only serialized if their type is registered, so it must be known
+            let codec = match codecs.get(recorded.message.as_str()) {
+                Some(codec) => codec,
+                None => continue,
+            };
+
+            (codec.replay)(payload, address.clone()).await?;
+            replayed += 1;
+        }
+
+        Ok(replayed)
+    }
+
+    /// Add the message in the envelope to the trace, as it is about to be handled.
+    pub(crate) fn record(&self, envelope: &dyn MessageEnvelope<Actor = A>) {
+        let message = envelope.message_type();
+        let payload = match (lock(&self.inner.codecs).get(message), envelope.message()) {
+            (Some(codec), Some(msg)) => (codec.serialize)(msg),
+            _ => None,
+        };
+
+        lock(&self.inner.trace).push(RecordedMessage {
+            message: message.to_string(),
+            payload,
+            elapsed: self.inner.start.elapsed(),
+            sender: envelope.sender(),
+        });
+    }
+}
+
+impl<A: Actor> Clone for Recorder<A> {
+    fn clone(&self) -> Self {
+        Recorder {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A: Actor> Default for Recorder<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // Nothing can panic while the locks are held, so poisoning can safely be ignored
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
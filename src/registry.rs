@@ -0,0 +1,238 @@
+use crate::{Actor, Address, AddressExt};
+use std::any::{self, Any, TypeId};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+type Entry = Box<dyn Any + Send + Sync>;
+
+#[derive(Default)]
+struct Entries {
+    by_type: HashMap<TypeId, Entry>,
+    by_name: HashMap<String, Entry>,
+}
+
+/// A type under which an address is registered in the [`Registry`](struct.Registry.html), and by
+/// which it is looked up. Every actor is a key for its own address, and
+/// [`Service`](struct.Service.html) keys allow several services to be provided by actors of the
+/// same type, or the actor providing a service to be swapped out without changing its users.
+pub trait RegistryKey: 'static {
+    /// The actor whose address is registered under this key.
+    type Actor: Actor;
+}
+
+impl<A: Actor> RegistryKey for A {
+    type Actor = A;
+}
+
+/// A service which is provided by an actor, and looked up in the [`Registry`](struct.Registry.html)
+/// as [`Service<S>`](struct.Service.html). This is usually implemented for an empty type naming
+/// the service.
+pub trait ServiceKey: 'static {
+    /// The actor which provides the service.
+    type Actor: Actor;
+}
+
+/// The [`RegistryKey`](trait.RegistryKey.html) of the service `S`, such that
+/// `Registry::get::<Service<S>>()` returns the address of the actor providing it. This is only
+/// used as a type, and is never constructed.
+pub struct Service<S>(PhantomData<fn() -> S>);
+
+impl<S: ServiceKey> RegistryKey for Service<S> {
+    type Actor = S::Actor;
+}
+
+/// The error returned by [`Registry::expect`](struct.Registry.html#method.expect) when no address
+/// is registered under a key.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MissingService {
+    /// The name of the key type.
+    pub key: &'static str,
+}
+
+impl Display for MissingService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "No address is registered under {}", self.key)
+    }
+}
+
+impl Error for MissingService {}
+
+/// A process-wide registry of actor addresses, which can be registered under the type of their
+/// actor or under a name. This removes the need to pass addresses to every actor which needs them.
+///
+/// The registry holds strong [`Address`es](struct.Address.html), so a registered actor will not be
+/// stopped for a lack of addresses until it is removed from the registry. Addresses of actors
+/// which have stopped otherwise are removed once they are looked up.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::Registry;
+/// struct Logger;
+/// impl Actor for Logger {}
+///
+/// struct Log(&'static str);
+/// impl Message for Log {
+///     type Result = ();
+/// }
+///
+/// impl SyncHandler<Log> for Logger {
+///     fn handle(&mut self, Log(line): Log, _: &mut Context<Self>) {
+///         println!("{}", line);
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     Registry::insert(Logger.spawn());
+///     Registry::insert_named("audit", Logger.spawn());
+///
+///     // Anywhere else in the application
+///     let logger = Registry::get::<Logger>().unwrap();
+///     logger.send(Log("hello")).await.unwrap();
+///
+///     let audit = Registry::get_named::<Logger>("audit").unwrap();
+///     audit.send(Log("hello again")).await.unwrap();
+///
+///     assert!(Registry::remove::<Logger>().is_some());
+///     assert!(Registry::get::<Logger>().is_none());
+/// }
+/// ```
+///
+/// Addresses can also be provided under a [`Service`](struct.Service.html) key, and the services
+/// which the application requires can be checked for when it starts:
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use xtra::{MissingService, Registry, Service, ServiceKey};
+///
+/// struct Postgres;
+/// impl Actor for Postgres {}
+///
+/// struct Database;
+/// impl ServiceKey for Database {
+///     type Actor = Postgres;
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() -> Result<(), MissingService> {
+///     Registry::provide::<Service<Database>>(Postgres.spawn());
+///
+///     // Fail fast if a required service was not provided
+///     let database: Address<Postgres> = Registry::expect::<Service<Database>>()?;
+///     # drop(database);
+///
+///     # struct Cache;
+///     # impl ServiceKey for Cache {
+///     #     type Actor = Postgres;
+///     # }
+///     assert!(Registry::expect::<Service<Cache>>().is_err());
+///     Ok(())
+/// }
+/// ```
+pub struct Registry {
+    _private: (),
+}
+
+impl Registry {
+    fn entries() -> MutexGuard<'static, Entries> {
+        static ENTRIES: OnceLock<Mutex<Entries>> = OnceLock::new();
+        // Nothing which can panic runs while the lock is held, so poisoning can safely be ignored
+        ENTRIES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register the address under the type of its actor, returning the address which was
+    /// previously registered for it, if any.
+    pub fn insert<A: Actor>(address: Address<A>) -> Option<Address<A>> {
+        Self::provide::<A>(address)
+    }
+
+    /// Register the address under the key `K`, such as a [`Service`](struct.Service.html),
+    /// returning the address which was previously registered under it, if any.
+    pub fn provide<K: RegistryKey>(address: Address<K::Actor>) -> Option<Address<K::Actor>> {
+        let old = Self::entries()
+            .by_type
+            .insert(TypeId::of::<K>(), Box::new(address));
+        old.and_then(downcast)
+    }
+
+    /// Get the address registered under the key `K`, which is either the type of an actor or a
+    /// [`Service`](struct.Service.html).
+    pub fn get<K: RegistryKey>() -> Option<Address<K::Actor>> {
+        let mut entries = Self::entries();
+        let key = TypeId::of::<K>();
+        let address = entries
+            .by_type
+            .get(&key)?
+            .downcast_ref::<Address<K::Actor>>()?;
+
+        if address.is_connected() {
+            Some(address.clone())
+        } else {
+            let stale = entries.by_type.remove(&key);
+            drop(entries);
+            drop(stale);
+            None
+        }
+    }
+
+    /// Get the address registered under the key `K` like [`Registry::get`](#method.get), but
+    /// return an error naming the key if there is none. This is useful to check that every
+    /// service which the application requires has been provided when it starts.
+    pub fn expect<K: RegistryKey>() -> Result<Address<K::Actor>, MissingService> {
+        Self::get::<K>().ok_or(MissingService {
+            key: any::type_name::<K>(),
+        })
+    }
+
+    /// Remove the address registered under the key `K`, returning it.
+    pub fn remove<K: RegistryKey>() -> Option<Address<K::Actor>> {
+        let old = Self::entries().by_type.remove(&TypeId::of::<K>());
+        old.and_then(downcast)
+    }
+
+    /// Register the address under the given name, returning the address which was previously
+    /// registered under it, if any and if it referred to an actor of the same type.
+    pub fn insert_named<A: Actor>(
+        name: impl Into<String>,
+        address: Address<A>,
+    ) -> Option<Address<A>> {
+        let old = Self::entries()
+            .by_name
+            .insert(name.into(), Box::new(address));
+        old.and_then(downcast)
+    }
+
+    /// Get the address registered under the given name, if it refers to an actor of type `A`.
+    pub fn get_named<A: Actor>(name: &str) -> Option<Address<A>> {
+        let mut entries = Self::entries();
+        let address = entries.by_name.get(name)?.downcast_ref::<Address<A>>()?;
+
+        if address.is_connected() {
+            Some(address.clone())
+        } else {
+            let stale = entries.by_name.remove(name);
+            drop(entries);
+            drop(stale);
+            None
+        }
+    }
+
+    /// Remove the address registered under the given name, returning it if it referred to an
+    /// actor of type `A`.
+    pub fn remove_named<A: Actor>(name: &str) -> Option<Address<A>> {
+        let old = Self::entries().by_name.remove(name);
+        old.and_then(downcast)
+    }
+}
+
+fn downcast<A: Actor>(entry: Entry) -> Option<Address<A>> {
+    entry.downcast::<Address<A>>().ok().map(|address| *address)
+}
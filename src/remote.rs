@@ -0,0 +1,599 @@
+//! Remote actors, which are sent messages from another process over a connection such as a TCP
+//! stream. Messages and their results are serialized with [`serde`](https://serde.rs), so remote
+//! messages must implement [`RemoteMessage`](trait.RemoteMessage.html).
+//!
+//! The process hosting the actors exposes their addresses on a [`Node`](struct.Node.html) under a
+//! name, and serves connections with [`Node::serve`](struct.Node.html#method.serve). The other
+//! process creates a [`Connection`](struct.Connection.html) from its end of a connection, and gets
+//! a [`RemoteAddress`](struct.RemoteAddress.html) to each actor from it by name, through which
+//! messages are sent as they would be through an [`Address`](../struct.Address.html).
+//!
+//! Connections are made by a [`Transport`](trait.Transport.html), which sends and receives whole
+//! frames. [`TcpTransport`](struct.TcpTransport.html) and
+//! [`MemoryTransport`](struct.MemoryTransport.html) are provided, and other kinds of connections
+//! can be plugged in by implementing the trait. Both sides can also be given any stream
+//! implementing `AsyncRead` and `AsyncWrite` from the `futures` crate directly, which is framed
+//! with [`length_delimited`](fn.length_delimited.html).
+//!
+//! Messages are encoded as JSON by default, and another [`Codec`](trait.Codec.html), such as a
+//! binary format, can be chosen for each connection.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//! use xtra::remote::{Connection, Node, RemoteMessage, TcpTransport};
+//!
+//! struct Greeter;
+//! impl Actor for Greeter {}
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Greet(String);
+//!
+//! impl Message for Greet {
+//!     type Result = String;
+//! }
+//!
+//! impl RemoteMessage for Greet {
+//!     const NAME: &'static str = "Greet";
+//! }
+//!
+//! impl SyncHandler<Greet> for Greeter {
+//!     fn handle(&mut self, Greet(name): Greet, _: &mut Context<Self>) -> String {
+//!         format!("Hello, {}!", name)
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() -> std::io::Result<()> {
+//!     // In the process hosting the actor
+//!     let listener = TcpTransport::bind("127.0.0.1:0".parse().unwrap()).await?;
+//!     let local_addr = listener.local_addr().unwrap()?;
+//!     let node = Node::new().expose::<Greeter, Greet>("greeter", Greeter.spawn());
+//!     smol::Task::spawn(async move { node.listen(&listener).await.unwrap() }).detach();
+//!
+//!     // In the process sending to it
+//!     let (connection, driver) = Connection::connect(&TcpTransport::default(), local_addr).await?;
+//!     smol::Task::spawn(async move { driver.await.unwrap() }).detach();
+//!
+//!     let greeter = connection.address::<Greeter>("greeter");
+//!     assert_eq!(greeter.send(Greet("Bob".to_string())).await, Ok("Hello, Bob!".to_string()));
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{Actor, Address, AddressExt, Handler, Message};
+use futures::channel::{mpsc, oneshot};
+use futures::future::{self, BoxFuture, Either, Future, FutureExt};
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::stream::{self, FuturesUnordered, StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+mod codec;
+mod transport;
+#[cfg(all(
+    feature = "websocket",
+    any(target_arch = "wasm32", feature = "with-tokio-0_2")
+))]
+mod websocket;
+
+pub use codec::{Codec, CodecError, Json};
+#[cfg(any(feature = "with-async_std-1", feature = "with-smol-0_1"))]
+pub use transport::TcpTransport;
+pub use transport::{
+    length_delimited, FrameRead, FrameWrite, LengthDelimited, MemoryRead, MemoryTransport,
+    MemoryWrite, Transport,
+};
+#[cfg(all(
+    feature = "websocket",
+    any(target_arch = "wasm32", feature = "with-tokio-0_2")
+))]
+pub use websocket::{WebSocketRead, WebSocketTransport, WebSocketWrite};
+
+/// The most requests which a node handles at once for a single connection.
+const MAX_IN_FLIGHT: usize = 64;
+
+/// A [`Message`](../trait.Message.html) which can be sent to a remote actor. Its result must also
+/// be serializable in order to be sent back.
+pub trait RemoteMessage: Message + Serialize + DeserializeOwned {
+    /// The name of the message, which identifies it in both processes. This must be unique among
+    /// the messages handled by an actor, and would usually be the name of the type.
+    const NAME: &'static str;
+}
+
+/// An error which can occur when sending a message to a remote actor.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum RemoteError {
+    /// The connection to the remote process was closed.
+    Disconnected,
+    /// The message or its result could not be serialized or deserialized.
+    Serialization(String),
+    /// The remote process could not deliver the message, for instance because no actor is exposed
+    /// under the name, or because the actor is disconnected.
+    Remote(String),
+}
+
+impl Display for RemoteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteError::Disconnected => f.write_str("Remote connection closed"),
+            RemoteError::Serialization(err) => write!(f, "Remote message serialization: {}", err),
+            RemoteError::Remote(err) => write!(f, "Remote delivery failed: {}", err),
+        }
+    }
+}
+
+impl Error for RemoteError {}
+
+/// The header of a frame sent over a connection. It is followed in the frame by the encoded
+/// message of a request, or the encoded result of a response.
+#[derive(Serialize, Deserialize)]
+enum Header {
+    /// A message to an exposed actor. If `id` is `None`, no response is sent.
+    Request {
+        id: Option<u64>,
+        actor: String,
+        message: String,
+    },
+    /// The result of the request with the same id, or why it could not be delivered. If there is
+    /// an error, there is no payload.
+    Response { id: u64, error: Option<String> },
+}
+
+/// Encode a frame as the length of the header as a big endian `u32`, the header, and the payload.
+fn encode_frame<C: Codec>(codec: &C, header: &Header, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let header = codec
+        .encode(header)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut frame = Vec::with_capacity(4 + header.len() + payload.len());
+    frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&header);
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+fn decode_frame<C: Codec>(codec: &C, mut frame: Vec<u8>) -> io::Result<(Header, Vec<u8>)> {
+    let invalid = |err| io::Error::new(io::ErrorKind::InvalidData, err);
+    let len = frame
+        .get(..4)
+        .ok_or_else(|| invalid("frame too short".into()))?;
+    let len = 4 + u32::from_be_bytes(len.try_into().unwrap()) as usize;
+    if len > frame.len() {
+        return Err(invalid("frame too short".into()));
+    }
+
+    let payload = frame.split_off(len);
+    let header = codec.decode(&frame[4..]).map_err(invalid)?;
+    Ok((header, payload))
+}
+
+/// Handles a request to a local actor, resolving to its encoded result.
+type Exposed<C> =
+    Box<dyn Fn(&Arc<C>, &[u8], bool) -> BoxFuture<'static, Result<Vec<u8>, String>> + Send + Sync>;
+
+/// The side of a connection which hosts actors, delivering the messages sent to them from the
+/// other side. Actors are exposed under a name with [`Node::expose`](struct.Node.html#method.expose).
+///
+/// A node decodes messages with the [`Codec`](trait.Codec.html) it is created with, which is
+/// [`Json`](struct.Json.html) unless another is given to
+/// [`Node::with_codec`](struct.Node.html#method.with_codec). In order to serve connections
+/// using different codecs, the same addresses can be exposed on one node for each codec.
+pub struct Node<C: Codec = Json> {
+    codec: Arc<C>,
+    /// Maps the name of each exposed actor and of each message it handles to its handler
+    exposed: HashMap<(String, String), Exposed<C>>,
+}
+
+impl Node {
+    /// Create a node which decodes messages as JSON, and does not expose any actors yet.
+    pub fn new() -> Self {
+        Node::with_codec(Json)
+    }
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Node::new()
+    }
+}
+
+impl<C: Codec> Node<C> {
+    /// Create a node which decodes messages with the given codec, and does not expose any actors
+    /// yet.
+    pub fn with_codec(codec: C) -> Self {
+        Node {
+            codec: Arc::new(codec),
+            exposed: HashMap::new(),
+        }
+    }
+
+    /// Expose the actor with the given address under `name`, so that it can be sent the message
+    /// `M` from the other side of the connections which this node serves. This must be called once
+    /// for every message which the actor should be sent remotely.
+    pub fn expose<A, M>(mut self, name: impl Into<String>, address: Address<A>) -> Self
+    where
+        A: Handler<M>,
+        M: RemoteMessage,
+        M::Result: Serialize,
+    {
+        let exposed: Exposed<C> = Box::new(move |codec, payload, respond| {
+            let message: M = match codec.decode(payload) {
+                Ok(message) => message,
+                Err(err) => return Box::pin(future::ready(Err(err.to_string()))),
+            };
+
+            if respond {
+                let response = address.send(message);
+                let codec = codec.clone();
+                Box::pin(async move {
+                    let result = response.await.map_err(|err| err.to_string())?;
+                    codec.encode(&result).map_err(|err| err.to_string())
+                })
+            } else {
+                let res = address.do_send(message).map_err(|err| err.to_string());
+                Box::pin(future::ready(res.map(|()| Vec::new())))
+            }
+        });
+
+        self.exposed
+            .insert((name.into(), M::NAME.to_string()), exposed);
+        self
+    }
+
+    /// Serve a connection over the given stream, delivering the messages sent over it to the
+    /// exposed actors and sending back their results, until the other side closes it. The stream
+    /// is framed with [`length_delimited`](fn.length_delimited.html).
+    pub async fn serve<S>(&self, stream: S) -> io::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send,
+    {
+        let (reader, writer) = length_delimited(stream);
+        self.serve_frames(reader, writer).await
+    }
+
+    /// Serve a connection over the given framed halves, delivering the messages sent over it to
+    /// the exposed actors and sending back their results, until the other side closes it. Several
+    /// requests from the connection are handled at once, so a slow actor does not hold up the
+    /// others.
+    pub async fn serve_frames<R, W>(&self, reader: R, writer: W) -> io::Result<()>
+    where
+        R: FrameRead,
+        W: FrameWrite,
+    {
+        let frames = stream::unfold(Some(reader), |reader| async move {
+            let mut reader = reader?;
+            match reader.read_frame().await {
+                Ok(Some(frame)) => Some((Ok(frame), Some(reader))),
+                Ok(None) => None,
+                Err(err) => Some((Err(err), None)),
+            }
+        });
+
+        frames
+            .and_then(|frame| future::ready(decode_frame(&*self.codec, frame)))
+            .map_ok(|(header, payload)| self.handle(header, payload))
+            .try_buffer_unordered(MAX_IN_FLIGHT)
+            .try_fold(writer, |mut writer, response| async move {
+                if let Some(response) = response {
+                    writer.write_frame(&response).await?;
+                }
+                Ok(writer)
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Accept connections from the given transport and serve each of them, until accepting a
+    /// connection fails. A connection failing only stops that connection from being served.
+    pub async fn listen<T: Transport>(&self, transport: &T) -> io::Result<()> {
+        let mut connections = FuturesUnordered::new();
+        let mut accept = transport.accept();
+
+        loop {
+            if connections.is_empty() {
+                let (reader, writer) = accept.await?;
+                connections.push(self.serve_frames(reader, writer));
+                accept = transport.accept();
+                continue;
+            }
+
+            match future::select(accept, connections.next()).await {
+                Either::Left((res, _)) => {
+                    let (reader, writer) = res?;
+                    connections.push(self.serve_frames(reader, writer));
+                    accept = transport.accept();
+                }
+                // The connection ended, and there is nobody to report its error to
+                Either::Right((_, pending)) => accept = pending,
+            }
+        }
+    }
+
+    /// Handle a frame, resolving to the encoded response to send if there is one.
+    fn handle(
+        &self,
+        header: Header,
+        payload: Vec<u8>,
+    ) -> BoxFuture<'static, io::Result<Option<Vec<u8>>>> {
+        let (id, actor, message) = match header {
+            Header::Request { id, actor, message } => (id, actor, message),
+            // This side does not send requests, so there is nothing to respond to
+            Header::Response { .. } => return Box::pin(future::ready(Ok(None))),
+        };
+
+        let result = match self.exposed.get(&(actor, message)) {
+            Some(exposed) => exposed(&self.codec, &payload, id.is_some()),
+            None => Box::pin(future::ready(Err(
+                "no such actor or message is exposed".to_string()
+            ))),
+        };
+
+        let codec = self.codec.clone();
+        Box::pin(result.map(move |result| {
+            let id = match id {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+
+            let frame = match result {
+                Ok(result) => encode_frame(&*codec, &Header::Response { id, error: None }, &result),
+                Err(err) => {
+                    let header = Header::Response {
+                        id,
+                        error: Some(err),
+                    };
+                    encode_frame(&*codec, &header, &[])
+                }
+            };
+            frame.map(Some)
+        }))
+    }
+}
+
+/// The requests sent over a connection which are waiting for a response, by id.
+type Pending = Arc<Mutex<HashMap<u64, Responder>>>;
+
+/// Sends the result of a request, or why it could not be delivered, to the task awaiting it.
+type Responder = oneshot::Sender<Result<Vec<u8>, String>>;
+
+fn lock(pending: &Pending) -> MutexGuard<'_, HashMap<u64, Responder>> {
+    // Nothing can panic while the lock is held, so poisoning can safely be ignored
+    pending.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The side of a connection which sends messages to the actors hosted by a
+/// [`Node`](struct.Node.html) on the other side. It can be cloned, and every clone and
+/// [`RemoteAddress`](struct.RemoteAddress.html) created from it send over the same connection.
+///
+/// Messages are encoded with the [`Codec`](trait.Codec.html) the connection is created with,
+/// which is [`Json`](struct.Json.html) unless another is given to
+/// [`Connection::with_codec`](struct.Connection.html#method.with_codec). The node on the other side
+/// must use the same codec.
+pub struct Connection<C: Codec = Json> {
+    codec: Arc<C>,
+    frames: mpsc::UnboundedSender<Vec<u8>>,
+    pending: Pending,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Connection {
+    /// Create a connection over the given stream, framed with
+    /// [`length_delimited`](fn.length_delimited.html), which encodes messages as JSON. See
+    /// [`Connection::with_codec`](struct.Connection.html#method.with_codec) for what the returned
+    /// future does.
+    pub fn new<S>(stream: S) -> (Connection, impl Future<Output = io::Result<()>> + Send)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, writer) = length_delimited(stream);
+        Connection::with_codec(Json, reader, writer)
+    }
+
+    /// Connect to the node listening at the given address of a transport, encoding messages as
+    /// JSON. See [`Connection::with_codec`](struct.Connection.html#method.with_codec) for what the
+    /// returned future does.
+    pub async fn connect<T: Transport>(
+        transport: &T,
+        addr: T::Addr,
+    ) -> io::Result<(Connection, impl Future<Output = io::Result<()>> + Send)> {
+        Connection::connect_with_codec(Json, transport, addr).await
+    }
+
+    /// Create a connection over the given framed halves, which encodes messages as JSON. See
+    /// [`Connection::with_codec`](struct.Connection.html#method.with_codec) for what the returned
+    /// future does.
+    pub fn from_frames<R, W>(
+        reader: R,
+        writer: W,
+    ) -> (Connection, impl Future<Output = io::Result<()>> + Send)
+    where
+        R: FrameRead + 'static,
+        W: FrameWrite + 'static,
+    {
+        Connection::with_codec(Json, reader, writer)
+    }
+}
+
+impl<C: Codec> Connection<C> {
+    /// Connect to the node listening at the given address of a transport, encoding messages with
+    /// the given codec. See [`Connection::with_codec`](struct.Connection.html#method.with_codec)
+    /// for what the returned future does.
+    pub async fn connect_with_codec<T: Transport>(
+        codec: C,
+        transport: &T,
+        addr: T::Addr,
+    ) -> io::Result<(Self, impl Future<Output = io::Result<()>> + Send)> {
+        let (reader, writer) = transport.connect(addr).await?;
+        Ok(Connection::with_codec(codec, reader, writer))
+    }
+
+    /// Create a connection over the given framed halves, which encodes messages with the given
+    /// codec. The returned future drives the connection, sending messages and receiving their
+    /// results, and has to be spawned onto an executor for the connection to work. It resolves
+    /// once the other side closes the connection, or once all clones of the connection and
+    /// addresses created from it have been dropped.
+    pub fn with_codec<R, W>(
+        codec: C,
+        reader: R,
+        writer: W,
+    ) -> (Self, impl Future<Output = io::Result<()>> + Send)
+    where
+        R: FrameRead + 'static,
+        W: FrameWrite + 'static,
+    {
+        let (frames, outgoing) = mpsc::unbounded();
+        let connection = Connection {
+            codec: Arc::new(codec),
+            frames,
+            pending: Pending::default(),
+            next_id: Arc::new(AtomicU64::new(0)),
+        };
+
+        let codec = connection.codec.clone();
+        let pending = connection.pending.clone();
+        let driver = async move {
+            let write = async move {
+                let (mut writer, mut outgoing) = (writer, outgoing);
+                while let Some(frame) = outgoing.next().await {
+                    writer.write_frame(&frame).await?;
+                }
+                Ok(())
+            };
+            let read = async {
+                let mut reader = reader;
+                while let Some(frame) = reader.read_frame().await? {
+                    if let (Header::Response { id, error }, payload) = decode_frame(&*codec, frame)?
+                    {
+                        if let Some(tx) = lock(&pending).remove(&id) {
+                            let _ = tx.send(error.map_or(Ok(payload), Err));
+                        }
+                    }
+                }
+                Ok(())
+            };
+
+            let res = match future::select(Box::pin(write), Box::pin(read)).await {
+                Either::Left((res, _)) | Either::Right((res, _)) => res,
+            };
+            // Any requests which are still waiting will never get a response
+            lock(&pending).clear();
+            res
+        };
+
+        (connection, driver)
+    }
+
+    /// Get the address of the actor exposed under `name` on the other side of the connection.
+    /// Whether there is such an actor, and whether it handles the messages sent to it, is only
+    /// checked by the other side once a message is sent.
+    pub fn address<A: Actor>(&self, name: impl Into<String>) -> RemoteAddress<A, C> {
+        RemoteAddress {
+            connection: self.clone(),
+            actor: name.into(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Required because #[derive] adds a C: Clone bound
+impl<C: Codec> Clone for Connection<C> {
+    fn clone(&self) -> Self {
+        Connection {
+            codec: self.codec.clone(),
+            frames: self.frames.clone(),
+            pending: self.pending.clone(),
+            next_id: self.next_id.clone(),
+        }
+    }
+}
+
+/// An address to an actor hosted in another process, through which messages implementing
+/// [`RemoteMessage`](trait.RemoteMessage.html) can be sent. It is created by
+/// [`Connection::address`](struct.Connection.html#method.address), and can be cloned.
+pub struct RemoteAddress<A, C: Codec = Json> {
+    connection: Connection<C>,
+    actor: String,
+    phantom: PhantomData<fn() -> A>,
+}
+
+impl<A: Actor, C: Codec> RemoteAddress<A, C> {
+    /// The name of the actor which this address refers to.
+    pub fn name(&self) -> &str {
+        &self.actor
+    }
+
+    /// Send a message to the remote actor, and wait for its result.
+    pub async fn send<M>(&self, message: M) -> Result<M::Result, RemoteError>
+    where
+        A: Handler<M>,
+        M: RemoteMessage,
+        M::Result: DeserializeOwned,
+    {
+        let id = self.connection.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        lock(&self.connection.pending).insert(id, tx);
+
+        if let Err(err) = self.request(Some(id), message) {
+            lock(&self.connection.pending).remove(&id);
+            return Err(err);
+        }
+
+        match rx.await {
+            Ok(Ok(result)) => self
+                .connection
+                .codec
+                .decode(&result)
+                .map_err(|err| RemoteError::Serialization(err.to_string())),
+            Ok(Err(err)) => Err(RemoteError::Remote(err)),
+            Err(oneshot::Canceled) => Err(RemoteError::Disconnected),
+        }
+    }
+
+    /// Send a message to the remote actor without waiting for it to be delivered or for its
+    /// result. An error is only returned if the message could not be sent at all.
+    pub fn do_send<M>(&self, message: M) -> Result<(), RemoteError>
+    where
+        A: Handler<M>,
+        M: RemoteMessage,
+    {
+        self.request(None, message)
+    }
+
+    fn request<M: RemoteMessage>(&self, id: Option<u64>, message: M) -> Result<(), RemoteError> {
+        let codec = &*self.connection.codec;
+        let serialization = |err: &dyn Display| RemoteError::Serialization(err.to_string());
+        let payload = codec.encode(&message).map_err(|err| serialization(&err))?;
+        let header = Header::Request {
+            id,
+            actor: self.actor.clone(),
+            message: M::NAME.to_string(),
+        };
+        let frame = encode_frame(codec, &header, &payload).map_err(|err| serialization(&err))?;
+
+        self.connection
+            .frames
+            .unbounded_send(frame)
+            .map_err(|_| RemoteError::Disconnected)
+    }
+}
+
+// Required because #[derive] adds A: Clone and C: Clone bounds
+impl<A, C: Codec> Clone for RemoteAddress<A, C> {
+    fn clone(&self) -> Self {
+        RemoteAddress {
+            connection: self.connection.clone(),
+            actor: self.actor.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+
+/// An error which occurred while encoding or decoding a value with a [`Codec`](trait.Codec.html).
+pub type CodecError = Box<dyn Error + Send + Sync>;
+
+/// A way of encoding messages, their results, and the frames they are sent in, chosen per
+/// connection. Both sides of a connection must use the same codec. [`Json`](struct.Json.html) is
+/// provided, and binary formats such as bincode, CBOR, or postcard can be plugged in by
+/// implementing this for them.
+///
+/// ```rust
+/// use serde::{de::DeserializeOwned, Serialize};
+/// use xtra::remote::{Codec, CodecError};
+///
+/// /// Encodes frames as indented JSON, which is easier to read when debugging.
+/// struct PrettyJson;
+///
+/// impl Codec for PrettyJson {
+///     fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+///         Ok(serde_json::to_vec_pretty(value)?)
+///     }
+///
+///     fn decode<T: DeserializeOwned>(&self, buf: &[u8]) -> Result<T, CodecError> {
+///         Ok(serde_json::from_slice(buf)?)
+///     }
+/// }
+/// ```
+pub trait Codec: Send + Sync + 'static {
+    /// Encode a value into bytes.
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError>;
+
+    /// Decode a value from bytes which were encoded by this codec.
+    fn decode<T: DeserializeOwned>(&self, buf: &[u8]) -> Result<T, CodecError>;
+}
+
+/// A [`Codec`](trait.Codec.html) which encodes values as JSON. This is the codec used unless
+/// another is chosen, and is the easiest to interoperate with from other languages and browsers.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Json;
+
+impl Codec for Json {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, buf: &[u8]) -> Result<T, CodecError> {
+        Ok(serde_json::from_slice(buf)?)
+    }
+}
@@ -0,0 +1,291 @@
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::future;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use futures::StreamExt;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// The longest frame which will be read from a byte stream, to protect against a peer making
+/// this process allocate arbitrary amounts of memory.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// The receiving half of a connection made by a [`Transport`](trait.Transport.html), from which
+/// whole frames are read.
+#[async_trait]
+pub trait FrameRead: Send {
+    /// Read the next frame, resolving to `None` once the other side has closed the connection.
+    async fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>>;
+}
+
+/// The sending half of a connection made by a [`Transport`](trait.Transport.html), to which whole
+/// frames are written.
+#[async_trait]
+pub trait FrameWrite: Send {
+    /// Write a frame, so that the other side reads it as one frame.
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()>;
+}
+
+/// A way of making connections between processes, over which
+/// [`Connection`](struct.Connection.html) and [`Node`](struct.Node.html) send their frames. This is
+/// implemented by [`TcpTransport`](struct.TcpTransport.html) and
+/// [`MemoryTransport`](struct.MemoryTransport.html), and can be implemented for other kinds of
+/// connections, such as Unix sockets. Any byte stream can be split into framed halves with
+/// [`length_delimited`](fn.length_delimited.html).
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// The address which is connected to.
+    type Addr: Send + 'static;
+    /// The receiving half of a connection.
+    type Read: FrameRead + 'static;
+    /// The sending half of a connection.
+    type Write: FrameWrite + 'static;
+
+    /// Connect to the process listening at the given address.
+    async fn connect(&self, addr: Self::Addr) -> io::Result<(Self::Read, Self::Write)>;
+
+    /// Wait for another process to connect to this one.
+    async fn accept(&self) -> io::Result<(Self::Read, Self::Write)>;
+}
+
+/// One half of a byte stream, over which each frame is sent prefixed by its length as a big
+/// endian `u32`. It is created by [`length_delimited`](fn.length_delimited.html).
+pub struct LengthDelimited<T>(T);
+
+/// Split a byte stream, such as a TCP stream or a Unix socket, into the framed halves which
+/// [`Connection`](struct.Connection.html) and [`Node`](struct.Node.html) send over.
+pub fn length_delimited<S>(
+    stream: S,
+) -> (LengthDelimited<ReadHalf<S>>, LengthDelimited<WriteHalf<S>>)
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (reader, writer) = stream.split();
+    (LengthDelimited(reader), LengthDelimited(writer))
+}
+
+#[async_trait]
+impl<R: AsyncRead + Unpin + Send> FrameRead for LengthDelimited<R> {
+    async fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut len = [0; 4];
+        match self.0.read_exact(&mut len).await {
+            Ok(()) => {}
+            // The connection was closed between two frames
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+        }
+
+        let mut buf = vec![0; len];
+        self.0.read_exact(&mut buf).await?;
+        Ok(Some(buf))
+    }
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> FrameWrite for LengthDelimited<W> {
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        if frame.len() > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame too long",
+            ));
+        }
+
+        self.0
+            .write_all(&(frame.len() as u32).to_be_bytes())
+            .await?;
+        self.0.write_all(frame).await?;
+        self.0.flush().await
+    }
+}
+
+#[cfg(feature = "with-async_std-1")]
+type TcpListener = async_std::net::TcpListener;
+#[cfg(feature = "with-async_std-1")]
+type TcpStream = async_std::net::TcpStream;
+
+#[cfg(all(feature = "with-smol-0_1", not(feature = "with-async_std-1")))]
+type TcpListener = smol::Async<std::net::TcpListener>;
+#[cfg(all(feature = "with-smol-0_1", not(feature = "with-async_std-1")))]
+type TcpStream = smol::Async<std::net::TcpStream>;
+
+/// A [`Transport`](trait.Transport.html) over TCP. It is created with
+/// [`TcpTransport::bind`](struct.TcpTransport.html#method.bind) to accept connections, or with
+/// [`TcpTransport::default`](struct.TcpTransport.html#impl-Default) to only connect to other
+/// processes. This uses the sockets of async-std or smol, whichever is enabled.
+#[cfg(any(feature = "with-async_std-1", feature = "with-smol-0_1"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "with-async_std-1", feature = "with-smol-0_1")))
+)]
+#[derive(Default)]
+pub struct TcpTransport {
+    listener: Option<TcpListener>,
+}
+
+#[cfg(any(feature = "with-async_std-1", feature = "with-smol-0_1"))]
+impl TcpTransport {
+    /// Create a transport which accepts connections on the given address.
+    pub async fn bind(addr: std::net::SocketAddr) -> io::Result<Self> {
+        #[cfg(feature = "with-async_std-1")]
+        let listener = TcpListener::bind(addr).await?;
+
+        #[cfg(all(feature = "with-smol-0_1", not(feature = "with-async_std-1")))]
+        let listener = TcpListener::bind(addr)?;
+
+        Ok(TcpTransport {
+            listener: Some(listener),
+        })
+    }
+
+    /// The address on which this transport accepts connections, if it was bound to one. This is
+    /// useful to find out which port was assigned when binding to port 0.
+    pub fn local_addr(&self) -> Option<io::Result<std::net::SocketAddr>> {
+        #[cfg(feature = "with-async_std-1")]
+        return self.listener.as_ref().map(|l| l.local_addr());
+
+        #[cfg(all(feature = "with-smol-0_1", not(feature = "with-async_std-1")))]
+        return self.listener.as_ref().map(|l| l.get_ref().local_addr());
+    }
+}
+
+#[cfg(any(feature = "with-async_std-1", feature = "with-smol-0_1"))]
+#[async_trait]
+impl Transport for TcpTransport {
+    type Addr = std::net::SocketAddr;
+    type Read = LengthDelimited<ReadHalf<TcpStream>>;
+    type Write = LengthDelimited<WriteHalf<TcpStream>>;
+
+    async fn connect(&self, addr: Self::Addr) -> io::Result<(Self::Read, Self::Write)> {
+        Ok(length_delimited(TcpStream::connect(addr).await?))
+    }
+
+    async fn accept(&self) -> io::Result<(Self::Read, Self::Write)> {
+        match &self.listener {
+            Some(listener) => Ok(length_delimited(listener.accept().await?.0)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "transport is not bound to an address",
+            )),
+        }
+    }
+}
+
+/// A [`Transport`](trait.Transport.html) which connects within the same process, mainly for
+/// testing. Clones of it connect to each other, and frames are sent over channels rather than
+/// being written to a byte stream.
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use serde::{Deserialize, Serialize};
+/// use xtra::remote::{Connection, MemoryTransport, Node, RemoteMessage};
+///
+/// # struct Counter(u32);
+/// # impl Actor for Counter {}
+/// #[derive(Serialize, Deserialize)]
+/// struct Increment;
+/// # impl Message for Increment {
+/// #     type Result = u32;
+/// # }
+/// # impl RemoteMessage for Increment {
+/// #     const NAME: &'static str = "Increment";
+/// # }
+/// # impl SyncHandler<Increment> for Counter {
+/// #     fn handle(&mut self, _: Increment, _: &mut Context<Self>) -> u32 {
+/// #         self.0 += 1;
+/// #         self.0
+/// #     }
+/// # }
+///
+/// #[smol_potat::main]
+/// async fn main() -> std::io::Result<()> {
+///     let transport = MemoryTransport::new();
+///     let node = Node::new().expose::<Counter, Increment>("counter", Counter(0).spawn());
+///     let listener = transport.clone();
+///     smol::Task::spawn(async move { node.listen(&listener).await.unwrap() }).detach();
+///
+///     let (connection, driver) = Connection::connect(&transport, ()).await?;
+///     smol::Task::spawn(async move { driver.await.unwrap() }).detach();
+///
+///     let counter = connection.address::<Counter>("counter");
+///     assert_eq!(counter.send(Increment).await, Ok(1));
+///     assert_eq!(counter.send(Increment).await, Ok(2));
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MemoryTransport {
+    connect: mpsc::UnboundedSender<(MemoryRead, MemoryWrite)>,
+    accept: Arc<Mutex<mpsc::UnboundedReceiver<(MemoryRead, MemoryWrite)>>>,
+}
+
+impl MemoryTransport {
+    /// Create a transport which is not yet connected to anything.
+    pub fn new() -> Self {
+        let (connect, accept) = mpsc::unbounded();
+        MemoryTransport {
+            connect,
+            accept: Arc::new(Mutex::new(accept)),
+        }
+    }
+}
+
+impl Default for MemoryTransport {
+    fn default() -> Self {
+        MemoryTransport::new()
+    }
+}
+
+#[async_trait]
+impl Transport for MemoryTransport {
+    type Addr = ();
+    type Read = MemoryRead;
+    type Write = MemoryWrite;
+
+    async fn connect(&self, _: ()) -> io::Result<(MemoryRead, MemoryWrite)> {
+        let (client_tx, server_rx) = mpsc::unbounded();
+        let (server_tx, client_rx) = mpsc::unbounded();
+        self.connect
+            .unbounded_send((MemoryRead(server_rx), MemoryWrite(server_tx)))
+            .map_err(|_| io::Error::from(io::ErrorKind::ConnectionRefused))?;
+        Ok((MemoryRead(client_rx), MemoryWrite(client_tx)))
+    }
+
+    async fn accept(&self) -> io::Result<(MemoryRead, MemoryWrite)> {
+        // The receiver only holds one waker, so this should only be awaited in one task at a time
+        let next = future::poll_fn(|cx| {
+            let mut accept = self.accept.lock().unwrap_or_else(|e| e.into_inner());
+            accept.poll_next_unpin(cx)
+        });
+
+        // This transport holds a sender, so the channel can never be closed
+        Ok(next.await.expect("memory transport channel closed"))
+    }
+}
+
+/// The receiving half of a connection made by a [`MemoryTransport`](struct.MemoryTransport.html).
+pub struct MemoryRead(mpsc::UnboundedReceiver<Vec<u8>>);
+
+#[async_trait]
+impl FrameRead for MemoryRead {
+    async fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.0.next().await)
+    }
+}
+
+/// The sending half of a connection made by a [`MemoryTransport`](struct.MemoryTransport.html).
+pub struct MemoryWrite(mpsc::UnboundedSender<Vec<u8>>);
+
+#[async_trait]
+impl FrameWrite for MemoryWrite {
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.0
+            .unbounded_send(frame.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
@@ -0,0 +1,270 @@
+use super::{FrameRead, FrameWrite, Transport};
+use async_trait::async_trait;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use std::io;
+use std::pin::Pin;
+
+/// The receiving half of a connection made by a
+/// [`WebSocketTransport`](struct.WebSocketTransport.html). Each binary or text message is read as
+/// one frame.
+pub struct WebSocketRead(Pin<Box<dyn Stream<Item = io::Result<Vec<u8>>> + Send>>);
+
+#[async_trait]
+impl FrameRead for WebSocketRead {
+    async fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.0.next().await.transpose()
+    }
+}
+
+/// The sending half of a connection made by a
+/// [`WebSocketTransport`](struct.WebSocketTransport.html). Each frame is written as one binary
+/// message.
+pub struct WebSocketWrite(Pin<Box<dyn Sink<Vec<u8>, Error = io::Error> + Send>>);
+
+#[async_trait]
+impl FrameWrite for WebSocketWrite {
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.0.send(frame.to_vec()).await
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::WebSocketTransport;
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::WebSocketTransport;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::*;
+    use futures::future;
+    use std::net::SocketAddr;
+    use tokio::net::TcpListener;
+    use tokio::sync::Mutex;
+    use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+    use tokio_tungstenite::WebSocketStream;
+
+    fn to_io(err: WsError) -> io::Error {
+        match err {
+            WsError::Io(err) => err,
+            err => io::Error::other(err),
+        }
+    }
+
+    fn split<S>(stream: WebSocketStream<S>) -> (WebSocketRead, WebSocketWrite)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    {
+        let (sink, stream) = stream.split();
+        let stream = stream.filter_map(|msg| {
+            future::ready(match msg {
+                Ok(Message::Binary(buf)) => Some(Ok(buf)),
+                Ok(Message::Text(text)) => Some(Ok(text.into_bytes())),
+                // Control messages are answered by tungstenite, and the stream ends after closing
+                Ok(_) | Err(WsError::ConnectionClosed) => None,
+                Err(err) => Some(Err(to_io(err))),
+            })
+        });
+        let sink = sink
+            .sink_map_err(to_io)
+            .with(|buf| future::ready(Ok(Message::Binary(buf))));
+
+        (
+            WebSocketRead(Box::pin(stream)),
+            WebSocketWrite(Box::pin(sink)),
+        )
+    }
+
+    /// A [`Transport`](trait.Transport.html) over WebSockets, through which a browser can send
+    /// messages to actors in a server process and the other way around. It is created with
+    /// [`WebSocketTransport::bind`](struct.WebSocketTransport.html#method.bind) to accept
+    /// connections, or with
+    /// [`WebSocketTransport::default`](struct.WebSocketTransport.html#impl-Default) to only connect
+    /// to other processes, which are addressed by URL, such as `ws://127.0.0.1:8080`.
+    ///
+    /// Outside of the browser, this uses [`tokio-tungstenite`](https://docs.rs/tokio-tungstenite),
+    /// and so requires the `with-tokio-0_2` feature and a tokio runtime. In the browser, it uses
+    /// the browser's `WebSocket` API, and can only connect.
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    #[derive(Default)]
+    pub struct WebSocketTransport {
+        listener: Option<(Mutex<TcpListener>, SocketAddr)>,
+    }
+
+    impl WebSocketTransport {
+        /// Create a transport which accepts WebSocket connections on the given address.
+        pub async fn bind(addr: SocketAddr) -> io::Result<Self> {
+            let listener = TcpListener::bind(addr).await?;
+            let local_addr = listener.local_addr()?;
+            Ok(WebSocketTransport {
+                listener: Some((Mutex::new(listener), local_addr)),
+            })
+        }
+
+        /// The address on which this transport accepts connections, if it was bound to one. This
+        /// is useful to find out which port was assigned when binding to port 0.
+        pub fn local_addr(&self) -> Option<SocketAddr> {
+            self.listener.as_ref().map(|(_, addr)| *addr)
+        }
+    }
+
+    #[async_trait]
+    impl Transport for WebSocketTransport {
+        type Addr = String;
+        type Read = WebSocketRead;
+        type Write = WebSocketWrite;
+
+        async fn connect(&self, url: String) -> io::Result<(WebSocketRead, WebSocketWrite)> {
+            let (stream, _) = tokio_tungstenite::connect_async(url).await.map_err(to_io)?;
+            Ok(split(stream))
+        }
+
+        async fn accept(&self) -> io::Result<(WebSocketRead, WebSocketWrite)> {
+            let listener = match &self.listener {
+                Some((listener, _)) => listener,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotConnected,
+                        "transport is not bound to an address",
+                    ))
+                }
+            };
+
+            let (stream, _) = listener.lock().await.accept().await?;
+            let stream = tokio_tungstenite::accept_async(stream)
+                .await
+                .map_err(to_io)?;
+            Ok(split(stream))
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use super::*;
+    use futures::channel::{mpsc, oneshot};
+    use js_sys::{ArrayBuffer, Uint8Array};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::{BinaryType, Event, MessageEvent, WebSocket};
+
+    type Opened = Rc<RefCell<Option<oneshot::Sender<io::Result<()>>>>>;
+
+    fn to_io(err: JsValue) -> io::Error {
+        let msg = err.as_string().unwrap_or_else(|| format!("{:?}", err));
+        io::Error::other(msg)
+    }
+
+    /// A [`Transport`](trait.Transport.html) over WebSockets, through which a browser can send
+    /// messages to actors in a server process and the other way around. Other processes are
+    /// addressed by URL, such as `ws://127.0.0.1:8080`.
+    ///
+    /// Outside of the browser, this uses [`tokio-tungstenite`](https://docs.rs/tokio-tungstenite),
+    /// and so requires the `with-tokio-0_2` feature and a tokio runtime. In the browser, it uses
+    /// the browser's `WebSocket` API, and can only connect.
+    #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
+    #[derive(Default)]
+    pub struct WebSocketTransport;
+
+    #[async_trait]
+    impl Transport for WebSocketTransport {
+        type Addr = String;
+        type Read = WebSocketRead;
+        type Write = WebSocketWrite;
+
+        async fn connect(&self, url: String) -> io::Result<(WebSocketRead, WebSocketWrite)> {
+            let (open_tx, open_rx) = oneshot::channel();
+            let (incoming_tx, incoming_rx) = mpsc::unbounded();
+            let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded::<Vec<u8>>();
+
+            {
+                let ws = WebSocket::new(&url).map_err(to_io)?;
+                ws.set_binary_type(BinaryType::Arraybuffer);
+
+                let opened = Rc::new(RefCell::new(Some(open_tx)));
+
+                let on_open_opened = opened.clone();
+                let on_open = Closure::wrap(Box::new(move |_: Event| {
+                    open(&on_open_opened, Ok(()));
+                }) as Box<dyn FnMut(Event)>);
+
+                let on_error_opened = opened.clone();
+                let tx = incoming_tx.clone();
+                let on_error = Closure::wrap(Box::new(move |_: Event| {
+                    let refused =
+                        io::Error::new(io::ErrorKind::ConnectionRefused, "WebSocket error");
+                    if !open(&on_error_opened, Err(refused)) {
+                        let err = io::Error::other("WebSocket error");
+                        let _ = tx.unbounded_send(Err(err));
+                    }
+                }) as Box<dyn FnMut(Event)>);
+
+                let tx = incoming_tx.clone();
+                let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+                    let data = event.data();
+                    let frame = match data.dyn_ref::<ArrayBuffer>() {
+                        Some(buf) => Uint8Array::new(buf).to_vec(),
+                        None => data.as_string().unwrap_or_default().into_bytes(),
+                    };
+                    let _ = tx.unbounded_send(Ok(frame));
+                }) as Box<dyn FnMut(MessageEvent)>);
+
+                let on_close = Closure::wrap(Box::new(move |_: Event| {
+                    let refused =
+                        io::Error::new(io::ErrorKind::ConnectionRefused, "WebSocket closed");
+                    open(&opened, Err(refused));
+                    incoming_tx.close_channel();
+                }) as Box<dyn FnMut(Event)>);
+
+                ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+                ws.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+                ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+                ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+                // The socket and its callbacks are not Send, so they are owned by a local task
+                // which writes the outgoing frames until the writing half is dropped
+                wasm_bindgen_futures::spawn_local(async move {
+                    let _callbacks = (on_open, on_error, on_message, on_close);
+                    while let Some(frame) = outgoing_rx.next().await {
+                        if ws.send_with_u8_array(&frame).is_err() {
+                            break;
+                        }
+                    }
+                    let _ = ws.close();
+                });
+            }
+
+            match open_rx.await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => return Err(err),
+                Err(oneshot::Canceled) => return Err(io::ErrorKind::ConnectionRefused.into()),
+            }
+
+            let sink = outgoing_tx.sink_map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe));
+            Ok((
+                WebSocketRead(Box::pin(incoming_rx)),
+                WebSocketWrite(Box::pin(sink)),
+            ))
+        }
+
+        async fn accept(&self) -> io::Result<(WebSocketRead, WebSocketWrite)> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "browsers cannot accept WebSocket connections",
+            ))
+        }
+    }
+
+    /// Tell the task connecting whether the socket opened, if it has not already been told.
+    fn open(opened: &Opened, result: io::Result<()>) -> bool {
+        match opened.borrow_mut().take() {
+            Some(tx) => {
+                let _ = tx.send(result);
+                true
+            }
+            None => false,
+        }
+    }
+}
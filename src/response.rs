@@ -0,0 +1,174 @@
+//! The channel over which the result of a message is sent back to the address waiting on it. We
+//! use our own rather than `futures::channel::oneshot`, so that the context can tell whether the
+//! result is still being waited for through a weak reference to the channel itself, rather than
+//! having to allocate a separate handle for every message. Once both halves are dropped, the
+//! memory of the channel is recycled for the next one (see `pool`).
+
+use crate::envelope::Request;
+use crate::pool;
+use futures::task::{Context, Poll, Waker};
+use futures::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// Create a new channel for one result.
+pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = pool::arc(Shared {
+        inner: Mutex::new(Inner {
+            value: None,
+            receiver_waker: None,
+        }),
+        complete: AtomicBool::new(false),
+        canceled: AtomicBool::new(false),
+        duplicate: AtomicBool::new(false),
+        timed_out: AtomicBool::new(false),
+    });
+
+    (Sender(Some(shared.clone())), Receiver(Some(shared)))
+}
+
+/// The state shared by the halves of a channel, which is only taken out of them once dropped.
+fn shared<T>(shared: &Option<Arc<Shared<T>>>) -> &Shared<T> {
+    shared
+        .as_ref()
+        .expect("the channel is only taken out once dropped")
+}
+
+struct Shared<T> {
+    inner: Mutex<Inner<T>>,
+    /// Set once the sender has been dropped, whether or not it sent a result
+    complete: AtomicBool,
+    /// Set once the receiver has been dropped
+    canceled: AtomicBool,
+    /// Set if the message was not handled because the actor had already handled it
+    duplicate: AtomicBool,
+    /// Set if the handler of the message was dropped because it ran for too long
+    timed_out: AtomicBool,
+}
+
+struct Inner<T> {
+    value: Option<T>,
+    receiver_waker: Option<Waker>,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        // Nothing can panic while the lock is held, so poisoning can safely be ignored
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T: Send> Request for Shared<T> {
+    fn is_cancelled(&self) -> bool {
+        // Once the result has been sent, the request it belonged to is over
+        self.canceled.load(Ordering::Acquire) && !self.complete.load(Ordering::Acquire)
+    }
+
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    fn time_out(&self) {
+        self.timed_out.store(true, Ordering::Release);
+    }
+}
+
+/// The error returned by a `Receiver` if its sender was dropped without sending a result.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Canceled {
+    /// The message was dropped before it was handled, or while it was being handled
+    Dropped,
+    /// The message was not handled since the actor had already handled one with the same id
+    Duplicate,
+    /// The handler of the message was dropped since it ran for longer than the handler timeout
+    TimedOut,
+}
+
+/// The sending half of a result channel, which is held by the envelope of the message.
+pub(crate) struct Sender<T>(Option<Arc<Shared<T>>>);
+
+impl<T: Send + 'static> Sender<T> {
+    /// Send the result, handing it back if the receiver has been dropped.
+    pub(crate) fn send(self, value: T) -> Result<(), T> {
+        if self.is_canceled() {
+            return Err(value);
+        }
+        shared(&self.0).lock().value = Some(value);
+        Ok(())
+    }
+
+    /// Whether the receiver has been dropped, so that the result is no longer waited for.
+    pub(crate) fn is_canceled(&self) -> bool {
+        shared(&self.0).canceled.load(Ordering::Acquire)
+    }
+
+    /// Drop the sender without a result because the message is a duplicate.
+    pub(crate) fn reject_duplicate(self) {
+        shared(&self.0).duplicate.store(true, Ordering::Release);
+    }
+
+    /// A weak reference to this request, through which the context can tell whether it has been
+    /// cancelled while the message is handled.
+    pub(crate) fn request(&self) -> Weak<dyn Request> {
+        Arc::downgrade(
+            self.0
+                .as_ref()
+                .expect("the channel is only taken out once dropped"),
+        ) as Weak<dyn Request>
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // Set while locked, so that the receiver either sees it or has registered its waker
+        let mut inner = shared(&self.0).lock();
+        shared(&self.0).complete.store(true, Ordering::Release);
+        let waker = inner.receiver_waker.take();
+        drop(inner);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        if let Some(shared) = self.0.take() {
+            pool::recycle_arc(shared);
+        }
+    }
+}
+
+/// The receiving half of a result channel, which is held by the response future of the message.
+pub(crate) struct Receiver<T>(Option<Arc<Shared<T>>>);
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, Canceled>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = shared(&self.0).lock();
+        if let Some(value) = inner.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+
+        if shared(&self.0).duplicate.load(Ordering::Acquire) {
+            Poll::Ready(Err(Canceled::Duplicate))
+        } else if shared(&self.0).timed_out.load(Ordering::Acquire) {
+            Poll::Ready(Err(Canceled::TimedOut))
+        } else if shared(&self.0).complete.load(Ordering::Acquire) {
+            Poll::Ready(Err(Canceled::Dropped))
+        } else {
+            inner.receiver_waker = Some(ctx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        shared(&self.0).canceled.store(true, Ordering::Release);
+        if let Some(shared) = self.0.take() {
+            pool::recycle_arc(shared);
+        }
+    }
+}
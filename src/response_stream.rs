@@ -0,0 +1,93 @@
+use futures::channel::mpsc;
+use futures::future::BoxFuture;
+use futures::task::{Context, Poll};
+use futures::{FutureExt, Stream, StreamExt};
+use std::pin::Pin;
+
+/// A stream of items sent by the handler of a message, for subscription-style requests such as
+/// tailing a log or watching a value. A handler responds with one by setting the `Result` of the
+/// message to `ResponseStream<T>`, and creating it along with the
+/// [`Yielder`](struct.Yielder.html) which sends its items with
+/// [`Context::yielder`](struct.Context.html#method.yielder). The stream ends once all yielders for
+/// it have been dropped, such as when the actor stops.
+///
+/// [`AddressExt::send_stream`](trait.AddressExt.html#method.send_stream) sends such a message and
+/// returns the stream right away, which ends without any items if the message could not be handled.
+pub struct ResponseStream<T> {
+    inner: ResponseStreamInner<T>,
+}
+
+enum ResponseStreamInner<T> {
+    /// Waiting for the handler to respond with the stream
+    Pending(BoxFuture<'static, Option<ResponseStream<T>>>),
+    Receiving(mpsc::UnboundedReceiver<T>),
+    Done,
+}
+
+impl<T: Send + 'static> ResponseStream<T> {
+    /// Create a stream along with the yielder which sends its items.
+    pub(crate) fn channel() -> (Yielder<T>, Self) {
+        let (tx, rx) = mpsc::unbounded();
+        let stream = ResponseStream {
+            inner: ResponseStreamInner::Receiving(rx),
+        };
+        (Yielder { sender: tx }, stream)
+    }
+
+    /// The stream which the future resolves to, or an empty stream if it resolves to `None`.
+    pub(crate) fn pending<F>(response: F) -> Self
+    where
+        F: futures::Future<Output = Option<ResponseStream<T>>> + Send + 'static,
+    {
+        ResponseStream {
+            inner: ResponseStreamInner::Pending(response.boxed()),
+        }
+    }
+}
+
+impl<T> Stream for ResponseStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            match &mut self.inner {
+                ResponseStreamInner::Pending(response) => match response.poll_unpin(ctx) {
+                    Poll::Ready(Some(stream)) => self.inner = stream.inner,
+                    Poll::Ready(None) => self.inner = ResponseStreamInner::Done,
+                    Poll::Pending => return Poll::Pending,
+                },
+                ResponseStreamInner::Receiving(rx) => return rx.poll_next_unpin(ctx),
+                ResponseStreamInner::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Sends the items of a [`ResponseStream`](struct.ResponseStream.html) from the handler which
+/// responded with it, and is created with [`Context::yielder`](struct.Context.html#method.yielder).
+/// It can be cloned, and can be kept by the actor or moved into a task to keep sending items after
+/// the handler has returned.
+pub struct Yielder<T> {
+    sender: mpsc::UnboundedSender<T>,
+}
+
+impl<T> Yielder<T> {
+    /// Send an item to the stream, returning whether it is still being received. If the stream was
+    /// dropped, the item is discarded and the yielder can be dropped too.
+    pub fn send(&self, item: T) -> bool {
+        self.sender.unbounded_send(item).is_ok()
+    }
+
+    /// Returns whether the stream was dropped, so that no more items will be received.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+impl<T> Clone for Yielder<T> {
+    fn clone(&self) -> Self {
+        Yielder {
+            sender: self.sender.clone(),
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use crate::address::{DoSendFuture, MessageResponseFuture};
+use crate::{
+    Actor, ActorManager, Address, AddressExt, DisconnectReason, Handler, Message, TrySendError,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A pool of actors of the same type, which distributes the messages sent through it round-robin
+/// among them. This is useful to parallelize work across several stateless workers. Workers which
+/// have stopped are skipped. The pool can be cloned cheaply, with all clones sharing the same
+/// round-robin position.
+///
+/// Like [`Address`](struct.Address.html), a pool holds strong addresses to its workers, so they are
+/// stopped once all clones of the pool (and any other addresses to them) have been dropped.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::ActorPool;
+/// struct Worker(usize);
+/// impl Actor for Worker {}
+///
+/// struct WhoAreYou;
+/// impl Message for WhoAreYou {
+///     type Result = usize;
+/// }
+///
+/// impl SyncHandler<WhoAreYou> for Worker {
+///     fn handle(&mut self, _: WhoAreYou, _: &mut Context<Self>) -> usize {
+///         self.0
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let mut id = 0;
+///     let pool = ActorPool::spawn(3, || {
+///         id += 1;
+///         Worker(id)
+///     });
+///
+///     for expected in [1, 2, 3, 1] {
+///         assert_eq!(pool.send(WhoAreYou).await, Ok(expected));
+///     }
+/// }
+/// ```
+pub struct ActorPool<A: Actor> {
+    workers: Arc<[Address<A>]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl<A: Actor> ActorPool<A> {
+    /// Create a pool from the addresses of already running workers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is empty.
+    pub fn new(workers: Vec<Address<A>>) -> Self {
+        assert!(
+            !workers.is_empty(),
+            "an actor pool needs at least one worker"
+        );
+        ActorPool {
+            workers: workers.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Create `size` workers with `factory`, returning the pool and the workers' managers. Like
+    /// with [`Actor::create`](trait.Actor.html#method.create), the managers have to be managed for
+    /// the workers to actually start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn create<F>(size: usize, mut factory: F) -> (Self, Vec<ActorManager<A>>)
+    where
+        F: FnMut() -> A,
+    {
+        let (workers, managers) = (0..size).map(|_| factory().create()).unzip();
+        (ActorPool::new(workers), managers)
+    }
+
+    /// Create and spawn `size` workers with `factory` (see
+    /// [`Actor::spawn`](trait.Actor.html#method.spawn)), returning the pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn spawn<F>(size: usize, mut factory: F) -> Self
+    where
+        F: FnMut() -> A,
+    {
+        ActorPool::new((0..size).map(|_| factory().spawn()).collect())
+    }
+
+    /// The addresses of the workers in this pool.
+    pub fn workers(&self) -> &[Address<A>] {
+        &self.workers
+    }
+
+    /// Returns whether any of the workers in this pool are still running and accepting messages.
+    pub fn is_connected(&self) -> bool {
+        self.workers.iter().any(AddressExt::is_connected)
+    }
+
+    /// Pick the next worker which is still connected, if any.
+    fn next_worker(&self) -> Option<&Address<A>> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.workers.len())
+            .map(|offset| &self.workers[start.wrapping_add(offset) % self.workers.len()])
+            .find(|worker| worker.is_connected())
+    }
+
+    /// Sends a [`Message`](trait.Message.html) to the next worker, and does not wait for a
+    /// response. See [`AddressExt::do_send`](trait.AddressExt.html#method.do_send).
+    pub fn do_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        match self.next_worker() {
+            Some(worker) => worker.do_send(message),
+            None => Err(TrySendError::Disconnected(
+                message,
+                DisconnectReason::Stopped,
+            )),
+        }
+    }
+
+    /// Sends a [`Message`](trait.Message.html) to the next worker, and does not wait for a
+    /// response, but does wait for space in its mailbox. See
+    /// [`AddressExt::do_send_async`](trait.AddressExt.html#method.do_send_async).
+    pub fn do_send_async<M>(&self, message: M) -> DoSendFuture
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        match self.next_worker() {
+            Some(worker) => worker.do_send_async(message),
+            None => DoSendFuture::disconnected(DisconnectReason::Stopped),
+        }
+    }
+
+    /// Sends a [`Message`](trait.Message.html) to the next worker, and waits for a response. See
+    /// [`AddressExt::send`](trait.AddressExt.html#method.send).
+    pub fn send<M>(&self, message: M) -> MessageResponseFuture<M>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        match self.next_worker() {
+            Some(worker) => worker.send(message),
+            None => MessageResponseFuture::disconnected(DisconnectReason::Stopped),
+        }
+    }
+}
+
+// Required because #[derive] adds an A: Clone bound
+impl<A: Actor> Clone for ActorPool<A> {
+    fn clone(&self) -> Self {
+        ActorPool {
+            workers: self.workers.clone(),
+            next: self.next.clone(),
+        }
+    }
+}
+
+/// A message which can be routed by a [`HashRouter`](struct.HashRouter.html), according to the key
+/// it returns.
+pub trait KeyedMessage: Message {
+    /// The type of the key by which the message is routed
+    type Key: Hash + ?Sized;
+
+    /// The key by which the message is routed. All messages with equal keys are handled by the
+    /// same worker.
+    fn key(&self) -> &Self::Key;
+}
+
+/// A router which picks the worker to send a message to by hashing the message's
+/// [key](trait.KeyedMessage.html#tymethod.key). Since all messages with equal keys are sent to the
+/// same worker, they are handled in the order in which they were sent, which a round-robin
+/// [`ActorPool`](struct.ActorPool.html) does not guarantee. Keys are assigned to workers with a
+/// consistent hash, so that routers over a different number of workers (e.g after scaling up)
+/// move as few keys as possible to a different worker.
+///
+/// Unlike [`ActorPool`](struct.ActorPool.html), the router does not skip workers which have
+/// stopped, since that would break the ordering guarantee. Sending a message whose key maps to a
+/// stopped worker returns [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected).
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::{HashRouter, KeyedMessage};
+/// # use std::collections::HashMap;
+/// #[derive(Default)]
+/// struct Account(HashMap<String, i64>);
+/// impl Actor for Account {}
+///
+/// struct Deposit {
+///     user: String,
+///     amount: i64,
+/// }
+///
+/// impl Message for Deposit {
+///     type Result = i64;
+/// }
+///
+/// impl KeyedMessage for Deposit {
+///     type Key = str;
+///
+///     fn key(&self) -> &str {
+///         &self.user
+///     }
+/// }
+///
+/// impl SyncHandler<Deposit> for Account {
+///     fn handle(&mut self, deposit: Deposit, _: &mut Context<Self>) -> i64 {
+///         let balance = self.0.entry(deposit.user).or_default();
+///         *balance += deposit.amount;
+///         *balance
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let router = HashRouter::spawn(4, Account::default);
+///
+///     // Both deposits for alice are handled by the same worker
+///     let deposit = |user: &str, amount| Deposit { user: user.to_string(), amount };
+///     assert_eq!(router.send(deposit("alice", 10)).await, Ok(10));
+///     assert_eq!(router.send(deposit("bob", 5)).await, Ok(5));
+///     assert_eq!(router.send(deposit("alice", 20)).await, Ok(30));
+/// }
+/// ```
+pub struct HashRouter<A: Actor> {
+    workers: Arc<[Address<A>]>,
+}
+
+impl<A: Actor> HashRouter<A> {
+    /// Create a router from the addresses of already running workers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is empty.
+    pub fn new(workers: Vec<Address<A>>) -> Self {
+        assert!(!workers.is_empty(), "a router needs at least one worker");
+        HashRouter {
+            workers: workers.into(),
+        }
+    }
+
+    /// Create `size` workers with `factory`, returning the router and the workers' managers. Like
+    /// with [`Actor::create`](trait.Actor.html#method.create), the managers have to be managed for
+    /// the workers to actually start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn create<F>(size: usize, mut factory: F) -> (Self, Vec<ActorManager<A>>)
+    where
+        F: FnMut() -> A,
+    {
+        let (workers, managers) = (0..size).map(|_| factory().create()).unzip();
+        (HashRouter::new(workers), managers)
+    }
+
+    /// Create and spawn `size` workers with `factory` (see
+    /// [`Actor::spawn`](trait.Actor.html#method.spawn)), returning the router.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn spawn<F>(size: usize, mut factory: F) -> Self
+    where
+        F: FnMut() -> A,
+    {
+        HashRouter::new((0..size).map(|_| factory().spawn()).collect())
+    }
+
+    /// The addresses of the workers in this router.
+    pub fn workers(&self) -> &[Address<A>] {
+        &self.workers
+    }
+
+    /// Returns whether all of the workers in this router are still running and accepting messages.
+    pub fn is_connected(&self) -> bool {
+        self.workers.iter().all(AddressExt::is_connected)
+    }
+
+    /// Get the worker which handles messages with the given key.
+    pub fn worker_for<K: Hash + ?Sized>(&self, key: &K) -> &Address<A> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.workers[jump_consistent_hash(hasher.finish(), self.workers.len())]
+    }
+
+    /// Sends a [`Message`](trait.Message.html) to the worker for its key, and does not wait for a
+    /// response. See [`AddressExt::do_send`](trait.AddressExt.html#method.do_send).
+    pub fn do_send<M>(&self, message: M) -> Result<(), TrySendError<M>>
+    where
+        M: KeyedMessage,
+        A: Handler<M>,
+    {
+        self.worker_for(message.key()).do_send(message)
+    }
+
+    /// Sends a [`Message`](trait.Message.html) to the worker for its key, and does not wait for a
+    /// response, but does wait for space in its mailbox. See
+    /// [`AddressExt::do_send_async`](trait.AddressExt.html#method.do_send_async).
+    pub fn do_send_async<M>(&self, message: M) -> DoSendFuture
+    where
+        M: KeyedMessage,
+        A: Handler<M>,
+    {
+        self.worker_for(message.key()).do_send_async(message)
+    }
+
+    /// Sends a [`Message`](trait.Message.html) to the worker for its key, and waits for a
+    /// response. See [`AddressExt::send`](trait.AddressExt.html#method.send).
+    pub fn send<M>(&self, message: M) -> MessageResponseFuture<M>
+    where
+        M: KeyedMessage,
+        A: Handler<M>,
+    {
+        self.worker_for(message.key()).send(message)
+    }
+}
+
+// Required because #[derive] adds an A: Clone bound
+impl<A: Actor> Clone for HashRouter<A> {
+    fn clone(&self) -> Self {
+        HashRouter {
+            workers: self.workers.clone(),
+        }
+    }
+}
+
+/// Map a hash to one of `buckets` buckets, such that increasing the number of buckets from `n` to
+/// `n + 1` only moves `1 / (n + 1)` of the hashes to a different bucket. This is the jump consistent
+/// hash by Lamping and Veach (<https://arxiv.org/abs/1406.2294>).
+fn jump_consistent_hash(mut hash: u64, buckets: usize) -> usize {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < buckets as i64 {
+        bucket = next;
+        hash = hash.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((hash >> 33) + 1) as f64)) as i64;
+    }
+    bucket as usize
+}
@@ -0,0 +1,132 @@
+//! Glue for the runtimes supported out of the box. If more than one runtime feature is enabled
+//! (which can happen through feature unification), the first one enabled out of tokio, async-std,
+//! smol, and wasm-bindgen is used.
+
+use futures::Future;
+use std::time::Duration;
+
+pub(crate) mod timer;
+
+/// Spawn a future onto the global executor of the enabled runtime.
+#[allow(unused_variables)] // When building docs without any runtime enabled
+pub(crate) fn spawn<F>(fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "with-tokio-0_2")]
+    tokio::spawn(fut);
+
+    #[cfg(all(feature = "with-async_std-1", not(feature = "with-tokio-0_2")))]
+    async_std::task::spawn(fut);
+
+    #[cfg(all(
+        feature = "with-smol-0_1",
+        not(any(feature = "with-tokio-0_2", feature = "with-async_std-1"))
+    ))]
+    smol::Task::spawn(fut).detach();
+
+    #[cfg(all(
+        feature = "with-wasm_bindgen-0_2",
+        not(any(
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-smol-0_1"
+        ))
+    ))]
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Spawn a future which is not `Send` onto the current thread with the enabled runtime.
+#[cfg(feature = "local")]
+#[allow(unused_variables)] // When building docs without any runtime enabled
+pub(crate) fn spawn_local<F>(fut: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    #[cfg(feature = "with-tokio-0_2")]
+    tokio::task::spawn_local(fut);
+
+    #[cfg(all(feature = "with-async_std-1", not(feature = "with-tokio-0_2")))]
+    async_std::task::spawn_local(fut);
+
+    #[cfg(all(
+        feature = "with-smol-0_1",
+        not(any(feature = "with-tokio-0_2", feature = "with-async_std-1"))
+    ))]
+    smol::Task::local(fut).detach();
+
+    #[cfg(all(
+        feature = "with-wasm_bindgen-0_2",
+        not(any(
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-smol-0_1"
+        ))
+    ))]
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Wait for the given duration to elapse using the timer of the enabled runtime.
+#[allow(unused_variables)] // When building docs without any runtime enabled
+pub(crate) async fn delay(duration: Duration) {
+    #[cfg(feature = "testing")]
+    if let Some(clock) = crate::testing::clock::installed() {
+        return clock.sleep(duration).await;
+    }
+
+    #[cfg(feature = "with-tokio-0_2")]
+    tokio::time::delay_for(duration).await;
+
+    #[cfg(all(feature = "with-async_std-1", not(feature = "with-tokio-0_2")))]
+    async_std::task::sleep(duration).await;
+
+    #[cfg(all(
+        feature = "with-smol-0_1",
+        not(any(feature = "with-tokio-0_2", feature = "with-async_std-1"))
+    ))]
+    smol::Timer::after(duration).await;
+
+    #[cfg(all(
+        feature = "with-wasm_bindgen-0_2",
+        not(any(
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-smol-0_1"
+        ))
+    ))]
+    futures_timer::Delay::new(duration).await;
+}
+
+/// Run a blocking function on the blocking thread pool of the enabled runtime, resolving to its
+/// result. If the function panics, the panic is resumed in the task awaiting it. Since there are no
+/// threads to block on wasm, the function is run on the current thread there instead.
+pub(crate) async fn spawn_blocking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    #[cfg(feature = "with-tokio-0_2")]
+    let result = match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    };
+
+    #[cfg(all(feature = "with-async_std-1", not(feature = "with-tokio-0_2")))]
+    let result = async_std::task::spawn_blocking(f).await;
+
+    #[cfg(all(
+        feature = "with-smol-0_1",
+        not(any(feature = "with-tokio-0_2", feature = "with-async_std-1"))
+    ))]
+    let result = smol::Task::blocking(async move { f() }).await;
+
+    // Also used when building docs without any runtime enabled
+    #[cfg(not(any(
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-smol-0_1"
+    )))]
+    let result = f();
+
+    result
+}
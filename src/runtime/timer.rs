@@ -0,0 +1,225 @@
+//! A hashed timer wheel for the delayed and periodic notifications of actors. Rather than each
+//! notification waiting on a timer of its own in a task of its own, all of them are kept in the
+//! slots of a single wheel, hashed by the tick at which they are due. One driver task advances the
+//! wheel while any notifications are pending, so that scheduling and cancelling a notification is
+//! only a push into a vector, however many of them there are. If the driver task is dropped, such
+//! as because its runtime was shut down, the next notification scheduled spawns a new one.
+
+use crate::clock::Timestamp;
+use futures::channel::oneshot;
+use futures::future::{self, Either};
+use futures::Future;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// The resolution of the wheel. Notifications are never handled early, but can be up to a tick late.
+const TICK: Duration = Duration::from_millis(1);
+
+/// The number of slots in the wheel, i.e how many ticks one turn of it takes
+const SLOTS: usize = 512;
+
+static WHEEL: Mutex<Option<Wheel>> = Mutex::new(None);
+
+fn wheel() -> MutexGuard<'static, Option<Wheel>> {
+    // The timers are fired after the lock is released, so poisoning can safely be ignored
+    WHEEL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+struct Timer {
+    /// The tick at which the timer fires
+    deadline: u64,
+    /// Timers whose notification was cancelled are dropped without firing
+    cancelled: Arc<AtomicBool>,
+    fire: Box<dyn FnOnce() + Send>,
+}
+
+struct Wheel {
+    /// When the wheel was created, from which ticks are counted
+    start: Timestamp,
+    /// The last tick whose slot was processed
+    processed: u64,
+    slots: Vec<Vec<Timer>>,
+    /// The number of timers in all slots
+    len: usize,
+    /// Whether a task is currently driving the wheel
+    driving: bool,
+    /// The tick until which the driver is sleeping, along with the sender to wake it up earlier
+    /// if a timer is scheduled before then
+    sleeping: Option<(u64, oneshot::Sender<()>)>,
+}
+
+impl Wheel {
+    fn new() -> Self {
+        Wheel {
+            start: Timestamp::now(),
+            processed: 0,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+            len: 0,
+            driving: false,
+            sleeping: None,
+        }
+    }
+
+    /// The current tick. There is no clock on wasm, so there the wheel goes by the ticks it slept.
+    fn now(&self) -> u64 {
+        match self.start.elapsed() {
+            Some(elapsed) => (elapsed.as_nanos() / TICK.as_nanos()) as u64,
+            None => self.processed,
+        }
+    }
+
+    fn insert(&mut self, timer: Timer) {
+        self.slots[(timer.deadline % SLOTS as u64) as usize].push(timer);
+        self.len += 1;
+    }
+
+    /// Take the timers due by the given tick out of the wheel, dropping those which were cancelled.
+    fn advance(&mut self, now: u64, due: &mut Vec<Timer>) {
+        // Past a full turn, every slot has been visited once and the later ticks visit them again
+        let first = self.processed + 1;
+        let last = now.min(self.processed + SLOTS as u64);
+
+        for tick in first..=last {
+            let slot = &mut self.slots[(tick % SLOTS as u64) as usize];
+            if slot.is_empty() {
+                continue;
+            }
+
+            for timer in mem::take(slot) {
+                if timer.cancelled.load(Ordering::Acquire) {
+                    self.len -= 1;
+                } else if timer.deadline <= now {
+                    self.len -= 1;
+                    due.push(timer);
+                } else {
+                    slot.push(timer);
+                }
+            }
+        }
+
+        self.processed = self.processed.max(now);
+    }
+
+    /// How many ticks until the next slot holding any timers comes up.
+    fn ticks_to_next(&self) -> u64 {
+        (1..=SLOTS as u64)
+            .find(|offset| {
+                !self.slots[((self.processed + offset) % SLOTS as u64) as usize].is_empty()
+            })
+            .unwrap_or(SLOTS as u64)
+    }
+}
+
+/// Call `fire` once the duration has elapsed, unless `cancelled` is set by then.
+pub(crate) fn schedule<F>(duration: Duration, cancelled: Arc<AtomicBool>, fire: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(feature = "testing")]
+    if let Some(clock) = crate::testing::clock::installed() {
+        return clock.schedule(duration, cancelled, Box::new(fire));
+    }
+
+    let mut guard = wheel();
+    let wheel = guard.get_or_insert_with(Wheel::new);
+
+    // Round up, so that the timer never fires early
+    let ticks = duration.as_nanos().div_ceil(TICK.as_nanos()) as u64;
+    let deadline = wheel.now().max(wheel.processed) + ticks.max(1);
+    wheel.insert(Timer {
+        deadline,
+        cancelled,
+        fire: Box::new(fire),
+    });
+
+    if !wheel.driving {
+        wheel.driving = true;
+        // The driver is dropped right away if the runtime is shutting down, which locks the wheel
+        drop(guard);
+        super::spawn(drive());
+    } else if matches!(wheel.sleeping, Some((until, _)) if deadline < until) {
+        if let Some((_, wake)) = wheel.sleeping.take() {
+            let _ = wake.send(());
+        }
+    }
+}
+
+/// Call `fire` every time the duration elapses, until it returns `false` or `cancelled` is set.
+pub(crate) fn schedule_interval<F>(duration: Duration, cancelled: Arc<AtomicBool>, mut fire: F)
+where
+    F: FnMut() -> bool + Send + 'static,
+{
+    let next = cancelled.clone();
+    schedule(duration, cancelled, move || {
+        if fire() {
+            schedule_interval(duration, next, fire);
+        }
+    });
+}
+
+/// Marks the wheel as no longer driven if the driver task is dropped before it is done, such as
+/// when the runtime it was spawned onto shuts down, so that the next timer scheduled spawns a new
+/// driver onto the runtime in use by then.
+struct Driving;
+
+impl Drop for Driving {
+    fn drop(&mut self) {
+        if let Some(wheel) = wheel().as_mut() {
+            wheel.driving = false;
+            wheel.sleeping = None;
+        }
+    }
+}
+
+/// Advance the wheel and fire the timers which are due, until no timers are left.
+fn drive() -> impl Future<Output = ()> + Send + 'static {
+    // Created outside of the future, so that it is dropped even if the future is never polled
+    let driving = Driving;
+    async move {
+        let driving = driving;
+        let mut due = Vec::new();
+
+        loop {
+            // Fire outside of the lock, since firing can schedule more timers
+            due.drain(..).for_each(|timer: Timer| (timer.fire)());
+
+            let (sleep, woken) = {
+                let mut guard = wheel();
+                let wheel = guard
+                    .as_mut()
+                    .expect("the wheel is created before it is driven");
+                if wheel.len == 0 {
+                    // This has to be done while the wheel is still locked, or a timer scheduled in the
+                    // meantime would not spawn a new driver
+                    wheel.driving = false;
+                    wheel.sleeping = None;
+                    mem::forget(driving);
+                    return;
+                }
+
+                let until = wheel.processed + wheel.ticks_to_next();
+                let (wake, woken) = oneshot::channel();
+                wheel.sleeping = Some((until, wake));
+                (until.saturating_sub(wheel.now()).max(1), woken)
+            };
+
+            let delay = Box::pin(super::delay(TICK * sleep as u32));
+            let slept = matches!(future::select(delay, woken).await, Either::Left(_));
+
+            let mut guard = wheel();
+            let wheel = guard
+                .as_mut()
+                .expect("the wheel is created before it is driven");
+            let now = match wheel.start.elapsed() {
+                Some(_) => wheel.now(),
+                // Without a clock, assume that the wheel slept for exactly as long as it asked to,
+                // unless it was woken up early, in which case it is not known how long it slept
+                None if slept => wheel.processed + sleep,
+                None => wheel.processed,
+            };
+            wheel.advance(now, &mut due);
+        }
+    }
+}
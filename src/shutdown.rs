@@ -0,0 +1,168 @@
+use crate::spawner;
+use crate::{Actor, ActorId, ActorManager, Address, AddressExt};
+use futures::channel::oneshot;
+use futures::future::{self, AbortHandle, Either, Future, FutureExt};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Keeps track of the actors of a system to shut all of them down together, such as when the
+/// process is asked to exit. Actors are tracked by spawning them with
+/// [`ShutdownCoordinator::spawn`](struct.ShutdownCoordinator.html#method.spawn), or by running
+/// their manager with [`ShutdownCoordinator::manage`](struct.ShutdownCoordinator.html#method.manage).
+///
+/// [`ShutdownCoordinator::shutdown`](struct.ShutdownCoordinator.html#method.shutdown) stops the
+/// actors gracefully (see [`Context::stop_gracefully`](struct.Context.html#method.stop_gracefully))
+/// one after another, in the reverse order of when they were tracked, waiting for each one to
+/// have stopped before stopping the next. Actors which are usually spawned before the actors
+/// that depend on them are therefore stopped after them. Once the timeout has elapsed, the manage
+/// loops of the actors which have not stopped yet are dropped, which stops them without handling
+/// the messages left in their mailboxes. Clones of a coordinator track the same actors.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use std::time::Duration;
+/// use xtra::ShutdownCoordinator;
+///
+/// struct Database;
+/// impl Actor for Database {}
+///
+/// struct Server(Address<Database>);
+/// impl Actor for Server {
+///     fn stopped(&mut self, _: &mut Context<Self>) {
+///         // The database is still running, so the server can flush its state to it here
+///         assert!(self.0.is_connected());
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let system = ShutdownCoordinator::new();
+///     let database = system.spawn(Database);
+///     let server = system.spawn(Server(database.clone()));
+///
+///     let report = system.shutdown(Duration::from_secs(5)).await;
+///     assert_eq!(report.stopped, vec![server.id(), database.id()]);
+///     assert!(report.aborted.is_empty());
+///     assert!(!database.is_connected());
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+#[derive(Clone, Default)]
+pub struct ShutdownCoordinator {
+    actors: Arc<Mutex<Vec<Tracked>>>,
+}
+
+/// An actor tracked by a `ShutdownCoordinator`
+struct Tracked {
+    id: ActorId,
+    stop_gracefully: Box<dyn Fn() + Send>,
+    /// Resolves once the manage loop of the actor has exited or was dropped
+    stopped: oneshot::Receiver<()>,
+    abort: AbortHandle,
+}
+
+/// What happened to the actors during
+/// [`ShutdownCoordinator::shutdown`](struct.ShutdownCoordinator.html#method.shutdown).
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// The actors which stopped gracefully before the timeout, in the order in which they stopped
+    pub stopped: Vec<ActorId>,
+    /// The actors which had not stopped by the timeout, whose manage loops were dropped
+    pub aborted: Vec<ActorId>,
+}
+
+impl ShutdownCoordinator {
+    /// Create a coordinator which does not track any actors yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn the actor onto the global spawner (see [`Actor::spawn`](trait.Actor.html#method.spawn)),
+    /// and track it to be shut down along with the others.
+    pub fn spawn<A: Actor>(&self, actor: A) -> Address<A> {
+        let (addr, mgr) = actor.create();
+        spawner::spawn(Box::pin(self.manage(mgr)));
+        addr
+    }
+
+    /// Track the actor run by the manager to be shut down along with the others, returning the
+    /// future which runs it. This future has to be spawned for the actor to start, as with
+    /// [`ActorManager::manage`](struct.ActorManager.html#method.manage).
+    pub fn manage<A: Actor>(&self, mgr: ActorManager<A>) -> impl Future<Output = ()> + Send {
+        let address = mgr.ctx.address.clone();
+        let (tx, stopped) = oneshot::channel();
+        let (manage, abort) = future::abortable(async move {
+            mgr.manage().await;
+            let _ = tx.send(());
+        });
+
+        let mut actors = self.lock();
+        // Forget the actors which have stopped on their own
+        actors.retain_mut(|tracked| matches!(tracked.stopped.try_recv(), Ok(None)));
+        actors.push(Tracked {
+            id: address.id(),
+            stop_gracefully: Box::new(move || address.stop_gracefully()),
+            stopped,
+            abort,
+        });
+
+        manage.map(|_| ())
+    }
+
+    /// The number of tracked actors which are still running, or whose manage loop has not been
+    /// started yet.
+    pub fn len(&self) -> usize {
+        let mut actors = self.lock();
+        actors.retain_mut(|tracked| matches!(tracked.stopped.try_recv(), Ok(None)));
+        actors.len()
+    }
+
+    /// Returns whether no tracked actors are still running.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stop all tracked actors gracefully in the reverse order of when they were tracked, waiting
+    /// for each one to have stopped before stopping the next, and drop the manage loops of those
+    /// which have not stopped once the timeout has elapsed. Actors tracked while this is running
+    /// are not shut down by it.
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        let actors = mem::take(&mut *self.lock());
+        let mut report = ShutdownReport::default();
+        let mut deadline = Box::pin(crate::runtime::delay(timeout));
+        let mut timed_out = false;
+
+        for tracked in actors.into_iter().rev() {
+            if !timed_out {
+                (tracked.stop_gracefully)();
+                match future::select(tracked.stopped, deadline.as_mut()).await {
+                    Either::Left(_) => {
+                        report.stopped.push(tracked.id);
+                        continue;
+                    }
+                    Either::Right(_) => timed_out = true,
+                }
+            }
+
+            tracked.abort.abort();
+            report.aborted.push(tracked.id);
+        }
+
+        report
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Tracked>> {
+        // Nothing can panic while the lock is held, so poisoning can safely be ignored
+        self.actors.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,192 @@
+//! Shutting down on the signals sent to the process, such as when ctrl-c is pressed or a service
+//! manager stops it. A [`SignalListener`](struct.SignalListener.html) is an actor which waits for
+//! `SIGINT` and `SIGTERM`, and on receiving either, sends [`Shutdown`](struct.Shutdown.html) to
+//! the actors which asked to be notified, and then shuts down the actors of a
+//! [`ShutdownCoordinator`](../struct.ShutdownCoordinator.html), if it was given one. With Tokio,
+//! the signals are received through `tokio::signal`, which needs its signal driver to be enabled,
+//! and on Windows, only ctrl-c is received. With async-std and smol, signals are only supported on
+//! Unix.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! # use std::time::Duration;
+//! use xtra::signal::{Shutdown, SignalListener};
+//! use xtra::ShutdownCoordinator;
+//!
+//! struct Server;
+//! impl Actor for Server {}
+//!
+//! impl SyncHandler<Shutdown> for Server {
+//!     fn handle(&mut self, Shutdown(signal): Shutdown, _: &mut Context<Self>) {
+//!         println!("Received {:?}, no longer accepting connections", signal);
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let system = ShutdownCoordinator::new();
+//!     let server = system.spawn(Server);
+//!
+//!     let signals = SignalListener::new()
+//!         .notify(server.channel())
+//!         .shutdown(system.clone(), Duration::from_secs(10))
+//!         .listen()?;
+//!     # std::process::Command::new("kill")
+//!     #     .args(&["-TERM", &std::process::id().to_string()])
+//!     #     .status()?;
+//!
+//!     // Once a signal is received and the system has shut down, the listener stops
+//!     signals.join().await;
+//!     assert!(!server.is_connected());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    Actor, Address, AddressExt, Context, Handler, Message, MessageChannel, MessageChannelExt,
+    ShutdownCoordinator,
+};
+use futures::stream::{BoxStream, StreamExt};
+use std::io;
+use std::time::Duration;
+
+/// A signal which asks the process to shut down.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Signal {
+    /// `SIGINT`, which is sent when ctrl-c is pressed
+    Interrupt,
+    /// `SIGTERM`, which is sent by service managers and `kill` by default
+    Terminate,
+}
+
+/// The message sent by a [`SignalListener`](struct.SignalListener.html) to the actors which asked
+/// to be notified once a signal is received, with the signal.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Shutdown(pub Signal);
+
+impl Message for Shutdown {
+    type Result = ();
+}
+
+/// A signal received by the listener
+struct Received(Signal);
+
+impl Message for Received {
+    type Result = ();
+}
+
+/// An actor which waits for `SIGINT` and `SIGTERM` to shut down the actors of the process. See the
+/// [module level docs](index.html) for an example.
+///
+/// The first signal is handled by sending [`Shutdown`](struct.Shutdown.html) to every actor given
+/// to [`SignalListener::notify`](struct.SignalListener.html#method.notify) which is still
+/// connected, and then shutting down the actors of the coordinator given to
+/// [`SignalListener::shutdown`](struct.SignalListener.html#method.shutdown), if any. The listener
+/// then stops, so awaiting [`Address::join`](../struct.Address.html#method.join) on its address
+/// waits for both. The listener keeps running until then, even if its addresses are dropped.
+pub struct SignalListener {
+    notify: Vec<MessageChannel<Shutdown>>,
+    shutdown: Option<(ShutdownCoordinator, Duration)>,
+}
+
+impl SignalListener {
+    /// Create a listener which does not notify any actors and does not shut down any coordinator.
+    pub fn new() -> Self {
+        SignalListener {
+            notify: Vec::new(),
+            shutdown: None,
+        }
+    }
+
+    /// Send [`Shutdown`](struct.Shutdown.html) to the actor once a signal is received.
+    pub fn notify(mut self, channel: MessageChannel<Shutdown>) -> Self {
+        self.notify.push(channel);
+        self
+    }
+
+    /// Shut down the actors of the coordinator with
+    /// [`ShutdownCoordinator::shutdown`](../struct.ShutdownCoordinator.html#method.shutdown) once
+    /// a signal is received, aborting those which have not stopped within `timeout`. The listener
+    /// should not itself be tracked by the coordinator.
+    pub fn shutdown(mut self, coordinator: ShutdownCoordinator, timeout: Duration) -> Self {
+        self.shutdown = Some((coordinator, timeout));
+        self
+    }
+
+    /// Start listening for signals, and spawn the listener onto the global spawner (see
+    /// [`Actor::spawn`](../trait.Actor.html#method.spawn)). This fails if the signal handlers
+    /// cannot be installed, such as when the signal driver of Tokio is not enabled.
+    pub fn listen(self) -> io::Result<Address<Self>> {
+        let signals = signals()?;
+        let addr = self.spawn();
+        // The stream holds a strong address, which keeps the listener running
+        addr.clone().attach_stream(signals.map(Received));
+        Ok(addr)
+    }
+}
+
+impl Default for SignalListener {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Actor for SignalListener {}
+
+#[async_trait::async_trait]
+impl Handler<Received> for SignalListener {
+    async fn handle(&mut self, Received(signal): Received, ctx: &mut Context<Self>) {
+        for channel in self.notify.drain(..) {
+            let _ = channel.do_send(Shutdown(signal));
+        }
+
+        if let Some((coordinator, timeout)) = self.shutdown.take() {
+            coordinator.shutdown(timeout).await;
+        }
+
+        ctx.stop();
+    }
+}
+
+/// The signals which ask the process to shut down, as they are received.
+#[cfg(all(unix, feature = "with-tokio-0_2"))]
+fn signals() -> io::Result<BoxStream<'static, Signal>> {
+    use futures::stream;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let interrupt = stream::poll_fn(move |cx| interrupt.poll_recv(cx)).map(|()| Signal::Interrupt);
+    let terminate = stream::poll_fn(move |cx| terminate.poll_recv(cx)).map(|()| Signal::Terminate);
+    Ok(stream::select(interrupt, terminate).boxed())
+}
+
+/// The signals which ask the process to shut down, as they are received.
+#[cfg(all(not(unix), feature = "with-tokio-0_2"))]
+fn signals() -> io::Result<BoxStream<'static, Signal>> {
+    let ctrl_c = futures::stream::unfold((), |()| async {
+        tokio::signal::ctrl_c()
+            .await
+            .ok()
+            .map(|()| (Signal::Interrupt, ()))
+    });
+    Ok(ctrl_c.boxed())
+}
+
+/// The signals which ask the process to shut down, as they are received.
+#[cfg(all(unix, not(feature = "with-tokio-0_2")))]
+fn signals() -> io::Result<BoxStream<'static, Signal>> {
+    use async_signal::{Signal as UnixSignal, Signals};
+
+    let signals = Signals::new([UnixSignal::Int, UnixSignal::Term])?;
+    let signals = signals.filter_map(|signal| {
+        futures::future::ready(match signal {
+            Ok(UnixSignal::Int) => Some(Signal::Interrupt),
+            Ok(UnixSignal::Term) => Some(Signal::Terminate),
+            _ => None,
+        })
+    });
+    Ok(signals.boxed())
+}
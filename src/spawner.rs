@@ -0,0 +1,194 @@
+use futures::executor;
+use futures::future::BoxFuture;
+use std::sync::OnceLock;
+use std::thread;
+
+/// Something which can spawn futures onto an executor. This is used by
+/// [`Actor::spawn`](trait.Actor.html#method.spawn) to run the actor's manage loop, and allows it
+/// to be used with any executor, not only those supported out of the box through the `with-*`
+/// features.
+///
+/// A global spawner can be set with [`set_global_spawner`](fn.set_global_spawner.html), and a
+/// spawner for a single actor can be given to [`Actor::spawn_on`](trait.Actor.html#method.spawn_on).
+/// It is implemented for any `Fn(BoxFuture<'static, ()>)`, so a closure can be used as a spawner.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # struct MyActor;
+/// # impl Actor for MyActor {}
+/// use futures::future::BoxFuture;
+///
+/// struct Smol;
+///
+/// impl xtra::Spawner for Smol {
+///     fn spawn(&self, fut: BoxFuture<'static, ()>) {
+///         smol::Task::spawn(fut).detach();
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let addr = MyActor.spawn_on(&Smol);
+///     assert!(addr.is_connected());
+/// }
+/// ```
+pub trait Spawner: Send + Sync {
+    /// Spawn the future, running it to completion in the background.
+    fn spawn(&self, fut: BoxFuture<'static, ()>);
+}
+
+impl<F> Spawner for F
+where
+    F: Fn(BoxFuture<'static, ()>) + Send + Sync,
+{
+    fn spawn(&self, fut: BoxFuture<'static, ()>) {
+        self(fut)
+    }
+}
+
+/// A [`Spawner`](trait.Spawner.html) which runs each future it is given on a new OS thread of its
+/// own, with a single-threaded executor. This suits actors which wrap a library that must always be
+/// called from the same thread, or which block for long stretches of time, since they then do not
+/// hold up the threads of the runtime. Sending messages to such an actor works as for any other.
+///
+/// If it is created within a tokio runtime, the runtime is entered on the spawned threads, so
+/// that the actors can still use tokio's timers and spawn tasks onto it.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # struct Printer;
+/// # impl Actor for Printer {}
+/// struct ThreadName;
+/// impl Message for ThreadName {
+///     type Result = Option<String>;
+/// }
+///
+/// impl SyncHandler<ThreadName> for Printer {
+///     fn handle(&mut self, _: ThreadName, _: &mut Context<Self>) -> Option<String> {
+///         std::thread::current().name().map(ToOwned::to_owned)
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let addr = Printer.spawn_on(&xtra::ThreadSpawner::new().name("printer"));
+///     assert_eq!(addr.send(ThreadName).await, Ok(Some("printer".to_owned())));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThreadSpawner {
+    name: Option<String>,
+    #[cfg(feature = "with-tokio-0_2")]
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+impl ThreadSpawner {
+    /// Create a spawner whose threads are unnamed.
+    pub fn new() -> Self {
+        ThreadSpawner {
+            name: None,
+            #[cfg(feature = "with-tokio-0_2")]
+            runtime: tokio::runtime::Handle::try_current().ok(),
+        }
+    }
+
+    /// Name the threads which are spawned, which shows up in panic messages and debuggers.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+impl Default for ThreadSpawner {
+    fn default() -> Self {
+        ThreadSpawner::new()
+    }
+}
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, fut: BoxFuture<'static, ()>) {
+        let mut builder = thread::Builder::new();
+        if let Some(name) = &self.name {
+            builder = builder.name(name.clone());
+        }
+
+        #[cfg(feature = "with-tokio-0_2")]
+        let runtime = self.runtime.clone();
+        let run = move || {
+            #[cfg(feature = "with-tokio-0_2")]
+            if let Some(runtime) = runtime {
+                return runtime.enter(|| executor::block_on(fut));
+            }
+
+            executor::block_on(fut)
+        };
+
+        builder
+            .spawn(run)
+            .expect("failed to spawn a thread for the actor");
+    }
+}
+
+static GLOBAL_SPAWNER: OnceLock<Box<dyn Spawner>> = OnceLock::new();
+
+/// Set the spawner used by [`Actor::spawn`](trait.Actor.html#method.spawn) for all actors which
+/// are not given their own. It takes precedence over the runtime enabled with a `with-*` feature.
+/// The global spawner can only be set once, so if it already was, the spawner is returned.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # struct MyActor;
+/// # impl Actor for MyActor {}
+/// use futures::future::BoxFuture;
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let spawner = |fut: BoxFuture<'static, ()>| smol::Task::spawn(fut).detach();
+///     assert!(xtra::set_global_spawner(spawner).is_ok());
+///
+///     let addr = MyActor.spawn(); // Spawned with the global spawner
+///     assert!(addr.is_connected());
+/// }
+/// ```
+pub fn set_global_spawner<S: Spawner + 'static>(spawner: S) -> Result<(), S> {
+    let mut spawner = Some(spawner);
+    GLOBAL_SPAWNER.get_or_init(|| Box::new(spawner.take().unwrap()));
+
+    match spawner {
+        Some(spawner) => Err(spawner),
+        None => Ok(()),
+    }
+}
+
+/// Spawn the future with the global spawner if one was set, or otherwise onto the runtime enabled
+/// with a `with-*` feature.
+pub(crate) fn spawn(fut: BoxFuture<'static, ()>) {
+    if let Some(spawner) = GLOBAL_SPAWNER.get() {
+        return spawner.spawn(fut);
+    }
+
+    #[cfg(any(
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    crate::runtime::spawn(fut);
+
+    #[cfg(not(any(
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    )))]
+    panic!(
+        "No spawner to spawn the actor with. Either enable one of the `with-*` features of xtra, \
+         or set a spawner with `xtra::set_global_spawner`"
+    );
+}
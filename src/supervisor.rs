@@ -0,0 +1,367 @@
+use crate::dedup::DedupWindow;
+use crate::manager::{self, ExitReason, StopOnDrop};
+use crate::{Actor, Address, Context, MailboxConfig};
+use futures::FutureExt;
+use std::panic::AssertUnwindSafe;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+use {crate::clock::Timestamp, std::collections::VecDeque, std::time::Duration};
+
+/// How a [`Supervisor`](struct.Supervisor.html) should react to its actor stopping or panicking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestartStrategy {
+    /// Always restart the actor immediately.
+    Always,
+    /// Restart the actor immediately, but at most this many times. When the actor stops or panics
+    /// after that, the supervisor exits and its address becomes disconnected.
+    Limited(usize),
+    /// Restart the actor after a delay which grows exponentially with each restart.
+    #[cfg(any(
+        doc,
+        feature = "with-tokio-0_2",
+        feature = "with-async_std-1",
+        feature = "with-wasm_bindgen-0_2",
+        feature = "with-smol-0_1"
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+    Backoff(Backoff),
+}
+
+/// An exponential backoff used by [`RestartStrategy::Backoff`](enum.RestartStrategy.html#variant.Backoff).
+/// The first restart waits for the initial delay, and every restart after that waits for the
+/// previous delay multiplied by the multiplier, up to the maximum delay. The number of restarts
+/// within a window of time can also be limited with
+/// [`Backoff::max_restarts`](struct.Backoff.html#method.max_restarts), so that the supervisor gives
+/// up on an actor which is stuck in a crash loop.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::Backoff;
+/// # use std::time::Duration;
+/// let backoff = Backoff::new(Duration::from_millis(10))
+///     .multiplier(3)
+///     .max_delay(Duration::from_secs(1));
+///
+/// assert_eq!(backoff.delay(0), Duration::from_millis(10));
+/// assert_eq!(backoff.delay(2), Duration::from_millis(90));
+/// assert_eq!(backoff.delay(10), Duration::from_secs(1));
+/// ```
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    multiplier: u32,
+    max_delay: Duration,
+    max_restarts: Option<(usize, Duration)>,
+}
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+impl Backoff {
+    /// Create a new backoff starting at the given delay. By default, the delay doubles with every
+    /// restart and is capped at one minute.
+    pub fn new(initial: Duration) -> Self {
+        Backoff {
+            initial,
+            multiplier: 2,
+            max_delay: Duration::from_secs(60),
+            max_restarts: None,
+        }
+    }
+
+    /// Set the factor by which the delay grows with every restart.
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the longest delay to wait before a restart.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Give up on the actor if it would be restarted more than `restarts` times within `window`.
+    /// By default, the actor is restarted indefinitely. Since time cannot be measured on wasm, all
+    /// restarts count towards the limit there, regardless of the window.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use xtra::prelude::*;
+    /// # use xtra::{Backoff, RestartStrategy, Supervisor};
+    /// # use std::time::Duration;
+    /// struct Flaky;
+    /// impl Actor for Flaky {}
+    ///
+    /// struct Crash;
+    /// impl Message for Crash {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl SyncHandler<Crash> for Flaky {
+    ///     fn handle(&mut self, _: Crash, _: &mut Context<Self>) {
+    ///         panic!("oh no");
+    ///     }
+    /// }
+    ///
+    /// #[smol_potat::main]
+    /// async fn main() {
+    ///     let backoff = Backoff::new(Duration::from_millis(1)).max_restarts(2, Duration::from_secs(60));
+    ///     let (addr, supervisor) = Supervisor::new(|| Flaky, RestartStrategy::Backoff(backoff));
+    ///     let supervising = smol::Task::spawn(supervisor.supervise());
+    ///
+    ///     for _ in 0..3 {
+    ///         let _ = addr.send(Crash).await;
+    ///     }
+    ///
+    ///     supervising.await; // The third crash within a minute was one too many
+    ///     assert!(!addr.is_connected());
+    /// }
+    /// ```
+    pub fn max_restarts(mut self, restarts: usize, window: Duration) -> Self {
+        self.max_restarts = Some((restarts, window));
+        self
+    }
+
+    /// The delay to wait before the restart after `restarts` previous restarts.
+    pub fn delay(&self, restarts: usize) -> Duration {
+        let mut delay = self.initial;
+        for _ in 0..restarts {
+            match delay.checked_mul(self.multiplier) {
+                Some(next) if next < self.max_delay => delay = next,
+                _ => return self.max_delay,
+            }
+        }
+        delay.min(self.max_delay)
+    }
+}
+
+/// A supervisor owns a factory for an actor and runs the actor produced by it, creating and
+/// starting a new instance whenever the previous one stops or panics, according to its
+/// [`RestartStrategy`](enum.RestartStrategy.html). All instances share the same mailbox, so the
+/// [`Address`](struct.Address.html) returned by [`Supervisor::new`](struct.Supervisor.html#method.new)
+/// keeps working across restarts. Since the actor's [`ActorId`](struct.ActorId.html) does not
+/// change either, addresses to it stay equal, so other actors which hold on to it (for instance
+/// in a set of subscribers) do not need to find the new instance. Messages which were queued when
+/// an instance stopped are handled by the next one. When a handler panics, [`Actor::panicked`](trait.Actor.html#method.panicked)
+/// decides whether the instance keeps running; if it does not, it is replaced. The
+/// response to a message whose handler panicked resolves to
+/// [`SendError::Disconnected`](enum.SendError.html#variant.Disconnected).
+///
+/// Supervision only ends (after which the address is disconnected) once all strong addresses to
+/// the actor have been dropped, the actor has been stopped gracefully (see
+/// [`AddressExt::stop_gracefully`](trait.AddressExt.html#method.stop_gracefully)), or the restart
+/// strategy does not allow for another restart.
+///
+/// Panics are caught with [`std::panic::catch_unwind`](https://doc.rust-lang.org/std/panic/fn.catch_unwind.html),
+/// so they can only be recovered from if the crate is compiled with `panic = "unwind"` (the default).
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use xtra::{RestartStrategy, Supervisor};
+/// struct Flaky;
+/// impl Actor for Flaky {}
+///
+/// struct Crash;
+/// impl Message for Crash {
+///     type Result = ();
+/// }
+///
+/// struct Ping;
+/// impl Message for Ping {
+///     type Result = &'static str;
+/// }
+///
+/// impl SyncHandler<Crash> for Flaky {
+///     fn handle(&mut self, _: Crash, _: &mut Context<Self>) {
+///         panic!("oh no");
+///     }
+/// }
+///
+/// impl SyncHandler<Ping> for Flaky {
+///     fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> &'static str {
+///         "pong"
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let (addr, supervisor) = Supervisor::new(|| Flaky, RestartStrategy::Always);
+///     smol::Task::spawn(supervisor.supervise()).detach();
+///     let id = addr.id();
+///
+///     assert!(addr.send(Crash).await.is_err()); // The handler panicked...
+///     assert_eq!(addr.send(Ping).await, Ok("pong")); // ...but a new actor took over
+///     assert_eq!(addr.id(), id); // ...behind the same address
+/// }
+/// ```
+pub struct Supervisor<A: Actor> {
+    factory: Box<dyn FnMut() -> A + Send>,
+    strategy: RestartStrategy,
+    ctx: Context<A>,
+}
+
+impl<A: Actor> Supervisor<A> {
+    /// Create a supervisor for actors created by `factory` with an unbounded mailbox, returning
+    /// it along with the address of the supervised actor. The
+    /// [`Supervisor::supervise`](struct.Supervisor.html#method.supervise) future has to be
+    /// executed for the actor to actually start.
+    pub fn new<F>(factory: F, strategy: RestartStrategy) -> (Address<A>, Supervisor<A>)
+    where
+        F: FnMut() -> A + Send + 'static,
+    {
+        Self::with_mailbox(factory, strategy, MailboxConfig::default())
+    }
+
+    /// Create a supervisor for actors created by `factory` with a mailbox created according to
+    /// `config`, returning it along with the address of the supervised actor.
+    pub fn with_mailbox<F>(
+        factory: F,
+        strategy: RestartStrategy,
+        config: MailboxConfig,
+    ) -> (Address<A>, Supervisor<A>)
+    where
+        F: FnMut() -> A + Send + 'static,
+    {
+        let (addr, ctx) = Context::new(config, None);
+        let supervisor = Supervisor {
+            factory: Box::new(factory),
+            strategy,
+            ctx,
+        };
+
+        (addr, supervisor)
+    }
+
+    /// Remember the ids of the last `capacity` messages the supervised actor handled, across all
+    /// of its instances, so that messages with the same id are not handled twice, as with
+    /// [`ActorManager::dedup_window`](struct.ActorManager.html#method.dedup_window).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn dedup_window(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the dedup window of an actor must hold at least one id"
+        );
+        self.ctx.dedup = Some(DedupWindow::new(capacity));
+        self
+    }
+
+    /// Record every message the supervised actor handles, across all of its instances, as with
+    /// [`ActorManager::record`](struct.ActorManager.html#method.record).
+    #[cfg(feature = "recording")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "recording")))]
+    pub fn record(mut self, recorder: crate::recording::Recorder<A>) -> Self {
+        self.ctx.recorder = Some(recorder);
+        self
+    }
+
+    /// Starts the supervision loop. This will create and start the actor, restarting it when it
+    /// stops or panics, until all strong addresses to it have been dropped or the restart strategy
+    /// gives up.
+    pub async fn supervise(mut self) {
+        let mut restarts = 0;
+        // When the most recent restarts happened, for `Backoff::max_restarts`
+        #[cfg(any(
+            doc,
+            feature = "with-tokio-0_2",
+            feature = "with-async_std-1",
+            feature = "with-wasm_bindgen-0_2",
+            feature = "with-smol-0_1"
+        ))]
+        let mut recent = VecDeque::new();
+
+        loop {
+            let mut actor = (self.factory)();
+            let res = {
+                // Stops the actor once the instance has exited, or if the future supervising it
+                // is dropped in the meantime
+                let guard = StopOnDrop {
+                    actor: &mut actor,
+                    ctx: &mut self.ctx,
+                };
+                AssertUnwindSafe(async {
+                    let reason = manager::run(guard.actor, guard.ctx).await;
+                    guard.ctx.receiver.exited(reason);
+                    guard.actor.async_stopped(guard.ctx).await;
+                    reason
+                })
+                .catch_unwind()
+                .await
+            };
+
+            if res.is_err() {
+                // The instance panicked outside of its handlers, such as in `Actor::started`
+                self.ctx.receiver.exited(ExitReason::Panicked);
+            }
+
+            if let Ok(reason @ ExitReason::AllAddressesDropped)
+            | Ok(reason @ ExitReason::MailboxClosed) = res
+            {
+                self.ctx.receiver.disconnect(reason.into());
+                return;
+            }
+
+            match &self.strategy {
+                RestartStrategy::Always => {}
+                RestartStrategy::Limited(max) if restarts < *max => {}
+                RestartStrategy::Limited(_) => return,
+                #[cfg(any(
+                    doc,
+                    feature = "with-tokio-0_2",
+                    feature = "with-async_std-1",
+                    feature = "with-wasm_bindgen-0_2",
+                    feature = "with-smol-0_1"
+                ))]
+                RestartStrategy::Backoff(backoff) => {
+                    if let Some((max, window)) = backoff.max_restarts {
+                        recent.retain(|at: &Timestamp| at.elapsed().is_none_or(|e| e < window));
+                        if recent.len() >= max {
+                            return;
+                        }
+                        recent.push_back(Timestamp::now());
+                    }
+
+                    crate::runtime::delay(backoff.delay(restarts)).await
+                }
+            }
+
+            restarts += 1;
+            self.ctx.reset();
+            // The next instance is created from scratch, so it has to recover all of its events
+            #[cfg(feature = "persistence")]
+            {
+                self.ctx.persisted = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Unit testing actors without spawning them. A [`TestContext`](struct.TestContext.html) stands in
+//! for the [`Context`](../struct.Context.html) of an actor which is never run, so its handlers can
+//! be called directly and awaited on any executor, or with `futures::executor::block_on`. The
+//! messages sent to the actor, such as the notifications it sends to itself, are kept in its
+//! mailbox to be inspected, and whether it was stopped can be checked.
+//!
+//! To check the messages an actor sends to other actors, give it the address of another test
+//! context, whose received messages can then be inspected in the same way. Collaborators which
+//! the actor holds as a [`MessageChannel`](../struct.MessageChannel.html) can instead be replaced
+//! with a [`MockChannel`](struct.MockChannel.html), which records the messages sent to it and
+//! responds with scripted results. There is no such mock for an [`Address`](../struct.Address.html),
+//! since an address is tied to the mailbox of an actor of its type (see
+//! [`MockChannel`](struct.MockChannel.html) for why).
+//!
+//! Actors can also be run together on a [`TestExecutor`](struct.TestExecutor.html), which steps
+//! through them in an order decided by a seed, so that a test which depends on how their messages
+//! interleave fails in the same way every time it is run with the same seed. Once a
+//! [`TestClock`](struct.TestClock.html) is installed, the timers of actors only fire when it is
+//! advanced, so tests do not have to wait for real time to pass.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use xtra::testing::TestContext;
+//!
+//! struct Log(String);
+//! impl Message for Log {
+//!     type Result = ();
+//! }
+//!
+//! struct Logger;
+//! impl Actor for Logger {}
+//!
+//! impl SyncHandler<Log> for Logger {
+//!     fn handle(&mut self, _: Log, _: &mut Context<Self>) {}
+//! }
+//!
+//! struct Withdraw(u32);
+//! impl Message for Withdraw {
+//!     type Result = bool;
+//! }
+//!
+//! struct Account {
+//!     balance: u32,
+//!     log: Address<Logger>,
+//! }
+//! impl Actor for Account {}
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Withdraw> for Account {
+//!     async fn handle(&mut self, Withdraw(amount): Withdraw, ctx: &mut Context<Self>) -> bool {
+//!         if amount > self.balance {
+//!             let _ = self.log.do_send(Log(format!("overdrawn by {}", amount - self.balance)));
+//!             ctx.stop();
+//!             return false;
+//!         }
+//!         self.balance -= amount;
+//!         true
+//!     }
+//! }
+//!
+//! let mut logger = TestContext::<Logger>::new();
+//! let mut account = Account {
+//!     balance: 10,
+//!     log: logger.address().unwrap(),
+//! };
+//! let mut ctx = TestContext::new();
+//!
+//! futures::executor::block_on(async {
+//!     assert!(account.handle(Withdraw(4), &mut ctx).await);
+//!     assert!(!ctx.is_stopped());
+//!
+//!     assert!(!account.handle(Withdraw(7), &mut ctx).await);
+//!     assert!(ctx.is_stopped());
+//! });
+//!
+//! let logged: Vec<_> = logger.received_of::<Log>().iter().map(|log| log.0.clone()).collect();
+//! assert_eq!(logged, vec!["overdrawn by 1".to_string()]);
+//! ```
+
+use crate::address::{DoSendFuture, MessageResponseFuture};
+use crate::envelope::AddressEnvelope;
+use crate::manager::{ContinueManageLoop, ManagerMessage};
+use crate::{
+    response, Actor, ActorId, Address, Context, DisconnectReason, Envelope, MailboxConfig, Message,
+    MessageChannel, SendError, TrySendError,
+};
+use futures::task::{self, Poll};
+use futures::Sink;
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub(crate) mod clock;
+#[cfg(any(
+    doc,
+    feature = "with-tokio-0_2",
+    feature = "with-async_std-1",
+    feature = "with-wasm_bindgen-0_2",
+    feature = "with-smol-0_1"
+))]
+pub use clock::TestClock;
+
+mod executor;
+pub use executor::TestExecutor;
+
+/// A context for an actor which is never run, to call its handlers directly in tests. It
+/// dereferences to a [`Context`](../struct.Context.html), so it can be passed to handlers and the
+/// actor's lifecycle methods. See the [module documentation](index.html) for an example.
+pub struct TestContext<A: Actor> {
+    ctx: Context<A>,
+    /// Kept so that the actor is not told that its last address was dropped
+    _address: Address<A>,
+    /// The messages taken out of the mailbox so far, which have not been handled
+    received: VecDeque<Envelope<A>>,
+}
+
+impl<A: Actor> TestContext<A> {
+    /// Create a test context with an unbounded mailbox.
+    pub fn new() -> Self {
+        let (address, ctx) = Context::new(MailboxConfig::unbounded(), None);
+        TestContext {
+            ctx,
+            _address: address,
+            received: VecDeque::new(),
+        }
+    }
+
+    /// Returns whether the actor was stopped, such as with
+    /// [`Context::stop`](../struct.Context.html#method.stop) or
+    /// [`Context::stop_gracefully`](../struct.Context.html#method.stop_gracefully).
+    pub fn is_stopped(&self) -> bool {
+        !self.ctx.running || self.ctx.exit.is_some() || self.ctx.address.sender.is_closed()
+    }
+
+    /// The messages which were sent to the actor and have not been handled, in the order in which
+    /// it would handle them. This includes the notifications which the actor sent to itself.
+    pub fn received(&mut self) -> &[Envelope<A>] {
+        self.collect();
+        self.received.make_contiguous()
+    }
+
+    /// The messages of type `M` which were sent to the actor and have not been handled.
+    pub fn received_of<M: Message>(&mut self) -> Vec<&M> {
+        self.collect();
+        self.received
+            .iter()
+            .filter_map(|envelope| envelope.downcast_ref())
+            .collect()
+    }
+
+    /// Forget all messages which were sent to the actor, without handling them.
+    pub fn clear(&mut self) {
+        self.collect();
+        self.received.clear();
+    }
+
+    /// Handle the messages which were sent to the actor, including those sent while handling
+    /// them, until there are none left or the actor stops. This resolves to how many were handled.
+    pub async fn handle_received(&mut self, actor: &mut A) -> usize {
+        let mut handled = 0;
+        self.collect();
+
+        while let Some(Envelope(message)) = self.received.pop_front() {
+            handled += 1;
+            if self.ctx.handle_message(message, actor).await != ContinueManageLoop::Yes {
+                break;
+            }
+            self.collect();
+        }
+
+        handled
+    }
+
+    /// Take the notifications and the messages in the mailbox into `received`.
+    fn collect(&mut self) {
+        // Immediate notifications are taken from the back
+        while let Some(notification) = self.ctx.immediate_notifications.pop() {
+            self.received
+                .push_back(Envelope(ManagerMessage::Message(notification)));
+        }
+
+        while let Some(message) = self.ctx.receiver.try_recv() {
+            self.received.push_back(Envelope(message));
+        }
+    }
+}
+
+impl<A: Actor> Default for TestContext<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Actor> Deref for TestContext<A> {
+    type Target = Context<A>;
+
+    fn deref(&self) -> &Context<A> {
+        &self.ctx
+    }
+}
+
+impl<A: Actor> DerefMut for TestContext<A> {
+    fn deref_mut(&mut self) -> &mut Context<A> {
+        &mut self.ctx
+    }
+}
+
+/// A stand-in for an actor which handles messages of type `M`, to test an actor in isolation from
+/// the collaborators it sends messages to. The actor is given a
+/// [`MessageChannel`](../struct.MessageChannel.html) from
+/// [`MockChannel::channel`](struct.MockChannel.html#method.channel) in place of the real one, and
+/// every message sent through it is recorded to be taken out with
+/// [`MockChannel::take_received`](struct.MockChannel.html#method.take_received). Messages sent with
+/// `send` are responded to with the results queued with
+/// [`MockChannel::respond`](struct.MockChannel.html#method.respond) and
+/// [`MockChannel::fail`](struct.MockChannel.html#method.fail), in order, and once there are none
+/// left with the function given to
+/// [`MockChannel::respond_with`](struct.MockChannel.html#method.respond_with). Sending a message
+/// with `send` without any response scripted panics.
+///
+/// Clones of a mock channel, and the channels created from it, share the same messages and
+/// responses. Weak channels behave like strong ones, since there is no actor to keep alive.
+///
+/// This takes the place of a mock address: an [`Address<A>`](../struct.Address.html) sends into
+/// the mailbox of an actor of type `A`, and what is sent can only be handled by the handlers of
+/// `A`, so there is nothing else that could respond in its place. To mock a collaborator, hold it
+/// as a message channel for each type of message sent to it (see
+/// [`Address::into_channel`](../struct.Address.html#method.into_channel)). If an actor has to hold
+/// an `Address<A>`, the address of a [`TestContext<A>`](struct.TestContext.html) can be given to
+/// it instead, which keeps the messages sent to it to be inspected, as shown in the
+/// [module documentation](index.html).
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use xtra::testing::{MockChannel, TestContext};
+/// use xtra::{DisconnectReason, SendError};
+///
+/// struct Price(&'static str);
+/// impl Message for Price {
+///     type Result = Option<u32>;
+/// }
+///
+/// struct Order(&'static str);
+/// impl Message for Order {
+///     type Result = Result<u32, String>;
+/// }
+///
+/// struct Shop {
+///     prices: MessageChannel<Price>,
+/// }
+/// impl Actor for Shop {}
+///
+/// #[async_trait::async_trait]
+/// impl Handler<Order> for Shop {
+///     async fn handle(&mut self, Order(item): Order, _: &mut Context<Self>) -> Result<u32, String> {
+///         match self.prices.send(Price(item)).await {
+///             Ok(Some(price)) => Ok(price),
+///             Ok(None) => Err(format!("{} is not for sale", item)),
+///             Err(_) => Err("prices are unavailable".to_string()),
+///         }
+///     }
+/// }
+///
+/// let prices = MockChannel::new();
+/// prices.respond_with(|Price(item)| if *item == "apple" { Some(3) } else { None });
+/// prices.fail(SendError::Disconnected(DisconnectReason::Stopped));
+///
+/// let mut shop = Shop { prices: prices.channel() };
+/// let mut ctx = TestContext::new();
+///
+/// futures::executor::block_on(async {
+///     // The queued failure is used up first
+///     assert!(shop.handle(Order("apple"), &mut ctx).await.is_err());
+///     assert_eq!(shop.handle(Order("apple"), &mut ctx).await, Ok(3));
+///     assert!(shop.handle(Order("pear"), &mut ctx).await.is_err());
+/// });
+///
+/// let asked: Vec<_> = prices.take_received().into_iter().map(|Price(item)| item).collect();
+/// assert_eq!(asked, vec!["apple", "apple", "pear"]);
+/// ```
+pub struct MockChannel<M: Message> {
+    inner: Arc<MockInner<M>>,
+}
+
+struct MockInner<M: Message> {
+    id: ActorId,
+    state: Mutex<MockState<M>>,
+}
+
+/// Responds to the messages sent to a mock once its queued responses are used up
+type Responder<M> = Box<dyn FnMut(&M) -> <M as Message>::Result + Send>;
+
+struct MockState<M: Message> {
+    received: Vec<M>,
+    responses: VecDeque<Result<M::Result, SendError>>,
+    respond_with: Option<Responder<M>>,
+    connected: bool,
+}
+
+impl<M: Message> MockChannel<M> {
+    /// Create a mock channel without any responses scripted.
+    pub fn new() -> Self {
+        MockChannel {
+            inner: Arc::new(MockInner {
+                id: ActorId::next(),
+                state: Mutex::new(MockState {
+                    received: Vec::new(),
+                    responses: VecDeque::new(),
+                    respond_with: None,
+                    connected: true,
+                }),
+            }),
+        }
+    }
+
+    /// A message channel through which messages are sent to this mock.
+    pub fn channel(&self) -> MessageChannel<M> {
+        MessageChannel {
+            address: Box::new(self.clone()),
+        }
+    }
+
+    /// Queue a result to respond to a message sent with `send` with, after the results queued
+    /// before it have been used up.
+    pub fn respond(&self, result: M::Result) {
+        self.lock().responses.push_back(Ok(result));
+    }
+
+    /// Queue an error to resolve a message sent with `send` to, after the results queued before it
+    /// have been used up.
+    pub fn fail(&self, err: SendError) {
+        self.lock().responses.push_back(Err(err));
+    }
+
+    /// Respond to messages sent with `send` by calling the function with them, once the queued
+    /// results have been used up.
+    pub fn respond_with<F>(&self, f: F)
+    where
+        F: FnMut(&M) -> M::Result + Send + 'static,
+    {
+        self.lock().respond_with = Some(Box::new(f));
+    }
+
+    /// Disconnect the mock, so that sending to it fails with
+    /// [`DisconnectReason::Stopped`](../enum.DisconnectReason.html#variant.Stopped), as if the
+    /// actor had stopped.
+    pub fn disconnect(&self) {
+        self.lock().connected = false;
+    }
+
+    /// Take out the messages sent to the mock so far, in the order in which they were sent.
+    pub fn take_received(&self) -> Vec<M> {
+        std::mem::take(&mut self.lock().received)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState<M>> {
+        // A panic in a scripted response already fails the test, so poisoning can safely be ignored
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a message which does not need a response.
+    fn record(&self, message: M) -> Result<(), TrySendError<M>> {
+        let mut state = self.lock();
+        if !state.connected {
+            return Err(TrySendError::Disconnected(
+                message,
+                DisconnectReason::Stopped,
+            ));
+        }
+
+        state.received.push(message);
+        Ok(())
+    }
+}
+
+impl<M: Message> Clone for MockChannel<M> {
+    fn clone(&self) -> Self {
+        MockChannel {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M: Message> Default for MockChannel<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Message> AddressEnvelope<M> for MockChannel<M> {
+    fn is_connected(&self) -> bool {
+        self.lock().connected
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    fn do_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        self.record(message)
+    }
+
+    fn try_send(&self, message: M) -> Result<(), TrySendError<M>> {
+        self.record(message)
+    }
+
+    fn do_send_async(&self, message: M) -> DoSendFuture {
+        match self.record(message) {
+            Ok(()) => DoSendFuture::sent(),
+            Err(_) => DoSendFuture::disconnected(DisconnectReason::Stopped),
+        }
+    }
+
+    fn send(&self, message: M) -> MessageResponseFuture<M> {
+        let mut state = self.lock();
+        if !state.connected {
+            return MessageResponseFuture::disconnected(DisconnectReason::Stopped);
+        }
+
+        let result = match state.responses.pop_front() {
+            Some(result) => result,
+            None => match &mut state.respond_with {
+                Some(respond_with) => Ok(respond_with(&message)),
+                None => panic!(
+                    "MockChannel<{}> was sent a message without a response scripted",
+                    std::any::type_name::<M>()
+                ),
+            },
+        };
+        state.received.push(message);
+        drop(state);
+
+        match result {
+            Ok(result) => {
+                let (tx, rx) = response::channel();
+                let _ = tx.send(result);
+                MessageResponseFuture::result(rx)
+            }
+            Err(err) => MessageResponseFuture::rejected(err),
+        }
+    }
+
+    fn id(&self) -> ActorId {
+        self.inner.id
+    }
+
+    fn clone_channel(&self) -> Box<dyn AddressEnvelope<M>> {
+        Box::new(self.clone())
+    }
+
+    fn downgrade(&self) -> Box<dyn AddressEnvelope<M>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<M: Message> Sink<M> for MockChannel<M> {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Result<(), SendError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: M) -> Result<(), SendError> {
+        self.record(message).map_err(Into::into)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Result<(), SendError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Result<(), SendError>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,185 @@
+use futures::channel::oneshot;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+static CLOCK: OnceLock<TestClock> = OnceLock::new();
+
+/// The test clock, if one was installed with `TestClock::install`.
+pub(crate) fn installed() -> Option<&'static TestClock> {
+    CLOCK.get()
+}
+
+/// A virtual clock which replaces the real one for all timers of xtra in the process once it is
+/// installed, so that tests can move time forward instantly rather than waiting for it to pass.
+/// This covers the delayed and periodic notifications of [`Context`](../struct.Context.html)
+/// (such as [`Context::notify_after`](../struct.Context.html#method.notify_after) and
+/// [`Context::notify_interval`](../struct.Context.html#method.notify_interval)), timeouts such as
+/// with [`AddressExt::send_timeout`](../trait.AddressExt.html#method.send_timeout), and the
+/// backoff of a [`Supervisor`](../struct.Supervisor.html). Time only moves when
+/// [`TestClock::advance`](struct.TestClock.html#method.advance) is called, which fires the timers
+/// which became due along the way, in order.
+///
+/// The clock cannot be uninstalled, so tests which use it should run in a process of their own,
+/// such as in an integration test file of their own, or not rely on real time otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// # use std::time::Duration;
+/// use xtra::testing::TestClock;
+///
+/// struct Tick;
+/// impl Message for Tick {
+///     type Result = ();
+/// }
+///
+/// struct Ticks;
+/// impl Message for Ticks {
+///     type Result = u32;
+/// }
+///
+/// struct Ticker(u32);
+///
+/// impl Actor for Ticker {
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         ctx.notify_interval(Duration::from_secs(60), || Tick);
+///     }
+/// }
+///
+/// impl SyncHandler<Tick> for Ticker {
+///     fn handle(&mut self, _: Tick, _: &mut Context<Self>) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// impl SyncHandler<Ticks> for Ticker {
+///     fn handle(&mut self, _: Ticks, _: &mut Context<Self>) -> u32 {
+///         self.0
+///     }
+/// }
+///
+/// #[smol_potat::main]
+/// async fn main() {
+///     let clock = TestClock::install();
+///     let addr = Ticker(0).spawn();
+///     assert_eq!(addr.send(Ticks).await, Ok(0)); // The interval has been started
+///
+///     // An hour passes in no time at all
+///     clock.advance(Duration::from_secs(60 * 60));
+///     assert_eq!(addr.send(Ticks).await, Ok(60));
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "with-tokio-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-async_std-1")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-wasm_bindgen-0_2")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "with-smol-0_1")))]
+#[derive(Clone)]
+pub struct TestClock {
+    inner: Arc<Mutex<VirtualTime>>,
+}
+
+struct VirtualTime {
+    /// How much virtual time has passed since the clock was installed
+    now: Duration,
+    /// The timers waiting for the clock to reach their deadline, ordered by deadline and then by
+    /// when they were scheduled
+    timers: BTreeMap<(Duration, u64), Timer>,
+    next_id: u64,
+}
+
+struct Timer {
+    cancelled: Arc<AtomicBool>,
+    fire: Box<dyn FnOnce() + Send>,
+}
+
+impl TestClock {
+    /// Install the test clock for the whole process, returning it so that time can be advanced.
+    /// If it was already installed, the installed clock is returned.
+    pub fn install() -> TestClock {
+        CLOCK
+            .get_or_init(|| TestClock {
+                inner: Arc::new(Mutex::new(VirtualTime {
+                    now: Duration::from_secs(0),
+                    timers: BTreeMap::new(),
+                    next_id: 0,
+                })),
+            })
+            .clone()
+    }
+
+    /// How much virtual time has passed since the clock was installed.
+    pub fn now(&self) -> Duration {
+        self.lock().now
+    }
+
+    /// The number of timers which are waiting for time to pass, including those which were
+    /// cancelled but have not been reached yet.
+    pub fn pending(&self) -> usize {
+        self.lock().timers.len()
+    }
+
+    /// Move time forward by the given duration, firing every timer which becomes due in the order
+    /// of their deadlines. The clock is at the deadline of a timer while it fires, so that timers
+    /// scheduled by it, such as the next notification of an interval, are due relative to then.
+    /// Firing a timer only sends the notification or wakes the task waiting on it, which then
+    /// runs on its executor as usual.
+    pub fn advance(&self, duration: Duration) {
+        let until = self.lock().now + duration;
+
+        loop {
+            let mut time = self.lock();
+            let next = match time.timers.keys().next() {
+                Some(&key) if key.0 <= until => key,
+                _ => break,
+            };
+
+            let timer = time.timers.remove(&next).unwrap();
+            time.now = next.0;
+            drop(time);
+
+            // Fire outside of the lock, since firing can schedule more timers
+            if !timer.cancelled.load(Ordering::Acquire) {
+                (timer.fire)();
+            }
+        }
+
+        self.lock().now = until;
+    }
+
+    /// Call `fire` once the clock has been advanced by the duration, unless `cancelled` is set
+    /// by then.
+    pub(crate) fn schedule(
+        &self,
+        duration: Duration,
+        cancelled: Arc<AtomicBool>,
+        fire: Box<dyn FnOnce() + Send>,
+    ) {
+        let mut time = self.lock();
+        let key = (time.now + duration, time.next_id);
+        time.next_id += 1;
+        time.timers.insert(key, Timer { cancelled, fire });
+    }
+
+    /// Resolve once the clock has been advanced by the duration.
+    pub(crate) fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let (tx, rx) = oneshot::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let fire = move || {
+            let _ = tx.send(());
+        };
+        self.schedule(duration, cancelled, Box::new(fire));
+
+        async move {
+            let _ = rx.await;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VirtualTime> {
+        // Timers are fired after the lock is released, so poisoning can safely be ignored
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
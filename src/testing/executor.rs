@@ -0,0 +1,238 @@
+use crate::Spawner;
+use futures::future::BoxFuture;
+use futures::task::{self, ArcWake, Poll};
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// A single-threaded executor which runs the tasks spawned onto it one poll at a time, in an order
+/// picked by a pseudo-random number generator seeded with the given seed. Running the same test
+/// with the same seed always interleaves the tasks in the same way, so a bug which only shows up
+/// with one interleaving can be reproduced by its seed, and running a test with many seeds tries
+/// out many interleavings.
+///
+/// Actors are run on it by spawning them with it as their [`Spawner`](../trait.Spawner.html). An
+/// actor handles up to its [budget](../struct.ActorBuilder.html#method.budget) of messages every
+/// time its manage loop is polled, so with a budget of one, every step of the executor handles at
+/// most one message.
+///
+/// Nothing runs unless the executor is driven with
+/// [`TestExecutor::step`](struct.TestExecutor.html#method.step),
+/// [`TestExecutor::run_until_stalled`](struct.TestExecutor.html#method.run_until_stalled), or
+/// [`TestExecutor::block_on`](struct.TestExecutor.html#method.block_on). Tasks waiting on anything
+/// outside of the executor, such as a timer, are only run again once that has woken them, so for
+/// the order to be deterministic, the actors should not depend on time.
+///
+/// # Example
+///
+/// ```rust
+/// # use xtra::prelude::*;
+/// use xtra::testing::TestExecutor;
+///
+/// struct Append(&'static str);
+/// impl Message for Append {
+///     type Result = ();
+/// }
+///
+/// struct Get;
+/// impl Message for Get {
+///     type Result = Vec<&'static str>;
+/// }
+///
+/// #[derive(Default)]
+/// struct Log(Vec<&'static str>);
+/// impl Actor for Log {}
+///
+/// impl SyncHandler<Append> for Log {
+///     fn handle(&mut self, Append(name): Append, _: &mut Context<Self>) {
+///         self.0.push(name);
+///     }
+/// }
+///
+/// impl SyncHandler<Get> for Log {
+///     fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Vec<&'static str> {
+///         self.0.clone()
+///     }
+/// }
+///
+/// struct Write;
+/// impl Message for Write {
+///     type Result = ();
+/// }
+///
+/// struct Writer(&'static str, Address<Log>);
+/// impl Actor for Writer {}
+///
+/// impl SyncHandler<Write> for Writer {
+///     fn handle(&mut self, _: Write, _: &mut Context<Self>) {
+///         let _ = self.1.do_send(Append(self.0));
+///     }
+/// }
+///
+/// fn run(seed: u64) -> Vec<&'static str> {
+///     let executor = TestExecutor::new(seed);
+///     let log = Log::default().builder().budget(1).spawn_on(&executor);
+///
+///     for name in ["a", "b"] {
+///         let writer = Writer(name, log.clone()).builder().budget(1).spawn_on(&executor);
+///         for _ in 0..3 {
+///             writer.do_send(Write).unwrap();
+///         }
+///     }
+///
+///     executor.run_until_stalled();
+///     executor.block_on(log.send(Get)).unwrap()
+/// }
+///
+/// // The writers are interleaved in some order, but always in the same one for the same seed
+/// assert_eq!(run(42).len(), 6);
+/// assert_eq!(run(42), run(42));
+/// ```
+#[derive(Clone)]
+pub struct TestExecutor {
+    inner: Arc<Mutex<Tasks>>,
+}
+
+struct Tasks {
+    /// The futures of the tasks which have not finished, except one which is being polled
+    pending: BTreeMap<u64, BoxFuture<'static, ()>>,
+    /// The tasks which were woken and can be polled
+    ready: BTreeSet<u64>,
+    next_id: u64,
+    /// The state of the random number generator choosing which ready task to poll next
+    rng: u64,
+}
+
+impl Tasks {
+    /// The next number of a SplitMix64 generator, which is fast and good enough to pick tasks.
+    fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Makes a task ready again once it is woken
+struct TaskWaker {
+    id: u64,
+    tasks: Weak<Mutex<Tasks>>,
+}
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if let Some(tasks) = arc_self.tasks.upgrade() {
+            lock(&tasks).ready.insert(arc_self.id);
+        }
+    }
+}
+
+/// Notes that the future given to `block_on` was woken
+struct MainWaker(AtomicBool);
+
+impl ArcWake for MainWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::Release);
+    }
+}
+
+fn lock(tasks: &Mutex<Tasks>) -> MutexGuard<'_, Tasks> {
+    // Tasks are polled after the lock is released, so poisoning can safely be ignored
+    tasks.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl TestExecutor {
+    /// Create an executor which picks the order of its tasks with the given seed.
+    pub fn new(seed: u64) -> Self {
+        TestExecutor {
+            inner: Arc::new(Mutex::new(Tasks {
+                pending: BTreeMap::new(),
+                ready: BTreeSet::new(),
+                next_id: 0,
+                rng: seed,
+            })),
+        }
+    }
+
+    /// Poll one of the tasks which are ready, picked at random, returning whether there was any.
+    pub fn step(&self) -> bool {
+        let (id, mut fut) = {
+            let mut tasks = lock(&self.inner);
+            loop {
+                if tasks.ready.is_empty() {
+                    return false;
+                }
+
+                let n = (tasks.next_random() % tasks.ready.len() as u64) as usize;
+                let id = *tasks.ready.iter().nth(n).unwrap();
+                tasks.ready.remove(&id);
+
+                // Tasks which have finished can still be woken, but are not polled
+                if let Some(fut) = tasks.pending.remove(&id) {
+                    break (id, fut);
+                }
+            }
+        };
+
+        let waker = task::waker(Arc::new(TaskWaker {
+            id,
+            tasks: Arc::downgrade(&self.inner),
+        }));
+        let mut cx = task::Context::from_waker(&waker);
+
+        // Poll outside of the lock, since the task can spawn other tasks or wake itself
+        if fut.as_mut().poll(&mut cx).is_pending() {
+            lock(&self.inner).pending.insert(id, fut);
+        }
+
+        true
+    }
+
+    /// Poll the tasks which are ready until none are, such as because all actors are waiting for
+    /// messages, returning how many times a task was polled.
+    pub fn run_until_stalled(&self) -> usize {
+        let mut steps = 0;
+        while self.step() {
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Run the future to completion on the current thread, polling it whenever it was woken and
+    /// the tasks of the executor in between.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future is waiting while no task is ready, since it could then only be woken
+    /// from outside of the executor, which is most likely a deadlock.
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        futures::pin_mut!(fut);
+        let main = Arc::new(MainWaker(AtomicBool::new(true)));
+        let waker = task::waker(main.clone());
+        let mut cx = task::Context::from_waker(&waker);
+
+        loop {
+            if main.0.swap(false, Ordering::AcqRel) {
+                if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+
+            if !self.step() && !main.0.load(Ordering::Acquire) {
+                panic!("TestExecutor::block_on stalled, since no task can wake the future");
+            }
+        }
+    }
+}
+
+impl Spawner for TestExecutor {
+    fn spawn(&self, fut: BoxFuture<'static, ()>) {
+        let mut tasks = lock(&self.inner);
+        let id = tasks.next_id;
+        tasks.next_id += 1;
+        tasks.pending.insert(id, fut);
+        tasks.ready.insert(id);
+    }
+}
@@ -0,0 +1,184 @@
+//! Introspection of which types of actors send which types of messages to which other types of
+//! actors, to visualize the topology of a running system. Every message sent through xtra is
+//! counted in a graph kept for the whole process, under the type of the actor whose handler sent
+//! it, the type of the message, and the type of the actor it was sent to. Since this takes a lock
+//! on every send, it is meant for debugging rather than for production.
+//!
+//! [`topology`](fn.topology.html) takes a snapshot of the graph, which can be exported to the
+//! [DOT](https://graphviz.org/doc/info/lang.html) language of Graphviz with
+//! [`Topology::to_dot`](struct.Topology.html#method.to_dot).
+//!
+//! # Example
+//!
+//! ```rust
+//! # use xtra::prelude::*;
+//! use xtra::topology;
+//!
+//! struct Ping;
+//! impl Message for Ping {
+//!     type Result = ();
+//! }
+//!
+//! struct Ponger;
+//! impl Actor for Ponger {}
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Ping> for Ponger {
+//!     async fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+//! }
+//!
+//! struct Start;
+//! impl Message for Start {
+//!     type Result = ();
+//! }
+//!
+//! struct Pinger(Address<Ponger>);
+//! impl Actor for Pinger {}
+//!
+//! #[async_trait::async_trait]
+//! impl Handler<Start> for Pinger {
+//!     async fn handle(&mut self, _: Start, _: &mut Context<Self>) {
+//!         for _ in 0..3 {
+//!             self.0.send(Ping).await.unwrap();
+//!         }
+//!     }
+//! }
+//!
+//! #[smol_potat::main]
+//! async fn main() {
+//!     let pinger = Pinger(Ponger.spawn()).spawn();
+//!     pinger.send(Start).await.unwrap();
+//!
+//!     let topology = topology::topology();
+//!     let ping = topology
+//!         .flows()
+//!         .iter()
+//!         .find(|flow| flow.message == std::any::type_name::<Ping>())
+//!         .unwrap();
+//!     assert_eq!(ping.from, Some(std::any::type_name::<Pinger>()));
+//!     assert_eq!(ping.to, std::any::type_name::<Ponger>());
+//!     assert_eq!(ping.count, 3);
+//!
+//!     println!("{}", topology.to_dot());
+//! }
+//! ```
+
+use crate::{Actor, Message};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// The number of messages sent for every sender, message type and receiver
+type Graph = BTreeMap<(Option<&'static str>, &'static str, &'static str), u64>;
+
+fn graph() -> MutexGuard<'static, Graph> {
+    static GRAPH: OnceLock<Mutex<Graph>> = OnceLock::new();
+    let graph = GRAPH.get_or_init(Default::default);
+    // The graph is never left in an inconsistent state, so poisoning can safely be ignored
+    graph.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Count a message of type `M` being sent to an actor of type `A` by the current actor, if any.
+pub(crate) fn sent<A: Actor, M: Message>() {
+    let key = (
+        crate::current::actor_type(),
+        std::any::type_name::<M>(),
+        std::any::type_name::<A>(),
+    );
+    *graph().entry(key).or_insert(0) += 1;
+}
+
+/// Messages of one type sent from one type of actor to another, as counted in a
+/// [`Topology`](struct.Topology.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageFlow {
+    /// The name of the type of the actor whose handler sent the messages, or `None` for messages
+    /// sent from outside of a handler, such as from `Actor::started` or from outside of any actor
+    pub from: Option<&'static str>,
+    /// The name of the type of the messages
+    pub message: &'static str,
+    /// The name of the type of the actor the messages were sent to
+    pub to: &'static str,
+    /// How many of these messages were sent
+    pub count: u64,
+}
+
+/// A snapshot of which types of actors sent which types of messages to which other types of
+/// actors, taken with [`topology`](fn.topology.html).
+#[derive(Debug, Clone)]
+pub struct Topology {
+    flows: Vec<MessageFlow>,
+}
+
+impl Topology {
+    /// The flows of messages between the types of actors, ordered by the type of the sender, then
+    /// by the type of the message, and then by the type of the receiver.
+    pub fn flows(&self) -> &[MessageFlow] {
+        &self.flows
+    }
+
+    /// The names of the types of all actors which sent or were sent messages, in order.
+    pub fn actors(&self) -> Vec<&'static str> {
+        let mut actors: Vec<_> = self
+            .flows
+            .iter()
+            .flat_map(|flow| flow.from.into_iter().chain(Some(flow.to)))
+            .collect();
+        actors.sort_unstable();
+        actors.dedup();
+        actors
+    }
+
+    /// Export the topology to the DOT language of Graphviz, with a node for every type of actor
+    /// and an edge for every flow of messages, labelled with their type and how many were sent.
+    /// Messages sent from outside of a handler come from a dashed node named `external`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph xtra {\n");
+
+        if self.flows.iter().any(|flow| flow.from.is_none()) {
+            dot.push_str("    \"external\" [style=dashed];\n");
+        }
+        for actor in self.actors() {
+            let _ = writeln!(dot, "    {} [shape=box];", quote(actor));
+        }
+        for flow in &self.flows {
+            let from = flow.from.map_or_else(|| "\"external\"".to_string(), quote);
+            let label = format!("{} ({})", flow.message, flow.count);
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label={}];",
+                from,
+                quote(flow.to),
+                quote(&label)
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Quote a type name as a DOT identifier.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Take a snapshot of the messages which have been sent between actors since the process started,
+/// or since the topology was last [`reset`](fn.reset.html).
+pub fn topology() -> Topology {
+    let flows = graph()
+        .iter()
+        .map(|(&(from, message, to), &count)| MessageFlow {
+            from,
+            message,
+            to,
+            count,
+        })
+        .collect();
+    Topology { flows }
+}
+
+/// Forget all messages which have been sent between actors so far.
+pub fn reset() {
+    graph().clear();
+}
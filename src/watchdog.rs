@@ -0,0 +1,59 @@
+use futures::future::{self, Either, Future};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Information about a handler which took longer than the threshold of its actor's watchdog to
+/// handle a message. This is passed to the hook given to
+/// [`ActorManager::watchdog`](struct.ActorManager.html#method.watchdog).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SlowHandler {
+    /// The name of the type of the actor
+    pub actor: &'static str,
+    /// The name of the type of the message being handled
+    pub message: &'static str,
+    /// How long the handler has been running for, which is the threshold of the watchdog
+    pub elapsed: Duration,
+}
+
+/// Calls a hook if a single handler runs for longer than a threshold.
+#[derive(Clone)]
+pub(crate) struct Watchdog {
+    threshold: Duration,
+    hook: Arc<dyn Fn(SlowHandler) + Send + Sync>,
+}
+
+impl Watchdog {
+    pub(crate) fn new<F>(threshold: Duration, hook: F) -> Self
+    where
+        F: Fn(SlowHandler) + Send + Sync + 'static,
+    {
+        Watchdog {
+            threshold,
+            hook: Arc::new(hook),
+        }
+    }
+
+    /// Run the future handling a message, calling the hook once if it has not completed within
+    /// the threshold. The future is still run to completion afterwards.
+    pub(crate) async fn watch<F: Future>(
+        &self,
+        actor: &'static str,
+        message: &'static str,
+        handling: F,
+    ) -> F::Output {
+        let handling = Box::pin(handling);
+        let timer = Box::pin(crate::runtime::delay(self.threshold));
+
+        match future::select(handling, timer).await {
+            Either::Left((output, _)) => output,
+            Either::Right(((), handling)) => {
+                (self.hook)(SlowHandler {
+                    actor,
+                    message,
+                    elapsed: self.threshold,
+                });
+                handling.await
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! The timer wheel is shared by all runtimes in the process, so a runtime which is dropped while a
+//! notification is pending must not take the wheel down with it.
+
+use futures::channel::oneshot;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+use xtra::prelude::*;
+
+struct Alarm {
+    after: Duration,
+    rung: Option<oneshot::Sender<()>>,
+}
+
+impl Actor for Alarm {
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.notify_after(self.after, Ring);
+    }
+}
+
+struct Ring;
+impl Message for Ring {
+    type Result = ();
+}
+
+impl SyncHandler<Ring> for Alarm {
+    fn handle(&mut self, _: Ring, _ctx: &mut Context<Self>) {
+        if let Some(rung) = self.rung.take() {
+            let _ = rung.send(());
+        }
+    }
+}
+
+fn runtime() -> Runtime {
+    Builder::new()
+        .basic_scheduler()
+        .enable_time()
+        .build()
+        .unwrap()
+}
+
+/// Spawn an alarm onto the runtime, returning its address and a receiver which completes once it
+/// rings.
+fn alarm(rt: &mut Runtime, after: Duration) -> (Address<Alarm>, oneshot::Receiver<()>) {
+    let (rung, ringing) = oneshot::channel();
+    let addr = rt.block_on(async move {
+        let addr = Alarm {
+            after,
+            rung: Some(rung),
+        }
+        .spawn();
+        // Let the actor start and schedule its notification
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+        addr
+    });
+    (addr, ringing)
+}
+
+#[test]
+fn notifications_fire_after_a_runtime_is_dropped() {
+    // The driver of the wheel is spawned onto this runtime, and dropped along with it
+    let mut rt = runtime();
+    let (_addr, mut ringing) = alarm(&mut rt, Duration::from_secs(60));
+    drop(rt);
+    assert!(ringing.try_recv().is_err(), "the alarm was dropped unrung");
+
+    for _ in 0..2 {
+        let mut rt = runtime();
+        let (_addr, ringing) = alarm(&mut rt, Duration::from_millis(10));
+        let rung =
+            rt.block_on(async { tokio::time::timeout(Duration::from_secs(5), ringing).await });
+        assert!(matches!(rung, Ok(Ok(()))), "the alarm did not ring");
+    }
+}
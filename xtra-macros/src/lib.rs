@@ -0,0 +1,441 @@
+//! Derive and attribute macros for [xtra](https://docs.rs/xtra). These are re-exported by xtra
+//! when its `macros` feature is enabled, and should be used through it rather than directly.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::ParseStream;
+use syn::{
+    parse_macro_input, parse_quote, DeriveInput, FnArg, Ident, ImplItem, ItemImpl, LitStr, Meta,
+    Pat, ReturnType, Type, Visibility,
+};
+
+/// Implement `xtra::Actor` with all of its default methods. What the actor does when one of its
+/// handlers panicked can be given with `#[actor(panic = "Resume")]`, and is to stop if it is not
+/// given.
+#[proc_macro_derive(Actor, attributes(actor))]
+pub fn derive_actor(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match actor_impl(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn actor_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut panic_action: Option<Ident> = None;
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("actor")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("panic") {
+                let lit: LitStr = meta.value()?.parse()?;
+                panic_action = Some(lit.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `panic`"))
+            }
+        })?;
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let panic_action = panic_action.map(|action| {
+        quote! {
+            const PANIC_ACTION: ::xtra::PanicAction = ::xtra::PanicAction::#action;
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::xtra::Actor for #name #ty_generics #where_clause {
+            #panic_action
+        }
+    })
+}
+
+/// Implement `xtra::Message`. The result type is given with `#[message(result = "Type")]` and is
+/// `()` if it is not given. The priority can be given with `#[message(priority = "High")]`.
+#[proc_macro_derive(Message, attributes(message))]
+pub fn derive_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match message_impl(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn message_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut result: Option<Type> = None;
+    let mut priority: Option<Ident> = None;
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("message")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("result") {
+                let ty: LitStr = meta.value()?.parse()?;
+                result = Some(ty.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("priority") {
+                let lit: LitStr = meta.value()?.parse()?;
+                priority = Some(lit.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `result` or `priority`"))
+            }
+        })?;
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let result = result.map_or_else(|| quote!(()), |ty| quote!(#ty));
+    let priority = priority.map(|priority| {
+        quote! {
+            const PRIORITY: ::xtra::Priority = ::xtra::Priority::#priority;
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::xtra::Message for #name #ty_generics #where_clause {
+            type Result = #result;
+            #priority
+        }
+    })
+}
+
+/// Turn each method of an inherent `impl` block of an actor into a `Handler` (for `async fn`s) or
+/// `SyncHandler` (for other `fn`s) implementation for the type of its message. Every method must
+/// take `&mut self`, the message, and `&mut Context<Self>`. If a method is marked with `#[message]`,
+/// `Message` is also implemented for its message type, with its return type as the result.
+///
+/// A method marked with `#[message(pub GetUser)]` instead takes the fields of its message between
+/// `&mut self` and the context, and a struct of that name and visibility is generated with them,
+/// implementing `Message` with the method's return type as its result.
+///
+/// With `#[handlers(address = "pub CounterAddress")]`, a trait of that name and visibility is also
+/// generated, and implemented for the addresses of the actor. For each method, it has a method of
+/// the same name which sends the message and waits for the response, as with `AddressExt::send`,
+/// and one prefixed with `cast_` which sends it without waiting, as with `AddressExt::do_send`.
+/// These take the fields of the message for methods marked with `#[message(...)]`, and the message
+/// itself otherwise, since the definition of a message type declared elsewhere is not known.
+#[proc_macro_attribute]
+pub fn handlers(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut address: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("address") {
+            address = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `address`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    let input = parse_macro_input!(input as ItemImpl);
+    let address = match address.map(|lit| lit.parse_with(address_trait)).transpose() {
+        Ok(address) => address,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    match handlers_impl(input, address) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// The visibility and name of the address trait, such as `pub CounterAddress`
+fn address_trait(input: ParseStream) -> syn::Result<(Visibility, Ident)> {
+    Ok((input.parse()?, input.parse()?))
+}
+
+fn handlers_impl(
+    mut input: ItemImpl,
+    address: Option<(Visibility, Ident)>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some((_, path, _)) = &input.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "`handlers` must be used on an inherent impl block",
+        ));
+    }
+
+    let self_ty = input.self_ty.clone();
+    let generics = input.generics.clone();
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let mut generated = Vec::new();
+    let mut address_methods = Vec::new();
+
+    for item in &mut input.items {
+        let method = match item {
+            ImplItem::Fn(method) => method,
+            _ => continue,
+        };
+
+        let message_attr = method
+            .attrs
+            .iter()
+            .find(|a| a.path().is_ident("message"))
+            .cloned();
+        method.attrs.retain(|a| !a.path().is_ident("message"));
+        // The visibility and name of the message struct to generate from the method's fields
+        let declared = match message_attr.as_ref().map(|attr| &attr.meta) {
+            Some(Meta::List(list)) => Some(list.parse_args_with(address_trait)?),
+            _ => None,
+        };
+
+        let sig = &method.sig;
+        let name = &sig.ident;
+        let result = match &sig.output {
+            ReturnType::Default => quote!(()),
+            ReturnType::Type(_, ty) => quote!(#ty),
+        };
+
+        let (message, fields) = match declared {
+            Some((vis, message)) => {
+                let fields = message_fields(sig)?;
+                let names: Vec<_> = fields.iter().map(|(name, _)| name).collect();
+                let types: Vec<_> = fields.iter().map(|(_, ty)| ty).collect();
+                let doc = format!(
+                    "The message handled by `{}::{}`, generated by `#[xtra::handlers]`.",
+                    quote!(#self_ty),
+                    name,
+                );
+                let field_docs = names.iter().map(|field| {
+                    format!(
+                        "The `{}` argument of `{}::{}`.",
+                        field,
+                        quote!(#self_ty),
+                        name
+                    )
+                });
+                generated.push(if fields.is_empty() {
+                    quote! {
+                        #[doc = #doc]
+                        #vis struct #message;
+                    }
+                } else {
+                    quote! {
+                        #[doc = #doc]
+                        #vis struct #message {
+                            #(#[doc = #field_docs] #vis #names: #types,)*
+                        }
+                    }
+                });
+
+                let message: Type = parse_quote!(#message);
+                let call = if sig.asyncness.is_some() {
+                    quote!(<#self_ty>::#name(self, #(#names,)* __xtra_ctx).await)
+                } else {
+                    quote!(<#self_ty>::#name(self, #(#names,)* __xtra_ctx))
+                };
+                // The context is moved out first, since a field could be named `ctx`
+                let body = quote! {
+                    let __xtra_ctx = ctx;
+                    let #message { #(#names),* } = message;
+                    #call
+                };
+                (Box::new(message), Some((fields, body)))
+            }
+            None => {
+                let mut inputs = sig.inputs.iter();
+                let (receiver, message, ctx) = (inputs.next(), inputs.next(), inputs.next());
+                match (receiver, message, ctx, inputs.next()) {
+                    (
+                        Some(FnArg::Receiver(_)),
+                        Some(FnArg::Typed(message)),
+                        Some(FnArg::Typed(_)),
+                        None,
+                    ) => (message.ty.clone(), None),
+                    _ => return Err(syn::Error::new_spanned(
+                        &sig.inputs,
+                        "a handler must take `&mut self`, the message, and `&mut Context<Self>`",
+                    )),
+                }
+            }
+        };
+
+        if message_attr.is_some() {
+            generated.push(quote! {
+                impl ::xtra::Message for #message {
+                    type Result = #result;
+                }
+            });
+        }
+
+        let (fields, body) = match fields {
+            Some((fields, body)) => (Some(fields), body),
+            None if sig.asyncness.is_some() => {
+                (None, quote!(<#self_ty>::#name(self, message, ctx).await))
+            }
+            None => (None, quote!(<#self_ty>::#name(self, message, ctx))),
+        };
+        address_methods.push(AddressMethod {
+            name: name.clone(),
+            message: message.clone(),
+            fields,
+        });
+
+        generated.push(if sig.asyncness.is_some() {
+            quote! {
+                #[::xtra::__private::async_trait]
+                impl #impl_generics ::xtra::Handler<#message> for #self_ty #where_clause {
+                    async fn handle(
+                        &mut self,
+                        message: #message,
+                        ctx: &mut ::xtra::Context<Self>,
+                    ) -> #result {
+                        #body
+                    }
+                }
+            }
+        } else {
+            quote! {
+                impl #impl_generics ::xtra::SyncHandler<#message> for #self_ty #where_clause {
+                    fn handle(
+                        &mut self,
+                        message: #message,
+                        ctx: &mut ::xtra::Context<Self>,
+                    ) -> #result {
+                        #body
+                    }
+                }
+            }
+        });
+    }
+
+    if let Some((vis, trait_name)) = address {
+        generated.push(address_trait_impl(
+            &vis,
+            &trait_name,
+            &self_ty,
+            &generics,
+            &address_methods,
+        ));
+    }
+
+    Ok(quote! {
+        #input
+        #(#generated)*
+    })
+}
+
+/// The names and types of the fields of a message given to a handler in place of the message, as
+/// in `fn get_user(&mut self, id: u32, ctx: &mut Context<Self>)`
+fn message_fields(sig: &syn::Signature) -> syn::Result<Vec<(Ident, Box<Type>)>> {
+    let error = || {
+        syn::Error::new_spanned(
+            &sig.inputs,
+            "a handler must take `&mut self`, the fields of the message, and `&mut Context<Self>`",
+        )
+    };
+
+    let inputs: Vec<_> = sig.inputs.iter().collect();
+    let fields = match inputs.as_slice() {
+        [FnArg::Receiver(_), fields @ .., FnArg::Typed(_)] => fields,
+        _ => return Err(error()),
+    };
+
+    fields
+        .iter()
+        .map(|input| match input {
+            FnArg::Typed(field) => match &*field.pat {
+                Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                    Ok((pat.ident.clone(), field.ty.clone()))
+                }
+                pat => Err(syn::Error::new_spanned(
+                    pat,
+                    "the fields of a message must be named by identifiers",
+                )),
+            },
+            FnArg::Receiver(_) => Err(error()),
+        })
+        .collect()
+}
+
+/// A method of the address trait of `#[handlers(address = "...")]`
+struct AddressMethod {
+    name: Ident,
+    message: Box<Type>,
+    /// The fields of the message which the method takes, if the message was generated from them
+    fields: Option<Vec<(Ident, Box<Type>)>>,
+}
+
+/// Generate the address trait of `#[handlers(address = "...")]` and implement it for everything
+/// implementing `AddressExt` for the actor.
+fn address_trait_impl(
+    vis: &Visibility,
+    trait_name: &Ident,
+    self_ty: &Type,
+    generics: &syn::Generics,
+    methods: &[AddressMethod],
+) -> proc_macro2::TokenStream {
+    let (trait_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut address_generics = generics.clone();
+    address_generics
+        .params
+        .push(parse_quote!(__XtraAddress: ::xtra::AddressExt<#self_ty>));
+    let (impl_generics, _, _) = address_generics.split_for_impl();
+
+    let mut declarations = Vec::new();
+    let mut definitions = Vec::new();
+    for AddressMethod {
+        name,
+        message,
+        fields,
+    } in methods
+    {
+        let (params, construct) = match fields {
+            Some(fields) => {
+                let names: Vec<_> = fields.iter().map(|(name, _)| name).collect();
+                let types: Vec<_> = fields.iter().map(|(_, ty)| ty).collect();
+                (
+                    quote!(#(#names: #types),*),
+                    quote!(#message { #(#names),* }),
+                )
+            }
+            None => (quote!(message: #message), quote!(message)),
+        };
+        let cast = Ident::new(&format!("cast_{}", name), name.span());
+        let call_doc = format!(
+            "Send a `{}` to the actor and wait for its response, as with `AddressExt::send`.",
+            quote!(#message),
+        );
+        let cast_doc = format!(
+            "Send a `{}` to the actor without waiting for a response, as with `AddressExt::do_send`.",
+            quote!(#message),
+        );
+        let call_sig = quote! {
+            fn #name(&self, #params) -> ::xtra::MessageResponseFuture<#message>
+        };
+        let cast_sig = quote! {
+            fn #cast(&self, #params) -> ::std::result::Result<(), ::xtra::TrySendError<#message>>
+        };
+
+        declarations.push(quote! {
+            #[doc = #call_doc]
+            #call_sig;
+            #[doc = #cast_doc]
+            #cast_sig;
+        });
+        definitions.push(quote! {
+            #call_sig {
+                ::xtra::AddressExt::send(self, #construct)
+            }
+            #cast_sig {
+                ::xtra::AddressExt::do_send(self, #construct)
+            }
+        });
+    }
+
+    let trait_doc = format!(
+        "Methods sending the messages handled by `{}` to it, generated by `#[xtra::handlers]`.",
+        quote!(#self_ty),
+    );
+    quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_name #trait_generics #where_clause {
+            #(#declarations)*
+        }
+
+        impl #impl_generics #trait_name #ty_generics for __XtraAddress #where_clause {
+            #(#definitions)*
+        }
+    }
+}